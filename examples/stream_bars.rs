@@ -28,7 +28,7 @@ fn main() -> anyhow::Result<()> {
     let client = Client::connect("127.0.0.1:4002", 100).unwrap();
 
     println!("server_version: {}", client.server_version());
-    println!("server_time: {}", client.connection_time());
+    println!("server_time: {:?}", client.connection_time());
     println!("managed_accounts: {}", client.managed_accounts());
     println!("next_order_id: {}", client.next_order_id());
 
@@ -1,12 +1,18 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
 use log::error;
 
-use crate::client::transport::GlobalResponseIterator;
+use crate::client::transport::DecodedSubscription;
+use crate::client::{Subscription, TryNext};
 use crate::contracts::Contract;
 use crate::messages::IncomingMessages;
-use crate::{server_versions, Client, Error};
+use crate::{server_versions, Client, Error, ToField};
 
 mod decoders;
 mod encoders;
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Default)]
 pub struct Position {
@@ -28,16 +34,227 @@ pub struct FamilyCode {
     pub family_code: String,
 }
 
+#[derive(Debug, Default)]
+pub struct AccountSummary {
+    /// Account the value belongs to
+    pub account: String,
+    /// Name of the account value, see [AccountSummaryTags]
+    pub tag: String,
+    /// Value of the tag
+    pub value: String,
+    /// Currency of the value, if applicable
+    pub currency: String,
+}
+
+/// Account values that can be requested with [Client::account_summary].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountSummaryTags {
+    /// Identifies the IB account structure
+    AccountType,
+    /// The basis for determining the price of the assets in your account. Total cash value + stock value + options value + bond value
+    NetLiquidation,
+    /// Total cash balance recognized at the time of trade + futures PNL
+    TotalCashValue,
+    /// Cash recognized at the time of settlement - purchases at the time of trade - commissions - taxes - fees
+    SettledCash,
+    /// Total accrued cash value of stock, commodities and securities
+    AccruedCash,
+    /// Buying power serves as a measurement of the dollar value of securities that one may purchase in a securities account without depositing additional funds
+    BuyingPower,
+    /// Forms the basis for determining whether a client has the necessary assets to either initiate or maintain security positions. Cash + stocks + bonds + mutual funds
+    EquityWithLoanValue,
+    /// Marginable Equity with Loan value as of 16:00 ET the previous day
+    PreviousEquityWithLoanValue,
+    /// The sum of the absolute value of all stock and equity option positions
+    GrossPositionValue,
+    /// Regulation T equity for universal account
+    RegTEquity,
+    /// Regulation T margin for universal account
+    RegTMargin,
+    /// Special Memorandum Account: Line of credit created when the market value of securities in a Regulation T account increase in value
+    SMA,
+    /// Initial Margin requirement of whole portfolio
+    InitMarginReq,
+    /// Maintenance Margin requirement of whole portfolio
+    MaintMarginReq,
+    /// This value tells what you have available for trading
+    AvailableFunds,
+    /// This value shows your margin cushion, before liquidation
+    ExcessLiquidity,
+    /// Excess liquidity as a percentage of net liquidation value
+    Cushion,
+    /// Initial Margin of whole portfolio with no discounts or intraday credits
+    FullInitMarginReq,
+    /// Maintenance Margin of whole portfolio with no discounts or intraday credits
+    FullMaintMarginReq,
+    /// Available funds of whole portfolio with no discounts or intraday credits
+    FullAvailableFunds,
+    /// Excess liquidity of whole portfolio with no discounts or intraday credits
+    FullExcessLiquidity,
+    /// Time when look-ahead values take effect
+    LookAheadNextChange,
+    /// Initial Margin requirement of whole portfolio as of next period's margin change
+    LookAheadInitMarginReq,
+    /// Maintenance Margin requirement of whole portfolio as of next period's margin change
+    LookAheadMaintMarginReq,
+    /// This value reflects your available funds at the next margin change
+    LookAheadAvailableFunds,
+    /// This value reflects your excess liquidity at the next margin change
+    LookAheadExcessLiquidity,
+    /// A measure of how close the account is to liquidation
+    HighestSeverity,
+    /// The Number of Open/Close trades a user could put on before Pattern Day Trading is detected
+    DayTradesRemaining,
+    /// GrossPositionValue / NetLiquidation
+    Leverage,
+}
+
+impl fmt::Display for AccountSummaryTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::AccountType => "AccountType",
+            Self::NetLiquidation => "NetLiquidation",
+            Self::TotalCashValue => "TotalCashValue",
+            Self::SettledCash => "SettledCash",
+            Self::AccruedCash => "AccruedCash",
+            Self::BuyingPower => "BuyingPower",
+            Self::EquityWithLoanValue => "EquityWithLoanValue",
+            Self::PreviousEquityWithLoanValue => "PreviousEquityWithLoanValue",
+            Self::GrossPositionValue => "GrossPositionValue",
+            Self::RegTEquity => "RegTEquity",
+            Self::RegTMargin => "RegTMargin",
+            Self::SMA => "SMA",
+            Self::InitMarginReq => "InitMarginReq",
+            Self::MaintMarginReq => "MaintMarginReq",
+            Self::AvailableFunds => "AvailableFunds",
+            Self::ExcessLiquidity => "ExcessLiquidity",
+            Self::Cushion => "Cushion",
+            Self::FullInitMarginReq => "FullInitMarginReq",
+            Self::FullMaintMarginReq => "FullMaintMarginReq",
+            Self::FullAvailableFunds => "FullAvailableFunds",
+            Self::FullExcessLiquidity => "FullExcessLiquidity",
+            Self::LookAheadNextChange => "LookAheadNextChange",
+            Self::LookAheadInitMarginReq => "LookAheadInitMarginReq",
+            Self::LookAheadMaintMarginReq => "LookAheadMaintMarginReq",
+            Self::LookAheadAvailableFunds => "LookAheadAvailableFunds",
+            Self::LookAheadExcessLiquidity => "LookAheadExcessLiquidity",
+            Self::HighestSeverity => "HighestSeverity",
+            Self::DayTradesRemaining => "DayTradesRemaining",
+            Self::Leverage => "Leverage",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Daily profit and loss for an account, received from [Client::pnl].
+#[derive(Debug, Default)]
+pub struct PnL {
+    /// Daily PnL
+    pub daily_pnl: f64,
+    /// Total unrealized PnL
+    pub unrealized_pnl: Option<f64>,
+    /// Total realized PnL
+    pub realized_pnl: Option<f64>,
+}
+
+/// Daily profit and loss for a single position, received from [Client::pnl_single].
+#[derive(Debug, Default)]
+pub struct PnLSingle {
+    /// Current size of the position
+    pub position: f64,
+    /// Daily PnL for the position
+    pub daily_pnl: f64,
+    /// Total unrealized PnL for the position (since inception) updating in real time
+    pub unrealized_pnl: Option<f64>,
+    /// Daily realized PnL for the position
+    pub realized_pnl: Option<f64>,
+    /// Current market value of the position
+    pub value: f64,
+}
+
+/// Account value received from [Client::account_updates], e.g. NetLiquidation.
+#[derive(Debug, Default)]
+pub struct AccountValue {
+    /// Name of the account value
+    pub key: String,
+    /// Value of the account value
+    pub value: String,
+    /// Currency of the value, if applicable
+    pub currency: String,
+    /// Account the value belongs to
+    pub account: String,
+}
+
+/// Position in the portfolio of the account, received from [Client::account_updates].
+#[derive(Debug, Default)]
+pub struct AccountPortfolioValue {
+    /// Contract of the position
+    pub contract: Contract,
+    /// Size of the position
+    pub position: f64,
+    /// Unit price of the instrument
+    pub market_price: f64,
+    /// Total market value of the position
+    pub market_value: f64,
+    /// Average cost of the position
+    pub average_cost: f64,
+    /// Unrealized PnL of the position
+    pub unrealized_pnl: f64,
+    /// Realized PnL of the position
+    pub realized_pnl: f64,
+    /// Account holding the position
+    pub account: String,
+}
+
+/// Update received from [Client::account_updates].
+#[derive(Debug)]
+pub enum AccountUpdate {
+    /// Value of the account, e.g. NetLiquidation
+    AccountValue(AccountValue),
+    /// Position in the portfolio of the account
+    PortfolioValue(Box<AccountPortfolioValue>),
+    /// Time of the last account update, e.g. 15:30
+    UpdateTime(String),
+    /// All values and positions of the account have been sent. Updates continue as values change.
+    End,
+}
+
+/// Configuration of a financial advisor account, see [Client::request_fa] and [Client::replace_fa].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaDataType {
+    /// Groups of accounts allocated together.
+    Groups = 1,
+    /// Allocation profiles of shares across accounts.
+    Profiles = 2,
+    /// Aliases of the accounts.
+    Aliases = 3,
+}
+
+impl ToField for FaDataType {
+    fn to_field(&self) -> String {
+        (*self as i32).to_string()
+    }
+}
+
 // Subscribes to position updates for all accessible accounts.
 // All positions sent initially, and then only updates as positions change.
-pub(crate) fn positions(client: &Client) -> Result<impl Iterator<Item = Position> + '_, Error> {
+pub(crate) fn positions(client: &Client) -> Result<PositionIterator<'_>, Error> {
     client.check_server_version(server_versions::ACCOUNT_SUMMARY, "It does not support position requests.")?;
 
     let message = encoders::request_positions()?;
 
-    let messages = client.request_positions(message)?;
+    let responses = DecodedSubscription::new(client.request_positions(message)?, |message| match message.message_type() {
+        IncomingMessages::Position => decoders::decode_position(message).map(Some),
+        IncomingMessages::PositionEnd => Ok(None),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    });
 
-    Ok(PositionIterator { client, messages })
+    Ok(PositionIterator {
+        client,
+        responses,
+        done: false,
+    })
 }
 
 pub(crate) fn cancel_positions(client: &Client) -> Result<(), Error> {
@@ -50,6 +267,66 @@ pub(crate) fn cancel_positions(client: &Client) -> Result<(), Error> {
     Ok(())
 }
 
+// Requests a summary of account values for all accounts in the given group.
+pub(crate) fn account_summary<'a>(client: &'a Client, group: &str, tags: &[AccountSummaryTags]) -> Result<AccountSummaryIterator<'a>, Error> {
+    client.check_server_version(server_versions::ACCOUNT_SUMMARY, "It does not support account summary requests.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_account_summary(request_id, group, tags)?;
+
+    let responses = DecodedSubscription::new(client.send_request(request_id, message)?, |message| match message.message_type() {
+        IncomingMessages::AccountSummary => decoders::decode_account_summary(message).map(Some),
+        IncomingMessages::AccountSummaryEnd => Ok(None),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    });
+
+    Ok(AccountSummaryIterator {
+        client,
+        request_id,
+        responses,
+    })
+}
+
+// Subscribes to daily PnL updates for an account.
+pub(crate) fn pnl<'a>(client: &'a Client, account: &str, model_code: Option<&str>) -> Result<PnLIterator<'a>, Error> {
+    client.check_server_version(server_versions::PNL, "It does not support PnL requests.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_pnl(request_id, account, model_code)?;
+
+    let server_version = client.server_version();
+    let responses = DecodedSubscription::new(client.send_request(request_id, message)?, move |message| match message.message_type() {
+        IncomingMessages::PnL => decoders::decode_pnl(server_version, message).map(Some),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    });
+
+    Ok(PnLIterator {
+        client,
+        request_id,
+        responses,
+    })
+}
+
+// Subscribes to daily PnL updates for a single position.
+pub(crate) fn pnl_single<'a>(client: &'a Client, account: &str, contract_id: i32, model_code: Option<&str>) -> Result<PnLSingleIterator<'a>, Error> {
+    client.check_server_version(server_versions::PNL, "It does not support PnL requests.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_pnl_single(request_id, account, contract_id, model_code)?;
+
+    let server_version = client.server_version();
+    let responses = DecodedSubscription::new(client.send_request(request_id, message)?, move |message| match message.message_type() {
+        IncomingMessages::PnLSingle => decoders::decode_pnl_single(server_version, message).map(Some),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    });
+
+    Ok(PnLSingleIterator {
+        client,
+        request_id,
+        responses,
+    })
+}
+
 // Determine whether an account exists under an account family and find the account family code.
 pub(crate) fn family_codes(client: &Client) -> Result<Vec<FamilyCode>, Error> {
     client.check_server_version(server_versions::REQ_FAMILY_CODES, "It does not support family codes requests.")?;
@@ -64,39 +341,300 @@ pub(crate) fn family_codes(client: &Client) -> Result<Vec<FamilyCode>, Error> {
         Ok(Vec::default())
     }
 }
+
+// Requests the XML configuration of a financial advisor account.
+pub(crate) fn request_fa(client: &Client, fa_data_type: FaDataType) -> Result<String, Error> {
+    let message = encoders::request_fa(fa_data_type)?;
+
+    let mut messages = client.send_fa_request(message)?;
+
+    match messages.next() {
+        Some(mut message) => decoders::decode_receive_fa(&mut message),
+        None => Err(Error::Simple("no financial advisor configuration received".into())),
+    }
+}
+
+// Replaces the XML configuration of a financial advisor account.
+// Servers supporting REPLACE_FA_END confirm the replacement, older servers don't answer.
+pub(crate) fn replace_fa(client: &Client, fa_data_type: FaDataType, xml: &str) -> Result<(), Error> {
+    let request_id = client.next_request_id();
+    let message = encoders::replace_fa(client.server_version(), request_id, fa_data_type, xml)?;
+
+    let mut messages = client.send_fa_request(message)?;
+
+    if client.server_version() < server_versions::REPLACE_FA_END {
+        return Ok(());
+    }
+
+    match messages.next() {
+        Some(message) if message.message_type() == IncomingMessages::ReplaceFAEnd => Ok(()),
+        Some(message) => Err(Error::Simple(format!("unexpected message: {message:?}"))),
+        None => Err(Error::Simple("no confirmation of financial advisor configuration received".into())),
+    }
+}
+
+// Subscribes to account values and portfolio positions of the account.
+pub(crate) fn account_updates<'a>(client: &'a Client, account: &str) -> Result<AccountUpdateIterator<'a>, Error> {
+    let message = encoders::request_account_updates(true, account)?;
+
+    let responses = DecodedSubscription::new(client.request_account_updates(message)?, |message| match message.message_type() {
+        IncomingMessages::AccountValue => decoders::decode_account_value(message).map(|value| Some(AccountUpdate::AccountValue(value))),
+        IncomingMessages::PortfolioValue => {
+            decoders::decode_account_portfolio_value(message).map(|value| Some(AccountUpdate::PortfolioValue(Box::new(value))))
+        }
+        IncomingMessages::AccountUpdateTime => decoders::decode_account_update_time(message).map(|time| Some(AccountUpdate::UpdateTime(time))),
+        IncomingMessages::AccountDownloadEnd => Ok(Some(AccountUpdate::End)),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    });
+
+    Ok(AccountUpdateIterator {
+        client,
+        account: account.to_owned(),
+        responses,
+    })
+}
+
 // Supports iteration over [Position].
 pub(crate) struct PositionIterator<'a> {
     client: &'a Client,
-    messages: GlobalResponseIterator,
+    responses: DecodedSubscription<Position>,
+    done: bool,
+}
+
+impl<'a> PositionIterator<'a> {
+    // Cancels the position subscription once, after PositionEnd or when dropped before it.
+    fn cancel(&mut self) {
+        if self.done {
+            return;
+        }
+
+        self.done = true;
+        if let Err(e) = cancel_positions(self.client) {
+            error!("error cancelling positions: {e}")
+        }
+    }
+
+    fn cancel_if_ended(&mut self) {
+        if self.responses.is_ended() {
+            self.cancel();
+        }
+    }
+}
+
+impl<'a> Drop for PositionIterator<'a> {
+    // Ensures position subscription is cancelled if dropped before PositionEnd.
+    fn drop(&mut self) {
+        self.cancel()
+    }
 }
 
 impl<'a> Iterator for PositionIterator<'a> {
     type Item = Position;
 
-    // Returns the next [Position]. Waits up to x seconds for next [OrderDataResult].
+    // Returns the next [Position]. Ends after PositionEnd is received.
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(mut message) = self.messages.next() {
-                match message.message_type() {
-                    IncomingMessages::Position => match decoders::decode_position(&mut message) {
-                        Ok(val) => return Some(val),
-                        Err(err) => {
-                            error!("error decoding execution data: {err}");
-                        }
-                    },
-                    IncomingMessages::PositionEnd => {
-                        if let Err(e) = cancel_positions(self.client) {
-                            error!("error cancelling positions: {e}")
-                        }
-                        return None;
-                    }
-                    message => {
-                        error!("order data iterator unexpected message: {message:?}");
-                    }
-                }
-            } else {
-                return None;
-            }
+        let position = self.responses.next();
+        self.cancel_if_ended();
+        position
+    }
+}
+
+impl<'a> Subscription for PositionIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        let position = self.responses.next_timeout(timeout);
+        self.cancel_if_ended();
+        position
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        let position = self.responses.recv_deadline(deadline);
+        self.cancel_if_ended();
+        position
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        let position = self.responses.try_next();
+        self.cancel_if_ended();
+        position
+    }
+}
+
+// Supports iteration over [AccountSummary].
+pub(crate) struct AccountSummaryIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: DecodedSubscription<AccountSummary>,
+}
+
+impl<'a> Drop for AccountSummaryIterator<'a> {
+    // Ensures account summary request is cancelled
+    fn drop(&mut self) {
+        let message = encoders::cancel_account_summary(self.request_id).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling account summary: {e}");
+        }
+    }
+}
+
+impl<'a> Iterator for AccountSummaryIterator<'a> {
+    type Item = AccountSummary;
+
+    // Returns the next [AccountSummary]. Ends when all values have been received.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for AccountSummaryIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
+
+// Supports iteration over [PnL].
+pub(crate) struct PnLIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: DecodedSubscription<PnL>,
+}
+
+impl<'a> Drop for PnLIterator<'a> {
+    // Ensures PnL subscription is cancelled
+    fn drop(&mut self) {
+        let message = encoders::cancel_pnl(self.request_id).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling pnl: {e}");
+        }
+    }
+}
+
+impl<'a> Iterator for PnLIterator<'a> {
+    type Item = PnL;
+
+    // Returns the next [PnL]. Updates are streamed until the iterator is dropped.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for PnLIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
+
+// Supports iteration over [PnLSingle].
+pub(crate) struct PnLSingleIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: DecodedSubscription<PnLSingle>,
+}
+
+impl<'a> Drop for PnLSingleIterator<'a> {
+    // Ensures PnL single subscription is cancelled
+    fn drop(&mut self) {
+        let message = encoders::cancel_pnl_single(self.request_id).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling pnl single: {e}");
         }
     }
 }
+
+impl<'a> Iterator for PnLSingleIterator<'a> {
+    type Item = PnLSingle;
+
+    // Returns the next [PnLSingle]. Updates are streamed until the iterator is dropped.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for PnLSingleIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
+
+// Supports iteration over [AccountUpdate].
+pub(crate) struct AccountUpdateIterator<'a> {
+    client: &'a Client,
+    account: String,
+    responses: DecodedSubscription<AccountUpdate>,
+}
+
+impl<'a> Drop for AccountUpdateIterator<'a> {
+    // Ensures account updates subscription is cancelled
+    fn drop(&mut self) {
+        let message = encoders::request_account_updates(false, &self.account).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling account updates: {e}");
+        }
+    }
+}
+
+impl<'a> Iterator for AccountUpdateIterator<'a> {
+    type Item = AccountUpdate;
+
+    // Returns the next [AccountUpdate]. Updates are streamed until the iterator is dropped.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for AccountUpdateIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
@@ -1,8 +1,8 @@
 use crate::contracts::SecurityType;
 use crate::messages::ResponseMessage;
-use crate::Error;
+use crate::{server_versions, Error};
 
-use super::{FamilyCode, Position};
+use super::{AccountPortfolioValue, AccountSummary, AccountValue, FamilyCode, PnL, PnLSingle, Position};
 
 pub(crate) fn decode_position(message: &mut ResponseMessage) -> Result<Position, Error> {
     message.skip(); // message type
@@ -38,6 +38,137 @@ pub(crate) fn decode_position(message: &mut ResponseMessage) -> Result<Position,
     Ok(position)
 }
 
+pub(crate) fn decode_account_summary(message: &mut ResponseMessage) -> Result<AccountSummary, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // request id
+
+    Ok(AccountSummary {
+        account: message.next_string()?,
+        tag: message.next_string()?,
+        value: message.next_string()?,
+        currency: message.next_string()?,
+    })
+}
+
+pub(crate) fn decode_pnl(server_version: i32, message: &mut ResponseMessage) -> Result<PnL, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let mut pnl = PnL {
+        daily_pnl: message.next_double()?,
+        ..Default::default()
+    };
+
+    if server_version >= server_versions::UNREALIZED_PNL {
+        pnl.unrealized_pnl = message.next_optional_double()?;
+    }
+
+    if server_version >= server_versions::REALIZED_PNL {
+        pnl.realized_pnl = message.next_optional_double()?;
+    }
+
+    Ok(pnl)
+}
+
+pub(crate) fn decode_pnl_single(server_version: i32, message: &mut ResponseMessage) -> Result<PnLSingle, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let mut pnl = PnLSingle {
+        position: message.next_double()?,
+        daily_pnl: message.next_double()?,
+        ..Default::default()
+    };
+
+    if server_version >= server_versions::UNREALIZED_PNL {
+        pnl.unrealized_pnl = message.next_optional_double()?;
+    }
+
+    if server_version >= server_versions::REALIZED_PNL {
+        pnl.realized_pnl = message.next_optional_double()?;
+    }
+
+    pnl.value = message.next_double()?;
+
+    Ok(pnl)
+}
+
+pub(crate) fn decode_account_value(message: &mut ResponseMessage) -> Result<AccountValue, Error> {
+    message.skip(); // message type
+
+    let message_version = message.next_int()?;
+
+    let mut account_value = AccountValue {
+        key: message.next_string()?,
+        value: message.next_string()?,
+        ..Default::default()
+    };
+
+    if message_version >= 2 {
+        account_value.currency = message.next_string()?;
+        account_value.account = message.next_string()?;
+    }
+
+    Ok(account_value)
+}
+
+pub(crate) fn decode_account_portfolio_value(message: &mut ResponseMessage) -> Result<AccountPortfolioValue, Error> {
+    message.skip(); // message type
+
+    let message_version = message.next_int()?;
+
+    let mut portfolio_value = AccountPortfolioValue::default();
+
+    if message_version >= 6 {
+        portfolio_value.contract.contract_id = message.next_int()?;
+    }
+
+    portfolio_value.contract.symbol = message.next_string()?;
+    portfolio_value.contract.security_type = SecurityType::from(&message.next_string()?);
+    portfolio_value.contract.last_trade_date_or_contract_month = message.next_string()?;
+    portfolio_value.contract.strike = message.next_double()?;
+    portfolio_value.contract.right = message.next_string()?;
+
+    if message_version >= 7 {
+        portfolio_value.contract.multiplier = message.next_string()?;
+        portfolio_value.contract.primary_exchange = message.next_string()?;
+    }
+
+    portfolio_value.contract.currency = message.next_string()?;
+
+    if message_version >= 2 {
+        portfolio_value.contract.local_symbol = message.next_string()?;
+    }
+
+    if message_version >= 8 {
+        portfolio_value.contract.trading_class = message.next_string()?;
+    }
+
+    portfolio_value.position = message.next_double()?;
+    portfolio_value.market_price = message.next_double()?;
+    portfolio_value.market_value = message.next_double()?;
+
+    if message_version >= 3 {
+        portfolio_value.average_cost = message.next_double()?;
+        portfolio_value.unrealized_pnl = message.next_double()?;
+        portfolio_value.realized_pnl = message.next_double()?;
+    }
+
+    if message_version >= 4 {
+        portfolio_value.account = message.next_string()?;
+    }
+
+    Ok(portfolio_value)
+}
+
+pub(crate) fn decode_account_update_time(message: &mut ResponseMessage) -> Result<String, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+
+    message.next_string()
+}
+
 pub(crate) fn decode_family_codes(message: &mut ResponseMessage) -> Result<Vec<FamilyCode>, Error> {
     message.skip(); // message type
 
@@ -61,6 +192,15 @@ pub(crate) fn decode_family_codes(message: &mut ResponseMessage) -> Result<Vec<F
     Ok(family_codes)
 }
 
+pub(crate) fn decode_receive_fa(message: &mut ResponseMessage) -> Result<String, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // fa data type
+
+    message.next_string()
+}
+
+#[cfg(test)]
 mod tests {
 
     #[test]
@@ -2,6 +2,10 @@ use crate::messages::OutgoingMessages;
 use crate::messages::RequestMessage;
 use crate::Error;
 
+use crate::server_versions;
+
+use super::{AccountSummaryTags, FaDataType};
+
 pub(crate) fn request_positions() -> Result<RequestMessage, Error> {
     encode_simple(OutgoingMessages::RequestPositions, 1)
 }
@@ -19,10 +23,114 @@ pub(crate) fn cancel_positions() -> Result<RequestMessage, Error> {
     encode_simple(OutgoingMessages::CancelPositions, 1)
 }
 
+// Subscribes to, or with subscribe false unsubscribes from, the account updates of account.
+pub(crate) fn request_account_updates(subscribe: bool, account: &str) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 2;
+
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::RequestAccountData);
+    message.push_field(&VERSION);
+    message.push_field(&subscribe);
+    message.push_field(&account);
+
+    Ok(message)
+}
+
 pub(crate) fn request_family_codes() -> Result<RequestMessage, Error> {
     encode_simple(OutgoingMessages::RequestFamilyCodes, 1)
 }
 
+pub(crate) fn request_fa(fa_data_type: FaDataType) -> Result<RequestMessage, Error> {
+    let mut message = encode_simple(OutgoingMessages::RequestFA, 1)?;
+
+    message.push_field(&fa_data_type);
+
+    Ok(message)
+}
+
+pub(crate) fn replace_fa(server_version: i32, request_id: i32, fa_data_type: FaDataType, xml: &str) -> Result<RequestMessage, Error> {
+    let mut message = encode_simple(OutgoingMessages::ReplaceFA, 1)?;
+
+    message.push_field(&fa_data_type);
+    message.push_field(&xml);
+
+    if server_version >= server_versions::REPLACE_FA_END {
+        message.push_field(&request_id);
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn request_account_summary(request_id: i32, group: &str, tags: &[AccountSummaryTags]) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::RequestAccountSummary);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+    message.push_field(&group);
+    message.push_field(&tags.join(","));
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_account_summary(request_id: i32) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::CancelAccountSummary);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+pub(crate) fn request_pnl(request_id: i32, account: &str, model_code: Option<&str>) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::RequestPnL);
+    message.push_field(&request_id);
+    message.push_field(&account);
+    message.push_field(&model_code.unwrap_or_default());
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_pnl(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::CancelPnL);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+pub(crate) fn request_pnl_single(request_id: i32, account: &str, contract_id: i32, model_code: Option<&str>) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::RequestPnLSingle);
+    message.push_field(&request_id);
+    message.push_field(&account);
+    message.push_field(&model_code.unwrap_or_default());
+    message.push_field(&contract_id);
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_pnl_single(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::new();
+
+    message.push_field(&OutgoingMessages::CancelPnLSingle);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ToField;
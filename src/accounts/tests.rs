@@ -0,0 +1,263 @@
+use std::cell::RefCell;
+
+use crate::stubs::MessageBusStub;
+
+use super::*;
+
+#[test]
+fn account_summary() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "63|1|9000|DU1236109|NetLiquidation|1001384.51|USD|".to_owned(),
+            "63|1|9000|DU1236109|BuyingPower|4005538.04|USD|".to_owned(),
+            "64|1|9000|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let tags = &[AccountSummaryTags::NetLiquidation, AccountSummaryTags::BuyingPower];
+    let results = client.account_summary("All", tags);
+    assert!(results.is_ok(), "failed to request account summary: {}", results.err().unwrap());
+
+    let summaries: Vec<AccountSummary> = results.unwrap().collect();
+    assert_eq!(summaries.len(), 2, "summaries.len()");
+
+    assert_eq!(summaries[0].account, "DU1236109", "summaries[0].account");
+    assert_eq!(summaries[0].tag, AccountSummaryTags::NetLiquidation.to_string(), "summaries[0].tag");
+    assert_eq!(summaries[0].value, "1001384.51", "summaries[0].value");
+    assert_eq!(summaries[0].currency, "USD", "summaries[0].currency");
+
+    assert_eq!(summaries[1].tag, AccountSummaryTags::BuyingPower.to_string(), "summaries[1].tag");
+    assert_eq!(summaries[1].value, "4005538.04", "summaries[1].value");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "62|1|9000|All|NetLiquidation,BuyingPower|",
+        "account summary request"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "63|1|9000|", "cancel account summary request");
+}
+
+#[test]
+fn positions() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "61|3|DU1236109|76792991|TSLA|STK||0.0|||NASDAQ|USD|TSLA|NMS|500|196.77|".to_owned(),
+            "61|3|DU1236109|265598|AAPL|STK||0.0|||NASDAQ|USD|AAPL|NMS|100|172.5|".to_owned(),
+            "62|1|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.positions();
+    assert!(results.is_ok(), "failed to request positions: {}", results.err().unwrap());
+
+    let positions: Vec<Position> = results.unwrap().collect();
+    assert_eq!(positions.len(), 2, "positions.len()");
+
+    assert_eq!(positions[0].account, "DU1236109", "positions[0].account");
+    assert_eq!(positions[0].contract.symbol, "TSLA", "positions[0].contract.symbol");
+    assert_eq!(positions[0].position, 500.0, "positions[0].position");
+    assert_eq!(positions[0].average_cost, 196.77, "positions[0].average_cost");
+
+    assert_eq!(positions[1].contract.contract_id, 265598, "positions[1].contract.contract_id");
+    assert_eq!(positions[1].contract.symbol, "AAPL", "positions[1].contract.symbol");
+    assert_eq!(positions[1].position, 100.0, "positions[1].position");
+    assert_eq!(positions[1].average_cost, 172.5, "positions[1].average_cost");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+    assert_eq!(request_messages[0].encode_simple(), "61|1|", "request positions");
+    assert_eq!(request_messages[1].encode_simple(), "64|1|", "cancel positions");
+}
+
+#[test]
+fn pnl() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["94|9000|-125.5|310.25|1.7976931348623157E308|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.pnl("DU1236109", None);
+    assert!(results.is_ok(), "failed to request pnl: {}", results.err().unwrap());
+
+    let updates: Vec<PnL> = results.unwrap().collect();
+    assert_eq!(updates.len(), 1, "updates.len()");
+
+    assert_eq!(updates[0].daily_pnl, -125.5, "updates[0].daily_pnl");
+    assert_eq!(updates[0].unrealized_pnl, Some(310.25), "updates[0].unrealized_pnl");
+    assert_eq!(updates[0].realized_pnl, None, "updates[0].realized_pnl");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "92|9000|DU1236109||", "request pnl");
+    assert_eq!(request_messages[1].encode_simple(), "93|9000|", "cancel pnl");
+}
+
+#[test]
+fn pnl_single() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["95|9000|100|-12.5|250.0|0.0|17250.0|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.pnl_single("DU1236109", 265598, Some("TARGET"));
+    assert!(results.is_ok(), "failed to request pnl single: {}", results.err().unwrap());
+
+    let updates: Vec<PnLSingle> = results.unwrap().collect();
+    assert_eq!(updates.len(), 1, "updates.len()");
+
+    assert_eq!(updates[0].position, 100.0, "updates[0].position");
+    assert_eq!(updates[0].daily_pnl, -12.5, "updates[0].daily_pnl");
+    assert_eq!(updates[0].unrealized_pnl, Some(250.0), "updates[0].unrealized_pnl");
+    assert_eq!(updates[0].realized_pnl, Some(0.0), "updates[0].realized_pnl");
+    assert_eq!(updates[0].value, 17250.0, "updates[0].value");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "94|9000|DU1236109|TARGET|265598|",
+        "request pnl single"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "95|9000|", "cancel pnl single");
+}
+
+#[test]
+fn account_updates() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "6|2|NetLiquidation|1001384.51|USD|DU1236109|".to_owned(),
+            "7|8|76792991|TSLA|STK||0.0|||NASDAQ|USD|TSLA|NMS|500|196.77|98385|192.1|2335|0|DU1236109|".to_owned(),
+            "8|1|15:30|".to_owned(),
+            "54|1|DU1236109|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.account_updates("DU1236109");
+    assert!(results.is_ok(), "failed to request account updates: {}", results.err().unwrap());
+
+    let updates: Vec<AccountUpdate> = results.unwrap().collect();
+    assert_eq!(updates.len(), 4, "updates.len()");
+
+    match &updates[0] {
+        AccountUpdate::AccountValue(value) => {
+            assert_eq!(value.key, "NetLiquidation", "value.key");
+            assert_eq!(value.value, "1001384.51", "value.value");
+            assert_eq!(value.currency, "USD", "value.currency");
+            assert_eq!(value.account, "DU1236109", "value.account");
+        }
+        update => assert!(false, "expected account value, got {update:?}"),
+    }
+
+    match &updates[1] {
+        AccountUpdate::PortfolioValue(position) => {
+            assert_eq!(position.contract.contract_id, 76792991, "position.contract.contract_id");
+            assert_eq!(position.contract.symbol, "TSLA", "position.contract.symbol");
+            assert_eq!(position.contract.primary_exchange, "NASDAQ", "position.contract.primary_exchange");
+            assert_eq!(position.contract.currency, "USD", "position.contract.currency");
+            assert_eq!(position.contract.trading_class, "NMS", "position.contract.trading_class");
+            assert_eq!(position.position, 500.0, "position.position");
+            assert_eq!(position.market_price, 196.77, "position.market_price");
+            assert_eq!(position.market_value, 98385.0, "position.market_value");
+            assert_eq!(position.average_cost, 192.1, "position.average_cost");
+            assert_eq!(position.unrealized_pnl, 2335.0, "position.unrealized_pnl");
+            assert_eq!(position.realized_pnl, 0.0, "position.realized_pnl");
+            assert_eq!(position.account, "DU1236109", "position.account");
+        }
+        update => assert!(false, "expected portfolio value, got {update:?}"),
+    }
+
+    match &updates[2] {
+        AccountUpdate::UpdateTime(time) => assert_eq!(time, "15:30", "update time"),
+        update => assert!(false, "expected update time, got {update:?}"),
+    }
+
+    assert!(
+        matches!(updates[3], AccountUpdate::End),
+        "expected account download end, got {:?}",
+        updates[3]
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "6|2|1|DU1236109|", "account updates request");
+    assert_eq!(request_messages[1].encode_simple(), "6|2|0|DU1236109|", "cancel account updates request");
+}
+
+#[test]
+fn request_fa() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><ListOfGroups><Group><name>Equal</name><ListOfAccts varName="list"><String>DU1234567</String><String>DU7654321</String></ListOfAccts><defaultMethod>AvailableEquity</defaultMethod></Group></ListOfGroups>"#;
+
+    for (fa_data_type, expected_request) in [
+        (FaDataType::Groups, "18|1|1|"),
+        (FaDataType::Profiles, "18|1|2|"),
+        (FaDataType::Aliases, "18|1|3|"),
+    ] {
+        let message_bus = RefCell::new(Box::new(MessageBusStub {
+            request_messages: RefCell::new(vec![]),
+            response_messages: vec![format!("16|1|{}|{xml}|", fa_data_type.to_field())],
+        }));
+
+        let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+        let results = client.request_fa(fa_data_type);
+
+        let request_messages = client.message_bus.lock().request_messages();
+        assert_eq!(request_messages[0].encode_simple(), expected_request, "request fa {fa_data_type:?}");
+
+        match results {
+            Ok(configuration) => assert_eq!(configuration, xml, "configuration of {fa_data_type:?}"),
+            Err(err) => assert!(false, "failed to request fa {fa_data_type:?}: {err}"),
+        }
+    }
+}
+
+#[test]
+fn replace_fa() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><ListOfGroups><Group><name>Equal</name><ListOfAccts varName="list"><String>DU1234567</String></ListOfAccts><defaultMethod>NetLiq</defaultMethod></Group></ListOfGroups>"#;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["103|9000|FA groups replaced|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REPLACE_FA_END);
+
+    let results = client.replace_fa(FaDataType::Groups, xml);
+    assert!(results.is_ok(), "failed to replace fa: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), format!("19|1|1|{xml}|9000|"), "replace fa");
+}
+
+#[test]
+fn replace_fa_without_confirmation() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    // servers before REPLACE_FA_END don't confirm the replacement
+    let client = Client::stubbed(message_bus, server_versions::PRICE_BASED_VOLATILITY);
+
+    let results = client.replace_fa(FaDataType::Aliases, "<ListOfAccountAliases/>");
+    assert!(results.is_ok(), "failed to replace fa: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "19|1|3|<ListOfAccountAliases/>|", "replace fa");
+}
@@ -1,48 +1,131 @@
-use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::Write;
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use byteorder::{BigEndian, WriteBytesExt};
-use log::{debug, error, info};
+use crossbeam::channel::Receiver;
+use log::{debug, error, info, warn};
 use time::macros::format_description;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 use time_tz::{timezones, OffsetResult, PrimitiveDateTimeExt, Tz};
 
-use crate::accounts::{FamilyCode, Position};
-use crate::client::transport::{GlobalResponseIterator, MessageBus, ResponseIterator, TcpMessageBus};
+use crate::accounts::{AccountSummary, AccountSummaryTags, AccountUpdate, FaDataType, FamilyCode, PnL, PnLSingle, Position};
+use crate::client::transport::{GlobalResponseIterator, MessageBus, ReplayMessageBus, ResponseIterator, SharedMessageBus};
 use crate::contracts::Contract;
 use crate::errors::Error;
-use crate::market_data::historical;
-use crate::market_data::realtime::{self, Bar, BarSize, WhatToShow};
-use crate::messages::RequestMessage;
-use crate::messages::{IncomingMessages, OutgoingMessages};
-use crate::orders::{Order, OrderDataResult, OrderNotification};
-use crate::{accounts, contracts, orders, server_versions};
-
+use crate::market_data::realtime::{self, BarSize, MarketDataType, WhatToShow};
+use crate::market_data::{historical, scanners};
+use crate::messages::OutgoingMessages;
+use crate::orders::{AdvancedOrderReject, Order, OrderDataResult, OrderNotification, OrderState};
+use crate::{accounts, contracts, news, orders, server_versions, wsh};
+
+pub use crate::messages::{IncomingMessages, RequestMessage, ResponseMessage};
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncClient;
+pub use builder::ClientBuilder;
+pub use features::Feature;
+pub use transport::{ConnectionState, MessageObserver, OverflowPolicy, Subscription, TryNext};
+
+#[cfg(feature = "async")]
+mod asynchronous;
+mod builder;
+mod features;
 pub(crate) mod transport;
 
 // Client
 
-const MIN_SERVER_VERSION: i32 = 100;
-const MAX_SERVER_VERSION: i32 = server_versions::HISTORICAL_SCHEDULE;
+pub(crate) const MIN_SERVER_VERSION: i32 = server_versions::min_version();
+pub(crate) const MAX_SERVER_VERSION: i32 = server_versions::max_version();
+// Request ids start well above the order ids typically handed out by TWS. The two are tracked independently by the server.
+const INITIAL_REQUEST_ID: i32 = 9000;
 
 /// TWS API Client. Manages the connection to TWS or Gateway.
 /// Tracks some global information such as server version and server time.
 /// Supports generation of order ids
+///
+/// Clones of the client share its connection, so subscriptions can be processed on separate threads.
+/// The connection is closed when the last clone is dropped.
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use ibapi::contracts::Contract;
+/// use ibapi::market_data::realtime::{BarSize, WhatToShow};
+/// use ibapi::Client;
+///
+/// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+///
+/// let handles: Vec<_> = ["AAPL", "MSFT"]
+///     .into_iter()
+///     .map(|symbol| {
+///         let client = client.clone();
+///         thread::spawn(move || {
+///             let bars = client.realtime_bars(&Contract::stock(symbol), BarSize::Sec5, WhatToShow::Trades, false).expect("request failed");
+///             for bar in bars.take(5) {
+///                 println!("{symbol}: {bar:?}");
+///             }
+///         })
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     handle.join().expect("subscription thread panicked");
+/// }
+/// ```
+#[derive(Clone)]
 pub struct Client {
     /// IB server version
     pub(crate) server_version: i32,
-    /// IB Server time
-    //    pub server_time: OffsetDateTime,
-    pub(crate) connection_time: OffsetDateTime,
+    /// Time of the server when the client connected, None when the time sent in the handshake couldn't be parsed
+    pub(crate) connection_time: Option<OffsetDateTime>,
+    /// Time of the server when the client connected, as sent in the handshake
+    connection_time_raw: String,
     pub(crate) time_zone: &'static Tz,
 
     managed_accounts: String,
-    client_id: i32, // ID of client.
-    pub(crate) message_bus: RefCell<Box<dyn MessageBus>>,
-    next_request_id: AtomicI32, // Next available request_id.
-    order_id: AtomicI32,        // Next available order_id. Starts with value returned on connection.
+    managed_accounts_list: Vec<String>, // managed_accounts split into account codes
+    client_id: i32,                     // ID of client.
+    optional_capabilities: String,      // Optional capabilities sent when starting the API.
+    version_range: (i32, i32),          // Range of server versions offered in the handshake.
+    pub(crate) message_bus: SharedMessageBus,
+    next_request_id: Arc<AtomicI32>,  // Next available request_id.
+    order_id: Arc<AtomicI32>,         // Next available order_id. Starts with value returned on connection, shared with message bus.
+    market_data_type: Arc<AtomicI32>, // Market data type requested by set_market_data_type.
+
+    // Active market data and market depth subscriptions. Subscriptions beyond max_market_data_lines are refused before being sent.
+    market_data_lines: Arc<AtomicUsize>,
+    pub(crate) max_market_data_lines: Option<usize>,
+}
+
+/// Message sent by TWS that is not associated with any request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notification {
+    /// Informational system message, e.g. 2104 market data farm connection is OK.
+    Info { code: i32, message: String },
+    /// System error, e.g. 1100 connectivity between IB and TWS has been lost.
+    Error {
+        code: i32,
+        message: String,
+        /// Details of an order rejection, sent by servers supporting advanced order rejects.
+        advanced_order_reject: Option<AdvancedOrderReject>,
+    },
+}
+
+impl Notification {
+    pub(crate) fn new(code: i32, message: String, advanced_order_reject: Option<AdvancedOrderReject>) -> Self {
+        if (2100..=2200).contains(&code) {
+            Notification::Info { code, message }
+        } else {
+            Notification::Error {
+                code,
+                message,
+                advanced_order_reject,
+            }
+        }
+    }
 }
 
 impl Client {
@@ -62,55 +145,121 @@ impl Client {
     /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
     ///
     /// println!("server_version: {}", client.server_version());
-    /// println!("connection_time: {}", client.connection_time());
+    /// println!("connection_time: {:?}", client.connection_time());
     /// println!("managed_accounts: {}", client.managed_accounts());
     /// println!("next_order_id: {}", client.next_order_id());
     /// ```
     pub fn connect(address: &str, client_id: i32) -> Result<Client, Error> {
-        let message_bus = RefCell::new(Box::new(TcpMessageBus::connect(address)?));
-        Client::do_connect(client_id, message_bus)
+        Client::builder().address(address).client_id(client_id).connect()
     }
 
-    fn do_connect(client_id: i32, message_bus: RefCell<Box<dyn MessageBus>>) -> Result<Client, Error> {
+    /// Returns a [ClientBuilder] for configuring connection options such as the connect timeout and optional capabilities.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::builder()
+    ///     .address("127.0.0.1:4002")
+    ///     .client_id(100)
+    ///     .optional_capabilities("+PACEAPI")
+    ///     .connect()
+    ///     .expect("connection failed");
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Creates a client that replays a session captured with `IBAPI_RECORDING_DIR`.
+    ///
+    /// Recorded responses are returned in their original order and every request sent by the client must match the recorded request.
+    /// This turns a captured live session into a deterministic regression test.
+    ///
+    /// # Arguments
+    /// * `recording_dir` - directory containing the recorded `NNNN-request.msg` and `NNNN-response.msg` files.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect_replay("/tmp/records/2024-03-15-09-30").expect("replay failed");
+    ///
+    /// println!("server_version: {}", client.server_version());
+    /// println!("managed_accounts: {}", client.managed_accounts());
+    /// ```
+    pub fn connect_replay(recording_dir: &str) -> Result<Client, Error> {
+        let replay = ReplayMessageBus::open(recording_dir)?;
+
+        let client_id = match replay.client_id() {
+            Some(client_id) => client_id,
+            None => return Err(Error::Simple(format!("no start api request recorded in {recording_dir}"))),
+        };
+
+        Client::builder().client_id(client_id).message_bus(Box::new(replay)).connect()
+    }
+
+    fn do_connect(client_id: i32, optional_capabilities: &str, version_range: (i32, i32), message_bus: Box<dyn MessageBus>) -> Result<Client, Error> {
+        let order_id = message_bus.order_ids();
+        let message_bus = SharedMessageBus::new(message_bus);
+
         let mut client = Client {
             server_version: 0,
-            connection_time: OffsetDateTime::now_utc(),
+            connection_time: None,
+            connection_time_raw: String::default(),
             time_zone: time_tz::timezones::db::UTC,
             managed_accounts: String::from(""),
+            managed_accounts_list: Vec::default(),
             message_bus,
             client_id,
-            next_request_id: AtomicI32::new(9000),
-            order_id: AtomicI32::new(-1),
+            optional_capabilities: optional_capabilities.to_owned(),
+            version_range,
+            next_request_id: Arc::new(AtomicI32::new(INITIAL_REQUEST_ID)),
+            order_id,
+            market_data_type: Arc::new(AtomicI32::new(MarketDataType::Live as i32)),
+            market_data_lines: Arc::new(AtomicUsize::new(0)),
+            max_market_data_lines: None,
         };
 
         client.handshake()?;
         client.start_api()?;
         client.receive_account_info()?;
 
-        client.message_bus.borrow_mut().process_messages(client.server_version)?;
+        client.message_bus.lock().process_messages(client.server_version)?;
 
         Ok(client)
     }
 
     // sends server handshake
     fn handshake(&mut self) -> Result<(), Error> {
-        let prefix = "API\0";
-        let version = format!("v{MIN_SERVER_VERSION}..{MAX_SERVER_VERSION}");
+        let (min_version, max_version) = self.version_range;
+        self.message_bus.lock().write(&encode_handshake(min_version, max_version))?;
 
-        let packet = prefix.to_owned() + &encode_packet(&version);
-        self.message_bus.borrow_mut().write(&packet)?;
-
-        let ack = self.message_bus.borrow_mut().read_message();
+        let ack = self.message_bus.lock().read_message();
 
         match ack {
             Ok(mut response_message) => {
                 self.server_version = response_message.next_int()?;
+                debug!(
+                    "server version: {} ({})",
+                    self.server_version,
+                    server_versions::name_of(self.server_version).unwrap_or("unknown")
+                );
 
                 let time = response_message.next_string()?;
-                (self.connection_time, self.time_zone) = parse_connection_time(time.as_str());
+                match parse_connection_time(&time) {
+                    Some((connection_time, time_zone)) => {
+                        self.connection_time = Some(connection_time);
+                        self.time_zone = time_zone;
+                    }
+                    None => warn!("unable to parse connection time: {time}"),
+                }
+                self.connection_time_raw = time;
             }
             Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
-                return Err(Error::Simple(format!("The server may be rejecting connections from this host: {err}")));
+                error!("error reading handshake: {err}");
+                return Err(Error::ConnectionFailed);
             }
             Err(err) => {
                 return Err(err);
@@ -121,21 +270,9 @@ impl Client {
 
     // asks server to start processing messages
     fn start_api(&mut self) -> Result<(), Error> {
-        const VERSION: i32 = 2;
-
-        let prelude = &mut RequestMessage::default();
-
-        prelude.push_field(&OutgoingMessages::StartApi);
-        prelude.push_field(&VERSION);
-        prelude.push_field(&self.client_id);
-
-        if self.server_version > server_versions::OPTIONAL_CAPABILITIES {
-            prelude.push_field(&"");
-        }
-
-        self.message_bus.borrow_mut().write_message(prelude)?;
-
-        Ok(())
+        self.message_bus
+            .lock()
+            .start_api(self.server_version, self.client_id, &self.optional_capabilities)
     }
 
     // Fetches next order id and managed accounts.
@@ -146,7 +283,7 @@ impl Client {
         let mut attempts = 0;
         const MAX_ATTEMPTS: i32 = 100;
         loop {
-            let mut message = self.message_bus.borrow_mut().read_message()?;
+            let mut message = self.message_bus.lock().read_message()?;
 
             match message.message_type() {
                 IncomingMessages::NextValidId => {
@@ -164,6 +301,7 @@ impl Client {
                     message.skip(); // message version
 
                     self.managed_accounts = message.next_string()?;
+                    self.managed_accounts_list = parse_managed_accounts(&self.managed_accounts);
                 }
                 IncomingMessages::Error => {
                     error!("message: {message:?}")
@@ -181,27 +319,208 @@ impl Client {
     }
 
     /// Returns the next request ID.
+    ///
+    /// Request IDs are allocated by the client for every request based API, so callers never need to pick one.
+    /// They do not share a space with order IDs, see [Client::next_order_id].
     pub fn next_request_id(&self) -> i32 {
-        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+        self.next_request_id.fetch_add(1, Ordering::SeqCst)
     }
 
     /// Returns and increments the order ID.
+    ///
+    /// The order ID is seeded by the server at connection time and refreshed whenever the server sends a new next valid order ID.
+    /// See [Client::next_valid_order_id] to request a new value from the server.
     pub fn next_order_id(&self) -> i32 {
-        self.order_id.fetch_add(1, Ordering::Relaxed)
+        self.order_id.fetch_add(1, Ordering::SeqCst)
     }
 
     /// Sets the current value of order ID.
     pub(crate) fn set_next_order_id(&self, order_id: i32) {
-        self.order_id.store(order_id, Ordering::Relaxed)
+        self.order_id.store(order_id, Ordering::SeqCst)
     }
 
     pub fn server_version(&self) -> i32 {
         self.server_version
     }
 
-    /// The time of the server when the client connected
-    pub fn connection_time(&self) -> &OffsetDateTime {
-        &self.connection_time
+    /// Returns true if the server connected to supports the feature.
+    ///
+    /// Requests for unsupported features fail with [Error::ServerVersion].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::client::Feature;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// if !client.supports(Feature::TickByTick) {
+    ///     println!("tick by tick data requires a newer version of TWS");
+    /// }
+    /// ```
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.server_version() <= self.server_version
+    }
+
+    /// Returns the client id sent to the server when starting the API.
+    ///
+    /// The id identifies the client to TWS, e.g. for binding orders. Connect with [Client::connect] or [ClientBuilder::client_id] to use another id.
+    pub fn client_id(&self) -> i32 {
+        self.client_id
+    }
+
+    /// Returns the range of server versions offered to the server in the handshake.
+    ///
+    /// The server picks the version used for the connection from this range, see [Client::server_version].
+    /// Defaults to the versions supported by this crate, see [ClientBuilder::version_range] to offer a narrower range.
+    pub fn client_version_range(&self) -> (i32, i32) {
+        self.version_range
+    }
+
+    /// Returns the optional capabilities sent to the server when starting the API, see [ClientBuilder::optional_capabilities].
+    pub fn optional_capabilities(&self) -> &str {
+        &self.optional_capabilities
+    }
+
+    /// The time of the server when the client connected.
+    ///
+    /// Returns None when the time sent by the server couldn't be parsed, see [Client::connection_time_raw].
+    pub fn connection_time(&self) -> Option<OffsetDateTime> {
+        self.connection_time
+    }
+
+    /// The time of the server when the client connected, as sent by the server, e.g. 20230224 12:04:56 EST
+    pub fn connection_time_raw(&self) -> &str {
+        &self.connection_time_raw
+    }
+
+    /// Requests the current time of the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let server_time = client.server_time().expect("error requesting server time");
+    /// println!("server time: {server_time}");
+    /// ```
+    pub fn server_time(&self) -> Result<OffsetDateTime, Error> {
+        let mut messages = self.request_current_time(transport::encode_request_current_time())?;
+
+        match messages.next() {
+            Some(mut message) => {
+                message.skip(); // message type
+                message.skip(); // message version
+
+                message.next_date_time()
+            }
+            None => Err(Error::Simple("no response from server".into())),
+        }
+    }
+
+    /// Returns the state of the connection to TWS or Gateway.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::client::ConnectionState;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// if client.connection_state() == ConnectionState::Reconnecting {
+    ///     println!("waiting for connection to be re-established");
+    /// }
+    /// ```
+    pub fn connection_state(&self) -> ConnectionState {
+        self.message_bus.lock().connection_state()
+    }
+
+    /// Returns true when messages are exchanged with the server, see [Client::connection_state].
+    pub fn is_connected(&self) -> bool {
+        self.connection_state() == ConnectionState::Connected
+    }
+
+    /// Cancels all open orders once the connection to TWS is re-established after it was lost, a dead man's switch for orders
+    /// that should not keep working unattended. Disabled by default.
+    ///
+    /// Orders keep working while the connection is down and are only cancelled after a reconnect. They are not cancelled when
+    /// reconnecting fails. To have orders cancelled as soon as the connection drops, enable the corresponding precaution in the
+    /// API settings of TWS or Gateway. As with [Client::global_cancel], orders placed from TWS or other clients are cancelled too.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    /// client.set_cancel_orders_on_disconnect(true).expect("request failed");
+    /// ```
+    pub fn set_cancel_orders_on_disconnect(&self, enabled: bool) -> Result<(), Error> {
+        if enabled {
+            self.check_server_version(server_versions::REQ_GLOBAL_CANCEL, "It does not support global cancel requests.")?;
+        }
+
+        self.message_bus.lock().set_cancel_orders_on_disconnect(enabled);
+        Ok(())
+    }
+
+    /// Returns false when the server stopped answering heartbeats, see [ClientBuilder::heartbeat].
+    /// The connection is re-established and reported healthy again once the server responds.
+    pub fn is_connection_healthy(&self) -> bool {
+        self.message_bus.lock().is_healthy()
+    }
+
+    /// Registers an observer notified of each message sent to and received from the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use ibapi::client::{MessageObserver, RequestMessage, ResponseMessage};
+    /// use ibapi::Client;
+    ///
+    /// #[derive(Default)]
+    /// struct MessageCounter(AtomicUsize);
+    ///
+    /// impl MessageObserver for MessageCounter {
+    ///     fn on_request(&self, _request_id: Option<i32>, _message: &RequestMessage) {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    ///
+    ///     fn on_response(&self, _message: &ResponseMessage) {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    /// client.add_observer(Arc::new(MessageCounter::default()));
+    /// ```
+    pub fn add_observer(&self, observer: Arc<dyn MessageObserver>) {
+        self.message_bus.lock().add_observer(observer);
+    }
+
+    /// Returns the number of responses received per message type since connecting, including those nobody was waiting for.
+    /// Helps to tell whether an expected message ever arrived when a subscription seems stuck.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::client::IncomingMessages;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let stats = client.message_stats();
+    /// println!("open order ends received: {}", stats.get(&IncomingMessages::OpenOrderEnd).unwrap_or(&0));
+    /// ```
+    pub fn message_stats(&self) -> HashMap<IncomingMessages, u64> {
+        self.message_bus.lock().message_stats()
     }
 
     /// Returns the managed accounts.
@@ -209,74 +528,467 @@ impl Client {
         self.managed_accounts.to_owned()
     }
 
+    /// Returns the account codes of the managed accounts, received when the client connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// for account in client.managed_accounts_list() {
+    ///     println!("account: {account}");
+    /// }
+    /// ```
+    pub fn managed_accounts_list(&self) -> Vec<String> {
+        self.managed_accounts_list.clone()
+    }
+
+    /// Returns a channel of [Notification]s sent by TWS that are not associated with a request.
+    ///
+    /// Notifications are delivered to a single receiver. Cloned receivers compete for messages.
+    /// Up to 1024 notifications are kept while nobody reads them, the oldest are dropped beyond that.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// for notification in client.notifications() {
+    ///     println!("{notification:?}");
+    /// }
+    /// ```
+    pub fn notifications(&self) -> Receiver<Notification> {
+        self.message_bus.lock().notifications()
+    }
+
     // === Accounts ===
 
     /// Get current [Position]s for all accessible accounts.
     #[allow(clippy::needless_lifetimes)]
-    pub fn positions<'a>(&'a self) -> core::result::Result<impl Iterator<Item = Position> + 'a, Error> {
+    pub fn positions<'a>(&'a self) -> core::result::Result<impl Subscription<Item = Position> + 'a, Error> {
         accounts::positions(self)
     }
 
-    // === Contracts ===
-
-    /// Requests contract information.
-    ///
-    /// Provides all the contracts matching the contract provided. It can also be used to retrieve complete options and futures chains. Though it is now (in API version > 9.72.12) advised to use reqSecDefOptParams for that purpose.
+    /// Requests a summary of account values for all accounts in the given group.
+    ///
+    /// Values are sent once and the iterator ends after all values have been received.
+    ///
+    /// # Arguments
+    /// * `group` - Set to "All" to return account summary data for all accounts, or set to a specific Advisor Account Group name that has already been created in TWS Global Configuration.
+    /// * `tags` - List of [AccountSummaryTags] to request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::accounts::AccountSummaryTags;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let tags = &[AccountSummaryTags::NetLiquidation, AccountSummaryTags::BuyingPower];
+    /// let summaries = client.account_summary("All", tags).expect("request failed");
+    ///
+    /// for summary in summaries {
+    ///     println!("{summary:?}");
+    /// }
+    /// ```
+    pub fn account_summary<'a>(&'a self, group: &str, tags: &[AccountSummaryTags]) -> Result<impl Subscription<Item = AccountSummary> + 'a, Error> {
+        accounts::account_summary(self, group, tags)
+    }
+
+    /// Subscribes to the account values and portfolio positions of an account.
+    ///
+    /// All values and positions are sent initially, followed by [AccountUpdate::End]. Afterwards updates are sent as values change.
+    /// Updates are streamed until the iterator is dropped, which cancels the subscription.
+    ///
+    /// # Arguments
+    /// * `account` - Account to subscribe to, e.g. DU1234567.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::accounts::AccountUpdate;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let updates = client.account_updates("DU1234567").expect("request failed");
+    ///
+    /// for update in updates {
+    ///     match update {
+    ///         AccountUpdate::AccountValue(value) => println!("{}: {} {}", value.key, value.value, value.currency),
+    ///         AccountUpdate::PortfolioValue(position) => println!("{}: {}", position.contract.symbol, position.position),
+    ///         AccountUpdate::UpdateTime(time) => println!("updated at {time}"),
+    ///         AccountUpdate::End => println!("account download complete"),
+    ///     }
+    /// }
+    /// ```
+    pub fn account_updates<'a>(&'a self, account: &str) -> Result<impl Subscription<Item = AccountUpdate> + 'a, Error> {
+        accounts::account_updates(self, account)
+    }
+
+    // === Contracts ===
+
+    /// Requests contract information.
+    ///
+    /// Provides all the contracts matching the contract provided. It can also be used to retrieve complete options and futures chains. Though it is now (in API version > 9.72.12) advised to use reqSecDefOptParams for that purpose.
+    ///
+    /// # Arguments
+    /// * `contract` - The [Contract] used as sample to query the available contracts. Typically, it will contain the [Contract]'s symbol, currency, security_type, and exchange.
+    ///
+    /// Fails with [Error::Incomplete], holding the contracts received so far, if the server does not complete the response within 30 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("TSLA");
+    /// let results = client.contract_details(&contract).expect("request failed");
+    /// for contract_detail in results {
+    ///     println!("contract: {:?}", contract_detail);
+    /// }
+    /// ```
+    pub fn contract_details(&self, contract: &Contract) -> Result<impl Iterator<Item = contracts::ContractDetails>, Error> {
+        Ok(contracts::contract_details(self, contract)?.into_iter())
+    }
+
+    /// Requests contract information for several contracts at once.
+    ///
+    /// All requests are sent before any response is read, so resolving many contracts takes about as long as the slowest request
+    /// instead of the sum of all requests. Returns the result for each of the contracts provided, in the same order.
+    ///
+    /// A request rejected by TWS yields [Error::Api] for its contract only. A request not completed within 30 seconds
+    /// yields [Error::Incomplete] with the contracts received so far. Fails as a whole only if the requests cannot be sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contracts = [Contract::stock("AAPL"), Contract::stock("MSFT"), Contract::stock("TSLA")];
+    /// let results = client.contract_details_batch(&contracts).expect("request failed");
+    /// for (contract, result) in contracts.iter().zip(results) {
+    ///     match result {
+    ///         Ok(details) => println!("{}: {} contracts", contract.symbol, details.len()),
+    ///         Err(err) => println!("{}: {err}", contract.symbol),
+    ///     }
+    /// }
+    /// ```
+    pub fn contract_details_batch(&self, contracts: &[Contract]) -> Result<Vec<Result<Vec<contracts::ContractDetails>, Error>>, Error> {
+        contracts::contract_details_batch(self, contracts)
+    }
+
+    /// Requests the contract details for a contract id, e.g. one reported with a position or execution.
+    ///
+    /// Returns an error unless exactly one contract matches.
+    ///
+    /// # Arguments
+    /// * `contract_id` - The unique IB contract identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let details = client.contract_details_by_contract_id(76792991).expect("request failed");
+    /// println!("contract: {:?}", details.contract);
+    /// ```
+    pub fn contract_details_by_contract_id(&self, contract_id: i32) -> Result<contracts::ContractDetails, Error> {
+        contracts::contract_details_by_contract_id(self, contract_id)
+    }
+
+    /// Requests the contract details of the single contract matching a partially specified contract.
+    ///
+    /// Returns [Error::AmbiguousContract] with the matching contracts when more than one contract matches,
+    /// e.g. a symbol listed in several currencies.
+    ///
+    /// # Arguments
+    /// * `contract` - The [Contract] to resolve. Typically, it will contain the [Contract]'s symbol, currency, security_type, and exchange.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("TSLA");
+    /// let details = client.resolve_contract(&contract).expect("request failed");
+    /// println!("contract id: {}", details.contract.contract_id);
+    /// ```
+    pub fn resolve_contract(&self, contract: &Contract) -> Result<contracts::ContractDetails, Error> {
+        contracts::resolve_contract(self, contract)
+    }
+
+    /// Subscribes to daily profit and loss updates for an account.
+    ///
+    /// # Arguments
+    /// * `account`    - account for which to receive PnL updates
+    /// * `model_code` - specify to request PnL updates for a specific model
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let updates = client.pnl("DU1234567", None).expect("request failed");
+    ///
+    /// for pnl in updates.take(5) {
+    ///     println!("{pnl:?}");
+    /// }
+    /// ```
+    pub fn pnl<'a>(&'a self, account: &str, model_code: Option<&str>) -> Result<impl Subscription<Item = PnL> + 'a, Error> {
+        accounts::pnl(self, account, model_code)
+    }
+
+    /// Subscribes to daily profit and loss updates for a single position.
+    ///
+    /// # Arguments
+    /// * `account`     - account in which the position exists
+    /// * `contract_id` - contract ID of the position
+    /// * `model_code`  - model in which the position exists
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let updates = client.pnl_single("DU1234567", 265598, None).expect("request failed");
+    ///
+    /// for pnl in updates.take(5) {
+    ///     println!("{pnl:?}");
+    /// }
+    /// ```
+    pub fn pnl_single<'a>(
+        &'a self,
+        account: &str,
+        contract_id: i32,
+        model_code: Option<&str>,
+    ) -> Result<impl Subscription<Item = PnLSingle> + 'a, Error> {
+        accounts::pnl_single(self, account, contract_id, model_code)
+    }
+
+    /// Get current [FamilyCode]s for all accessible accounts.
+    pub fn family_codes(&self) -> Result<Vec<FamilyCode>, Error> {
+        accounts::family_codes(self)
+    }
+
+    /// Requests the XML configuration of the groups, profiles or aliases of a financial advisor account.
+    ///
+    /// # Arguments
+    /// * `fa_data_type` - The [FaDataType] of the configuration to request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::accounts::FaDataType;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let groups = client.request_fa(FaDataType::Groups).expect("request failed");
+    /// println!("{groups}");
+    /// ```
+    pub fn request_fa(&self, fa_data_type: FaDataType) -> Result<String, Error> {
+        accounts::request_fa(self, fa_data_type)
+    }
+
+    /// Replaces the groups, profiles or aliases of a financial advisor account with an XML configuration.
+    ///
+    /// # Arguments
+    /// * `fa_data_type` - The [FaDataType] of the configuration to replace.
+    /// * `xml` - The new configuration, in the format returned by [Client::request_fa].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::accounts::FaDataType;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let groups = client.request_fa(FaDataType::Groups).expect("request failed");
+    /// client.replace_fa(FaDataType::Groups, &groups).expect("request failed");
+    /// ```
+    pub fn replace_fa(&self, fa_data_type: FaDataType, xml: &str) -> Result<(), Error> {
+        accounts::replace_fa(self, fa_data_type, xml)
+    }
+
+    /// Requests details about a given market rule
+    ///
+    /// The market rule for an instrument on a particular exchange provides details about how the minimum price increment changes with price.
+    /// A list of market rule ids can be obtained by invoking [Self::contract_details()] for a particular contract.
+    /// The returned market rule ID list will provide the market rule ID for the instrument in the correspond valid exchange list in [contracts::ContractDetails].
+    pub fn market_rule(&self, market_rule_id: i32) -> Result<contracts::MarketRule, Error> {
+        contracts::market_rule(self, market_rule_id)
+    }
+
+    /// Requests matching stock symbols.
+    ///
+    /// # Arguments
+    /// * `pattern` - Either start of ticker symbol or (for larger strings) company name.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contracts = client.matching_symbols("IB").expect("request failed");
+    /// for contract in contracts {
+    ///     println!("contract: {:?}", contract);
+    /// }
+    /// ```
+    pub fn matching_symbols(&self, pattern: &str) -> Result<impl Iterator<Item = contracts::ContractDescription>, Error> {
+        Ok(contracts::matching_symbols(self, pattern)?.into_iter())
+    }
+
+    /// Requests security definition option parameters for viewing a contract's option chain.
     ///
     /// # Arguments
-    /// * `contract` - The [Contract] used as sample to query the available contracts. Typically, it will contain the [Contract]'s symbol, currency, security_type, and exchange.
+    /// * `symbol`                 - Underlying symbol.
+    /// * `exchange`               - The exchange on which the returned options are trading. Can be set to the empty string for all exchanges.
+    /// * `security_type`          - The type of the underlying security, i.e. STK
+    /// * `underlying_contract_id` - The contract ID of the underlying security.
+    ///
+    /// Fails with [Error::Incomplete], holding the option chains received so far, if the server does not complete the response within 30 seconds.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use ibapi::Client;
-    /// use ibapi::contracts::Contract;
+    /// use ibapi::contracts::SecurityType;
     ///
     /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
     ///
-    /// let contract = Contract::stock("TSLA");
-    /// let results = client.contract_details(&contract).expect("request failed");
-    /// for contract_detail in results {
-    ///     println!("contract: {:?}", contract_detail);
+    /// let option_chains = client.option_chains("AAPL", "", SecurityType::Stock, 265598).expect("request failed");
+    /// for option_chain in option_chains {
+    ///     println!("{option_chain:?}");
     /// }
     /// ```
-    pub fn contract_details(&self, contract: &Contract) -> Result<impl Iterator<Item = contracts::ContractDetails>, Error> {
-        Ok(contracts::contract_details(self, contract)?.into_iter())
+    pub fn option_chains(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        security_type: contracts::SecurityType,
+        underlying_contract_id: i32,
+    ) -> Result<impl Iterator<Item = contracts::OptionChain>, Error> {
+        Ok(contracts::option_chains(self, symbol, exchange, security_type, underlying_contract_id)?.into_iter())
     }
 
-    /// Get current [FamilyCode]s for all accessible accounts.
-    pub fn family_codes(&self) -> Result<Vec<FamilyCode>, Error> {
-        accounts::family_codes(self)
+    /// Calculates an option's implied volatility based on the specified option and underlying prices.
+    ///
+    /// # Arguments
+    /// * `contract`         - The option [Contract] for which the implied volatility is calculated.
+    /// * `option_price`     - The option price.
+    /// * `underlying_price` - The price of the underlying.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::{Contract, SecurityType};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract {
+    ///     symbol: "AAPL".to_string(),
+    ///     security_type: SecurityType::Option,
+    ///     last_trade_date_or_contract_month: "20240621".to_string(),
+    ///     strike: 180.0,
+    ///     right: "C".to_string(),
+    ///     exchange: "SMART".to_string(),
+    ///     currency: "USD".to_string(),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let computation = client.calculate_implied_volatility(&contract, 5.40, 185.50).expect("request failed");
+    /// println!("{computation:?}");
+    /// ```
+    pub fn calculate_implied_volatility(
+        &self,
+        contract: &Contract,
+        option_price: f64,
+        underlying_price: f64,
+    ) -> Result<contracts::OptionComputation, Error> {
+        contracts::calculate_implied_volatility(self, contract, option_price, underlying_price)
     }
 
-    /// Requests details about a given market rule
+    /// Calculates an option's price based on the specified volatility and underlying price.
     ///
-    /// The market rule for an instrument on a particular exchange provides details about how the minimum price increment changes with price.
-    /// A list of market rule ids can be obtained by invoking [Self::contract_details()] for a particular contract.
-    /// The returned market rule ID list will provide the market rule ID for the instrument in the correspond valid exchange list in [contracts::ContractDetails].
-    pub fn market_rule(&self, market_rule_id: i32) -> Result<contracts::MarketRule, Error> {
-        contracts::market_rule(self, market_rule_id)
+    /// # Arguments
+    /// * `contract`         - The option [Contract] for which the price is calculated.
+    /// * `volatility`       - The volatility of the option.
+    /// * `underlying_price` - The price of the underlying.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::{Contract, SecurityType};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract {
+    ///     symbol: "AAPL".to_string(),
+    ///     security_type: SecurityType::Option,
+    ///     last_trade_date_or_contract_month: "20240621".to_string(),
+    ///     strike: 180.0,
+    ///     right: "C".to_string(),
+    ///     exchange: "SMART".to_string(),
+    ///     currency: "USD".to_string(),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let computation = client.calculate_option_price(&contract, 0.25, 185.50).expect("request failed");
+    /// println!("{computation:?}");
+    /// ```
+    pub fn calculate_option_price(&self, contract: &Contract, volatility: f64, underlying_price: f64) -> Result<contracts::OptionComputation, Error> {
+        contracts::calculate_option_price(self, contract, volatility, underlying_price)
     }
 
-    /// Requests matching stock symbols.
+    /// Requests a fundamental data report for a contract.
+    ///
+    /// The report is returned as the raw XML sent by TWS. Parsing it is left to the caller.
     ///
     /// # Arguments
-    /// * `pattern` - Either start of ticker symbol or (for larger strings) company name.
+    /// * `contract`    - The [Contract] for which the report is requested.
+    /// * `report_type` - The [contracts::FundamentalReport] to request.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use ibapi::Client;
+    /// use ibapi::contracts::{Contract, FundamentalReport};
     ///
     /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
     ///
-    /// let contracts = client.matching_symbols("IB").expect("request failed");
-    /// for contract in contracts {
-    ///     println!("contract: {:?}", contract);
-    /// }
+    /// let contract = Contract::stock("AAPL");
+    ///
+    /// let report = client.fundamental_data(&contract, FundamentalReport::ReportSnapshot).expect("request failed");
+    /// println!("{report}");
     /// ```
-    pub fn matching_symbols(&self, pattern: &str) -> Result<impl Iterator<Item = contracts::ContractDescription>, Error> {
-        Ok(contracts::matching_symbols(self, pattern)?.into_iter())
+    pub fn fundamental_data(&self, contract: &Contract, report_type: contracts::FundamentalReport) -> Result<String, Error> {
+        contracts::fundamental_data(self, contract, report_type)
     }
 
     // === Orders ===
@@ -325,7 +1037,7 @@ impl Client {
     ///
     /// # Arguments
     /// * `order_id` - ID of [Order] to cancel.
-    /// * `manual_order_cancel_time` - can't find documentation. leave blank.
+    /// * `manual_order_cancel_time` - Time the cancellation was decided manually. Leave blank for automated cancellations.
     ///
     /// # Examples
     ///
@@ -341,7 +1053,45 @@ impl Client {
     /// }
     /// ```
     pub fn cancel_order(&self, order_id: i32, manual_order_cancel_time: &str) -> Result<impl Iterator<Item = orders::CancelOrderResult>, Error> {
-        orders::cancel_order(self, order_id, manual_order_cancel_time)
+        let order_cancel = orders::OrderCancel {
+            manual_order_cancel_time: manual_order_cancel_time.to_owned(),
+            ..orders::OrderCancel::default()
+        };
+        orders::cancel_order(self, order_id, &order_cancel)
+    }
+
+    /// Cancels an open [Order], recording who cancelled it and whether the cancellation was manual.
+    ///
+    /// Fields of [orders::OrderCancel] are only sent to server versions supporting them.
+    ///
+    /// # Arguments
+    /// * `order_id` - ID of [Order] to cancel.
+    /// * `order_cancel` - Attributes of the cancellation.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::orders::OrderCancel;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let order_cancel = OrderCancel {
+    ///     ext_operator: "trader-7".to_owned(),
+    ///     manual_order_indicator: Some(1),
+    ///     ..OrderCancel::default()
+    /// };
+    /// let results = client.cancel_order_with(15, &order_cancel).expect("request failed");
+    /// for result in results {
+    ///    println!("{result:?}");
+    /// }
+    /// ```
+    pub fn cancel_order_with(
+        &self,
+        order_id: i32,
+        order_cancel: &orders::OrderCancel,
+    ) -> Result<impl Iterator<Item = orders::CancelOrderResult>, Error> {
+        orders::cancel_order(self, order_id, order_cancel)
     }
 
     /// Requests completed [Order]s.
@@ -411,7 +1161,7 @@ impl Client {
         orders::global_cancel(self)
     }
 
-    /// Cancels all open [Order]s.
+    /// Requests the next valid order id from the server and resets [Client::next_order_id] to it.
     ///
     /// # Examples
     ///
@@ -427,6 +1177,27 @@ impl Client {
         orders::next_valid_order_id(self)
     }
 
+    /// Requests a new pool of valid order ids (reqIds) and resets [Client::next_order_id] to the first id of it.
+    ///
+    /// Returns the next valid order id sent by the server.
+    ///
+    /// # Arguments
+    /// * `num_ids` - Number of order ids requested. Ignored by TWS, which always replies with the next valid id.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let next_valid_order_id = client.request_ids(1).expect("request failed");
+    /// assert_eq!(client.next_order_id(), next_valid_order_id);
+    /// ```
+    pub fn request_ids(&self, num_ids: i32) -> Result<i32, Error> {
+        orders::request_ids(self, num_ids)
+    }
+
     /// Requests all open orders places by this specific API client (identified by the API client id).
     /// For client ID 0, this will bind previous manual TWS orders.
     ///
@@ -487,6 +1258,179 @@ impl Client {
         orders::place_order(self, order_id, contract, order)
     }
 
+    /// Submits an [Order] after checking its limit, aux and trail stop prices are multiples of the contract's minimum tick.
+    ///
+    /// Misaligned prices are rejected with [Error::InvalidPrice], carrying the nearest valid price, instead of a round trip to TWS.
+    ///
+    /// # Arguments
+    /// * `order_id`         - ID for the order.
+    /// * `contract_details` - [contracts::ContractDetails] of the contract to place the order for, as returned by [Client::contract_details].
+    /// * `order`            - [Order] to submit.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::orders::{order_builder, Action};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    /// let contract_details = client.contract_details(&contract).expect("request failed").next().expect("contract not found");
+    ///
+    /// let order = order_builder::limit_order(Action::Buy, 100.0, 300.005);
+    /// let order_id = client.next_order_id();
+    ///
+    /// match client.place_order_validated(order_id, &contract_details, &order) {
+    ///     Ok(notifications) => notifications.for_each(|notification| println!("{notification:?}")),
+    ///     Err(err) => println!("order rejected: {err}"),
+    /// }
+    /// ```
+    pub fn place_order_validated(
+        &self,
+        order_id: i32,
+        contract_details: &contracts::ContractDetails,
+        order: &Order,
+    ) -> Result<impl Iterator<Item = OrderNotification>, Error> {
+        orders::place_order_validated(self, order_id, contract_details, order)
+    }
+
+    /// Submits a bracket order: a parent limit order with attached take profit and stop loss orders.
+    ///
+    /// Orders are assigned sequential order IDs from [Client::next_order_id]. Only the stop loss order is transmitted,
+    /// which activates the parent and take profit orders. Returns notifications for the parent, take profit and stop loss orders, in that order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::orders::Action;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    /// let notifications = client
+    ///     .place_bracket_order(&contract, Action::Buy, 100.0, 300.0, 310.0, 295.0)
+    ///     .expect("request failed");
+    ///
+    /// for notification in notifications.into_iter().flatten() {
+    ///     println!("{notification:?}");
+    /// }
+    /// ```
+    pub fn place_bracket_order(
+        &self,
+        contract: &Contract,
+        action: orders::Action,
+        quantity: f64,
+        limit_price: f64,
+        take_profit_limit_price: f64,
+        stop_loss_price: f64,
+    ) -> Result<Vec<impl Iterator<Item = OrderNotification>>, Error> {
+        let orders = orders::order_builder::bracket_order(0, action, quantity, limit_price, take_profit_limit_price, stop_loss_price);
+        orders::place_bracket_order(self, contract, orders)
+    }
+
+    /// Submits orders linked in a One-Cancels-All group. Execution of one order cancels or reduces the remaining orders.
+    ///
+    /// Orders are assigned sequential order IDs from [Client::next_order_id] and must already be assigned to a group, see [orders::order_builder::oca_group].
+    /// Returns notifications for each order, in the order given.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::orders::{order_builder, Action, OcaType};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    /// let mut orders = vec![
+    ///     order_builder::limit_order(Action::Sell, 100.0, 310.0),
+    ///     order_builder::limit_order(Action::Sell, 100.0, 315.0),
+    /// ];
+    /// order_builder::oca_group(&mut orders, "MSFT-exit", OcaType::CancelWithBlock);
+    ///
+    /// let notifications = client.place_oca_orders(&contract, orders).expect("request failed");
+    ///
+    /// for notification in notifications.into_iter().flatten() {
+    ///     println!("{notification:?}");
+    /// }
+    /// ```
+    pub fn place_oca_orders(&self, contract: &Contract, orders: Vec<Order>) -> Result<Vec<impl Iterator<Item = OrderNotification>>, Error> {
+        orders::place_oca_orders(self, contract, orders)
+    }
+
+    /// Modifies an open [Order] by re-submitting it with the same order ID.
+    ///
+    /// Notifications for the modified order keep arriving on the iterator returned by [Client::place_order], which must still
+    /// be alive. Returns an error once that iterator is dropped, or for orders placed before the client was restarted.
+    /// For partially filled orders `total_quantity` remains the size of the whole order, not the unfilled remainder.
+    ///
+    /// # Arguments
+    /// * `order_id` - ID of the order to modify.
+    /// * `contract` - [Contract] the order was placed for.
+    /// * `order`    - Updated [Order].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::orders::{order_builder, Action};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    /// let order = order_builder::limit_order(Action::Buy, 100.0, 300.0);
+    /// let order_id = client.next_order_id();
+    ///
+    /// let notifications = client.place_order(order_id, &contract, &order).expect("request failed");
+    ///
+    /// let order = order_builder::with_limit_price(&order, 301.0);
+    /// client.modify_order(order_id, &contract, &order).expect("request failed");
+    ///
+    /// for notification in notifications {
+    ///     println!("{notification:?}");
+    /// }
+    /// ```
+    pub fn modify_order(&self, order_id: i32, contract: &Contract, order: &Order) -> Result<(), Error> {
+        orders::modify_order(self, order_id, contract, order)
+    }
+
+    /// Previews the margin and commission impact of an order without placing it.
+    ///
+    /// Sends the order with [Order::what_if] set and returns the [OrderState] reported by TWS.
+    ///
+    /// # Arguments
+    /// * `order_id` - ID for the previewed order.
+    /// * `contract` - [Contract] to preview the order for.
+    /// * `order`    - [Order] to preview.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::orders::{order_builder, Action};
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    /// let order = order_builder::limit_order(Action::Buy, 100.0, 300.0);
+    /// let order_id = client.next_order_id();
+    ///
+    /// let order_state = client.what_if_order(order_id, &contract, &order).expect("request failed");
+    ///
+    /// println!("initial margin change: {:?}", order_state.initial_margin_change);
+    /// println!("commission: {:?} {}", order_state.commission, order_state.commission_currency);
+    /// ```
+    pub fn what_if_order(&self, order_id: i32, contract: &Contract, order: &Order) -> Result<OrderState, Error> {
+        orders::what_if_order(self, order_id, contract, order)
+    }
+
     // === Historical Market Data ===
 
     /// Returns the timestamp of earliest available historical data for a contract and data type.
@@ -509,6 +1453,34 @@ impl Client {
         historical::head_timestamp(self, contract, what_to_show, use_rth)
     }
 
+    /// Returns the number of trades at each price for a [Contract] over a period.
+    ///
+    /// # Arguments
+    /// * `contract` - [Contract] to retrieve the histogram for.
+    /// * `use_rth`  - use regular trading hours.
+    /// * `period`   - period to build the histogram over, e.g. 3 days.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::market_data::historical::ToDuration;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("MSFT");
+    ///
+    /// let entries = client.histogram_data(&contract, true, 3.days()).expect("histogram data request failed");
+    ///
+    /// for entry in &entries {
+    ///     println!("{}: {}", entry.price, entry.count);
+    /// }
+    /// ```
+    pub fn histogram_data(&self, contract: &Contract, use_rth: bool, period: historical::Duration) -> Result<Vec<historical::HistogramEntry>, Error> {
+        historical::histogram_data(self, contract, use_rth, period)
+    }
+
     /// Requests interval of historical data ending at specified time for [Contract].
     ///
     /// # Arguments
@@ -666,6 +1638,54 @@ impl Client {
         historical::historical_schedule(self, contract, None, duration)
     }
 
+    /// Requests historical time & sales data for an instrument, returning the [historical::HistoricalTick] variant for `what_to_show`.
+    ///
+    /// # Arguments
+    /// * `contract` - [Contract] object that is subject of query
+    /// * `start`    - Start time. Either start time or end time is specified.
+    /// * `end`      - End time. Either start time or end time is specified.
+    /// * `number_of_ticks` - Number of distinct data points. Max currently 1000 per request.
+    /// * `what_to_show`    - [historical::WhatToShow::Trades], [historical::WhatToShow::MidPoint] or [historical::WhatToShow::BidAsk].
+    /// * `use_rth`         - Data from regular trading hours (true), or all available hours (false)
+    /// * `ignore_size`     - A filter only used when the source price is Bid_Ask
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use time::macros::datetime;
+    ///
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::market_data::historical::{HistoricalTick, WhatToShow};
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("TSLA");
+    ///
+    /// let ticks = client
+    ///     .historical_ticks(&contract, Some(datetime!(2023-04-15 0:00 UTC)), None, 100, WhatToShow::Trades, true, false)
+    ///     .expect("historical ticks request failed");
+    ///
+    /// for tick in ticks {
+    ///     if let HistoricalTick::Last(trade) = tick {
+    ///         println!("{} {} @ {}", trade.timestamp, trade.size, trade.price);
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn historical_ticks(
+        &self,
+        contract: &Contract,
+        start: Option<OffsetDateTime>,
+        end: Option<OffsetDateTime>,
+        number_of_ticks: i32,
+        what_to_show: historical::WhatToShow,
+        use_rth: bool,
+        ignore_size: bool,
+    ) -> Result<Vec<historical::HistoricalTick>, Error> {
+        historical::historical_ticks(self, contract, start, end, number_of_ticks, what_to_show, use_rth, ignore_size)
+    }
+
     /// Requests historical time & sales data (Bid/Ask) for an instrument.
     ///
     /// # Arguments
@@ -790,6 +1810,150 @@ impl Client {
 
     // === Realtime Market Data ===
 
+    /// Requests streaming market data.
+    ///
+    /// # Arguments
+    /// * `contract` - The [Contract] for which the data is being requested.
+    /// * `generic_ticks` - Ids of the available generic ticks, see [realtime::GenericTick::ids].
+    ///   See [Generic Tick Types](https://interactivebrokers.github.io/tws-api/tick_types.html).
+    /// * `snapshot` - When true a single snapshot of market data is returned and the iterator ends. Otherwise, market data is streamed.
+    /// * `regulatory_snapshot` - Snapshot for US stocks requests NBBO snapshots for users which have "US Securities Snapshot Bundle" subscription but not corresponding Network A, B, or C subscription.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("AAPL");
+    /// let ticks = client.market_data(&contract, &["233"], false, false).expect("request failed");
+    ///
+    /// for tick in ticks.take(20) {
+    ///     println!("tick: {tick:?}");
+    /// }
+    /// ```
+    pub fn market_data<'a>(
+        &'a self,
+        contract: &Contract,
+        generic_ticks: &[&str],
+        snapshot: bool,
+        regulatory_snapshot: bool,
+    ) -> Result<realtime::MarketDataIterator<'a>, Error> {
+        realtime::market_data(self, contract, generic_ticks, snapshot, regulatory_snapshot)
+    }
+
+    /// Requests a single snapshot of market data, consolidated into one quote.
+    ///
+    /// Waits for the end of the snapshot, which may take several seconds. Returns [Error::Api] if TWS rejects the request,
+    /// or [Error::Timeout] if the snapshot doesn't end in time.
+    ///
+    /// # Arguments
+    /// * `contract` - The [Contract] for which the data is being requested.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("AAPL");
+    /// let snapshot = client.snapshot(&contract).expect("request failed");
+    ///
+    /// println!("bid: {:?} ask: {:?}", snapshot.bid, snapshot.ask);
+    /// ```
+    pub fn snapshot(&self, contract: &Contract) -> Result<realtime::Snapshot, Error> {
+        realtime::snapshot(self, contract)
+    }
+
+    /// Returns the number of active [Client::market_data] and [Client::market_depth] subscriptions.
+    ///
+    /// TWS limits the number of simultaneous market data lines of an account, see [ClientBuilder::max_market_data_lines].
+    pub fn active_market_data_lines(&self) -> usize {
+        self.market_data_lines.load(Ordering::SeqCst)
+    }
+
+    /// Switches the type of market data returned by subsequent [Client::market_data] requests.
+    ///
+    /// Accounts without market data subscriptions can request [MarketDataType::Delayed] data.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::market_data::realtime::MarketDataType;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// client.set_market_data_type(MarketDataType::Delayed).expect("request failed");
+    /// ```
+    pub fn set_market_data_type(&self, market_data_type: MarketDataType) -> Result<(), Error> {
+        realtime::set_market_data_type(self, market_data_type)?;
+        self.market_data_type.store(market_data_type as i32, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the market data type last set with [Client::set_market_data_type]. Defaults to [MarketDataType::Live].
+    pub fn market_data_type(&self) -> MarketDataType {
+        MarketDataType::from(self.market_data_type.load(Ordering::Relaxed))
+    }
+
+    /// Requests the exchanges represented by the bits of a bit-mapped exchange, as found in the exchange of ticks for contracts routed SMART.
+    /// Returns a map of bit number to exchange letter and exchange name.
+    ///
+    /// # Arguments
+    /// * `bbo_exchange` - Bit-mapped exchange reported for a market data subscription, e.g. a6.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let components = client.smart_components("a6").expect("request failed");
+    /// for (bit, (letter, exchange)) in &components {
+    ///     println!("{bit}: {letter} {exchange}");
+    /// }
+    /// ```
+    pub fn smart_components(&self, bbo_exchange: &str) -> Result<HashMap<i32, (char, String)>, Error> {
+        realtime::smart_components(self, bbo_exchange)
+    }
+
+    /// Requests market depth (order book) for a [Contract].
+    ///
+    /// # Arguments
+    /// * `contract`       - The [Contract] for which the depth is being requested.
+    /// * `number_of_rows` - The number of rows on each side of the order book.
+    /// * `is_smart_depth` - Flag indicating that this is smart depth request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::contracts::Contract;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let contract = Contract::stock("AAPL");
+    /// let updates = client.market_depth(&contract, 5, true).expect("request failed");
+    ///
+    /// for update in updates.take(20) {
+    ///     println!("update: {update:?}");
+    /// }
+    /// ```
+    pub fn market_depth<'a>(
+        &'a self,
+        contract: &Contract,
+        number_of_rows: i32,
+        is_smart_depth: bool,
+    ) -> Result<realtime::MarketDepthIterator<'a>, Error> {
+        realtime::market_depth(self, contract, number_of_rows, is_smart_depth)
+    }
+
     /// Requests realtime bars.
     ///
     /// This method will provide all the contracts matching the contract provided. It can also be used to retrieve complete options and futures chains. Though it is now (in API version > 9.72.12) advised to use reqSecDefOptParams for that purpose.
@@ -819,7 +1983,7 @@ impl Client {
         bar_size: BarSize,
         what_to_show: WhatToShow,
         use_rth: bool,
-    ) -> Result<impl Iterator<Item = Bar> + 'a, Error> {
+    ) -> Result<realtime::RealTimeBarIterator<'a>, Error> {
         realtime::realtime_bars(self, contract, &bar_size, &what_to_show, use_rth, Vec::default())
     }
 
@@ -834,7 +1998,7 @@ impl Client {
         contract: &Contract,
         number_of_ticks: i32,
         ignore_size: bool,
-    ) -> Result<impl Iterator<Item = realtime::Trade> + 'a, Error> {
+    ) -> Result<realtime::TradeIterator<'a>, Error> {
         realtime::tick_by_tick_all_last(self, contract, number_of_ticks, ignore_size)
     }
 
@@ -849,7 +2013,7 @@ impl Client {
         contract: &Contract,
         number_of_ticks: i32,
         ignore_size: bool,
-    ) -> Result<impl Iterator<Item = realtime::BidAsk> + 'a, Error> {
+    ) -> Result<realtime::BidAskIterator<'a>, Error> {
         realtime::tick_by_tick_bid_ask(self, contract, number_of_ticks, ignore_size)
     }
 
@@ -864,7 +2028,7 @@ impl Client {
         contract: &Contract,
         number_of_ticks: i32,
         ignore_size: bool,
-    ) -> Result<impl Iterator<Item = realtime::Trade> + 'a, Error> {
+    ) -> Result<realtime::TradeIterator<'a>, Error> {
         realtime::tick_by_tick_last(self, contract, number_of_ticks, ignore_size)
     }
 
@@ -879,63 +2043,371 @@ impl Client {
         contract: &Contract,
         number_of_ticks: i32,
         ignore_size: bool,
-    ) -> Result<impl Iterator<Item = realtime::MidPoint> + 'a, Error> {
+    ) -> Result<realtime::MidPointIterator<'a>, Error> {
         realtime::tick_by_tick_midpoint(self, contract, number_of_ticks, ignore_size)
     }
 
+    // === Market Scanners ===
+
+    /// Requests an XML list of scanner parameters valid in TWS.
+    ///
+    /// The XML lists the valid instruments, location codes, scan codes and filters for [Self::scanner_subscription()].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let parameters = client.scanner_parameters().expect("request failed");
+    /// println!("{parameters}");
+    /// ```
+    pub fn scanner_parameters(&self) -> Result<String, Error> {
+        scanners::scanner_parameters(self)
+    }
+
+    /// Starts a subscription to market scan results based on the provided parameters.
+    ///
+    /// Each item is a complete scan result, ranked by the scan criteria. Results are refreshed until the iterator is dropped.
+    ///
+    /// # Arguments
+    /// * `subscription` - [scanners::ScannerSubscription] describing the scan.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use ibapi::market_data::scanners::ScannerSubscription;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let subscription = ScannerSubscription {
+    ///     number_of_rows: 10,
+    ///     instrument: "STK".to_string(),
+    ///     location_code: "STK.US.MAJOR".to_string(),
+    ///     scan_code: "TOP_PERC_GAIN".to_string(),
+    ///     above_price: Some(5.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let results = client.scanner_subscription(&subscription).expect("request failed");
+    /// for rows in results.take(1) {
+    ///     for row in rows {
+    ///         println!("{} {}", row.rank, row.contract_details.contract.symbol);
+    ///     }
+    /// }
+    /// ```
+    pub fn scanner_subscription<'a>(&'a self, subscription: &scanners::ScannerSubscription) -> Result<scanners::ScannerDataIterator<'a>, Error> {
+        scanners::scanner_subscription(self, subscription)
+    }
+
+    // === News ===
+
+    /// Requests news providers which the user has subscribed to.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let news_providers = client.news_providers().expect("request failed");
+    /// for news_provider in news_providers {
+    ///     println!("{news_provider:?}");
+    /// }
+    /// ```
+    pub fn news_providers(&self) -> Result<Vec<news::NewsProvider>, Error> {
+        news::news_providers(self)
+    }
+
+    /// Requests historical news headlines for a contract.
+    ///
+    /// # Arguments
+    /// * `contract_id`    - Contract id of the ticker for which news is requested.
+    /// * `provider_codes` - Codes of the news providers to query, see [Self::news_providers()].
+    /// * `start`          - Start of the period for which headlines are returned.
+    /// * `end`            - End of the period for which headlines are returned.
+    /// * `total_results`  - The maximum number of headlines to fetch (1 - 300).
+    ///
+    /// Fails with [Error::Incomplete], holding the headlines received so far, if the server does not complete the response within 30 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    /// use time::macros::datetime;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let start = datetime!(2024-03-01 0:00 UTC);
+    /// let end = datetime!(2024-03-15 0:00 UTC);
+    ///
+    /// let articles = client.historical_news(265598, &["BRFG", "DJNL"], start, end, 10).expect("request failed");
+    /// for article in articles {
+    ///     println!("{} {}: {}", article.time, article.provider_code, article.headline);
+    /// }
+    /// ```
+    pub fn historical_news(
+        &self,
+        contract_id: i32,
+        provider_codes: &[&str],
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        total_results: i32,
+    ) -> Result<Vec<news::NewsArticle>, Error> {
+        news::historical_news(self, contract_id, provider_codes, start, end, total_results)
+    }
+
+    /// Requests the body of a news article.
+    ///
+    /// # Arguments
+    /// * `provider_code` - Code of the news provider, i.e. BRFG.
+    /// * `article_id`    - Id of the article, as returned in [news::NewsArticle].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let article = client.news_article("BRFG", "BRFG$12345").expect("request failed");
+    /// println!("{}", article.article_text);
+    /// ```
+    pub fn news_article(&self, provider_code: &str, article_id: &str) -> Result<news::NewsArticleBody, Error> {
+        news::news_article(self, provider_code, article_id)
+    }
+
+    // === Wall Street Horizon ===
+
+    /// Requests the Wall Street Horizon event types and filters available, as JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let meta_data = client.wsh_meta_data().expect("request failed");
+    /// println!("{meta_data}");
+    /// ```
+    pub fn wsh_meta_data(&self) -> Result<String, Error> {
+        wsh::wsh_meta_data(self)
+    }
+
+    /// Requests Wall Street Horizon corporate events, such as earnings dates.
+    ///
+    /// # Arguments
+    /// * `contract_id`      - Contract id of the company. Required unless a filter is given.
+    /// * `filter`           - JSON filter of the events returned, see [Client::wsh_meta_data].
+    /// * `fill_watchlist`   - Include events of the companies in the watchlist.
+    /// * `fill_portfolio`   - Include events of the companies in the portfolio.
+    /// * `fill_competitors` - Include events of the competitors of the company.
+    /// * `start_date`       - Earliest date of the events returned.
+    /// * `end_date`         - Latest date of the events returned.
+    /// * `total_limit`      - Maximum number of events returned.
+    ///
+    /// The filters require [Feature::WshEventDataFilters], the dates and limit [Feature::WshEventDataDateFilters].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// let events = client.wsh_event_data(Some(76792991), "", false, false, false, None, None, Some(10)).expect("request failed");
+    /// for event in &events {
+    ///     println!("{event:?}");
+    /// }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn wsh_event_data(
+        &self,
+        contract_id: Option<i32>,
+        filter: &str,
+        fill_watchlist: bool,
+        fill_portfolio: bool,
+        fill_competitors: bool,
+        start_date: Option<Date>,
+        end_date: Option<Date>,
+        total_limit: Option<i32>,
+    ) -> Result<Vec<wsh::WshEvent>, Error> {
+        wsh::wsh_event_data(
+            self,
+            contract_id,
+            filter,
+            fill_watchlist,
+            fill_portfolio,
+            fill_competitors,
+            start_date,
+            end_date,
+            total_limit,
+        )
+    }
+
+    // === Raw Messages ===
+
+    /// Sends a request built from raw fields, for requests not supported by this library yet.
+    ///
+    /// A request id is allocated and replaces each [RAW_REQUEST_ID] field. Responses carrying the request id are returned undecoded.
+    /// Responses are only routed for message types whose request id position is known, see [ResponseMessage::request_id].
+    ///
+    /// # Arguments
+    /// * `fields` - Fields of the request, starting with the message type. See the [TWS API](https://github.com/InteractiveBrokers/tws-api).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::client::RAW_REQUEST_ID;
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// // head timestamp of the AAPL trades
+    /// let fields = ["87", RAW_REQUEST_ID, "265598", "", "", "", "", "0", "", "", "", "", "", "", "1", "TRADES", "1"];
+    /// let responses = client.send_raw(&fields).expect("request failed");
+    ///
+    /// for response in responses.take(1) {
+    ///     println!("{response:?}");
+    /// }
+    /// ```
+    pub fn send_raw(&self, fields: &[&str]) -> Result<RawResponses, Error> {
+        let request_id = self.next_request_id();
+
+        let mut message = RequestMessage::new();
+        for field in fields {
+            if *field == RAW_REQUEST_ID {
+                message.push_field(&request_id);
+            } else {
+                message.push_field(field);
+            }
+        }
+
+        let responses = self.send_request(request_id, message)?;
+
+        Ok(RawResponses { request_id, responses })
+    }
+
     // == Internal Use ==
 
     #[cfg(test)]
-    pub(crate) fn stubbed(message_bus: RefCell<Box<dyn MessageBus>>, server_version: i32) -> Client {
+    pub(crate) fn stubbed(message_bus: std::cell::RefCell<Box<dyn MessageBus>>, server_version: i32) -> Client {
+        let message_bus = SharedMessageBus::new(message_bus.into_inner());
+
         Client {
             server_version: server_version,
-            connection_time: OffsetDateTime::now_utc(),
+            connection_time: None,
+            connection_time_raw: String::default(),
             time_zone: time_tz::timezones::db::UTC,
             managed_accounts: String::from(""),
+            managed_accounts_list: Vec::default(),
             message_bus,
             client_id: 100,
-            next_request_id: AtomicI32::new(9000),
-            order_id: AtomicI32::new(-1),
+            optional_capabilities: String::default(),
+            version_range: (MIN_SERVER_VERSION, MAX_SERVER_VERSION),
+            next_request_id: Arc::new(AtomicI32::new(INITIAL_REQUEST_ID)),
+            order_id: Arc::new(AtomicI32::new(-1)),
+            market_data_type: Arc::new(AtomicI32::new(MarketDataType::Live as i32)),
+            market_data_lines: Arc::new(AtomicUsize::new(0)),
+            max_market_data_lines: None,
+        }
+    }
+
+    // Counts a new market data line, failing with Error::TooManyLines when the limit is reached.
+    pub(crate) fn acquire_market_data_line(&self) -> Result<(), Error> {
+        let limit = self.max_market_data_lines;
+        let acquired = self
+            .market_data_lines
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| match limit {
+                Some(limit) if active >= limit => None,
+                _ => Some(active + 1),
+            });
+
+        match (acquired, limit) {
+            (Err(_), Some(limit)) => Err(Error::TooManyLines { limit }),
+            _ => Ok(()),
         }
     }
 
+    pub(crate) fn release_market_data_line(&self) {
+        let _ = self
+            .market_data_lines
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| active.checked_sub(1));
+    }
+
     pub(crate) fn send_message(&self, packet: RequestMessage) -> Result<(), Error> {
-        self.message_bus.borrow_mut().write_message(&packet)
+        self.message_bus.lock().write_message(&packet)
     }
 
     pub(crate) fn send_request(&self, request_id: i32, message: RequestMessage) -> Result<ResponseIterator, Error> {
         debug!("send_message({:?}, {:?})", request_id, message);
-        self.message_bus.borrow_mut().send_generic_message(request_id, &message)
+        let responses = self.message_bus.lock().send_generic_message(request_id, &message)?;
+        Ok(responses.with_server_version(self.server_version))
     }
 
     pub(crate) fn send_order(&self, order_id: i32, message: RequestMessage) -> Result<ResponseIterator, Error> {
         debug!("send_order({:?}, {:?})", order_id, message);
-        self.message_bus.borrow_mut().send_order_message(order_id, &message)
+        let responses = self.message_bus.lock().send_order_message(order_id, &message)?;
+        Ok(responses.with_server_version(self.server_version))
+    }
+
+    pub(crate) fn resend_order(&self, order_id: i32, message: RequestMessage) -> Result<(), Error> {
+        debug!("resend_order({:?}, {:?})", order_id, message);
+        self.message_bus.lock().resend_order_message(order_id, &message)
     }
 
     /// Sends request for the next valid order id.
     pub(crate) fn request_next_order_id(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
-        self.message_bus.borrow_mut().request_next_order_id(&message)
+        self.message_bus.lock().request_next_order_id(&message)
     }
 
     /// Sends request for open orders.
     pub(crate) fn request_order_data(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
-        self.message_bus.borrow_mut().request_open_orders(&message)
+        self.message_bus.lock().request_open_orders(&message)
     }
 
     /// Sends request for market rule.
     pub(crate) fn request_market_rule(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
-        self.message_bus.borrow_mut().request_market_rule(&message)
+        self.message_bus.lock().request_market_rule(&message)
     }
 
     /// Sends request for positions.
     pub(crate) fn request_positions(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
-        self.message_bus.borrow_mut().request_positions(&message)
+        self.message_bus.lock().request_positions(&message)
+    }
+
+    /// Sends request for account updates.
+    pub(crate) fn request_account_updates(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.message_bus.lock().request_account_updates(&message)
     }
 
     /// Sends request for family codes.
     pub(crate) fn request_family_codes(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
-        self.message_bus.borrow_mut().request_family_codes(&message)
+        self.message_bus.lock().request_family_codes(&message)
+    }
+
+    /// Sends request for financial advisor configuration.
+    pub(crate) fn send_fa_request(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.message_bus.lock().request_fa(&message)
+    }
+
+    /// Sends request for news providers.
+    pub(crate) fn request_news_providers(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.message_bus.lock().request_news_providers(&message)
+    }
+
+    /// Sends request for scanner parameters.
+    pub(crate) fn request_scanner_parameters(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.message_bus.lock().request_scanner_parameters(&message)
+    }
+
+    /// Sends request for the current server time.
+    pub(crate) fn request_current_time(&self, message: RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.message_bus.lock().request_current_time(&message)
     }
 
     pub(crate) fn check_server_version(&self, version: i32, message: &str) -> Result<(), Error> {
@@ -947,9 +2419,38 @@ impl Client {
     }
 }
 
-impl Drop for Client {
-    fn drop(&mut self) {
-        info!("dropping basic client")
+/// Placeholder replaced by the allocated request id in the fields of [Client::send_raw].
+pub const RAW_REQUEST_ID: &str = "{request_id}";
+
+/// Undecoded responses to a request sent with [Client::send_raw]. Dropping it stops the routing of responses.
+pub struct RawResponses {
+    request_id: i32,
+    responses: ResponseIterator,
+}
+
+impl RawResponses {
+    /// Request id allocated for the request.
+    pub fn request_id(&self) -> i32 {
+        self.request_id
+    }
+
+    /// Returns the next response if one was received, without waiting. Allows polling several requests from one thread.
+    pub fn try_next(&mut self) -> TryNext<ResponseMessage> {
+        self.responses.try_next()
+    }
+
+    /// Waits for the next response until the deadline, returning [Error::Timeout] if none arrives before it.
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+}
+
+impl Iterator for RawResponses {
+    type Item = ResponseMessage;
+
+    /// Waits for the next response to the request.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
     }
 }
 
@@ -964,18 +2465,56 @@ impl Debug for Client {
 }
 
 // Parses following format: 20230405 22:20:39 PST
-fn parse_connection_time(connection_time: &str) -> (OffsetDateTime, &'static Tz) {
-    let parts: Vec<&str> = connection_time.split(' ').collect();
+fn parse_connection_time(connection_time: &str) -> Option<(OffsetDateTime, &'static Tz)> {
+    let mut parts = connection_time.split_whitespace();
+    let (date, time, zone) = (parts.next()?, parts.next()?, parts.next()?);
 
-    let zones = timezones::find_by_name(parts[2]);
+    // abbreviations like PST only match as part of a time zone name, e.g. PST8PDT
+    let timezone = timezones::get_by_name(zone).or_else(|| timezones::find_by_name(zone).first().copied())?;
 
     let format = format_description!("[year][month][day] [hour]:[minute]:[second]");
-    let date = time::PrimitiveDateTime::parse(format!("{} {}", parts[0], parts[1]).as_str(), format).unwrap();
-    let timezone = zones[0];
+    let date = time::PrimitiveDateTime::parse(&format!("{date} {time}"), format).ok()?;
+
     match date.assume_timezone(timezone) {
-        OffsetResult::Some(date) => (date, timezone),
-        _ => (OffsetDateTime::now_utc(), time_tz::timezones::db::UTC),
+        OffsetResult::Some(date) => Some((date, timezone)),
+        _ => None,
+    }
+}
+
+// Splits the comma separated managed accounts into account codes.
+fn parse_managed_accounts(managed_accounts: &str) -> Vec<String> {
+    managed_accounts
+        .split(',')
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Encodes the API prefix followed by the range of server versions supported by the client.
+// The server answers with the version it picked from the range, see Client::server_version.
+fn encode_handshake(min_version: i32, max_version: i32) -> String {
+    let prefix = "API\0";
+    let version = format!("v{min_version}..{max_version}");
+
+    prefix.to_owned() + &encode_packet(&version)
+}
+
+// Encodes the request asking the server to start processing messages.
+pub(crate) fn encode_start_api(server_version: i32, client_id: i32, optional_capabilities: &str) -> RequestMessage {
+    const VERSION: i32 = 2;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::StartApi);
+    message.push_field(&VERSION);
+    message.push_field(&client_id);
+
+    if server_version >= server_versions::OPTIONAL_CAPABILITIES {
+        message.push_field(&optional_capabilities);
     }
+
+    message
 }
 
 fn encode_packet(message: &str) -> String {
@@ -0,0 +1,294 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+use futures::StreamExt;
+use log::{error, info, warn};
+use time::OffsetDateTime;
+
+use super::transport::asynchronous::{AsyncMessageBus, ResponseStream};
+use super::transport::encode_request_current_time;
+use super::{encode_handshake, encode_start_api, parse_connection_time, ClientBuilder, Feature, INITIAL_REQUEST_ID};
+use crate::contracts::Contract;
+use crate::market_data::realtime::{self, BarSize, RealTimeBarStream, WhatToShow};
+use crate::messages::{IncomingMessages, RequestMessage};
+use crate::Error;
+
+/// Asynchronous TWS API Client, available with the `async` feature. Manages the connection to TWS or Gateway using tokio.
+///
+/// Subscriptions are returned as [futures::Stream]s instead of blocking iterators.
+pub struct AsyncClient {
+    server_version: i32,
+    connection_time: Option<OffsetDateTime>,
+    connection_time_raw: String,
+    managed_accounts: String,
+    client_id: i32,
+    optional_capabilities: String,
+    version_range: (i32, i32), // server versions offered in the handshake
+    pub(crate) message_bus: Box<dyn AsyncMessageBus>,
+    next_request_id: AtomicI32,
+    order_id: Arc<AtomicI32>,
+}
+
+impl AsyncClient {
+    /// Establishes connection to TWS or Gateway. See [ClientBuilder::connect_async](crate::ClientBuilder::connect_async) for more options.
+    ///
+    /// # Arguments
+    /// * `address`   - address of server. e.g. 127.0.0.1:4002
+    /// * `client_id` - id of client. e.g. 100
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::AsyncClient;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = AsyncClient::connect("127.0.0.1:4002", 100).await.expect("connection failed");
+    ///
+    ///     println!("server_version: {}", client.server_version());
+    ///     println!("managed_accounts: {}", client.managed_accounts());
+    /// }
+    /// ```
+    pub async fn connect(address: &str, client_id: i32) -> Result<AsyncClient, Error> {
+        ClientBuilder::new().address(address).client_id(client_id).connect_async().await
+    }
+
+    pub(crate) async fn do_connect(
+        client_id: i32,
+        optional_capabilities: &str,
+        version_range: (i32, i32),
+        message_bus: Box<dyn AsyncMessageBus>,
+    ) -> Result<AsyncClient, Error> {
+        let order_id = message_bus.order_ids();
+
+        let mut client = AsyncClient {
+            server_version: 0,
+            connection_time: None,
+            connection_time_raw: String::default(),
+            managed_accounts: String::from(""),
+            client_id,
+            optional_capabilities: optional_capabilities.to_owned(),
+            version_range,
+            message_bus,
+            next_request_id: AtomicI32::new(INITIAL_REQUEST_ID),
+            order_id,
+        };
+
+        client.handshake().await?;
+        client.start_api().await?;
+        client.receive_account_info().await?;
+
+        client.message_bus.process_messages(client.server_version)?;
+
+        Ok(client)
+    }
+
+    // sends server handshake
+    async fn handshake(&mut self) -> Result<(), Error> {
+        let (min_version, max_version) = self.version_range;
+        self.message_bus.write(&encode_handshake(min_version, max_version)).await?;
+
+        match self.message_bus.read_message().await {
+            Ok(mut response_message) => {
+                self.server_version = response_message.next_int()?;
+
+                let time = response_message.next_string()?;
+                self.connection_time = parse_connection_time(&time).map(|(connection_time, _)| connection_time);
+                if self.connection_time.is_none() {
+                    warn!("unable to parse connection time: {time}");
+                }
+                self.connection_time_raw = time;
+            }
+            Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                error!("error reading handshake: {err}");
+                return Err(Error::ConnectionFailed);
+            }
+            Err(err) => return Err(err),
+        }
+
+        Ok(())
+    }
+
+    // asks server to start processing messages
+    async fn start_api(&mut self) -> Result<(), Error> {
+        let prelude = encode_start_api(self.server_version, self.client_id, &self.optional_capabilities);
+        self.message_bus.write_message(&prelude).await
+    }
+
+    // Fetches next order id and managed accounts.
+    async fn receive_account_info(&mut self) -> Result<(), Error> {
+        const MAX_ATTEMPTS: i32 = 100;
+
+        let mut saw_next_order_id = false;
+        let mut saw_managed_accounts = false;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let mut message = self.message_bus.read_message().await?;
+
+            match message.message_type() {
+                IncomingMessages::NextValidId => {
+                    saw_next_order_id = true;
+
+                    message.skip(); // message type
+                    message.skip(); // message version
+
+                    self.order_id.store(message.next_int()?, Ordering::Relaxed);
+                }
+                IncomingMessages::ManagedAccounts => {
+                    saw_managed_accounts = true;
+
+                    message.skip(); // message type
+                    message.skip(); // message version
+
+                    self.managed_accounts = message.next_string()?;
+                }
+                IncomingMessages::Error => error!("message: {message:?}"),
+                _ => info!("message: {message:?}"),
+            }
+
+            if saw_next_order_id && saw_managed_accounts {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next request ID.
+    pub fn next_request_id(&self) -> i32 {
+        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns and increments the order ID.
+    pub fn next_order_id(&self) -> i32 {
+        self.order_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Returns the version of the TWS API server.
+    pub fn server_version(&self) -> i32 {
+        self.server_version
+    }
+
+    /// Returns true if the server connected to supports the feature, see [Client::supports](crate::Client::supports).
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.server_version() <= self.server_version
+    }
+
+    /// The time of the server when the client connected, see [Client::connection_time](crate::Client::connection_time).
+    pub fn connection_time(&self) -> Option<OffsetDateTime> {
+        self.connection_time
+    }
+
+    /// The time of the server when the client connected, as sent by the server.
+    pub fn connection_time_raw(&self) -> &str {
+        &self.connection_time_raw
+    }
+
+    /// Returns the managed accounts.
+    pub fn managed_accounts(&self) -> String {
+        self.managed_accounts.to_owned()
+    }
+
+    /// Returns the ID of the client.
+    pub fn client_id(&self) -> i32 {
+        self.client_id
+    }
+
+    /// Requests the current time of the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::AsyncClient;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = AsyncClient::connect("127.0.0.1:4002", 100).await.expect("connection failed");
+    ///
+    ///     let server_time = client.server_time().await.expect("error requesting server time");
+    ///     println!("server time: {server_time}");
+    /// }
+    /// ```
+    pub async fn server_time(&self) -> Result<OffsetDateTime, Error> {
+        let mut messages = self.message_bus.request_current_time(&encode_request_current_time()).await?;
+
+        match messages.next().await {
+            Some(mut message) => {
+                message.skip(); // message type
+                message.skip(); // message version
+
+                message.next_date_time()
+            }
+            None => Err(Error::Simple("no response from server".into())),
+        }
+    }
+
+    /// Requests realtime bars. Bars are streamed until the stream is dropped, which cancels the subscription.
+    ///
+    /// # Arguments
+    /// * `contract` - The [Contract] used as sample to query the available contracts. Typically, it will contain the [Contract]'s symbol, currency, security_type, and exchange.
+    /// * `bar_size` - Currently being ignored
+    /// * `what_to_show` - The nature of the data being retrieved
+    /// * `use_rth` - Set to false to obtain the data which was also generated outside of the Regular Trading Hours, set to true to obtain only the RTH data
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures::StreamExt;
+    ///
+    /// use ibapi::contracts::Contract;
+    /// use ibapi::market_data::realtime::{BarSize, WhatToShow};
+    /// use ibapi::AsyncClient;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = AsyncClient::connect("127.0.0.1:4002", 100).await.expect("connection failed");
+    ///
+    ///     let contract = Contract::stock("TSLA");
+    ///     let mut bars = client
+    ///         .realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, false)
+    ///         .await
+    ///         .expect("realtime bars request failed!");
+    ///
+    ///     while let Some(bar) = bars.next().await {
+    ///         println!("{bar:?}");
+    ///     }
+    /// }
+    /// ```
+    pub async fn realtime_bars<'a>(
+        &'a self,
+        contract: &Contract,
+        bar_size: BarSize,
+        what_to_show: WhatToShow,
+        use_rth: bool,
+    ) -> Result<RealTimeBarStream<'a>, Error> {
+        realtime::realtime_bars_async(self, contract, &bar_size, &what_to_show, use_rth, Vec::default()).await
+    }
+
+    /// Closes the connection to the server.
+    pub async fn disconnect(&self) -> Result<(), Error> {
+        self.message_bus.disconnect().await
+    }
+
+    // == Internal Use ==
+
+    pub(crate) async fn send_request(&self, request_id: i32, message: RequestMessage) -> Result<ResponseStream, Error> {
+        self.message_bus.send_generic_message(request_id, &message).await
+    }
+
+    // Sends message without awaiting the write, e.g. to cancel a subscription when its stream is dropped.
+    pub(crate) fn send_detached(&self, message: RequestMessage) {
+        self.message_bus.send_detached(message)
+    }
+
+    pub(crate) fn check_server_version(&self, version: i32, message: &str) -> Result<(), Error> {
+        if version <= self.server_version {
+            Ok(())
+        } else {
+            Err(Error::ServerVersion(version, self.server_version, message.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
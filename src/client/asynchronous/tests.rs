@@ -0,0 +1,176 @@
+use std::io;
+use std::time::Duration;
+
+use time::macros::datetime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::time::timeout;
+
+use super::*;
+use crate::server_versions;
+
+// Serves a scripted session. Answers the handshake, then writes the responses scripted for each request it reads.
+// Every request, starting with the supported versions of the handshake and the start api request, is forwarded to the returned receiver.
+async fn mock_server(script: Vec<Vec<&'static str>>) -> (String, UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let (requests_send, requests_recv) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut prefix = [0_u8; 4];
+        stream.read_exact(&mut prefix).await.unwrap();
+        requests_send.send(read_request(&mut stream).await.unwrap()).unwrap(); // supported versions
+        write_response(&mut stream, "173|20230405 22:20:39 PST|").await;
+
+        requests_send.send(read_request(&mut stream).await.unwrap()).unwrap();
+        write_response(&mut stream, "9|1|90|").await;
+        write_response(&mut stream, "15|1|DU1234567|").await;
+
+        for responses in script {
+            requests_send.send(read_request(&mut stream).await.unwrap()).unwrap();
+            for response in responses {
+                write_response(&mut stream, response).await;
+            }
+        }
+
+        while let Ok(request) = read_request(&mut stream).await {
+            let _ = requests_send.send(request);
+        }
+    });
+
+    (address, requests_recv)
+}
+
+async fn read_request(stream: &mut TcpStream) -> io::Result<String> {
+    let size = stream.read_u32().await? as usize;
+    let mut data = vec![0_u8; size];
+    stream.read_exact(&mut data).await?;
+
+    Ok(String::from_utf8(data).unwrap().replace('\0', "|"))
+}
+
+async fn write_response(stream: &mut TcpStream, response: &str) {
+    let data = response.replace('|', "\0");
+    stream.write_u32(data.len() as u32).await.unwrap();
+    stream.write_all(data.as_bytes()).await.unwrap();
+}
+
+async fn next_request(requests: &mut UnboundedReceiver<String>) -> String {
+    match timeout(Duration::from_secs(5), requests.recv()).await {
+        Ok(Some(request)) => request,
+        Ok(None) => panic!("mock server stopped"),
+        Err(_) => panic!("timed out waiting for request"),
+    }
+}
+
+#[tokio::test]
+async fn connect() {
+    let (address, mut requests) = mock_server(vec![]).await;
+
+    let client = match AsyncClient::connect(&address, 100).await {
+        Ok(client) => client,
+        Err(err) => panic!("failed to connect: {err}"),
+    };
+
+    assert_eq!(
+        next_request(&mut requests).await,
+        format!("v{}..{}", server_versions::min_version(), server_versions::max_version()),
+        "supported versions"
+    );
+    assert_eq!(next_request(&mut requests).await, "71|2|100||", "start api request");
+
+    assert_eq!(client.server_version(), 173, "client.server_version()");
+    assert_eq!(client.managed_accounts(), "DU1234567", "client.managed_accounts()");
+    assert_eq!(client.next_order_id(), 90, "client.next_order_id()");
+    assert_eq!(client.client_id(), 100, "client.client_id()");
+}
+
+#[tokio::test]
+async fn connect_with_builder_options() {
+    let (address, mut requests) = mock_server(vec![]).await;
+
+    let result = ClientBuilder::new()
+        .address(&address)
+        .client_id(100)
+        .version_range(150, 160)
+        .optional_capabilities("+PACEAPI")
+        .connect_async()
+        .await;
+    assert!(result.is_ok(), "failed to connect: {:?}", result.err());
+
+    assert_eq!(next_request(&mut requests).await, "v150..160", "supported versions");
+    assert_eq!(next_request(&mut requests).await, "71|2|100|+PACEAPI|", "start api request");
+
+    let result = ClientBuilder::new()
+        .address(&address)
+        .client_id(100)
+        .version_range(170, 150)
+        .connect_async()
+        .await;
+    match result {
+        Err(Error::Simple(message)) => assert_eq!(message, "invalid server version range: 170..150"),
+        _ => assert!(false, "expected invalid server version range"),
+    }
+}
+
+#[tokio::test]
+async fn server_time() {
+    let (address, mut requests) = mock_server(vec![vec!["49|1|1678890000|"]]).await;
+
+    let client = AsyncClient::connect(&address, 100).await.expect("failed to connect");
+    next_request(&mut requests).await; // supported versions
+    next_request(&mut requests).await; // start api
+
+    let result = client.server_time().await;
+
+    assert_eq!(next_request(&mut requests).await, "49|1|", "current time request");
+
+    match result {
+        Ok(server_time) => assert_eq!(server_time, datetime!(2023-03-15 14:20:00 UTC), "server_time"),
+        Err(err) => assert!(false, "error requesting server time: {err}"),
+    }
+}
+
+#[tokio::test]
+async fn realtime_bars() {
+    let (address, mut requests) = mock_server(vec![vec!["50|3|9000|1678323335|4028.75|4029.00|4028.25|4028.50|2|4026.75|1|"]]).await;
+
+    let client = AsyncClient::connect(&address, 100).await.expect("failed to connect");
+    next_request(&mut requests).await; // supported versions
+    next_request(&mut requests).await; // start api
+
+    let contract = Contract::stock("TSLA");
+    let mut bars = client
+        .realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, false)
+        .await
+        .expect("realtime bars request failed");
+
+    assert_eq!(
+        next_request(&mut requests).await,
+        "50|8|9000|0|TSLA|STK||0|||SMART||USD|||0|TRADES|0||",
+        "realtime bars request"
+    );
+
+    let bar = match timeout(Duration::from_secs(5), bars.next()).await {
+        Ok(Some(bar)) => bar,
+        Ok(None) => panic!("stream ended before first bar"),
+        Err(_) => panic!("timed out waiting for bar"),
+    };
+
+    assert_eq!(bar.date, datetime!(2023-03-09 00:55:35 UTC), "bar.date");
+    assert_eq!(bar.open, 4028.75, "bar.open");
+    assert_eq!(bar.high, 4029.00, "bar.high");
+    assert_eq!(bar.low, 4028.25, "bar.low");
+    assert_eq!(bar.close, 4028.50, "bar.close");
+    assert_eq!(bar.volume, 2.0, "bar.volume");
+    assert_eq!(bar.wap, 4026.75, "bar.wap");
+    assert_eq!(bar.count, 1, "bar.count");
+
+    drop(bars);
+
+    assert_eq!(next_request(&mut requests).await, "51|1|9000|", "cancel realtime bars request");
+}
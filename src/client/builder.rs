@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use super::transport::asynchronous::AsyncTcpMessageBus;
+use super::transport::{MessageBus, MessageObserver, OverflowPolicy, TcpMessageBus};
+#[cfg(feature = "async")]
+use super::AsyncClient;
+use super::{Client, MAX_SERVER_VERSION, MIN_SERVER_VERSION};
+use crate::Error;
+
+/// Configures and establishes a connection to TWS or Gateway.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use ibapi::Client;
+///
+/// let client = Client::builder()
+///     .address("127.0.0.1:4002")
+///     .client_id(100)
+///     .connect_timeout(Duration::from_secs(5))
+///     .connect()
+///     .expect("connection failed");
+///
+/// println!("server_version: {}", client.server_version());
+/// ```
+#[derive(Default)]
+pub struct ClientBuilder {
+    address: String,
+    client_id: i32,
+    connect_timeout: Option<Duration>,
+    heartbeat_interval: Option<Duration>,
+    max_reconnect_attempts: Option<u32>,
+    channel_bound: Option<(usize, OverflowPolicy)>,
+    optional_capabilities: String,
+    max_market_data_lines: Option<usize>,
+    version_range: Option<(i32, i32)>,
+    observers: Vec<Arc<dyn MessageObserver>>,
+    message_bus: Option<Box<dyn MessageBus>>,
+}
+
+impl ClientBuilder {
+    pub(crate) fn new() -> Self {
+        ClientBuilder::default()
+    }
+
+    /// Address of the server. e.g. 127.0.0.1:4002
+    pub fn address(mut self, address: &str) -> Self {
+        self.address = address.to_owned();
+        self
+    }
+
+    /// Id of the client. e.g. 100
+    pub fn client_id(mut self, client_id: i32) -> Self {
+        self.client_id = client_id;
+        self
+    }
+
+    /// Fails the connection attempt if the server does not accept it within the timeout. By default the operating system timeout applies.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Requests the server time every interval and reconnects when a request is not answered before the next one is due.
+    /// See [Client::is_connection_healthy].
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self
+    }
+
+    /// Gives up re-establishing a lost connection after attempts failed attempts, backing off exponentially between them.
+    /// Zero disables reconnecting. Defaults to 5, or to the IBAPI_MAX_RECONNECT_ATTEMPTS environment variable when set.
+    pub fn max_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.max_reconnect_attempts = Some(attempts);
+        self
+    }
+
+    /// Buffers at most capacity responses per request, applying overflow_policy when the application does not keep up with a subscription.
+    /// By default responses are buffered without limit. Connecting fails if capacity is zero, as no response could be buffered.
+    pub fn bounded_channels(mut self, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        self.channel_bound = Some((capacity, overflow_policy));
+        self
+    }
+
+    /// Optional capabilities sent to the server when starting the API. e.g. +PACEAPI
+    pub fn optional_capabilities(mut self, optional_capabilities: &str) -> Self {
+        self.optional_capabilities = optional_capabilities.to_owned();
+        self
+    }
+
+    /// Range of server versions offered in the handshake, e.g. to test against the behaviour of older gateways.
+    /// By default all versions supported by this crate are offered, see [server_versions](crate::server_versions).
+    /// Connecting fails if min_version is greater than max_version, or the range is not within
+    /// [server_versions::min_version](crate::server_versions::min_version) and [server_versions::max_version](crate::server_versions::max_version).
+    pub fn version_range(mut self, min_version: i32, max_version: i32) -> Self {
+        self.version_range = Some((min_version, max_version));
+        self
+    }
+
+    /// Refuses market data and market depth subscriptions with [Error::TooManyLines] once limit subscriptions are active,
+    /// instead of sending requests TWS rejects for exceeding the market data lines of the account. Not limited by default.
+    pub fn max_market_data_lines(mut self, limit: usize) -> Self {
+        self.max_market_data_lines = Some(limit);
+        self
+    }
+
+    /// Notifies observer of each message sent to and received from the server, including those exchanged while starting the API.
+    /// See [Client::add_observer].
+    pub fn observer(mut self, observer: Arc<dyn MessageObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    // Uses the given message bus instead of connecting to address. Used to run a client against stubs and recorded sessions.
+    pub(crate) fn message_bus(mut self, message_bus: Box<dyn MessageBus>) -> Self {
+        self.message_bus = Some(message_bus);
+        self
+    }
+
+    /// Connects to the server and starts the API.
+    pub fn connect(self) -> Result<Client, Error> {
+        let version_range = self.checked_version_range()?;
+
+        if let Some((0, _)) = self.channel_bound {
+            return Err(Error::Simple("invalid channel capacity: 0".into()));
+        }
+
+        let mut message_bus = match self.message_bus {
+            Some(message_bus) => message_bus,
+            None if self.address.is_empty() => return Err(Error::Simple("address is required to connect".into())),
+            None => {
+                let mut message_bus = match self.connect_timeout {
+                    Some(timeout) => TcpMessageBus::connect_timeout(&self.address, timeout)?,
+                    None => TcpMessageBus::connect(&self.address)?,
+                };
+
+                if let Some(interval) = self.heartbeat_interval {
+                    message_bus = message_bus.heartbeat(interval);
+                }
+
+                if let Some(attempts) = self.max_reconnect_attempts {
+                    message_bus = message_bus.max_reconnect_attempts(attempts);
+                }
+
+                if let Some((capacity, overflow_policy)) = self.channel_bound {
+                    message_bus = message_bus.bounded_channels(capacity, overflow_policy);
+                }
+
+                Box::new(message_bus)
+            }
+        };
+
+        for observer in self.observers {
+            message_bus.add_observer(observer);
+        }
+
+        let mut client = Client::do_connect(self.client_id, &self.optional_capabilities, version_range, message_bus)?;
+        client.max_market_data_lines = self.max_market_data_lines;
+
+        Ok(client)
+    }
+
+    /// Connects to the server and starts the API, returning an [AsyncClient]. Available with the `async` feature.
+    ///
+    /// The address, client id, connect timeout, optional capabilities and version range are applied.
+    /// Heartbeats, reconnecting, bounded channels, market data line limits and observers are only supported by [Client].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ibapi::Client;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = Client::builder()
+    ///         .address("127.0.0.1:4002")
+    ///         .client_id(100)
+    ///         .optional_capabilities("+PACEAPI")
+    ///         .connect_async()
+    ///         .await
+    ///         .expect("connection failed");
+    ///
+    ///     println!("server_version: {}", client.server_version());
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn connect_async(self) -> Result<AsyncClient, Error> {
+        let version_range = self.checked_version_range()?;
+
+        if self.address.is_empty() {
+            return Err(Error::Simple("address is required to connect".into()));
+        }
+
+        let message_bus = match self.connect_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, AsyncTcpMessageBus::connect(&self.address)).await {
+                Ok(message_bus) => message_bus?,
+                Err(_) => return Err(Error::Io(std::io::Error::from(std::io::ErrorKind::TimedOut))),
+            },
+            None => AsyncTcpMessageBus::connect(&self.address).await?,
+        };
+
+        AsyncClient::do_connect(self.client_id, &self.optional_capabilities, version_range, Box::new(message_bus)).await
+    }
+
+    // Range of server versions to offer in the handshake, checked against the versions supported by this crate.
+    fn checked_version_range(&self) -> Result<(i32, i32), Error> {
+        match self.version_range {
+            Some((min_version, max_version)) if min_version > max_version || min_version < MIN_SERVER_VERSION || max_version > MAX_SERVER_VERSION => {
+                Err(Error::Simple(format!("invalid server version range: {min_version}..{max_version}")))
+            }
+            Some(version_range) => Ok(version_range),
+            None => Ok((MIN_SERVER_VERSION, MAX_SERVER_VERSION)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,149 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::net::TcpListener;
+use std::thread;
+
+use super::*;
+use crate::client::transport::ReplayMessageBus;
+use crate::server_versions;
+
+// Writes a recorded session starting the API with the given start api request.
+fn write_session(name: &str, start_api: &str) -> String {
+    let recording_dir = env::temp_dir().join(format!("ibapi-builder-{name}-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+
+    let _ = fs::remove_dir_all(&recording_dir);
+    fs::create_dir_all(&recording_dir).unwrap();
+
+    fs::write(format!("{recording_dir}/0000-response.msg"), "173|20230405 22:20:39 PST||").unwrap();
+    fs::write(format!("{recording_dir}/0001-request.msg"), start_api).unwrap();
+    fs::write(format!("{recording_dir}/0002-response.msg"), "9|1|90||").unwrap();
+    fs::write(format!("{recording_dir}/0003-response.msg"), "15|1|DU1234567||").unwrap();
+
+    recording_dir
+}
+
+#[test]
+fn connects_with_message_bus() {
+    let recording_dir = write_session("message-bus", "71|2|100||");
+    let message_bus = ReplayMessageBus::open(&recording_dir).unwrap();
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    let result = Client::builder().client_id(100).message_bus(Box::new(message_bus)).connect();
+
+    match result {
+        Ok(client) => {
+            assert_eq!(client.server_version(), 173, "client.server_version()");
+            assert_eq!(client.managed_accounts(), "DU1234567", "client.managed_accounts()");
+            assert_eq!(client.next_order_id(), 90, "client.next_order_id()");
+        }
+        Err(err) => assert!(false, "failed to connect with message bus: {err}"),
+    }
+}
+
+#[test]
+fn sends_optional_capabilities() {
+    let recording_dir = write_session("optional-capabilities", "71|2|100|+PACEAPI|");
+    let message_bus = ReplayMessageBus::open(&recording_dir).unwrap();
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    let result = Client::builder()
+        .client_id(100)
+        .optional_capabilities("+PACEAPI")
+        .message_bus(Box::new(message_bus))
+        .connect();
+
+    match result {
+        Ok(client) => assert_eq!(client.optional_capabilities(), "+PACEAPI", "client.optional_capabilities()"),
+        Err(err) => assert!(false, "failed to connect with optional capabilities: {err}"),
+    }
+}
+
+#[test]
+fn requires_address() {
+    let result = Client::builder().client_id(100).connect();
+
+    match result {
+        Err(Error::Simple(message)) => assert_eq!(message, "address is required to connect"),
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected connect without address to fail"),
+    }
+}
+
+#[test]
+fn sends_configured_version_range() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // Reads the handshake and closes the connection without answering.
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+
+        let mut prefix = [0u8; 4];
+        stream.read_exact(&mut prefix).unwrap();
+        assert_eq!(&prefix, b"API\0", "handshake prefix");
+
+        let mut length = [0u8; 4];
+        stream.read_exact(&mut length).unwrap();
+        let mut version = vec![0u8; u32::from_be_bytes(length) as usize];
+        stream.read_exact(&mut version).unwrap();
+
+        String::from_utf8(version).unwrap()
+    });
+
+    let result = Client::builder()
+        .address(&address)
+        .client_id(100)
+        .version_range(100, server_versions::PRICE_MGMT_ALGO)
+        .connect();
+
+    assert!(result.is_err(), "expected connect to fail without handshake response");
+    assert_eq!(server.join().unwrap(), "v100..151", "handshake version range");
+}
+
+#[test]
+fn rejects_invalid_version_range() {
+    let result = Client::builder()
+        .address("127.0.0.1:4002")
+        .client_id(100)
+        .version_range(server_versions::PRICE_MGMT_ALGO, 100)
+        .connect();
+
+    match result {
+        Err(Error::Simple(message)) => assert_eq!(message, "invalid server version range: 151..100"),
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected connect with invalid version range to fail"),
+    }
+}
+
+#[test]
+fn rejects_version_range_beyond_supported_versions() {
+    let max_version = server_versions::max_version() + 1;
+    let result = Client::builder()
+        .address("127.0.0.1:4002")
+        .client_id(100)
+        .version_range(100, max_version)
+        .connect();
+
+    match result {
+        Err(Error::Simple(message)) => assert_eq!(message, format!("invalid server version range: 100..{max_version}")),
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected connect with unsupported max version to fail"),
+    }
+}
+
+#[test]
+fn rejects_zero_channel_capacity() {
+    let result = Client::builder()
+        .address("127.0.0.1:4002")
+        .client_id(100)
+        .bounded_channels(0, OverflowPolicy::Block)
+        .connect();
+
+    match result {
+        Err(Error::Simple(message)) => assert_eq!(message, "invalid channel capacity: 0"),
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected connect with zero channel capacity to fail"),
+    }
+}
@@ -0,0 +1,87 @@
+use crate::server_versions;
+
+/// Optional features of the API that depend on the version of the server connected to, see [Client::supports](crate::Client::supports).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// [Client::account_summary](crate::Client::account_summary)
+    AccountSummary,
+    /// [Client::pnl](crate::Client::pnl) and [Client::pnl_single](crate::Client::pnl_single)
+    Pnl,
+    /// [Client::family_codes](crate::Client::family_codes)
+    FamilyCodes,
+    /// [Client::matching_symbols](crate::Client::matching_symbols)
+    MatchingSymbols,
+    /// [Client::market_rule](crate::Client::market_rule)
+    MarketRules,
+    /// [Client::smart_components](crate::Client::smart_components)
+    SmartComponents,
+    /// [Client::set_market_data_type](crate::Client::set_market_data_type)
+    MarketDataType,
+    /// [Client::realtime_bars](crate::Client::realtime_bars)
+    RealTimeBars,
+    /// Market depth aggregated across exchanges.
+    SmartDepth,
+    /// [Client::tick_by_tick_last](crate::Client::tick_by_tick_last) and related tick by tick requests
+    TickByTick,
+    /// [Client::head_timestamp](crate::Client::head_timestamp)
+    HeadTimestamp,
+    /// [Client::histogram_data](crate::Client::histogram_data)
+    Histogram,
+    /// [Client::historical_ticks_trade](crate::Client::historical_ticks_trade) and related historical tick requests
+    HistoricalTicks,
+    /// Fundamental data reports.
+    FundamentalData,
+    /// [Client::news_providers](crate::Client::news_providers)
+    NewsProviders,
+    /// [Client::news_article](crate::Client::news_article)
+    NewsArticle,
+    /// [Client::historical_news](crate::Client::historical_news)
+    HistoricalNews,
+    /// Previewing the margin and commission of orders before placing them.
+    WhatIfOrders,
+    /// [Client::global_cancel](crate::Client::global_cancel)
+    GlobalCancel,
+    /// [Client::completed_orders](crate::Client::completed_orders)
+    CompletedOrders,
+    /// Order and position sizes with fractional quantities.
+    FractionalSize,
+    /// [Client::wsh_meta_data](crate::Client::wsh_meta_data) and [Client::wsh_event_data](crate::Client::wsh_event_data)
+    WshEventData,
+    /// Filters, watchlist, portfolio and competitor events of [Client::wsh_event_data](crate::Client::wsh_event_data)
+    WshEventDataFilters,
+    /// Start date, end date and limit of [Client::wsh_event_data](crate::Client::wsh_event_data)
+    WshEventDataDateFilters,
+}
+
+impl Feature {
+    // Minimum server version supporting the feature.
+    pub(crate) fn server_version(&self) -> i32 {
+        match self {
+            Feature::AccountSummary => server_versions::ACCOUNT_SUMMARY,
+            Feature::Pnl => server_versions::PNL,
+            Feature::FamilyCodes => server_versions::REQ_FAMILY_CODES,
+            Feature::MatchingSymbols => server_versions::REQ_MATCHING_SYMBOLS,
+            Feature::MarketRules => server_versions::MARKET_RULES,
+            Feature::SmartComponents => server_versions::REQ_SMART_COMPONENTS,
+            Feature::MarketDataType => server_versions::REQ_MARKET_DATA_TYPE,
+            Feature::RealTimeBars => server_versions::REAL_TIME_BARS,
+            Feature::SmartDepth => server_versions::SMART_DEPTH,
+            Feature::TickByTick => server_versions::TICK_BY_TICK,
+            Feature::HeadTimestamp => server_versions::REQ_HEAD_TIMESTAMP,
+            Feature::Histogram => server_versions::REQ_HISTOGRAM,
+            Feature::HistoricalTicks => server_versions::HISTORICAL_TICKS,
+            Feature::FundamentalData => server_versions::FUNDAMENTAL_DATA,
+            Feature::NewsProviders => server_versions::REQ_NEWS_PROVIDERS,
+            Feature::NewsArticle => server_versions::REQ_NEWS_ARTICLE,
+            Feature::HistoricalNews => server_versions::REQ_HISTORICAL_NEWS,
+            Feature::WhatIfOrders => server_versions::WHAT_IF_ORDERS,
+            Feature::GlobalCancel => server_versions::REQ_GLOBAL_CANCEL,
+            Feature::CompletedOrders => server_versions::COMPLETED_ORDERS,
+            Feature::FractionalSize => server_versions::FRACTIONAL_SIZE_SUPPORT,
+            Feature::WshEventData => server_versions::WSHE_CALENDAR,
+            Feature::WshEventDataFilters => server_versions::WSH_EVENT_DATA_FILTERS,
+            Feature::WshEventDataDateFilters => server_versions::WSH_EVENT_DATA_FILTERS_DATE,
+        }
+    }
+}
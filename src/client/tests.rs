@@ -6,10 +6,320 @@ use super::*;
 #[test]
 fn test_parse_connection_time() {
     let example = "20230405 22:20:39 PST";
-    let (connection_time, _) = parse_connection_time(example);
+    let (connection_time, _) = parse_connection_time(example).expect("failed to parse connection time");
 
     let la = timezones::db::america::LOS_ANGELES;
     if let OffsetResult::Some(other) = datetime!(2023-04-05 22:20:39).assume_timezone(la) {
         assert_eq!(connection_time, other);
     }
 }
+
+#[test]
+fn test_parse_connection_time_to_utc() {
+    let (connection_time, _) = parse_connection_time("20230224 12:04:56 EST").expect("failed to parse connection time");
+    assert_eq!(connection_time, datetime!(2023-02-24 17:04:56 UTC), "connection time");
+
+    assert!(parse_connection_time("").is_none(), "empty connection time");
+    assert!(parse_connection_time("20230224 12:04:56").is_none(), "connection time without time zone");
+    assert!(parse_connection_time("2023-02-24 12:04:56 EST").is_none(), "unexpected date format");
+}
+
+#[test]
+fn test_parse_managed_accounts() {
+    assert_eq!(parse_managed_accounts("DU123,DU456"), vec!["DU123", "DU456"]);
+    assert_eq!(parse_managed_accounts(" DU123 , DU456,"), vec!["DU123", "DU456"]);
+    assert_eq!(parse_managed_accounts("DU123"), vec!["DU123"]);
+    assert!(parse_managed_accounts("").is_empty());
+}
+
+#[test]
+fn test_encode_handshake() {
+    let version = format!("v{MIN_SERVER_VERSION}..{MAX_SERVER_VERSION}");
+
+    let mut expected = b"API\0".to_vec();
+    expected.extend((version.len() as u32).to_be_bytes());
+    expected.extend(version.as_bytes());
+
+    assert_eq!(
+        encode_handshake(MIN_SERVER_VERSION, MAX_SERVER_VERSION).as_bytes(),
+        expected,
+        "handshake bytes"
+    );
+
+    let handshake = encode_handshake(100, server_versions::PRICE_MGMT_ALGO);
+    assert_eq!(&handshake[8..], "v100..151", "handshake with narrower range");
+}
+
+#[test]
+fn test_encode_start_api() {
+    let start_api = encode_start_api(server_versions::OPTIONAL_CAPABILITIES, 100, "+PACEAPI");
+    assert_eq!(start_api.encode_simple(), "71|2|100|+PACEAPI|", "with optional capabilities");
+
+    let start_api = encode_start_api(server_versions::OPTIONAL_CAPABILITIES - 1, 100, "+PACEAPI");
+    assert_eq!(start_api.encode_simple(), "71|2|100|", "before optional capabilities");
+}
+
+#[test]
+fn next_order_id_advances_across_threads() {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::thread;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.set_next_order_id(100);
+
+    let mut seen: HashSet<i32> = HashSet::new();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| (0..250).map(|_| client.next_order_id()).collect::<Vec<i32>>()))
+            .collect();
+
+        for handle in handles {
+            seen.extend(handle.join().unwrap());
+        }
+    });
+
+    assert_eq!(seen.len(), 1000, "unique order ids");
+    assert!(seen.iter().all(|id| (100..1100).contains(id)), "order ids start at 100");
+    assert_eq!(client.next_order_id(), 1100, "next order id");
+}
+
+#[test]
+fn next_request_id_is_unique_across_threads() {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::thread;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let mut seen: HashSet<i32> = HashSet::new();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| (0..250).map(|_| client.next_request_id()).collect::<Vec<i32>>()))
+            .collect();
+
+        for handle in handles {
+            seen.extend(handle.join().unwrap());
+        }
+    });
+
+    assert_eq!(seen.len(), 1000, "unique request ids");
+    assert!(
+        seen.iter().all(|id| *id >= INITIAL_REQUEST_ID),
+        "request ids start at {INITIAL_REQUEST_ID}"
+    );
+    assert_eq!(client.next_request_id(), INITIAL_REQUEST_ID + 1000, "next request id");
+}
+
+#[test]
+fn connect_replay() {
+    use std::env;
+    use std::fs;
+
+    let recording_dir = env::temp_dir().join(format!("ibapi-connect-replay-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+    let _ = fs::remove_dir_all(&recording_dir);
+    fs::create_dir_all(&recording_dir).unwrap();
+
+    fs::write(format!("{recording_dir}/0000-response.msg"), "173|20230405 22:20:39 PST||").unwrap();
+    fs::write(format!("{recording_dir}/0001-request.msg"), "71|2|100||").unwrap();
+    fs::write(format!("{recording_dir}/0002-response.msg"), "9|1|90||").unwrap();
+    fs::write(format!("{recording_dir}/0003-response.msg"), "15|1|DU1234567||").unwrap();
+
+    let result = Client::connect_replay(&recording_dir);
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    match result {
+        Ok(client) => {
+            assert_eq!(client.server_version(), 173, "client.server_version()");
+            assert_eq!(client.managed_accounts(), "DU1234567", "client.managed_accounts()");
+            assert_eq!(client.next_order_id(), 90, "client.next_order_id()");
+        }
+        Err(err) => assert!(false, "failed to replay session: {err}"),
+    }
+}
+
+#[test]
+fn supports_features_of_server_version() {
+    use std::cell::RefCell;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_SMART_COMPONENTS);
+
+    assert!(client.supports(Feature::RealTimeBars), "supports(RealTimeBars)");
+    assert!(client.supports(Feature::SmartComponents), "supports(SmartComponents)");
+
+    assert!(!client.supports(Feature::TickByTick), "supports(TickByTick)");
+    assert!(!client.supports(Feature::Pnl), "supports(Pnl)");
+    assert!(!client.supports(Feature::WshEventData), "supports(WshEventData)");
+
+    assert!(
+        matches!(client.wsh_meta_data(), Err(Error::ServerVersion(_, _, _))),
+        "unsupported feature should fail with server version error"
+    );
+}
+
+#[test]
+fn server_time() {
+    use std::cell::RefCell;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["49|1|1678890000|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let result = client.server_time();
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "49|1|");
+
+    match result {
+        Ok(server_time) => assert_eq!(server_time, datetime!(2023-03-15 14:20:00 UTC), "server_time"),
+        Err(err) => assert!(false, "error requesting server time: {err}"),
+    }
+}
+
+#[test]
+fn managed_accounts_list() {
+    use std::env;
+    use std::fs;
+
+    let recording_dir = env::temp_dir().join(format!("ibapi-managed-accounts-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+    let _ = fs::remove_dir_all(&recording_dir);
+    fs::create_dir_all(&recording_dir).unwrap();
+
+    fs::write(format!("{recording_dir}/0000-response.msg"), "173|20230405 22:20:39 PST||").unwrap();
+    fs::write(format!("{recording_dir}/0001-request.msg"), "71|2|100||").unwrap();
+    fs::write(format!("{recording_dir}/0002-response.msg"), "9|1|90||").unwrap();
+    fs::write(format!("{recording_dir}/0003-response.msg"), "15|1|DU123,DU456||").unwrap();
+
+    let result = Client::connect_replay(&recording_dir);
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    match result {
+        Ok(client) => {
+            assert_eq!(client.managed_accounts(), "DU123,DU456", "client.managed_accounts()");
+            assert_eq!(client.managed_accounts_list(), vec!["DU123", "DU456"], "client.managed_accounts_list()");
+        }
+        Err(err) => assert!(false, "failed to replay session: {err}"),
+    }
+}
+
+#[test]
+fn send_raw() {
+    use std::cell::RefCell;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["88|9000|20230224  12:04:56|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let fields = ["87", RAW_REQUEST_ID, "265598", "", "TRADES", "1"];
+    let responses = client.send_raw(&fields).expect("failed to send raw request");
+    assert_eq!(responses.request_id(), 9000, "responses.request_id()");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode(), "87\09000\0265598\0\0TRADES\01\0", "raw request");
+
+    let responses: Vec<ResponseMessage> = responses.collect();
+    assert_eq!(responses.len(), 1, "responses.len()");
+    assert_eq!(responses[0].message_type(), IncomingMessages::HeadTimestamp, "response message type");
+    assert_eq!(responses[0].request_id(), Some(9000), "response request id");
+    assert_eq!(responses[0].peek_string(2), "20230224  12:04:56", "response field");
+}
+
+#[test]
+fn cloned_clients_share_connection_across_threads() {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::thread;
+
+    use crate::stubs::MessageBusStub;
+
+    fn assert_shareable<T: Send + Sync + Clone>() {}
+    assert_shareable::<Client>();
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["88|9000|20230224  12:04:56|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let client = client.clone();
+            thread::spawn(move || {
+                let responses = client.send_raw(&["87", RAW_REQUEST_ID, "265598"]).expect("failed to send raw request");
+                let request_id = responses.request_id();
+                assert_eq!(responses.count(), 1, "responses for request {request_id}");
+                (request_id, client.next_order_id())
+            })
+        })
+        .collect();
+
+    let results: Vec<(i32, i32)> = handles
+        .into_iter()
+        .map(|handle| handle.join().expect("request thread panicked"))
+        .collect();
+
+    let request_ids: HashSet<i32> = results.iter().map(|(request_id, _)| *request_id).collect();
+    assert_eq!(request_ids, HashSet::from([9000, 9001]), "request ids");
+
+    let order_ids: HashSet<i32> = results.iter().map(|(_, order_id)| *order_id).collect();
+    assert_eq!(order_ids.len(), 2, "order ids: {order_ids:?}");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+}
+
+#[test]
+fn cancel_orders_on_disconnect_requires_global_cancel() {
+    use std::cell::RefCell;
+
+    use crate::stubs::MessageBusStub;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_GLOBAL_CANCEL - 1);
+
+    let result = client.set_cancel_orders_on_disconnect(true);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+
+    assert!(client.set_cancel_orders_on_disconnect(false).is_ok(), "disabling is always supported");
+}
@@ -1,55 +1,246 @@
+use std::any::Any;
 use std::collections::HashMap;
-use std::io::{prelude::*, Cursor};
+use std::env;
+use std::fmt;
+use std::io::{prelude::*, Cursor, ErrorKind};
 use std::iter::Iterator;
-use std::net::TcpStream;
-use std::sync::{Arc, RwLock};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use crossbeam::channel::{self, Receiver, Sender};
-use log::{debug, error, info};
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, SendTimeoutError, Sender, TryRecvError, TrySendError};
+use log::{debug, error, info, warn};
 
-use crate::messages::IncomingMessages;
+use crate::client::{encode_start_api, Notification};
+use crate::messages::{unescape, IncomingMessages, OutgoingMessages};
 use crate::messages::{RequestMessage, ResponseMessage};
+use crate::orders::{encoders as order_encoders, AdvancedOrderReject, ExecutionFilter};
 use crate::{server_versions, Error};
 use recorder::MessageRecorder;
+pub(crate) use replay::ReplayMessageBus;
 
+#[cfg(feature = "async")]
+pub(crate) mod asynchronous;
 mod recorder;
+mod replay;
 
-pub(crate) trait MessageBus {
+pub(crate) trait MessageBus: Send {
     fn read_message(&mut self) -> Result<ResponseMessage, Error>;
 
     fn write_message(&mut self, packet: &RequestMessage) -> Result<(), Error>;
 
     fn send_generic_message(&mut self, request_id: i32, packet: &RequestMessage) -> Result<ResponseIterator, Error>;
     fn send_order_message(&mut self, request_id: i32, packet: &RequestMessage) -> Result<ResponseIterator, Error>;
+    // Sends an updated order for an order id placed before. Responses keep arriving on the iterator returned when it was placed.
+    fn resend_order_message(&mut self, order_id: i32, packet: &RequestMessage) -> Result<(), Error>;
     fn request_next_order_id(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
     fn request_open_orders(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
     fn request_market_rule(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
     fn request_positions(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
+    fn request_account_updates(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
     fn request_family_codes(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
+    fn request_news_providers(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
+    fn request_scanner_parameters(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
+    fn request_current_time(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
+    fn request_fa(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error>;
 
     fn write(&mut self, packet: &str) -> Result<(), Error>;
 
+    // Asks the server to start processing messages for the client. Sent after the handshake, and replayed by buses that reconnect.
+    fn start_api(&mut self, server_version: i32, client_id: i32, optional_capabilities: &str) -> Result<(), Error> {
+        self.write_message(&encode_start_api(server_version, client_id, optional_capabilities))
+    }
+
     fn process_messages(&mut self, server_version: i32) -> Result<(), Error>;
 
+    // Stops message processing and closes the connection.
+    fn disconnect(&mut self) -> Result<(), Error>;
+
+    // Next valid order id. Updated whenever the server sends NextValidId.
+    fn order_ids(&self) -> Arc<AtomicI32>;
+
+    // Messages from the server not associated with a request.
+    fn notifications(&self) -> Receiver<Notification>;
+
+    // False once the server stops answering heartbeats, until it sends another message.
+    fn is_healthy(&self) -> bool {
+        true
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        ConnectionState::Connected
+    }
+
+    // Registers an observer of the messages sent and received. Ignored by buses not connected to a server.
+    fn add_observer(&mut self, _observer: Arc<dyn MessageObserver>) {}
+
+    // Cancels all open orders after the connection is re-established. Ignored by buses that don't reconnect.
+    fn set_cancel_orders_on_disconnect(&mut self, _enabled: bool) {}
+
+    // Number of responses received per message type since connecting.
+    fn message_stats(&self) -> HashMap<IncomingMessages, u64> {
+        HashMap::new()
+    }
+
     fn request_messages(&self) -> Vec<RequestMessage> {
         vec![]
     }
 }
 
+/// Observes the messages exchanged with TWS or Gateway, e.g. to forward them to a logging or metrics pipeline.
+///
+/// Observers are called on the thread sending a request and on the thread reading responses, so should return quickly.
+pub trait MessageObserver: Send + Sync {
+    /// Called before a request is sent. request_id is the request or order id the message belongs to, if any.
+    fn on_request(&self, request_id: Option<i32>, message: &RequestMessage);
+
+    /// Called when a response is received, before it is routed to the request it belongs to.
+    fn on_response(&self, message: &ResponseMessage);
+}
+
+// Message bus shared by the clones of a client. The bus is disconnected when the last clone is dropped.
+#[derive(Clone)]
+pub(crate) struct SharedMessageBus(Arc<MessageBusHandle>);
+
+struct MessageBusHandle(Mutex<Box<dyn MessageBus>>);
+
+impl SharedMessageBus {
+    pub fn new(message_bus: Box<dyn MessageBus>) -> Self {
+        SharedMessageBus(Arc::new(MessageBusHandle(Mutex::new(message_bus))))
+    }
+
+    // Locks the bus for writing a request. Responses are routed by the bus' own threads,
+    // so the lock must not be held while waiting for them or requests of other threads stall.
+    pub fn lock(&self) -> MutexGuard<'_, Box<dyn MessageBus>> {
+        self.0 .0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MessageBusHandle {
+    fn drop(&mut self) {
+        info!("dropping basic client");
+
+        let message_bus = self.0.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Err(err) = message_bus.disconnect() {
+            error!("error disconnecting: {err}");
+        }
+    }
+}
+
+// Observers registered on a message bus. Shared with the reader thread, so observers added once message processing started are notified too.
+#[derive(Clone, Default)]
+struct MessageObservers(Arc<RwLock<Vec<Arc<dyn MessageObserver>>>>);
+
+impl MessageObservers {
+    fn add(&self, observer: Arc<dyn MessageObserver>) {
+        self.0.write().unwrap().push(observer);
+    }
+
+    fn on_request(&self, request_id: Option<i32>, message: &RequestMessage) {
+        for observer in self.0.read().unwrap().iter() {
+            observer.on_request(request_id, message);
+        }
+    }
+
+    fn on_response(&self, message: &ResponseMessage) {
+        for observer in self.0.read().unwrap().iter() {
+            observer.on_response(message);
+        }
+    }
+}
+
+impl fmt::Debug for MessageObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageObservers").field(&self.0.read().unwrap().len()).finish()
+    }
+}
+
+// Counts the responses received per message type. Indexed by message type, offset by the lowest message type.
+#[derive(Debug)]
+struct MessageStats([AtomicU64; MESSAGE_STATS_SLOTS]);
+
+const MESSAGE_STATS_SLOTS: usize = 128;
+
+impl MessageStats {
+    fn record(&self, message_type: IncomingMessages) {
+        if let Some(count) = self.0.get(message_stats_slot(message_type)) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Counts of the message types received at least once.
+    fn counts(&self) -> HashMap<IncomingMessages, u64> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(slot, count)| (slot, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(slot, count)| (IncomingMessages::from(slot as i32 + IncomingMessages::ConnectionLost as i32), count))
+            .collect()
+    }
+}
+
+impl Default for MessageStats {
+    fn default() -> Self {
+        MessageStats(std::array::from_fn(|_| AtomicU64::new(0)))
+    }
+}
+
+fn message_stats_slot(message_type: IncomingMessages) -> usize {
+    (message_type as i32 - IncomingMessages::ConnectionLost as i32) as usize
+}
+
+/// State of the connection to TWS or Gateway. See [Client::connection_state](crate::Client::connection_state).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connection is open, message processing has not started yet.
+    Connecting,
+    /// Messages are exchanged with the server.
+    Connected,
+    /// Connection was lost and is being re-established.
+    Reconnecting,
+    /// Connection was closed, or could not be re-established.
+    Disconnected,
+}
+
+/// What happens to a response when the channel buffering the responses of its request is full. See [ClientBuilder::bounded_channels](crate::ClientBuilder::bounded_channels).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Waits up to 100 milliseconds for the consumer to make room, then discards the response as [OverflowPolicy::DropNewest] does.
+    /// Delivery of responses to all other requests stalls while waiting.
+    Block,
+    /// Discards the oldest queued response to make room.
+    DropOldest,
+    /// Discards the response.
+    DropNewest,
+    /// Ends the subscription and logs an error. The consumer receives the queued responses, after which the iteration ends.
+    Error,
+}
+
 #[derive(Debug)]
 pub struct TcpMessageBus {
-    reader: Arc<TcpStream>,
-    writer: Box<TcpStream>,
+    reader: TcpStream,
+    connection: Arc<Connection>,
     handles: Vec<JoinHandle<i32>>,
+    shutdown_requested: Arc<AtomicBool>,
+    order_ids: Arc<AtomicI32>,
     requests: Arc<SenderHash<i32, ResponseMessage>>,
     orders: Arc<SenderHash<i32, ResponseMessage>>,
-    recorder: MessageRecorder,
+    observers: MessageObservers,
     globals: Arc<GlobalChannels>,
     signals_send: Sender<Signal>,
     signals_recv: Receiver<Signal>,
+    heartbeat_interval: Option<Duration>,
+    channel_bound: Option<(usize, OverflowPolicy)>, // capacity of request and order channels, unbounded when None
+    last_received: Arc<Mutex<Instant>>,
+    healthy: Arc<AtomicBool>,
+    stats: Arc<MessageStats>,
+    state: Arc<RwLock<ConnectionState>>,
+    cancel_orders_on_disconnect: Arc<AtomicBool>,
 }
 
 pub enum Signal {
@@ -67,8 +258,25 @@ struct GlobalChannels {
     recv_market_rule: Arc<Receiver<ResponseMessage>>,
     send_positions: Arc<Sender<ResponseMessage>>,
     recv_positions: Arc<Receiver<ResponseMessage>>,
+    send_account_updates: Arc<Sender<ResponseMessage>>,
+    recv_account_updates: Arc<Receiver<ResponseMessage>>,
     send_family_codes: Arc<Sender<ResponseMessage>>,
     recv_family_codes: Arc<Receiver<ResponseMessage>>,
+    send_news_providers: Arc<Sender<ResponseMessage>>,
+    recv_news_providers: Arc<Receiver<ResponseMessage>>,
+    send_scanner_parameters: Arc<Sender<ResponseMessage>>,
+    recv_scanner_parameters: Arc<Receiver<ResponseMessage>>,
+    send_current_time: Arc<Sender<ResponseMessage>>,
+    recv_current_time: Arc<Receiver<ResponseMessage>>,
+    send_fa: Arc<Sender<ResponseMessage>>,
+    recv_fa: Arc<Receiver<ResponseMessage>>,
+    send_notifications: Sender<Notification>,
+    recv_notifications: Receiver<Notification>,
+    // executions routed to order subscriptions, by execution id, so those resent after a reconnect are skipped
+    seen_executions: Mutex<HashMap<String, i32>>,
+    // dropped on disconnect, which ends the iterators over all global channels
+    send_disconnected: Mutex<Option<Sender<()>>>,
+    recv_disconnected: Receiver<()>,
 }
 
 impl GlobalChannels {
@@ -77,7 +285,16 @@ impl GlobalChannels {
         let (open_orders_in, open_orders_out) = channel::unbounded();
         let (send_market_rule, recv_market_rule) = channel::unbounded();
         let (send_positions, recv_positions) = channel::unbounded();
+        let (send_account_updates, recv_account_updates) = channel::unbounded();
         let (send_family_codes, recv_family_codes) = channel::unbounded();
+        let (send_news_providers, recv_news_providers) = channel::unbounded();
+        let (send_scanner_parameters, recv_scanner_parameters) = channel::unbounded();
+        // holds the latest answer only, heartbeat responses nobody waits for are dropped
+        let (send_current_time, recv_current_time) = channel::bounded(1);
+        let (send_fa, recv_fa) = channel::unbounded();
+        // keeps the latest notifications when nobody reads them
+        let (send_notifications, recv_notifications) = channel::bounded(NOTIFICATIONS_CAPACITY);
+        let (send_disconnected, recv_disconnected) = channel::bounded(0);
 
         GlobalChannels {
             order_ids_in: Arc::new(order_ids_in),
@@ -88,9 +305,143 @@ impl GlobalChannels {
             recv_market_rule: Arc::new(recv_market_rule),
             send_positions: Arc::new(send_positions),
             recv_positions: Arc::new(recv_positions),
+            send_account_updates: Arc::new(send_account_updates),
+            recv_account_updates: Arc::new(recv_account_updates),
             send_family_codes: Arc::new(send_family_codes),
             recv_family_codes: Arc::new(recv_family_codes),
+            send_news_providers: Arc::new(send_news_providers),
+            recv_news_providers: Arc::new(recv_news_providers),
+            send_scanner_parameters: Arc::new(send_scanner_parameters),
+            recv_scanner_parameters: Arc::new(recv_scanner_parameters),
+            send_current_time: Arc::new(send_current_time),
+            recv_current_time: Arc::new(recv_current_time),
+            send_fa: Arc::new(send_fa),
+            recv_fa: Arc::new(recv_fa),
+            send_notifications,
+            recv_notifications,
+            seen_executions: Mutex::new(HashMap::new()),
+            send_disconnected: Mutex::new(Some(send_disconnected)),
+            recv_disconnected,
+        }
+    }
+
+    // Returns an iterator over the messages of a global channel that ends when the bus disconnects.
+    fn responses(&self, messages: &Arc<Receiver<ResponseMessage>>) -> GlobalResponseIterator {
+        GlobalResponseIterator::new(Arc::clone(messages)).until(self.recv_disconnected.clone())
+    }
+
+    fn disconnect(&self) {
+        self.send_disconnected.lock().unwrap_or_else(PoisonError::into_inner).take();
+    }
+}
+
+// Number of notifications kept for Client::notifications, older ones are dropped once full.
+const NOTIFICATIONS_CAPACITY: usize = 1024;
+
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+// Shared state needed to re-establish a lost connection.
+#[derive(Debug)]
+struct Connection {
+    connection_string: String,
+    max_reconnect_attempts: AtomicU32,
+    writer: Mutex<TcpStream>,
+    handshake: Mutex<Vec<Vec<u8>>>, // packets written before message processing started, replayed on reconnect
+    server_version: AtomicI32,      // negotiated when connecting, messages are encoded and decoded for it
+}
+
+impl Connection {
+    fn new(connection_string: &str, writer: TcpStream) -> Self {
+        // IBAPI_MAX_RECONNECT_ATTEMPTS changes the default of clients not configured with ClientBuilder::max_reconnect_attempts
+        let max_reconnect_attempts = match env::var("IBAPI_MAX_RECONNECT_ATTEMPTS") {
+            Ok(attempts) => attempts.parse().unwrap_or(MAX_RECONNECT_ATTEMPTS),
+            _ => MAX_RECONNECT_ATTEMPTS,
+        };
+
+        Connection {
+            connection_string: connection_string.into(),
+            max_reconnect_attempts: AtomicU32::new(max_reconnect_attempts),
+            writer: Mutex::new(writer),
+            handshake: Mutex::new(Vec::default()),
+            server_version: AtomicI32::new(0),
+        }
+    }
+
+    fn write_all(&self, packet: &[u8]) -> Result<(), Error> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(packet)?;
+        Ok(())
+    }
+
+    // Re-establishes the connection, backing off exponentially between attempts.
+    // Returns a new reader for the dispatch loop.
+    fn reconnect(&self) -> Result<TcpStream, Error> {
+        let max_reconnect_attempts = self.max_reconnect_attempts.load(Ordering::Relaxed);
+        let mut backoff = Duration::from_secs(1);
+
+        for attempt in 1..=max_reconnect_attempts {
+            info!(
+                "reconnecting to {} (attempt {attempt} of {max_reconnect_attempts})",
+                self.connection_string
+            );
+
+            match self.establish() {
+                Ok(reader) => return Ok(reader),
+                // messages of the client are encoded for the version negotiated when connecting
+                Err(err @ Error::ServerVersion(..)) => {
+                    error!("reconnect attempt {attempt} failed: {err}");
+                    return Err(err);
+                }
+                Err(err) => error!("reconnect attempt {attempt} failed: {err}"),
+            }
+
+            if attempt < max_reconnect_attempts {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+        }
+
+        error!(
+            "could not reconnect to {} after {max_reconnect_attempts} attempts",
+            self.connection_string
+        );
+        Err(Error::ConnectionFailed)
+    }
+
+    // Opens a new stream and replays the handshake on it.
+    fn establish(&self) -> Result<TcpStream, Error> {
+        let mut stream = TcpStream::connect(&self.connection_string)?;
+        let reader = stream.try_clone()?;
+
+        let handshake = self.handshake.lock().unwrap();
+        let mut packets = handshake.iter();
+
+        // server acknowledges the API version negotiation before anything else is sent
+        if let Some(packet) = packets.next() {
+            stream.write_all(packet)?;
+
+            let mut ack = read_packet(&reader)?;
+            let server_version = ack.next_int()?;
+
+            let negotiated = self.server_version.load(Ordering::SeqCst);
+            if server_version != negotiated {
+                return Err(Error::ServerVersion(
+                    negotiated,
+                    server_version,
+                    "server version changed while reconnecting".into(),
+                ));
+            }
+
+            info!("reconnected to {}, server_version: {server_version}", self.connection_string);
+        }
+
+        for packet in packets {
+            stream.write_all(packet)?;
         }
+
+        *self.writer.lock().unwrap() = stream;
+
+        Ok(reader)
     }
 }
 
@@ -98,50 +449,188 @@ impl TcpMessageBus {
     // establishes TCP connection to server
     pub fn connect(connection_string: &str) -> Result<TcpMessageBus, Error> {
         let stream = TcpStream::connect(connection_string)?;
+        TcpMessageBus::with_stream(connection_string, stream)
+    }
+
+    // Connects to the first address connection_string resolves to that accepts the connection within timeout.
+    pub fn connect_timeout(connection_string: &str, timeout: Duration) -> Result<TcpMessageBus, Error> {
+        let mut last_error = None;
+
+        for address in connection_string.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return TcpMessageBus::with_stream(connection_string, stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        match last_error {
+            Some(err) => Err(Error::Io(err)),
+            None => Err(Error::Simple(format!("could not resolve address {connection_string}"))),
+        }
+    }
 
-        let reader = Arc::new(stream.try_clone()?);
-        let writer = Box::new(stream);
+    fn with_stream(connection_string: &str, stream: TcpStream) -> Result<TcpMessageBus, Error> {
+        let reader = stream.try_clone()?;
+        let connection = Arc::new(Connection::new(connection_string, stream));
         let requests = Arc::new(SenderHash::new());
         let orders = Arc::new(SenderHash::new());
 
         let (signals_send, signals_recv) = channel::unbounded();
 
+        // recording is enabled with IBAPI_RECORDING_DIR
+        let observers = MessageObservers::default();
+        observers.add(Arc::new(MessageRecorder::new()));
+
         Ok(TcpMessageBus {
             reader,
-            writer,
+            connection,
             handles: Vec::default(),
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
+            order_ids: Arc::new(AtomicI32::new(-1)),
             requests,
             orders,
-            recorder: MessageRecorder::new(),
+            observers,
             globals: Arc::new(GlobalChannels::new()),
             signals_send,
             signals_recv,
+            heartbeat_interval: None,
+            channel_bound: None,
+            last_received: Arc::new(Mutex::new(Instant::now())),
+            healthy: Arc::new(AtomicBool::new(true)),
+            stats: Arc::new(MessageStats::default()),
+            state: Arc::new(RwLock::new(ConnectionState::Connecting)),
+            cancel_orders_on_disconnect: Arc::new(AtomicBool::new(false)),
         })
     }
 
-    fn add_request(&mut self, request_id: i32, sender: Sender<ResponseMessage>) -> Result<(), Error> {
+    // Requests the server time every interval once message processing starts. The connection is considered stale
+    // and is re-established when a request is not answered before the next one is due.
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self
+    }
+
+    // Gives up re-establishing a lost connection after attempts failed attempts. Zero disables reconnecting.
+    pub fn max_reconnect_attempts(self, attempts: u32) -> Self {
+        self.connection.max_reconnect_attempts.store(attempts, Ordering::Relaxed);
+        self
+    }
+
+    // Buffers at most capacity responses per request or order, applying overflow_policy when a consumer falls behind.
+    pub fn bounded_channels(mut self, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        self.channel_bound = Some((capacity, overflow_policy));
+        self
+    }
+
+    // Creates the channel for the responses to a request or order.
+    fn response_channel(&self) -> (ResponseSender<ResponseMessage>, Receiver<ResponseMessage>) {
+        match self.channel_bound {
+            Some((capacity, overflow_policy)) => {
+                let (sender, receiver) = channel::bounded(capacity);
+                (ResponseSender::bounded(sender, &receiver, overflow_policy), receiver)
+            }
+            None => {
+                let (sender, receiver) = channel::unbounded();
+                (ResponseSender::from(sender), receiver)
+            }
+        }
+    }
+
+    fn add_request(&mut self, request_id: i32, sender: ResponseSender<ResponseMessage>) -> Result<(), Error> {
         self.requests.insert(request_id, sender);
         Ok(())
     }
 
-    fn add_order(&mut self, order_id: i32, sender: Sender<ResponseMessage>) -> Result<(), Error> {
+    fn add_order(&mut self, order_id: i32, sender: ResponseSender<ResponseMessage>) -> Result<(), Error> {
         self.orders.insert(order_id, sender);
         Ok(())
     }
+
+    // Writes message to server, recording the request or order id it belongs to.
+    fn write_request(&mut self, request_id: Option<i32>, message: &RequestMessage) -> Result<(), Error> {
+        let packet = frame_message(message)?;
+
+        // observed before writing so the request precedes its responses in recordings
+        self.observers.on_request(request_id, message);
+
+        self.write_packet(&packet)?;
+
+        Ok(())
+    }
+
+    // Pings the server every interval. Heartbeats are not recorded, they are not part of the client's session.
+    fn start_heartbeat(&mut self, interval: Duration) -> Result<(), Error> {
+        let ping = frame_message(&encode_request_current_time())?;
+        let connection = Arc::clone(&self.connection);
+        let last_received = Arc::clone(&self.last_received);
+        let healthy = Arc::clone(&self.healthy);
+        let shutdown_requested = Arc::clone(&self.shutdown_requested);
+
+        let handle = thread::spawn(move || {
+            let mut ping_sent_at: Option<Instant> = None;
+
+            loop {
+                let next_ping = Instant::now() + interval;
+                while Instant::now() < next_ping {
+                    if shutdown_requested.load(Ordering::SeqCst) {
+                        return 0;
+                    }
+                    thread::sleep(HEARTBEAT_POLL_INTERVAL.min(interval));
+                }
+
+                let unanswered = matches!(ping_sent_at, Some(sent_at) if *last_received.lock().unwrap() < sent_at);
+                if unanswered && healthy.swap(false, Ordering::SeqCst) {
+                    error!("heartbeat not answered within {interval:?}, connection is stale");
+
+                    // the reader sees the closed connection and reconnects
+                    if let Err(err) = connection.writer.lock().unwrap().shutdown(Shutdown::Both) {
+                        error!("error closing stale connection: {err}");
+                    }
+                }
+
+                debug!("heartbeat ->");
+                if let Err(err) = connection.write_all(&ping) {
+                    error!("error sending heartbeat: {err}");
+                }
+                ping_sent_at = Some(Instant::now());
+            }
+        });
+
+        self.handles.push(handle);
+
+        Ok(())
+    }
+
+    // Writes packet to server. Packets written before message processing starts make up the handshake.
+    fn write_packet(&mut self, packet: &[u8]) -> Result<(), Error> {
+        self.connection.write_all(packet)?;
+
+        if self.handles.is_empty() {
+            self.connection.handshake.lock().unwrap().push(packet.to_vec());
+        }
+
+        Ok(())
+    }
 }
 
 const UNSPECIFIED_REQUEST_ID: i32 = -1;
+// Request id of the executions resent by resync_orders, never handed out by Client::next_request_id.
+const RESYNC_REQUEST_ID: i32 = i32::MAX;
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+const HEARTBEAT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 impl MessageBus for TcpMessageBus {
     fn read_message(&mut self) -> Result<ResponseMessage, Error> {
-        read_packet(&self.reader)
+        let message = read_packet(&self.reader)?;
+        self.observers.on_response(&message);
+        Ok(message)
     }
 
     fn send_generic_message(&mut self, request_id: i32, packet: &RequestMessage) -> Result<ResponseIterator, Error> {
-        let (sender, receiver) = channel::unbounded();
+        let (sender, receiver) = self.response_channel();
 
         self.add_request(request_id, sender)?;
-        self.write_message(packet)?;
+        self.write_request(Some(request_id), packet)?;
 
         Ok(ResponseIterator::new(
             receiver,
@@ -153,10 +642,10 @@ impl MessageBus for TcpMessageBus {
     }
 
     fn send_order_message(&mut self, order_id: i32, message: &RequestMessage) -> Result<ResponseIterator, Error> {
-        let (sender, receiver) = channel::unbounded();
+        let (sender, receiver) = self.response_channel();
 
         self.add_order(order_id, sender)?;
-        self.write_message(message)?;
+        self.write_request(Some(order_id), message)?;
 
         Ok(ResponseIterator::new(
             receiver,
@@ -167,77 +656,161 @@ impl MessageBus for TcpMessageBus {
         ))
     }
 
+    fn resend_order_message(&mut self, order_id: i32, message: &RequestMessage) -> Result<(), Error> {
+        if !self.orders.contains(&order_id) {
+            return Err(Error::Simple(format!("order id {order_id} has not been placed by this client")));
+        }
+
+        self.write_request(Some(order_id), message)
+    }
+
     fn request_next_order_id(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         self.write_message(message)?;
-        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.order_ids_out)))
+        Ok(self.globals.responses(&self.globals.order_ids_out))
     }
 
     fn request_open_orders(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        // discards open orders nobody asked for, e.g. resent after a reconnect
+        while self.globals.open_orders_out.try_recv().is_ok() {}
+
         self.write_message(message)?;
-        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.open_orders_out)))
+        Ok(self.globals.responses(&self.globals.open_orders_out))
     }
 
     fn request_market_rule(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         self.write_message(message)?;
-        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_market_rule)))
+        Ok(self.globals.responses(&self.globals.recv_market_rule))
     }
 
     fn request_positions(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         self.write_message(message)?;
-        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_positions)))
+        Ok(self.globals.responses(&self.globals.recv_positions))
+    }
+
+    fn request_account_updates(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(self.globals.responses(&self.globals.recv_account_updates))
     }
 
     fn request_family_codes(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         self.write_message(message)?;
-        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_family_codes)))
+        Ok(self.globals.responses(&self.globals.recv_family_codes))
     }
 
-    fn write_message(&mut self, message: &RequestMessage) -> Result<(), Error> {
-        let data = message.encode();
-        debug!("-> {data:?}");
+    fn request_news_providers(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(self.globals.responses(&self.globals.recv_news_providers))
+    }
 
-        let data = data.as_bytes();
+    fn request_scanner_parameters(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(self.globals.responses(&self.globals.recv_scanner_parameters))
+    }
 
-        let mut packet = Vec::with_capacity(data.len() + 4);
+    fn request_fa(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        // discards a reply left over from an earlier request, replies are not routed by request id
+        while self.globals.recv_fa.try_recv().is_ok() {}
 
-        packet.write_u32::<BigEndian>(data.len() as u32)?;
-        packet.write_all(data)?;
+        self.write_message(message)?;
+        Ok(self.globals.responses(&self.globals.recv_fa))
+    }
 
-        self.writer.write_all(&packet)?;
+    fn request_current_time(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        // discards an answer left over from a heartbeat
+        while self.globals.recv_current_time.try_recv().is_ok() {}
 
-        self.recorder.record_request(message);
+        self.write_message(message)?;
+        Ok(self.globals.responses(&self.globals.recv_current_time))
+    }
 
-        Ok(())
+    fn write_message(&mut self, message: &RequestMessage) -> Result<(), Error> {
+        self.write_request(None, message)
     }
 
     fn write(&mut self, data: &str) -> Result<(), Error> {
         debug!("{data:?} ->");
-        self.writer.write_all(data.as_bytes())?;
-        Ok(())
+        self.write_packet(data.as_bytes())
     }
 
     fn process_messages(&mut self, server_version: i32) -> Result<(), Error> {
-        let reader = Arc::clone(&self.reader);
+        let mut reader = self.reader.try_clone()?;
+        let connection = Arc::clone(&self.connection);
         let requests = Arc::clone(&self.requests);
-        let recorder = self.recorder.clone();
+        let observers = self.observers.clone();
         let orders = Arc::clone(&self.orders);
         let globals = Arc::clone(&self.globals);
         let executions = SenderHash::<String, ResponseMessage>::new();
+        let shutdown_requested = Arc::clone(&self.shutdown_requested);
+        let order_ids = Arc::clone(&self.order_ids);
+        let last_received = Arc::clone(&self.last_received);
+        let healthy = Arc::clone(&self.healthy);
+        let stats = Arc::clone(&self.stats);
+        let state = Arc::clone(&self.state);
+        let cancel_orders_on_disconnect = Arc::clone(&self.cancel_orders_on_disconnect);
+
+        self.connection.server_version.store(server_version, Ordering::SeqCst);
+        set_connection_state(&state, ConnectionState::Connected);
 
         let handle = thread::spawn(move || loop {
-            match read_packet(&reader) {
-                Ok(message) => {
-                    recorder.record_response(&message);
-                    dispatch_message(message, server_version, &requests, &orders, &globals, &executions);
+            let failure = read_messages(&reader, &shutdown_requested, |message| {
+                *last_received.lock().unwrap() = Instant::now();
+                healthy.store(true, Ordering::SeqCst);
+                stats.record(message.message_type());
+
+                // a message that can't be handled must not stop the processing of those that follow
+                let dispatched = panic::catch_unwind(AssertUnwindSafe(|| {
+                    observers.on_response(&message);
+                    dispatch_message(message, server_version, &requests, &orders, &globals, &executions, &order_ids)
+                }));
+                if let Err(cause) = dispatched {
+                    error!("error dispatching message: {}", panic_message(&*cause));
                 }
-                Err(err) => {
-                    error!("error reading packet: {:?}", err);
-                    continue;
+            });
+
+            let err = match failure {
+                Some(err) => err,
+                None => {
+                    set_connection_state(&state, ConnectionState::Disconnected);
+                    return 0;
+                }
+            };
+
+            error!("connection lost: {err}");
+
+            let message = ResponseMessage::connection_lost();
+            requests.notify_all(&message);
+            orders.notify_all(&message);
+
+            let reconnected = match classify_read_error(&err) {
+                ReadFailure::ConnectionLost => {
+                    set_connection_state(&state, ConnectionState::Reconnecting);
+                    connection.reconnect()
                 }
+                _ => Err(Error::Simple(format!("can't recover from read error: {err}"))),
             };
 
-            // FIXME - does read block?
-            // thread::sleep(Duration::from_secs(1));
+            match reconnected {
+                Ok(stream) => {
+                    reader = stream;
+                    set_connection_state(&state, ConnectionState::Connected);
+                    if cancel_orders_on_disconnect.load(Ordering::SeqCst) {
+                        if let Err(err) = cancel_orders(&connection, server_version) {
+                            error!("error cancelling orders after reconnect: {err}");
+                        }
+                    }
+                    if let Err(err) = resync_orders(&connection, server_version, &orders, &globals) {
+                        error!("error resynchronizing orders: {err}");
+                    }
+                }
+                Err(err) => {
+                    error!("{err}");
+                    set_connection_state(&state, ConnectionState::Disconnected);
+                    requests.clear();
+                    orders.clear();
+                    globals.disconnect();
+                    return 0;
+                }
+            }
         });
 
         self.handles.push(handle);
@@ -245,26 +818,161 @@ impl MessageBus for TcpMessageBus {
         let requests = Arc::clone(&self.requests);
         let orders = Arc::clone(&self.orders);
         let signal_recv = self.signals_recv.clone();
+        let shutdown_requested = Arc::clone(&self.shutdown_requested);
 
         let handle = thread::spawn(move || loop {
-            for signal in &signal_recv {
-                match signal {
-                    Signal::Request(request_id) => {
-                        requests.remove(&request_id);
-                        debug!("released request_id {}, requests.len()={}", request_id, requests.len());
-                    }
-                    Signal::Order(order_id) => {
-                        orders.remove(&order_id);
-                        debug!("released order_id {}, orders.len()={}", order_id, requests.len());
-                    }
-                }
+            if shutdown_requested.load(Ordering::SeqCst) {
+                return 0;
+            }
+
+            match signal_recv.recv_timeout(Duration::from_millis(100)) {
+                Ok(signal) => release_sender(signal, &requests, &orders),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return 0,
             }
         });
 
         self.handles.push(handle);
 
+        if let Some(interval) = self.heartbeat_interval {
+            self.start_heartbeat(interval)?;
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), Error> {
+        if self.shutdown_requested.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        set_connection_state(&self.state, ConnectionState::Disconnected);
+
+        // unblocks the reader thread. Fails when the socket is closed already, e.g. after reconnecting failed, the teardown continues regardless.
+        if let Err(err) = self
+            .connection
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .shutdown(Shutdown::Both)
+        {
+            warn!("error shutting down connection: {err}");
+        }
+
+        // dropping the senders ends the iterators of pending requests instead of leaving them to wait out their timeout
+        self.requests.clear();
+        self.orders.clear();
+        self.globals.disconnect();
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while self.handles.iter().any(|handle| !handle.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let (finished, running): (Vec<_>, Vec<_>) = self.handles.drain(..).partition(|handle| handle.is_finished());
+        for handle in finished {
+            if handle.join().is_err() {
+                error!("message processing thread panicked");
+            }
+        }
+
+        if !running.is_empty() {
+            error!("{} message processing threads did not stop within {:?}", running.len(), SHUTDOWN_TIMEOUT);
+        }
+        self.handles = running;
+
         Ok(())
     }
+
+    fn order_ids(&self) -> Arc<AtomicI32> {
+        Arc::clone(&self.order_ids)
+    }
+
+    fn notifications(&self) -> Receiver<Notification> {
+        self.globals.recv_notifications.clone()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::SeqCst)
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        *self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn add_observer(&mut self, observer: Arc<dyn MessageObserver>) {
+        self.observers.add(observer);
+    }
+
+    fn set_cancel_orders_on_disconnect(&mut self, enabled: bool) {
+        self.cancel_orders_on_disconnect.store(enabled, Ordering::SeqCst);
+    }
+
+    fn message_stats(&self) -> HashMap<IncomingMessages, u64> {
+        self.stats.counts()
+    }
+}
+
+fn set_connection_state(state: &RwLock<ConnectionState>, new_state: ConnectionState) {
+    let mut state = state.write().unwrap_or_else(PoisonError::into_inner);
+    if *state != new_state {
+        info!("connection state: {:?} -> {new_state:?}", *state);
+        *state = new_state;
+    }
+}
+
+// Cancels the orders that kept working while the connection was lost, see Client::set_cancel_orders_on_disconnect.
+// Sent before the orders are resynced, so subscribers receive the cancellations.
+fn cancel_orders(connection: &Connection, server_version: i32) -> Result<(), Error> {
+    warn!("cancelling all open orders after reconnect");
+
+    let global_cancel = order_encoders::encode_global_cancel(server_version)?;
+    connection.write_all(&frame_message(&global_cancel)?)
+}
+
+// Subscriptions to orders outlive a reconnect, the orders are still working on the server.
+// Asks for the open orders and the day's executions so that updates missed while disconnected reach the subscribers.
+// Resent executions may have been delivered before the connection was lost, those are skipped by execution id.
+fn resync_orders(
+    connection: &Connection,
+    server_version: i32,
+    orders: &SenderHash<i32, ResponseMessage>,
+    globals: &GlobalChannels,
+) -> Result<(), Error> {
+    // executions of released subscriptions are not routed again
+    globals
+        .seen_executions
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .retain(|_, order_id| orders.contains(order_id));
+
+    if orders.is_empty() {
+        return Ok(());
+    }
+
+    info!("resynchronizing {} order subscriptions", orders.len());
+
+    let open_orders = order_encoders::encode_open_orders()?;
+    connection.write_all(&frame_message(&open_orders)?)?;
+
+    let executions = order_encoders::encode_executions(server_version, RESYNC_REQUEST_ID, &ExecutionFilter::default())?;
+    connection.write_all(&frame_message(&executions)?)?;
+
+    Ok(())
+}
+
+// Removes the sender for a request or order once its consumer is done with it.
+fn release_sender(signal: Signal, requests: &SenderHash<i32, ResponseMessage>, orders: &SenderHash<i32, ResponseMessage>) {
+    match signal {
+        Signal::Request(request_id) => {
+            requests.remove(&request_id);
+            debug!("released request_id {}, requests.len()={}", request_id, requests.len());
+        }
+        Signal::Order(order_id) => {
+            orders.remove(&order_id);
+            debug!("released order_id {}, orders.len()={}", order_id, orders.len());
+        }
+    }
 }
 
 fn dispatch_message(
@@ -274,28 +982,70 @@ fn dispatch_message(
     orders: &Arc<SenderHash<i32, ResponseMessage>>,
     globals: &Arc<GlobalChannels>,
     executions: &SenderHash<String, ResponseMessage>,
+    order_ids: &AtomicI32,
 ) {
     match message.message_type() {
         IncomingMessages::Error => {
             let request_id = message.peek_int(2).unwrap_or(-1);
 
             if request_id == UNSPECIFIED_REQUEST_ID {
-                error_event(server_version, message).unwrap();
+                if let Err(err) = error_event(server_version, message, globals) {
+                    error!("error decoding error event: {err}");
+                }
             } else {
-                process_response(requests, orders, message);
+                process_response(server_version, requests, orders, message);
             }
         }
         IncomingMessages::NextValidId => {
-            globals.order_ids_in.send(message).unwrap();
+            match message.peek_int(2) {
+                Ok(order_id) => order_ids.store(order_id, Ordering::SeqCst),
+                Err(err) => error!("error decoding next valid id: {err}"),
+            }
+            if let Err(e) = globals.order_ids_in.send(message) {
+                error!("error sending IncomingMessages::NextValidId: {e}");
+            }
         }
         IncomingMessages::MarketRule => {
-            globals.send_market_rule.send(message).unwrap();
+            if let Err(e) = globals.send_market_rule.send(message) {
+                error!("error sending IncomingMessages::MarketRule: {e}");
+            }
         }
         IncomingMessages::Position | IncomingMessages::PositionEnd => {
-            globals.send_positions.send(message).unwrap();
+            if let Err(e) = globals.send_positions.send(message) {
+                error!("error sending IncomingMessages::Position: {e}");
+            }
+        }
+        IncomingMessages::AccountValue
+        | IncomingMessages::PortfolioValue
+        | IncomingMessages::AccountUpdateTime
+        | IncomingMessages::AccountDownloadEnd => {
+            if let Err(e) = globals.send_account_updates.send(message) {
+                error!("error sending account update: {e}");
+            }
         }
         IncomingMessages::FamilyCodes => {
-            globals.send_family_codes.send(message).unwrap();
+            if let Err(e) = globals.send_family_codes.send(message) {
+                error!("error sending IncomingMessages::FamilyCodes: {e}");
+            }
+        }
+        IncomingMessages::NewsProviders => {
+            if let Err(e) = globals.send_news_providers.send(message) {
+                error!("error sending IncomingMessages::NewsProviders: {e}");
+            }
+        }
+        IncomingMessages::ScannerParameters => {
+            if let Err(e) = globals.send_scanner_parameters.send(message) {
+                error!("error sending IncomingMessages::ScannerParameters: {e}");
+            }
+        }
+        IncomingMessages::CurrentTime => {
+            // full when nobody is waiting for the answer, e.g. for heartbeats
+            let _ = globals.send_current_time.try_send(message);
+        }
+        IncomingMessages::ReceiveFA | IncomingMessages::ReplaceFAEnd => {
+            if let Err(e) = globals.send_fa.send(message) {
+                error!("error sending financial advisor configuration: {e}");
+            }
         }
 
         IncomingMessages::ManagedAccounts => process_managed_accounts(server_version, message),
@@ -307,13 +1057,100 @@ fn dispatch_message(
         | IncomingMessages::ExecutionData
         | IncomingMessages::ExecutionDataEnd
         | IncomingMessages::CommissionsReport => process_orders(message, requests, orders, executions, globals),
-        _ => process_response(requests, orders, message),
+        _ => process_response(server_version, requests, orders, message),
     };
 }
 
-fn read_packet(mut reader: &TcpStream) -> Result<ResponseMessage, Error> {
-    let message_size = read_header(reader)?;
-    let mut data = vec![0_u8; message_size];
+// Describes the payload of a caught panic.
+fn panic_message(cause: &(dyn Any + Send)) -> String {
+    if let Some(message) = cause.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = cause.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".into()
+    }
+}
+
+// Encodes request for the current server time.
+pub(crate) fn encode_request_current_time() -> RequestMessage {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestCurrentTime);
+    message.push_field(&VERSION);
+
+    message
+}
+
+// Prefixes the encoded message with its length.
+fn frame_message(message: &RequestMessage) -> Result<Vec<u8>, Error> {
+    let data = message.encode();
+    debug!("-> {data:?}");
+
+    let data = data.as_bytes();
+
+    let mut packet = Vec::with_capacity(data.len() + 4);
+
+    packet.write_u32::<BigEndian>(data.len() as u32)?;
+    packet.write_all(data)?;
+
+    Ok(packet)
+}
+
+// How the reader thread proceeds after a failed read.
+#[derive(Debug, PartialEq)]
+enum ReadFailure {
+    // The frame was read but could not be decoded. The next frame can be read.
+    Malformed,
+    // No data was available yet. Reading again may succeed.
+    Transient,
+    // The server closed the connection. Reconnecting may restore it.
+    ConnectionLost,
+    // The connection can't be read from anymore.
+    Fatal,
+}
+
+fn classify_read_error(err: &Error) -> ReadFailure {
+    match err {
+        Error::Io(err) if is_connection_lost(err) => ReadFailure::ConnectionLost,
+        Error::Io(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted | ErrorKind::TimedOut) => ReadFailure::Transient,
+        Error::Io(_) => ReadFailure::Fatal,
+        _ => ReadFailure::Malformed,
+    }
+}
+
+// Reads messages and passes them to dispatch until shutdown is requested, returning None, or reading fails with
+// an error reading again can't resolve, which is returned. Malformed messages are skipped and transient errors retried.
+fn read_messages(mut reader: impl Read, shutdown_requested: &AtomicBool, mut dispatch: impl FnMut(ResponseMessage)) -> Option<Error> {
+    loop {
+        if shutdown_requested.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        match read_packet(&mut reader) {
+            Ok(message) => dispatch(message),
+            Err(_) if shutdown_requested.load(Ordering::SeqCst) => return None,
+            Err(err) => match classify_read_error(&err) {
+                ReadFailure::Malformed => error!("error reading packet: {err:?}"),
+                ReadFailure::Transient => debug!("retrying read: {err}"),
+                ReadFailure::ConnectionLost | ReadFailure::Fatal => return Some(err),
+            },
+        }
+    }
+}
+
+fn is_connection_lost(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+    )
+}
+
+fn read_packet(mut reader: impl Read) -> Result<ResponseMessage, Error> {
+    let message_size = read_header(&mut reader)?;
+    let mut data = vec![0_u8; message_size];
 
     reader.read_exact(&mut data)?;
 
@@ -325,7 +1162,7 @@ fn read_packet(mut reader: &TcpStream) -> Result<ResponseMessage, Error> {
     Ok(packet)
 }
 
-fn read_header(mut reader: &TcpStream) -> Result<usize, Error> {
+fn read_header(reader: &mut impl Read) -> Result<usize, Error> {
     let buffer = &mut [0_u8; 4];
     reader.read_exact(buffer)?;
 
@@ -335,7 +1172,7 @@ fn read_header(mut reader: &TcpStream) -> Result<usize, Error> {
     Ok(count as usize)
 }
 
-fn error_event(server_version: i32, mut packet: ResponseMessage) -> Result<(), Error> {
+fn error_event(server_version: i32, mut packet: ResponseMessage, globals: &GlobalChannels) -> Result<(), Error> {
     packet.skip(); // message_id
 
     let version = packet.next_int()?;
@@ -347,7 +1184,7 @@ fn error_event(server_version: i32, mut packet: ResponseMessage) -> Result<(), E
     } else {
         let request_id = packet.next_int()?;
         let error_code = packet.next_int()?;
-        let error_message = packet.next_string()?;
+        let mut error_message = packet.next_string()?;
 
         // if 322 forward to market_rule_id
 
@@ -355,15 +1192,48 @@ fn error_event(server_version: i32, mut packet: ResponseMessage) -> Result<(), E
         if server_version >= server_versions::ADVANCED_ORDER_REJECT {
             advanced_order_reject_json = packet.next_string()?;
         }
+
+        if server_version >= server_versions::ENCODE_MSG_ASCII7 {
+            error_message = unescape(&error_message);
+            advanced_order_reject_json = unescape(&advanced_order_reject_json);
+        }
         debug!(
             "request_id: {}, error_code: {}, error_message: {}, advanced_order_reject_json: {}",
             request_id, error_code, error_message, advanced_order_reject_json
         );
-        println!("[{error_code}] {error_message}");
+
+        let notification = Notification::new(error_code, error_message, AdvancedOrderReject::parse(&advanced_order_reject_json));
+        match &notification {
+            Notification::Info { code, message } => info!("[{code}] {message}"),
+            Notification::Error { code, message, .. } => error!("[{code}] {message}"),
+        }
+
+        publish_notification(globals, notification);
+
         Ok(())
     }
 }
 
+// Publishes the notification, dropping the oldest one when the channel is full.
+fn publish_notification(globals: &GlobalChannels, mut notification: Notification) {
+    loop {
+        match globals.send_notifications.try_send(notification) {
+            Ok(()) => return,
+            Err(TrySendError::Full(rejected)) => {
+                if let Ok(dropped) = globals.recv_notifications.try_recv() {
+                    debug!("notifications channel full, dropped {dropped:?}");
+                }
+                notification = rejected;
+            }
+            // receiver is held by globals, so sending only fails if the channel is gone entirely
+            Err(TrySendError::Disconnected(_)) => {
+                error!("error publishing notification: channel disconnected");
+                return;
+            }
+        }
+    }
+}
+
 fn process_managed_accounts(_server_version: i32, mut packet: ResponseMessage) {
     packet.skip(); // message_id
     packet.skip(); // version
@@ -372,12 +1242,26 @@ fn process_managed_accounts(_server_version: i32, mut packet: ResponseMessage) {
     info!("managed accounts: {}", managed_accounts)
 }
 
-fn process_response(requests: &Arc<SenderHash<i32, ResponseMessage>>, orders: &Arc<SenderHash<i32, ResponseMessage>>, message: ResponseMessage) {
-    let request_id = message.request_id().unwrap_or(-1); // pass in request id?
+fn process_response(
+    server_version: i32,
+    requests: &Arc<SenderHash<i32, ResponseMessage>>,
+    orders: &Arc<SenderHash<i32, ResponseMessage>>,
+    message: ResponseMessage,
+) {
+    let request_id = message.request_id_for(server_version).unwrap_or(-1);
     if requests.contains(&request_id) {
-        requests.send(&request_id, message).unwrap();
+        if let Err(e) = requests.send(&request_id, message) {
+            error!("error routing message for request_id({request_id}): {e}");
+        }
     } else if orders.contains(&request_id) {
-        orders.send(&request_id, message).unwrap();
+        if let Err(e) = orders.send(&request_id, message) {
+            error!("error routing message for order_id({request_id}): {e}");
+        }
+    } else if requests.recently_removed(&request_id) || orders.recently_removed(&request_id) {
+        // trailing message sent before the server processed the cancellation
+        debug!("discarding message for removed request_id({request_id}): {message:?}");
+    } else {
+        error!("no recipient found for request_id({request_id}): {message:?}");
     }
 }
 
@@ -390,47 +1274,59 @@ fn process_orders(
 ) {
     match message.message_type() {
         IncomingMessages::ExecutionData => {
-            match (message.order_id(), message.request_id()) {
-                // First check matching orders channel
-                (Some(order_id), _) if orders.contains(&order_id) => {
-                    if let Err(e) = orders.send(&order_id, message) {
-                        error!("error routing message for order_id({order_id}): {e}");
-                    }
-                }
-                (_, Some(request_id)) if requests.contains(&request_id) => {
-                    if let Some(sender) = requests.copy_sender(request_id) {
-                        if let Some(execution_id) = message.execution_id() {
-                            executions.insert(execution_id, sender);
+            let resent = message.request_id() == Some(RESYNC_REQUEST_ID);
+            let execution_id = message.execution_id();
+
+            // executions requested with Client::executions carry the request id, executions of placed orders carry -1
+            let (senders, id) = match (message.request_id(), message.order_id()) {
+                (Some(request_id), _) if requests.contains(&request_id) => (requests, request_id),
+                (_, Some(order_id)) if orders.contains(&order_id) => {
+                    if let Some(execution_id) = &execution_id {
+                        let mut seen_executions = globals.seen_executions.lock().unwrap_or_else(PoisonError::into_inner);
+                        if seen_executions.insert(execution_id.clone(), order_id).is_some() && resent {
+                            debug!("discarding resent execution already delivered {message:?}");
+                            return;
                         }
                     }
-
-                    if let Err(e) = requests.send(&request_id, message) {
-                        error!("error routing message for request_id({request_id}): {e}");
+                    (orders, order_id)
+                }
+                (_, order_id) if resent => {
+                    // remembered so that its resent commission report is discarded as well
+                    if let Some(execution_id) = execution_id {
+                        let mut seen_executions = globals.seen_executions.lock().unwrap_or_else(PoisonError::into_inner);
+                        seen_executions.insert(execution_id, order_id.unwrap_or(UNSPECIFIED_REQUEST_ID));
                     }
+                    debug!("discarding resent execution without subscriber {message:?}");
+                    return;
                 }
                 _ => {
                     error!("could not route message {message:?}");
+                    return;
                 }
+            };
+
+            // commission reports only carry the execution id
+            if let (Some(sender), Some(execution_id)) = (senders.copy_sender(id), execution_id) {
+                executions.insert(execution_id, sender);
+            }
+
+            if let Err(e) = senders.send(&id, message) {
+                error!("error routing execution for id({id}): {e}");
             }
         }
-        IncomingMessages::ExecutionDataEnd => {
-            match (message.order_id(), message.request_id()) {
-                // First check matching orders channel
-                (Some(order_id), _) if orders.contains(&order_id) => {
-                    if let Err(e) = orders.send(&order_id, message) {
-                        error!("error routing message for order_id({order_id}): {e}");
-                    }
-                }
-                (_, Some(request_id)) if requests.contains(&request_id) => {
-                    if let Err(e) = requests.send(&request_id, message) {
-                        error!("error routing message for request_id({request_id}): {e}");
-                    }
-                }
-                _ => {
-                    error!("could not route message {message:?}");
+        IncomingMessages::ExecutionDataEnd => match message.request_id() {
+            Some(request_id) if requests.contains(&request_id) => {
+                if let Err(e) = requests.send(&request_id, message) {
+                    error!("error routing message for request_id({request_id}): {e}");
                 }
             }
-        }
+            Some(RESYNC_REQUEST_ID) => {
+                info!("resynchronized order subscriptions");
+            }
+            _ => {
+                error!("could not route message {message:?}");
+            }
+        },
         IncomingMessages::OpenOrder | IncomingMessages::OrderStatus => {
             if let Some(order_id) = message.order_id() {
                 if orders.contains(&order_id) {
@@ -457,66 +1353,219 @@ fn process_orders(
                 error!("error sending IncomingMessages::CompletedOrdersEnd: {e}");
             }
         }
-        IncomingMessages::CommissionsReport => {
-            if let Some(execution_id) = message.execution_id() {
+        IncomingMessages::CommissionsReport => match message.execution_id() {
+            // the report completes the execution, no further messages are routed by its id
+            Some(execution_id) if executions.contains(&execution_id) => {
                 if let Err(e) = executions.send(&execution_id, message) {
-                    error!("error sending commission report for execution {}: {}", execution_id, e);
+                    error!("error sending commission report for execution {execution_id}: {e}");
                 }
+                executions.remove(&execution_id);
             }
-        }
+            Some(execution_id)
+                if globals
+                    .seen_executions
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .contains_key(&execution_id) =>
+            {
+                debug!("discarding resent commission report {message:?}");
+            }
+            _ => {
+                error!("no recipient found for commission report {message:?}");
+            }
+        },
         _ => (),
     }
 }
 
+// How long OverflowPolicy::Block waits for room per response. Bounded, so that a stalled consumer cannot hold up the responses of other requests.
+const BLOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Sends the responses to a request, applying the overflow policy when its channel is bounded and full.
+#[derive(Debug)]
+struct ResponseSender<V> {
+    sender: Sender<V>,
+    overflow_policy: OverflowPolicy,
+    receiver: Option<Receiver<V>>, // used to discard the oldest response, only kept for DropOldest
+}
+
+// Implemented by hand as deriving Clone requires V: Clone, which responses don't need to be.
+impl<V> Clone for ResponseSender<V> {
+    fn clone(&self) -> Self {
+        ResponseSender {
+            sender: self.sender.clone(),
+            overflow_policy: self.overflow_policy,
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<V> ResponseSender<V> {
+    fn bounded(sender: Sender<V>, receiver: &Receiver<V>, overflow_policy: OverflowPolicy) -> Self {
+        // holding on to a receiver otherwise keeps a blocked send from noticing that the consumer is gone
+        let receiver = match overflow_policy {
+            OverflowPolicy::DropOldest => Some(receiver.clone()),
+            _ => None,
+        };
+
+        ResponseSender {
+            sender,
+            overflow_policy,
+            receiver,
+        }
+    }
+
+    // Returns TrySendError::Full when the subscription overflowed and should end.
+    fn send(&self, message: V) -> Result<(), TrySendError<V>> {
+        match self.sender.try_send(message) {
+            Err(TrySendError::Full(message)) => match self.overflow_policy {
+                OverflowPolicy::Block => match self.sender.send_timeout(message, BLOCK_TIMEOUT) {
+                    Err(SendTimeoutError::Timeout(_)) => {
+                        warn!("response channel full for {BLOCK_TIMEOUT:?}, dropping response");
+                        Ok(())
+                    }
+                    Err(SendTimeoutError::Disconnected(message)) => Err(TrySendError::Disconnected(message)),
+                    Ok(()) => Ok(()),
+                },
+                OverflowPolicy::DropOldest => {
+                    if let Some(receiver) = &self.receiver {
+                        let _ = receiver.try_recv();
+                    }
+                    self.sender.try_send(message)
+                }
+                OverflowPolicy::DropNewest => {
+                    debug!("response channel full, dropping response");
+                    Ok(())
+                }
+                OverflowPolicy::Error => Err(TrySendError::Full(message)),
+            },
+            result => result,
+        }
+    }
+}
+
+impl<V> From<Sender<V>> for ResponseSender<V> {
+    fn from(sender: Sender<V>) -> Self {
+        ResponseSender {
+            sender,
+            overflow_policy: OverflowPolicy::Block,
+            receiver: None,
+        }
+    }
+}
+
+// How long messages still in flight for a removed sender are expected, and discarded without logging an error.
+const RECENTLY_REMOVED_TTL: Duration = Duration::from_secs(10);
+
 #[derive(Debug)]
 struct SenderHash<K, V> {
-    data: RwLock<HashMap<K, Sender<V>>>,
+    data: RwLock<HashMap<K, ResponseSender<V>>>,
+    // when the senders were removed, e.g. by cancelling a subscription
+    removed: Mutex<HashMap<K, Instant>>,
 }
 
-impl<K: std::hash::Hash + Eq + std::fmt::Debug, V: std::fmt::Debug> SenderHash<K, V> {
+impl<K: std::hash::Hash + Eq + Clone + std::fmt::Debug, V: std::fmt::Debug> SenderHash<K, V> {
     pub fn new() -> Self {
         Self {
             data: RwLock::new(HashMap::new()),
+            removed: Mutex::new(HashMap::new()),
         }
     }
 
     pub fn send(&self, id: &K, message: V) -> Result<(), Error> {
-        let senders = self.data.read().unwrap();
-        debug!("senders: {senders:?}");
-        if let Some(sender) = senders.get(id) {
-            if let Err(err) = sender.send(message) {
-                error!("error sending: {id:?}, {err}")
-            }
-        } else {
-            error!("no recipient found for: {id:?}, {message:?}")
+        // sent without holding the lock, a blocking send must not keep senders from being released
+        let sender = self.read().get(id).cloned();
+        match sender {
+            Some(sender) => self.deliver(id, &sender, message),
+            None if self.recently_removed(id) => debug!("discarding message for removed recipient: {id:?}, {message:?}"),
+            None => error!("no recipient found for: {id:?}, {message:?}"),
         }
         Ok(())
     }
 
-    pub fn copy_sender(&self, id: K) -> Option<Sender<V>> {
-        let senders = self.data.read().unwrap();
+    // Sends a copy of message to every recipient.
+    pub fn notify_all(&self, message: &V)
+    where
+        V: Clone,
+        K: Clone,
+    {
+        let senders: Vec<(K, ResponseSender<V>)> = self.read().iter().map(|(id, sender)| (id.clone(), sender.clone())).collect();
+        for (id, sender) in senders {
+            self.deliver(&id, &sender, message.clone());
+        }
+    }
+
+    fn deliver(&self, id: &K, sender: &ResponseSender<V>, message: V) {
+        match sender.send(message) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => {
+                error!("response channel full, ending subscription: {id:?}");
+                self.remove(id);
+            }
+            Err(err) => error!("error sending: {id:?}, {err}"),
+        }
+    }
+
+    pub fn copy_sender(&self, id: K) -> Option<ResponseSender<V>> {
+        let senders = self.read();
         senders.get(&id).cloned()
     }
 
-    pub fn insert(&self, id: K, message: Sender<V>) -> Option<Sender<V>> {
-        let mut senders = self.data.write().unwrap();
-        senders.insert(id, message)
+    pub fn insert(&self, id: K, sender: impl Into<ResponseSender<V>>) -> Option<ResponseSender<V>> {
+        let mut senders = self.write();
+        senders.insert(id, sender.into())
+    }
+
+    pub fn remove(&self, id: &K) -> Option<ResponseSender<V>> {
+        let sender = self.write().remove(id);
+
+        if sender.is_some() {
+            let mut removed = self.removed.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            removed.retain(|_, removed_at| now.duration_since(*removed_at) < RECENTLY_REMOVED_TTL);
+            removed.insert(id.clone(), now);
+        }
+
+        sender
+    }
+
+    // Whether the sender of id was removed within RECENTLY_REMOVED_TTL.
+    fn recently_removed(&self, id: &K) -> bool {
+        let removed = self.removed.lock().unwrap_or_else(PoisonError::into_inner);
+        match removed.get(id) {
+            Some(removed_at) => removed_at.elapsed() < RECENTLY_REMOVED_TTL,
+            None => false,
+        }
     }
 
-    pub fn remove(&self, id: &K) -> Option<Sender<V>> {
-        let mut senders = self.data.write().unwrap();
-        senders.remove(id)
+    // Drops all senders, ending the iterators of their recipients.
+    pub fn clear(&self) {
+        let mut senders = self.write();
+        senders.clear()
     }
 
     pub fn contains(&self, id: &K) -> bool {
-        let senders = self.data.read().unwrap();
+        let senders = self.read();
         senders.contains_key(id)
     }
 
     pub fn len(&self) -> usize {
-        let senders = self.data.read().unwrap();
+        let senders = self.read();
         senders.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    // Locks are recovered when poisoned, a panic while dispatching a message must not break routing for all others.
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<K, ResponseSender<V>>> {
+        self.data.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<K, ResponseSender<V>>> {
+        self.data.write().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 #[derive(Debug)]
@@ -526,6 +1575,8 @@ pub(crate) struct ResponseIterator {
     request_id: Option<i32>,             // initiating request_id
     order_id: Option<i32>,               // initiating order_id
     timeout: Duration,                   // How long to wait for next message
+    cancelled: bool,                     // no more messages are returned once cancelled
+    server_version: i32,                 // layout of the error messages received
 }
 
 impl ResponseIterator {
@@ -542,28 +1593,104 @@ impl ResponseIterator {
             request_id,
             order_id,
             timeout,
+            cancelled: false,
+            server_version: server_versions::max_version(),
         }
     }
-}
 
-impl Drop for ResponseIterator {
-    fn drop(&mut self) {
-        if let Some(request_id) = self.request_id {
-            self.signals.send(Signal::Request(request_id)).unwrap();
+    /// Sets how long to wait for the next message. Defaults to 10 seconds.
+    pub(crate) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the server version error messages are decoded for. Defaults to [server_versions::max_version].
+    pub(crate) fn with_server_version(mut self, server_version: i32) -> Self {
+        self.server_version = server_version;
+        self
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Releases the request and order ids so no further messages are routed here. Subsequent calls to next return None.
+    pub(crate) fn cancel(&mut self) {
+        self.release();
+        self.cancelled = true;
+    }
+
+    fn release(&mut self) {
+        if let Some(request_id) = self.request_id.take() {
+            if let Err(e) = self.signals.send(Signal::Request(request_id)) {
+                error!("error releasing request_id({request_id}): {e}");
+            }
+        }
+
+        if let Some(order_id) = self.order_id.take() {
+            if let Err(e) = self.signals.send(Signal::Order(order_id)) {
+                error!("error releasing order_id({order_id}): {e}");
+            }
+        }
+    }
+
+    /// Returns the next message if one is queued, without waiting.
+    pub(crate) fn try_next(&mut self) -> TryNext<ResponseMessage> {
+        if self.cancelled {
+            return TryNext::Ended;
+        }
+
+        self.messages.try_recv().into()
+    }
+
+    /// Waits for the next message, returning [Error::Timeout] if none arrives within the timeout.
+    pub(crate) fn recv_timeout(&mut self) -> Result<ResponseMessage, Error> {
+        if self.cancelled {
+            return Err(Error::Simple("request cancelled".into()));
+        }
+
+        match self.messages.recv_timeout(self.timeout) {
+            Ok(message) => Ok(message),
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::Simple("response channel disconnected".into())),
+        }
+    }
+
+    /// Waits for the next message until the deadline, returning [Error::Timeout] if none arrives before it.
+    pub(crate) fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error> {
+        if self.cancelled {
+            return Err(Error::Simple("request cancelled".into()));
         }
 
-        if let Some(order_id) = self.order_id {
-            self.signals.send(Signal::Order(order_id)).unwrap();
+        match self.messages.recv_deadline(deadline) {
+            Ok(message) => Ok(message),
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::Simple("response channel disconnected".into())),
+        }
+    }
+
+    /// Returns the next message, decoding TWS error messages for the request into [Error::Api].
+    pub(crate) fn next_result(&mut self) -> Option<Result<ResponseMessage, Error>> {
+        match self.next() {
+            Some(message) if message.message_type() == IncomingMessages::Error => Some(Err(message.api_error_for(self.server_version))),
+            Some(message) => Some(Ok(message)),
+            None => None,
         }
     }
 }
 
+impl Drop for ResponseIterator {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
 impl Iterator for ResponseIterator {
     type Item = ResponseMessage;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.messages.recv_timeout(self.timeout) {
+        match self.recv_timeout() {
             Err(err) => {
-                info!("timeout receiving packet: {err}");
+                info!("error receiving packet: {err}");
                 None
             }
             Ok(message) => Some(message),
@@ -571,23 +1698,371 @@ impl Iterator for ResponseIterator {
     }
 }
 
+/// Outcome of polling a [Subscription] without waiting, see [Subscription::try_next].
+#[derive(Debug, PartialEq)]
+pub enum TryNext<T> {
+    /// The next value of the subscription.
+    Value(T),
+    /// No value is queued. The subscription is active and more values may arrive.
+    Empty,
+    /// The subscription has ended. The server sent its end marker, the subscription was cancelled or the connection was closed.
+    /// No more values will arrive.
+    Ended,
+}
+
+impl<T> From<Result<T, TryRecvError>> for TryNext<T> {
+    fn from(result: Result<T, TryRecvError>) -> Self {
+        match result {
+            Ok(value) => TryNext::Value(value),
+            Err(TryRecvError::Empty) => TryNext::Empty,
+            Err(TryRecvError::Disconnected) => TryNext::Ended,
+        }
+    }
+}
+
+/// Subscription to values streamed by the server, e.g. market data or positions.
+///
+/// Iterating a subscription waits a limited time for each value, see [Subscription::set_timeout]. [Iterator::next] returns None
+/// both when the subscription ends and when no value arrives in time, use [Subscription::next_timeout] to tell them apart.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use ibapi::client::Subscription;
+/// use ibapi::contracts::Contract;
+/// use ibapi::market_data::realtime::{BarSize, WhatToShow};
+/// use ibapi::{Client, Error};
+///
+/// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+///
+/// let contract = Contract::stock("AAPL");
+/// let mut bars = client
+///     .realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, false)
+///     .expect("request failed");
+///
+/// loop {
+///     match bars.next_timeout(Duration::from_secs(30)) {
+///         Ok(Some(bar)) => println!("bar: {bar:?}"),
+///         Ok(None) => break,
+///         Err(Error::Timeout) => println!("no bar in the last 30 seconds"),
+///         Err(err) => panic!("{err}"),
+///     }
+/// }
+/// ```
+pub trait Subscription: Iterator {
+    /// Sets how long [Iterator::next] waits for the next value before returning None.
+    fn set_timeout(&mut self, timeout: Duration);
+
+    /// Waits up to timeout for the next value.
+    ///
+    /// Returns Ok(None) when the subscription has ended, and [Error::Timeout] when no value arrived within timeout.
+    /// The subscription remains active after a timeout.
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error>;
+
+    /// Waits until the deadline for the next value. Like [Subscription::next_timeout], for waiting on several subscriptions
+    /// up to a common deadline.
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error>;
+
+    /// Returns the next value if one was received, without waiting. Allows polling several subscriptions from one thread.
+    fn try_next(&mut self) -> TryNext<Self::Item>;
+}
+
+// Source of the responses decoded by a DecodedSubscription. Either the responses to a request,
+// or a channel shared by all requests of a kind, e.g. positions.
+pub(crate) trait ResponseSource: Send {
+    // Waits for the next message until the deadline, returning Error::Timeout if none arrives before it.
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error>;
+
+    // Returns the next message if one is queued, without waiting.
+    fn try_recv(&mut self) -> TryNext<ResponseMessage>;
+
+    fn timeout(&self) -> Duration;
+
+    fn set_timeout(&mut self, timeout: Duration);
+
+    // Stops receiving messages, releasing the request if routed by request id.
+    fn cancel(&mut self);
+}
+
+impl ResponseSource for ResponseIterator {
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error> {
+        ResponseIterator::recv_deadline(self, deadline)
+    }
+
+    fn try_recv(&mut self) -> TryNext<ResponseMessage> {
+        self.try_next()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        ResponseIterator::set_timeout(self, timeout)
+    }
+
+    fn cancel(&mut self) {
+        ResponseIterator::cancel(self)
+    }
+}
+
+impl ResponseSource for GlobalResponseIterator {
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error> {
+        GlobalResponseIterator::recv_deadline(self, deadline)
+    }
+
+    fn try_recv(&mut self) -> TryNext<ResponseMessage> {
+        self.try_next()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+// Decodes a response into a value, or None for the end marker of the response.
+type Decoder<T> = Box<dyn FnMut(&mut ResponseMessage) -> Result<Option<T>, Error> + Send>;
+
+// Decodes the responses to a request into typed values, so request types only need to supply their decoder.
+// The decoder returns None for the end marker of the response, which ends the iteration and releases the request.
+// Messages that fail to decode are logged and skipped. A lost connection ends the iteration before reaching the decoder,
+// as requests are not resent after reconnecting.
+pub(crate) struct DecodedSubscription<T> {
+    responses: Box<dyn ResponseSource>,
+    decode: Decoder<T>,
+    ignored: &'static [IncomingMessages], // informational messages skipped without decoding
+    cancelled: bool,                      // set by cancel, not when the end marker is received
+    ended: bool,                          // set when the end marker is received or the connection is lost
+    connection_lost: bool,
+}
+
+impl<T> DecodedSubscription<T> {
+    pub(crate) fn new(
+        responses: impl ResponseSource + 'static,
+        decode: impl FnMut(&mut ResponseMessage) -> Result<Option<T>, Error> + Send + 'static,
+    ) -> Self {
+        DecodedSubscription {
+            responses: Box::new(responses),
+            decode: Box::new(decode),
+            ignored: &[],
+            cancelled: false,
+            ended: false,
+            connection_lost: false,
+        }
+    }
+
+    // Skips messages of the given types, e.g. parameters the server sends along with market data.
+    pub(crate) fn ignoring(mut self, message_types: &'static [IncomingMessages]) -> Self {
+        self.ignored = message_types;
+        self
+    }
+
+    // Stops the iteration and releases the request. Subsequent calls to next return None.
+    pub(crate) fn cancel(&mut self) {
+        self.responses.cancel();
+        self.cancelled = true;
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    pub(crate) fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout);
+    }
+
+    pub(crate) fn next_timeout(&mut self, timeout: Duration) -> Result<Option<T>, Error> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+
+    // Returns the next value, None once the response ended, or Error::Timeout if no value arrives before the deadline.
+    // Returns Error::ConnectionReset when the connection was lost, the subscription has to be requested again.
+    pub(crate) fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<T>, Error> {
+        loop {
+            let message = match self.responses.recv_deadline(deadline) {
+                Ok(message) => message,
+                Err(Error::Timeout) => return Err(Error::Timeout),
+                Err(err) => {
+                    debug!("subscription ended: {err}");
+                    return Ok(None);
+                }
+            };
+
+            match self.decode_message(message) {
+                TryNext::Value(value) => return Ok(Some(value)),
+                TryNext::Ended if self.connection_lost => return Err(Error::ConnectionReset),
+                TryNext::Ended => return Ok(None),
+                TryNext::Empty => (),
+            }
+        }
+    }
+
+    pub(crate) fn try_next(&mut self) -> TryNext<T> {
+        loop {
+            let message = match self.responses.try_recv() {
+                TryNext::Value(message) => message,
+                TryNext::Empty => return TryNext::Empty,
+                TryNext::Ended => return TryNext::Ended,
+            };
+
+            match self.decode_message(message) {
+                TryNext::Empty => (),
+                decoded => return decoded,
+            }
+        }
+    }
+
+    // Decodes a message into the next value, Ended for the end marker, or Empty for messages that are skipped.
+    fn decode_message(&mut self, mut message: ResponseMessage) -> TryNext<T> {
+        if message.message_type() == IncomingMessages::ConnectionLost {
+            warn!("connection lost, ending subscription");
+            self.responses.cancel();
+            self.ended = true;
+            self.connection_lost = true;
+            return TryNext::Ended;
+        }
+
+        if self.ignored.contains(&message.message_type()) {
+            debug!("ignoring message {message:?}");
+            return TryNext::Empty;
+        }
+
+        match (self.decode)(&mut message) {
+            Ok(Some(value)) => TryNext::Value(value),
+            Ok(None) => {
+                self.responses.cancel();
+                self.ended = true;
+                TryNext::Ended
+            }
+            Err(err) => {
+                error!("error decoding message {message:?}: {err}");
+                TryNext::Empty
+            }
+        }
+    }
+}
+
+impl<T> Iterator for DecodedSubscription<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.recv_deadline(Instant::now() + self.responses.timeout()) {
+            Ok(value) => value,
+            Err(err) => {
+                info!("error receiving message: {err}");
+                None
+            }
+        }
+    }
+}
+
+impl<T> Subscription for DecodedSubscription<T> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        DecodedSubscription::set_timeout(self, timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<T>, Error> {
+        DecodedSubscription::next_timeout(self, timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<T>, Error> {
+        DecodedSubscription::recv_deadline(self, deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<T> {
+        DecodedSubscription::try_next(self)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct GlobalResponseIterator {
     messages: Arc<Receiver<ResponseMessage>>,
+    disconnected: Option<Receiver<()>>, // disconnects when the message bus does
+    timeout: Duration,                  // How long to wait for next message
+    cancelled: bool,                    // no more messages are returned once cancelled
 }
 
 impl GlobalResponseIterator {
     pub fn new(messages: Arc<Receiver<ResponseMessage>>) -> Self {
-        Self { messages }
+        Self {
+            messages,
+            disconnected: None,
+            timeout: Duration::from_secs(5),
+            cancelled: false,
+        }
+    }
+
+    // Ends the iteration once disconnected is disconnected, after the messages already received.
+    fn until(mut self, disconnected: Receiver<()>) -> Self {
+        self.disconnected = Some(disconnected);
+        self
+    }
+
+    /// Returns the next message if one is queued, without waiting.
+    pub(crate) fn try_next(&mut self) -> TryNext<ResponseMessage> {
+        if self.cancelled {
+            return TryNext::Ended;
+        }
+
+        match self.messages.try_recv() {
+            Err(TryRecvError::Empty) if self.is_disconnected() => TryNext::Ended,
+            result => result.into(),
+        }
+    }
+
+    fn is_disconnected(&self) -> bool {
+        match &self.disconnected {
+            Some(disconnected) => matches!(disconnected.try_recv(), Err(TryRecvError::Disconnected)),
+            None => false,
+        }
+    }
+
+    /// Waits for the next message until the deadline, returning [Error::Timeout] if none arrives before it.
+    pub(crate) fn recv_deadline(&mut self, deadline: Instant) -> Result<ResponseMessage, Error> {
+        if self.cancelled {
+            return Err(Error::Simple("request cancelled".into()));
+        }
+
+        let Some(disconnected) = &self.disconnected else {
+            return match self.messages.recv_deadline(deadline) {
+                Ok(message) => Ok(message),
+                Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+                Err(RecvTimeoutError::Disconnected) => Err(Error::Simple("response channel disconnected".into())),
+            };
+        };
+
+        if let Ok(message) = self.messages.try_recv() {
+            return Ok(message);
+        }
+
+        channel::select! {
+            recv(self.messages.as_ref()) -> message => message.map_err(|_| Error::Simple("response channel disconnected".into())),
+            recv(disconnected) -> _ => Err(Error::Simple("message bus disconnected".into())),
+            default(deadline.saturating_duration_since(Instant::now())) => Err(Error::Timeout),
+        }
     }
 }
 
 impl Iterator for GlobalResponseIterator {
     type Item = ResponseMessage;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.messages.recv_timeout(Duration::from_secs(5)) {
+        match self.recv_deadline(Instant::now() + self.timeout) {
             Err(err) => {
-                info!("timeout receiving packet: {err}");
+                info!("error receiving packet: {err}");
                 None
             }
             Ok(message) => Some(message),
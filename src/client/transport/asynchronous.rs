@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use futures::Stream;
+use log::{debug, error, info};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task::JoinHandle;
+
+use super::{classify_read_error, frame_message, ReadFailure, UNSPECIFIED_REQUEST_ID};
+use crate::messages::{IncomingMessages, RequestMessage, ResponseMessage};
+use crate::Error;
+
+type Senders = Arc<Mutex<HashMap<i32, UnboundedSender<ResponseMessage>>>>;
+
+// Async counterpart of MessageBus. Responses are delivered as streams instead of blocking iterators.
+#[async_trait]
+pub(crate) trait AsyncMessageBus: Send + Sync {
+    // Reads the next message. Only available until message processing starts.
+    async fn read_message(&mut self) -> Result<ResponseMessage, Error>;
+
+    async fn write_message(&self, message: &RequestMessage) -> Result<(), Error>;
+
+    async fn write(&self, packet: &str) -> Result<(), Error>;
+
+    async fn send_generic_message(&self, request_id: i32, message: &RequestMessage) -> Result<ResponseStream, Error>;
+
+    async fn request_current_time(&self, message: &RequestMessage) -> Result<ResponseStream, Error>;
+
+    // Writes message from a background task. Used where awaiting is not possible, e.g. to cancel a subscription when it is dropped.
+    fn send_detached(&self, message: RequestMessage);
+
+    // Spawns the task dispatching incoming messages to their streams.
+    fn process_messages(&mut self, server_version: i32) -> Result<(), Error>;
+
+    // Stops message processing and closes the connection.
+    async fn disconnect(&self) -> Result<(), Error>;
+
+    // Next valid order id. Updated whenever the server sends NextValidId.
+    fn order_ids(&self) -> Arc<AtomicI32>;
+}
+
+pub(crate) struct AsyncTcpMessageBus {
+    reader: Option<OwnedReadHalf>,
+    writer: Arc<AsyncMutex<OwnedWriteHalf>>,
+    requests: Senders,
+    current_time: Arc<Mutex<Option<UnboundedSender<ResponseMessage>>>>, // waiting for the answer to a current time request
+    order_ids: Arc<AtomicI32>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AsyncTcpMessageBus {
+    // establishes TCP connection to server
+    pub async fn connect(connection_string: &str) -> Result<AsyncTcpMessageBus, Error> {
+        let stream = TcpStream::connect(connection_string).await?;
+        let (reader, writer) = stream.into_split();
+
+        Ok(AsyncTcpMessageBus {
+            reader: Some(reader),
+            writer: Arc::new(AsyncMutex::new(writer)),
+            requests: Arc::new(Mutex::new(HashMap::new())),
+            current_time: Arc::new(Mutex::new(None)),
+            order_ids: Arc::new(AtomicI32::new(-1)),
+            handle: Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncMessageBus for AsyncTcpMessageBus {
+    async fn read_message(&mut self) -> Result<ResponseMessage, Error> {
+        match self.reader.as_mut() {
+            Some(reader) => read_packet(reader).await,
+            None => Err(Error::Simple("messages are read by the dispatch task once processing starts".into())),
+        }
+    }
+
+    async fn write_message(&self, message: &RequestMessage) -> Result<(), Error> {
+        let packet = frame_message(message)?;
+        write_packet(&self.writer, &packet).await
+    }
+
+    async fn write(&self, data: &str) -> Result<(), Error> {
+        debug!("{data:?} ->");
+        write_packet(&self.writer, data.as_bytes()).await
+    }
+
+    async fn send_generic_message(&self, request_id: i32, message: &RequestMessage) -> Result<ResponseStream, Error> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        self.requests.lock().unwrap().insert(request_id, sender);
+
+        // the stream releases the request when dropped, including when the write fails
+        let stream = ResponseStream::new(receiver, Some((request_id, Arc::clone(&self.requests))));
+        self.write_message(message).await?;
+
+        Ok(stream)
+    }
+
+    async fn request_current_time(&self, message: &RequestMessage) -> Result<ResponseStream, Error> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        *self.current_time.lock().unwrap() = Some(sender);
+        self.write_message(message).await?;
+
+        Ok(ResponseStream::new(receiver, None))
+    }
+
+    fn send_detached(&self, message: RequestMessage) {
+        let packet = match frame_message(&message) {
+            Ok(packet) => packet,
+            Err(err) => {
+                error!("error encoding message: {err}");
+                return;
+            }
+        };
+
+        match Handle::try_current() {
+            Ok(runtime) => {
+                let writer = Arc::clone(&self.writer);
+                runtime.spawn(async move {
+                    if let Err(err) = write_packet(&writer, &packet).await {
+                        error!("error sending message: {err}");
+                    }
+                });
+            }
+            Err(err) => error!("could not send message outside of a tokio runtime: {err}"),
+        }
+    }
+
+    fn process_messages(&mut self, server_version: i32) -> Result<(), Error> {
+        let mut reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => return Err(Error::Simple("message processing already started".into())),
+        };
+
+        let requests = Arc::clone(&self.requests);
+        let current_time = Arc::clone(&self.current_time);
+        let order_ids = Arc::clone(&self.order_ids);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                match read_packet(&mut reader).await {
+                    Ok(message) => dispatch_message(message, server_version, &requests, &current_time, &order_ids),
+                    Err(err) => {
+                        match classify_read_error(&err) {
+                            ReadFailure::Transient => {
+                                debug!("retrying read: {err}");
+                                continue;
+                            }
+                            ReadFailure::ConnectionLost => error!("connection lost: {err}"),
+                            // the start of the next frame is unknown once a frame can't be read
+                            ReadFailure::Malformed | ReadFailure::Fatal => error!("error reading packet, closing connection: {err:?}"),
+                        }
+
+                        end_streams(&requests, &current_time);
+                        return;
+                    }
+                }
+            }
+        });
+
+        *self.handle.lock().unwrap() = Some(handle);
+
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<(), Error> {
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.abort();
+        }
+
+        self.writer.lock().await.shutdown().await?;
+
+        Ok(())
+    }
+
+    fn order_ids(&self) -> Arc<AtomicI32> {
+        Arc::clone(&self.order_ids)
+    }
+}
+
+// Ends every stream after telling it the connection was lost.
+fn end_streams(requests: &Senders, current_time: &Mutex<Option<UnboundedSender<ResponseMessage>>>) {
+    let message = ResponseMessage::connection_lost();
+    for (_, sender) in requests.lock().unwrap().drain() {
+        let _ = sender.send(message.clone());
+    }
+    current_time.lock().unwrap().take();
+}
+
+fn dispatch_message(
+    message: ResponseMessage,
+    server_version: i32,
+    requests: &Senders,
+    current_time: &Mutex<Option<UnboundedSender<ResponseMessage>>>,
+    order_ids: &AtomicI32,
+) {
+    match message.message_type() {
+        IncomingMessages::Error if message.peek_int(2).unwrap_or(UNSPECIFIED_REQUEST_ID) == UNSPECIFIED_REQUEST_ID => {
+            let code = message.peek_int(3).unwrap_or_default();
+            let text = message.peek_string(4);
+            if (2100..=2200).contains(&code) {
+                info!("[{code}] {text}");
+            } else {
+                error!("[{code}] {text}");
+            }
+        }
+        IncomingMessages::NextValidId => match message.peek_int(2) {
+            Ok(order_id) => order_ids.store(order_id, Ordering::SeqCst),
+            Err(err) => error!("error decoding next valid id: {err}"),
+        },
+        IncomingMessages::CurrentTime => {
+            // dropped when nobody is waiting for the answer
+            if let Some(sender) = current_time.lock().unwrap().take() {
+                let _ = sender.send(message);
+            }
+        }
+        IncomingMessages::ManagedAccounts => debug!("managed accounts: {message:?}"),
+        _ => process_response(server_version, requests, message),
+    }
+}
+
+fn process_response(server_version: i32, requests: &Senders, message: ResponseMessage) {
+    let request_id = match message.request_id_for(server_version) {
+        Some(request_id) => request_id,
+        None => {
+            error!("could not route message {message:?}");
+            return;
+        }
+    };
+
+    match requests.lock().unwrap().get(&request_id) {
+        Some(sender) => {
+            if let Err(err) = sender.send(message) {
+                error!("error routing message for request_id({request_id}): {err}");
+            }
+        }
+        None => error!("no recipient found for: {request_id}, {message:?}"),
+    }
+}
+
+async fn write_packet(writer: &AsyncMutex<OwnedWriteHalf>, packet: &[u8]) -> Result<(), Error> {
+    let mut writer = writer.lock().await;
+    writer.write_all(packet).await?;
+    Ok(())
+}
+
+async fn read_packet(reader: &mut OwnedReadHalf) -> Result<ResponseMessage, Error> {
+    let message_size = reader.read_u32().await? as usize;
+    let mut data = vec![0_u8; message_size];
+
+    reader.read_exact(&mut data).await?;
+
+    let raw_string = String::from_utf8(data)?;
+    debug!("<- {:?}", raw_string);
+
+    Ok(ResponseMessage::from(&raw_string))
+}
+
+// Async sibling of ResponseIterator. Messages for the request are yielded as a Stream.
+pub(crate) struct ResponseStream {
+    messages: UnboundedReceiver<ResponseMessage>,
+    request: Option<(i32, Senders)>, // released when the stream is dropped
+}
+
+impl ResponseStream {
+    fn new(messages: UnboundedReceiver<ResponseMessage>, request: Option<(i32, Senders)>) -> Self {
+        ResponseStream { messages, request }
+    }
+}
+
+impl Stream for ResponseStream {
+    type Item = ResponseMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.poll_recv(cx)
+    }
+}
+
+impl Drop for ResponseStream {
+    fn drop(&mut self) {
+        if let Some((request_id, requests)) = self.request.take() {
+            requests.lock().unwrap().remove(&request_id);
+            debug!("released request_id {request_id}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,126 @@
+use crate::server_versions;
+
+use super::*;
+
+#[test]
+fn dispatches_response_to_request() {
+    let requests: Senders = Arc::new(Mutex::new(HashMap::new()));
+    let current_time = Mutex::new(None);
+    let order_ids = AtomicI32::new(-1);
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    requests.lock().unwrap().insert(9000, sender);
+
+    let message = ResponseMessage::from("50\03\09000\01678323335\04028.75\04029.00\04028.25\04028.50\02\04026.75\01\0");
+    dispatch_message(message, server_versions::SIZE_RULES, &requests, &current_time, &order_ids);
+
+    match receiver.try_recv() {
+        Ok(message) => assert_eq!(message.message_type(), IncomingMessages::RealTimeBars, "message.message_type()"),
+        Err(err) => assert!(false, "expected message for request 9000: {err}"),
+    }
+}
+
+#[test]
+fn dispatches_current_time_to_waiting_request() {
+    let requests: Senders = Arc::new(Mutex::new(HashMap::new()));
+    let order_ids = AtomicI32::new(-1);
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let current_time = Mutex::new(Some(sender));
+
+    dispatch_message(
+        ResponseMessage::from("49\01\01678890000\0"),
+        server_versions::SIZE_RULES,
+        &requests,
+        &current_time,
+        &order_ids,
+    );
+    dispatch_message(
+        ResponseMessage::from("49\01\01678890001\0"),
+        server_versions::SIZE_RULES,
+        &requests,
+        &current_time,
+        &order_ids,
+    );
+
+    assert!(receiver.try_recv().is_ok(), "expected answer to current time request");
+    assert!(receiver.try_recv().is_err(), "unrequested answer should be dropped");
+}
+
+#[test]
+fn tracks_next_valid_id() {
+    let requests: Senders = Arc::new(Mutex::new(HashMap::new()));
+    let current_time = Mutex::new(None);
+    let order_ids = AtomicI32::new(-1);
+
+    dispatch_message(
+        ResponseMessage::from("9\01\0120\0"),
+        server_versions::SIZE_RULES,
+        &requests,
+        &current_time,
+        &order_ids,
+    );
+
+    assert_eq!(order_ids.load(Ordering::SeqCst), 120, "order_ids");
+}
+
+#[test]
+fn releases_request_when_stream_dropped() {
+    let requests: Senders = Arc::new(Mutex::new(HashMap::new()));
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    requests.lock().unwrap().insert(9000, sender);
+
+    let stream = ResponseStream::new(receiver, Some((9000, Arc::clone(&requests))));
+    drop(stream);
+
+    assert!(!requests.lock().unwrap().contains_key(&9000), "request 9000 should be released");
+}
+
+#[tokio::test]
+async fn ends_streams_when_frame_cannot_be_read() {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+
+    use crate::client::transport::encode_request_current_time;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // answers the request with a frame that is not valid UTF-8
+        let size = stream.read_u32().await.unwrap() as usize;
+        stream.read_exact(&mut vec![0_u8; size]).await.unwrap();
+        stream.write_u32(2).await.unwrap();
+        stream.write_all(&[0xff, 0xfe]).await.unwrap();
+
+        // keeps the connection open, so only the malformed frame can end the stream
+        let _ = stream.read_u32().await;
+    });
+
+    let mut message_bus = AsyncTcpMessageBus::connect(&address).await.expect("failed to connect");
+    message_bus
+        .process_messages(server_versions::SIZE_RULES)
+        .expect("failed to process messages");
+
+    let mut stream = message_bus
+        .send_generic_message(9000, &encode_request_current_time())
+        .await
+        .expect("failed to send request");
+
+    match tokio::time::timeout(Duration::from_secs(5), stream.next()).await {
+        Ok(Some(message)) => assert_eq!(message.message_type(), IncomingMessages::ConnectionLost, "message.message_type()"),
+        Ok(None) => assert!(false, "expected connection lost message"),
+        Err(_) => assert!(false, "timed out waiting for stream to end"),
+    }
+
+    assert!(
+        matches!(tokio::time::timeout(Duration::from_secs(5), stream.next()).await, Ok(None)),
+        "stream should end"
+    );
+    assert!(message_bus.requests.lock().unwrap().is_empty(), "requests should be released");
+}
@@ -1,18 +1,22 @@
 use std::env;
-use std::fs;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 
+use log::error;
 use time::macros::format_description;
 use time::OffsetDateTime;
 
-use super::{RequestMessage, ResponseMessage};
+use super::{MessageObserver, RequestMessage, ResponseMessage};
 
-static RECORDING_SEQ: AtomicUsize = AtomicUsize::new(0);
+const INDEX_FILE: &str = "index.jsonl";
 
 #[derive(Clone, Debug)]
 pub(crate) struct MessageRecorder {
     enabled: bool,
     recording_dir: String,
+    // Next sequence number. Held while a message is written so the index is in sequence order.
+    next_seq: Arc<Mutex<usize>>,
 }
 
 impl MessageRecorder {
@@ -20,46 +24,71 @@ impl MessageRecorder {
         match env::var("IBAPI_RECORDING_DIR") {
             Ok(dir) => {
                 if dir.is_empty() {
-                    MessageRecorder {
-                        enabled: false,
-                        recording_dir: String::from(""),
-                    }
+                    MessageRecorder::disabled()
                 } else {
                     let format = format_description!("[year]-[month]-[day]-[hour]-[minute]");
                     let now = OffsetDateTime::now_utc();
-                    let recording_dir = format!("{}/{}", dir, now.format(&format).unwrap());
-
-                    fs::create_dir_all(&recording_dir).unwrap();
-
-                    MessageRecorder {
-                        enabled: true,
-                        recording_dir,
-                    }
+                    MessageRecorder::with_dir(&format!("{}/{}", dir, now.format(&format).unwrap()))
                 }
             }
-            _ => MessageRecorder {
-                enabled: false,
-                recording_dir: String::from(""),
-            },
+            _ => MessageRecorder::disabled(),
         }
     }
 
-    pub fn record_request(&self, message: &RequestMessage) {
-        if !self.enabled {
-            return;
+    // Records messages to the given directory.
+    pub fn with_dir(recording_dir: &str) -> Self {
+        fs::create_dir_all(recording_dir).unwrap();
+
+        MessageRecorder {
+            enabled: true,
+            recording_dir: recording_dir.to_owned(),
+            next_seq: Arc::new(Mutex::new(0)),
         }
+    }
 
-        let record_id = RECORDING_SEQ.fetch_add(1, Ordering::SeqCst);
-        fs::write(self.request_file(record_id), message.encode().replace('\0', "|")).unwrap();
+    fn disabled() -> Self {
+        MessageRecorder {
+            enabled: false,
+            recording_dir: String::from(""),
+            next_seq: Arc::new(Mutex::new(0)),
+        }
     }
 
-    pub fn record_response(&self, message: &ResponseMessage) {
-        if !self.enabled {
+    // Writes the message file and appends its entry to the index, in sequence order.
+    fn record(&self, direction: Direction, request_id: Option<i32>, message_type: i32, data: &str) {
+        let mut next_seq = self.next_seq.lock().unwrap();
+
+        let seq = *next_seq;
+        *next_seq += 1;
+
+        let file = match direction {
+            Direction::Request => self.request_file(seq),
+            Direction::Response => self.response_file(seq),
+        };
+
+        if let Err(err) = fs::write(&file, data.replace('\0', "|")) {
+            error!("error recording message to {file}: {err}");
             return;
         }
 
-        let record_id = RECORDING_SEQ.fetch_add(1, Ordering::SeqCst);
-        fs::write(self.response_file(record_id), message.encode().replace('\0', "|")).unwrap();
+        let entry = IndexEntry {
+            seq,
+            direction,
+            request_id,
+            message_type,
+            filename: file.rsplit('/').next().unwrap_or(&file),
+        };
+
+        let index_file = format!("{}/{}", self.recording_dir, INDEX_FILE);
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index_file)
+            .and_then(|mut index| writeln!(index, "{}", entry.to_json()));
+
+        if let Err(err) = appended {
+            error!("error appending to {index_file}: {err}");
+        }
     }
 
     fn request_file(&self, record_id: usize) -> String {
@@ -71,5 +100,64 @@ impl MessageRecorder {
     }
 }
 
+// Records messages to disk, for replay by the ReplayMessageBus.
+impl MessageObserver for MessageRecorder {
+    fn on_request(&self, request_id: Option<i32>, message: &RequestMessage) {
+        if !self.enabled {
+            return;
+        }
+
+        let message_type = message[0].parse().unwrap_or(-1);
+        self.record(Direction::Request, request_id, message_type, &message.encode());
+    }
+
+    fn on_response(&self, message: &ResponseMessage) {
+        if !self.enabled {
+            return;
+        }
+
+        self.record(
+            Direction::Response,
+            message.request_id(),
+            message.message_type() as i32,
+            &message.encode(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Direction {
+    Request,
+    Response,
+}
+
+// Line of the index file, recording the wire order of a message.
+struct IndexEntry<'a> {
+    seq: usize,
+    direction: Direction,
+    request_id: Option<i32>,
+    message_type: i32,
+    filename: &'a str,
+}
+
+impl IndexEntry<'_> {
+    fn to_json(&self) -> String {
+        let direction = match self.direction {
+            Direction::Request => "request",
+            Direction::Response => "response",
+        };
+
+        let request_id = match self.request_id {
+            Some(request_id) => request_id.to_string(),
+            None => "null".to_string(),
+        };
+
+        format!(
+            r#"{{"seq":{},"direction":"{}","request_id":{},"message_type":{},"filename":"{}"}}"#,
+            self.seq, direction, request_id, self.message_type, self.filename
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests;
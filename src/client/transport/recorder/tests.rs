@@ -34,8 +34,66 @@ fn recorder_generates_output_file() {
     let recorder = MessageRecorder {
         enabled: true,
         recording_dir: recording_dir,
+        next_seq: Arc::new(Mutex::new(0)),
     };
 
     assert_eq!(format!("{}/0001-request.msg", recorder.recording_dir), recorder.request_file(1));
     assert_eq!(format!("{}/0002-response.msg", recorder.recording_dir), recorder.response_file(2));
 }
+
+#[test]
+fn recorder_indexes_interleaved_messages() {
+    let recording_dir = env::temp_dir().join(format!("ibapi-recorder-index-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+    let _ = fs::remove_dir_all(&recording_dir);
+
+    let recorder = MessageRecorder::with_dir(&recording_dir);
+
+    let mut contract_details = RequestMessage::default();
+    contract_details.push_field(&9);
+    contract_details.push_field(&8);
+    contract_details.push_field(&9000);
+
+    let mut head_timestamp = RequestMessage::default();
+    head_timestamp.push_field(&87);
+    head_timestamp.push_field(&9001);
+
+    recorder.on_request(Some(9000), &contract_details);
+    recorder.on_request(Some(9001), &head_timestamp);
+    recorder.on_response(&ResponseMessage::from("88\09001\01678323335"));
+    recorder.on_response(&ResponseMessage::from("52\01\09000"));
+    recorder.on_response(&ResponseMessage::from("15\01\0DU1234567"));
+
+    let index = fs::read_to_string(format!("{recording_dir}/index.jsonl")).unwrap();
+    let lines: Vec<&str> = index.lines().collect();
+
+    assert_eq!(lines.len(), 5, "index entries");
+    assert_eq!(
+        lines[0],
+        r#"{"seq":0,"direction":"request","request_id":9000,"message_type":9,"filename":"0000-request.msg"}"#
+    );
+    assert_eq!(
+        lines[1],
+        r#"{"seq":1,"direction":"request","request_id":9001,"message_type":87,"filename":"0001-request.msg"}"#
+    );
+    assert_eq!(
+        lines[2],
+        r#"{"seq":2,"direction":"response","request_id":9001,"message_type":88,"filename":"0002-response.msg"}"#
+    );
+    assert_eq!(
+        lines[3],
+        r#"{"seq":3,"direction":"response","request_id":9000,"message_type":52,"filename":"0003-response.msg"}"#
+    );
+    assert_eq!(
+        lines[4],
+        r#"{"seq":4,"direction":"response","request_id":null,"message_type":15,"filename":"0004-response.msg"}"#
+    );
+
+    assert_eq!(fs::read_to_string(format!("{recording_dir}/0000-request.msg")).unwrap(), "9|8|9000|");
+    assert_eq!(
+        fs::read_to_string(format!("{recording_dir}/0002-response.msg")).unwrap(),
+        "88|9001|1678323335|"
+    );
+
+    fs::remove_dir_all(&recording_dir).unwrap();
+}
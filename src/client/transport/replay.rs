@@ -0,0 +1,306 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::sync::atomic::AtomicI32;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam::channel::{self, Receiver, Sender};
+use log::debug;
+
+use super::{dispatch_message, release_sender, GlobalChannels, GlobalResponseIterator, MessageBus, ResponseIterator, SenderHash, Signal};
+use crate::client::Notification;
+use crate::messages::{OutgoingMessages, RequestMessage, ResponseMessage};
+use crate::Error;
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+#[derive(Debug)]
+struct RecordedMessage {
+    seq: usize,
+    direction: Direction,
+    data: String,
+}
+
+// Replays a session captured by the MessageRecorder.
+//
+// Recorded responses are dispatched as if they were read from the server. Each outgoing message
+// must match the next recorded request, so a replayed session fails as soon as the client diverges
+// from the captured one.
+pub(crate) struct ReplayMessageBus {
+    recording: VecDeque<RecordedMessage>,
+    started: bool,
+    server_version: i32,
+    order_ids: Arc<AtomicI32>,
+    requests: Arc<SenderHash<i32, ResponseMessage>>,
+    orders: Arc<SenderHash<i32, ResponseMessage>>,
+    executions: SenderHash<String, ResponseMessage>,
+    globals: Arc<GlobalChannels>,
+    signals_send: Sender<Signal>,
+    signals_recv: Receiver<Signal>,
+}
+
+impl ReplayMessageBus {
+    // Loads the NNNN-request.msg and NNNN-response.msg files of a recording directory.
+    pub fn open(recording_dir: &str) -> Result<ReplayMessageBus, Error> {
+        let mut recording = Vec::new();
+
+        for entry in fs::read_dir(recording_dir)? {
+            let path = entry?.path();
+            let file_name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+
+            let (seq, direction) = match parse_file_name(&file_name) {
+                Some(parsed) => parsed,
+                None => continue,
+            };
+
+            recording.push(RecordedMessage {
+                seq,
+                direction,
+                data: fs::read_to_string(&path)?,
+            });
+        }
+
+        if recording.is_empty() {
+            return Err(Error::Simple(format!("no recorded messages found in {recording_dir}")));
+        }
+
+        recording.sort_by_key(|message| message.seq);
+
+        let (signals_send, signals_recv) = channel::unbounded();
+
+        Ok(ReplayMessageBus {
+            recording: recording.into(),
+            started: false,
+            server_version: 0,
+            order_ids: Arc::new(AtomicI32::new(-1)),
+            requests: Arc::new(SenderHash::new()),
+            orders: Arc::new(SenderHash::new()),
+            executions: SenderHash::new(),
+            globals: Arc::new(GlobalChannels::new()),
+            signals_send,
+            signals_recv,
+        })
+    }
+
+    // Client id sent in the recorded StartApi request.
+    pub fn client_id(&self) -> Option<i32> {
+        let start_api = (OutgoingMessages::StartApi as i32).to_string();
+
+        self.recording
+            .iter()
+            .filter(|message| message.direction == Direction::Request)
+            .map(|message| message.data.split('|').collect::<Vec<&str>>())
+            .find(|fields| fields.first() == Some(&start_api.as_str()))
+            .and_then(|fields| fields.get(2).and_then(|client_id| client_id.parse().ok()))
+    }
+
+    // Dispatches recorded responses up to the next recorded request.
+    fn replay_responses(&mut self) {
+        if !self.started {
+            return;
+        }
+
+        for signal in self.signals_recv.try_iter() {
+            release_sender(signal, &self.requests, &self.orders);
+        }
+
+        while let Some(recorded) = self.recording.pop_front() {
+            if recorded.direction == Direction::Request {
+                self.recording.push_front(recorded);
+                break;
+            }
+
+            debug!("replaying <- {:?}", recorded.data);
+
+            dispatch_message(
+                decode_response(&recorded.data),
+                self.server_version,
+                &self.requests,
+                &self.orders,
+                &self.globals,
+                &self.executions,
+                &self.order_ids,
+            );
+        }
+    }
+}
+
+// Parses record files named NNNN-request.msg or NNNN-response.msg.
+fn parse_file_name(file_name: &str) -> Option<(usize, Direction)> {
+    let (seq, kind) = file_name.strip_suffix(".msg")?.split_once('-')?;
+
+    let direction = match kind {
+        "request" => Direction::Request,
+        "response" => Direction::Response,
+        _ => return None,
+    };
+
+    Some((seq.parse().ok()?, direction))
+}
+
+// Recorded responses are the encoded message, which adds a trailing separator to the fields read from the wire.
+fn decode_response(data: &str) -> ResponseMessage {
+    let data = data.strip_suffix('|').unwrap_or(data);
+    ResponseMessage::from(&data.replace('|', "\0"))
+}
+
+impl MessageBus for ReplayMessageBus {
+    fn read_message(&mut self) -> Result<ResponseMessage, Error> {
+        match self.recording.pop_front() {
+            Some(recorded) if recorded.direction == Direction::Response => Ok(decode_response(&recorded.data)),
+            Some(recorded) => {
+                let error = Error::Simple(format!(
+                    "expected to read a response, next recorded message is request {}: {}",
+                    recorded.seq, recorded.data
+                ));
+                self.recording.push_front(recorded);
+                Err(error)
+            }
+            None => Err(Error::Io(io::Error::from(io::ErrorKind::UnexpectedEof))),
+        }
+    }
+
+    fn write_message(&mut self, message: &RequestMessage) -> Result<(), Error> {
+        let data = message.encode().replace('\0', "|");
+
+        match self.recording.pop_front() {
+            Some(recorded) if recorded.direction == Direction::Request && recorded.data == data => {
+                debug!("replaying -> {data:?}");
+            }
+            Some(recorded) => {
+                return Err(Error::Simple(format!(
+                    "request {data} does not match recorded {:?} {}: {}",
+                    recorded.direction, recorded.seq, recorded.data
+                )));
+            }
+            None => return Err(Error::Simple(format!("request {data} sent after end of recording"))),
+        }
+
+        self.replay_responses();
+
+        Ok(())
+    }
+
+    fn send_generic_message(&mut self, request_id: i32, packet: &RequestMessage) -> Result<ResponseIterator, Error> {
+        let (sender, receiver) = channel::unbounded();
+
+        self.requests.insert(request_id, sender);
+        self.write_message(packet)?;
+
+        Ok(ResponseIterator::new(
+            receiver,
+            self.signals_send.clone(),
+            Some(request_id),
+            None,
+            Duration::from_secs(10),
+        ))
+    }
+
+    fn send_order_message(&mut self, order_id: i32, message: &RequestMessage) -> Result<ResponseIterator, Error> {
+        let (sender, receiver) = channel::unbounded();
+
+        self.orders.insert(order_id, sender);
+        self.write_message(message)?;
+
+        Ok(ResponseIterator::new(
+            receiver,
+            self.signals_send.clone(),
+            None,
+            Some(order_id),
+            Duration::from_secs(10),
+        ))
+    }
+
+    fn resend_order_message(&mut self, order_id: i32, message: &RequestMessage) -> Result<(), Error> {
+        if !self.orders.contains(&order_id) {
+            return Err(Error::Simple(format!("order id {order_id} has not been placed by this client")));
+        }
+
+        self.write_message(message)
+    }
+
+    fn request_next_order_id(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.order_ids_out)))
+    }
+
+    fn request_open_orders(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.open_orders_out)))
+    }
+
+    fn request_market_rule(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_market_rule)))
+    }
+
+    fn request_positions(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_positions)))
+    }
+
+    fn request_account_updates(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_account_updates)))
+    }
+
+    fn request_family_codes(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_family_codes)))
+    }
+
+    fn request_news_providers(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_news_providers)))
+    }
+
+    fn request_scanner_parameters(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_scanner_parameters)))
+    }
+
+    fn request_fa(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_fa)))
+    }
+
+    fn request_current_time(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        self.write_message(message)?;
+        Ok(GlobalResponseIterator::new(Arc::clone(&self.globals.recv_current_time)))
+    }
+
+    // The handshake prefix and supported versions are not recorded.
+    fn write(&mut self, _packet: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn process_messages(&mut self, server_version: i32) -> Result<(), Error> {
+        self.server_version = server_version;
+        self.started = true;
+        self.replay_responses();
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn order_ids(&self) -> Arc<AtomicI32> {
+        Arc::clone(&self.order_ids)
+    }
+
+    fn notifications(&self) -> Receiver<Notification> {
+        self.globals.recv_notifications.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests;
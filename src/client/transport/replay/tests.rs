@@ -0,0 +1,67 @@
+use std::env;
+
+use super::*;
+
+// Writes a recording directory with the given messages, numbered in order.
+fn write_recording(name: &str, messages: &[(Direction, &str)]) -> String {
+    let recording_dir = env::temp_dir().join(format!("ibapi-{name}-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+
+    let _ = fs::remove_dir_all(&recording_dir);
+    fs::create_dir_all(&recording_dir).unwrap();
+
+    for (seq, (direction, data)) in messages.iter().enumerate() {
+        let kind = match direction {
+            Direction::Request => "request",
+            Direction::Response => "response",
+        };
+        fs::write(format!("{recording_dir}/{seq:04}-{kind}.msg"), data).unwrap();
+    }
+
+    recording_dir
+}
+
+#[test]
+fn parses_record_file_names() {
+    assert_eq!(parse_file_name("0001-request.msg"), Some((1, Direction::Request)));
+    assert_eq!(parse_file_name("0012-response.msg"), Some((12, Direction::Response)));
+    assert_eq!(parse_file_name("index.jsonl"), None);
+    assert_eq!(parse_file_name("0003-other.msg"), None);
+}
+
+#[test]
+fn reads_client_id_from_start_api() {
+    let recording_dir = write_recording(
+        "reads-client-id",
+        &[(Direction::Response, "173|20230405 22:20:39 PST||"), (Direction::Request, "71|2|100||")],
+    );
+
+    let replay = ReplayMessageBus::open(&recording_dir).unwrap();
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    assert_eq!(replay.client_id(), Some(100), "replay.client_id()");
+}
+
+#[test]
+fn rejects_requests_not_in_recording() {
+    let recording_dir = write_recording(
+        "rejects-requests",
+        &[(Direction::Response, "173|20230405 22:20:39 PST||"), (Direction::Request, "71|2|100||")],
+    );
+
+    let mut replay = ReplayMessageBus::open(&recording_dir).unwrap();
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    let mut ack = replay.read_message().unwrap();
+    assert_eq!(ack.next_int().unwrap(), 173, "server version");
+
+    let mut start_api = RequestMessage::new();
+    start_api.push_field(&OutgoingMessages::StartApi);
+    start_api.push_field(&2);
+    start_api.push_field(&200);
+
+    assert!(
+        matches!(replay.write_message(&start_api), Err(Error::Simple(_))),
+        "expected request mismatch"
+    );
+}
@@ -1 +1,1609 @@
+use super::*;
+use crate::stubs::{accept_handshake, read_frame, serve_handshake, write_frame};
 
+#[test]
+fn release_senders_for_dropped_iterators() {
+    let requests = SenderHash::<i32, ResponseMessage>::new();
+    let orders = SenderHash::<i32, ResponseMessage>::new();
+    let (signals_send, signals_recv) = channel::unbounded();
+
+    for id in 0..10_000 {
+        let (sender, receiver) = channel::unbounded();
+        requests.insert(id, sender);
+        drop(ResponseIterator::new(
+            receiver,
+            signals_send.clone(),
+            Some(id),
+            None,
+            Duration::from_secs(1),
+        ));
+
+        let (sender, receiver) = channel::unbounded();
+        orders.insert(id, sender);
+        drop(ResponseIterator::new(
+            receiver,
+            signals_send.clone(),
+            None,
+            Some(id),
+            Duration::from_secs(1),
+        ));
+    }
+
+    assert_eq!(requests.len(), 10_000, "requests.len() before release");
+    assert_eq!(orders.len(), 10_000, "orders.len() before release");
+
+    for signal in signals_recv.try_iter() {
+        release_sender(signal, &requests, &orders);
+    }
+
+    assert_eq!(requests.len(), 0, "requests.len() after release");
+    assert_eq!(orders.len(), 0, "orders.len() after release");
+}
+
+#[test]
+fn discard_messages_for_cancelled_subscriptions() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+    let (signals_send, signals_recv) = channel::unbounded();
+
+    let (sender, receiver) = channel::unbounded();
+    requests.insert(9000, sender);
+    let mut subscription = ResponseIterator::new(receiver.clone(), signals_send, Some(9000), None, Duration::from_secs(1));
+
+    let (other_sender, other_receiver) = channel::unbounded();
+    requests.insert(9002, other_sender);
+
+    subscription.cancel();
+    for signal in signals_recv.try_iter() {
+        release_sender(signal, &requests, &orders);
+    }
+
+    assert!(requests.recently_removed(&9000), "cancelled request id is recently removed");
+    assert!(!requests.recently_removed(&9001), "unknown request id is not recently removed");
+
+    // trailing message sent before the server processed the cancellation, and one for an unknown request id
+    for message in ["1\06\09000\01\0185.50\0", "1\06\09001\01\0185.50\0", "1\06\09002\01\0185.50\0"] {
+        dispatch_message(
+            ResponseMessage::from(message),
+            server_versions::SIZE_RULES,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+    }
+
+    assert!(receiver.try_recv().is_err(), "message delivered to cancelled subscription");
+    assert_eq!(requests.len(), 1, "requests.len()");
+    match other_receiver.try_recv() {
+        Ok(message) => assert_eq!(message.peek_int(2).unwrap(), 9002, "request id of routed message"),
+        Err(err) => assert!(false, "expected message for active subscription: {err}"),
+    }
+}
+
+#[test]
+fn reconnects_after_connection_lost() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, start_api) = accept_handshake(&listener);
+        read_frame(&mut stream); // subscription request
+        drop(stream);
+
+        let (_stream, replayed_start_api) = accept_handshake(&listener);
+        (start_api, replayed_start_api)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::RequestContractData);
+    let mut responses = bus.send_generic_message(9000, &request).unwrap();
+
+    match responses.next() {
+        Some(message) => assert_eq!(message.message_type(), IncomingMessages::ConnectionLost, "message.message_type()"),
+        None => assert!(false, "expected connection lost notification"),
+    }
+
+    let (start_api, replayed_start_api) = server.join().unwrap();
+    assert_eq!(start_api, "71\02\0100\0\0", "start api");
+    assert_eq!(replayed_start_api, start_api, "replayed start api");
+}
+
+#[test]
+fn max_reconnect_attempts_limits_reconnecting() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let (resume_send, resume_recv) = channel::unbounded::<()>();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = accept_handshake(&listener);
+        resume_recv.recv().unwrap();
+        drop(stream);
+        listener
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap().max_reconnect_attempts(0);
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+    let mut positions = bus.globals.responses(&bus.globals.recv_positions);
+
+    // server closes the connection but keeps accepting new ones
+    resume_send.send(()).unwrap();
+    let listener = server.join().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while bus.connection_state() != ConnectionState::Disconnected && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(bus.connection_state(), ConnectionState::Disconnected, "after connection lost");
+
+    match positions.recv_deadline(Instant::now() + Duration::from_secs(1)) {
+        Err(Error::Simple(message)) => assert_eq!(message, "message bus disconnected", "positions after connection lost"),
+        result => assert!(false, "expected positions to end, got {result:?}"),
+    }
+
+    listener.set_nonblocking(true).unwrap();
+    assert!(listener.accept().is_err(), "expected no reconnect attempt");
+}
+
+#[test]
+fn request_fa_discards_stale_replies() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        let request = read_frame(&mut stream);
+        write_frame(&mut stream, "16|1|1|<current/>|");
+        (stream, request)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    // reply to an earlier request that nobody read
+    bus.globals.send_fa.send(ResponseMessage::from("16\01\01\0<stale/>\0")).unwrap();
+
+    let mut request = RequestMessage::default();
+    request.push_field(&OutgoingMessages::RequestFA);
+    request.push_field(&1);
+    request.push_field(&1);
+    let mut replies = bus.request_fa(&request).unwrap();
+
+    match replies.next() {
+        Some(message) => assert_eq!(message.peek_string(3), "<current/>", "fa reply"),
+        None => assert!(false, "expected fa reply"),
+    }
+
+    let (_stream, request) = server.join().unwrap();
+    assert_eq!(request, "18\01\01\0", "fa request");
+}
+
+#[test]
+fn resyncs_order_subscriptions_after_reconnect() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        read_frame(&mut stream); // place order
+        drop(stream);
+
+        let (mut stream, _) = accept_handshake(&listener);
+        let open_orders = read_frame(&mut stream);
+        let executions = read_frame(&mut stream);
+
+        write_frame(&mut stream, "3\013\0Filled\0100\00\0196.52\01376327563\00\0196.52\0100\0\00\0");
+        write_frame(&mut stream, "53\01\0");
+        write_frame(&mut stream, &format!("55\01\0{RESYNC_REQUEST_ID}\0"));
+
+        (stream, open_orders, executions)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::PlaceOrder);
+    request.push_field(&13);
+    let mut notifications = bus.send_order_message(13, &request).unwrap();
+
+    match notifications.next() {
+        Some(message) => assert_eq!(message.message_type(), IncomingMessages::ConnectionLost, "message.message_type()"),
+        None => assert!(false, "expected connection lost notification"),
+    }
+
+    match notifications.next() {
+        Some(message) => {
+            assert_eq!(message.message_type(), IncomingMessages::OrderStatus, "message.message_type()");
+            assert_eq!(message.order_id(), Some(13), "message.order_id()");
+        }
+        None => assert!(false, "expected order status after reconnect"),
+    }
+
+    let (_stream, open_orders, executions) = server.join().unwrap();
+    assert_eq!(open_orders, "5\01\0", "open orders request");
+    assert!(
+        executions.starts_with(&format!("7\03\0{RESYNC_REQUEST_ID}\0")),
+        "executions request: {executions:?}"
+    );
+
+    // open orders without subscriber still reach callers of open_orders
+    match bus.globals.open_orders_out.recv_timeout(Duration::from_secs(1)) {
+        Ok(message) => assert_eq!(message.message_type(), IncomingMessages::OpenOrderEnd, "message.message_type()"),
+        Err(err) => assert!(false, "expected open order end: {err}"),
+    }
+}
+
+#[test]
+fn resent_executions_are_delivered_once() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    let (sender, receiver) = channel::unbounded();
+    orders.insert(13, sender);
+
+    let execution = |request_id: i32, execution_id: &str| {
+        format!("11\0{request_id}\013\076792991\0TSLA\0STK\0\00.0\0\0\0ISLAND\0USD\0TSLA\0NMS\0{execution_id}\020230224  12:04:56\0DU1234567\0ISLAND\0BOT\0100\0196.52\01376327563\0100\00\0100\0196.52\0\0\0\0\02\0")
+    };
+    let commission_report = |execution_id: &str| format!("59\01\0{execution_id}\01.0\0USD\0\0\0\0");
+
+    let messages = [
+        // delivered before the connection was lost
+        execution(-1, "0001.01"),
+        commission_report("0001.01"),
+        // resent after the reconnect, along with an execution missed while disconnected
+        execution(RESYNC_REQUEST_ID, "0001.01"),
+        commission_report("0001.01"),
+        execution(RESYNC_REQUEST_ID, "0001.02"),
+        commission_report("0001.02"),
+        // execution of an order without subscriber
+        execution(RESYNC_REQUEST_ID, "0002.01").replace("\013\076792991", "\014\076792991"),
+        commission_report("0002.01"),
+        format!("55\01\0{RESYNC_REQUEST_ID}\0"),
+    ];
+
+    for message in messages {
+        dispatch_message(
+            ResponseMessage::from(&message),
+            server_versions::SIZE_RULES,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+    }
+
+    let received: Vec<(IncomingMessages, Option<String>)> = receiver
+        .try_iter()
+        .map(|message| (message.message_type(), message.execution_id()))
+        .collect();
+
+    assert_eq!(
+        received,
+        vec![
+            (IncomingMessages::ExecutionData, Some("0001.01".to_owned())),
+            (IncomingMessages::CommissionsReport, Some("0001.01".to_owned())),
+            (IncomingMessages::ExecutionData, Some("0001.02".to_owned())),
+            (IncomingMessages::CommissionsReport, Some("0001.02".to_owned())),
+        ],
+        "messages received by the order subscriber"
+    );
+}
+
+#[test]
+fn resend_order_keeps_original_subscription() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        let placed = read_frame(&mut stream);
+        let modified = read_frame(&mut stream);
+
+        write_frame(&mut stream, "3\013\0Submitted\00\0100\00\01376327563\00\00\0100\0\00\0");
+
+        (stream, placed, modified)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::PlaceOrder);
+    request.push_field(&13);
+    request.push_field(&"LMT");
+    let mut notifications = bus.send_order_message(13, &request).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::PlaceOrder);
+    request.push_field(&13);
+    request.push_field(&"MKT");
+    bus.resend_order_message(13, &request).unwrap();
+
+    assert!(bus.resend_order_message(14, &request).is_err(), "expected error for order not placed");
+
+    match notifications.next() {
+        Some(message) => {
+            assert_eq!(message.message_type(), IncomingMessages::OrderStatus, "message.message_type()");
+            assert_eq!(message.order_id(), Some(13), "message.order_id()");
+        }
+        None => assert!(false, "expected order status on the original subscription"),
+    }
+
+    let (_stream, placed, modified) = server.join().unwrap();
+    assert_eq!(placed, "3\013\0LMT\0", "placed order");
+    assert_eq!(modified, "3\013\0MKT\0", "modified order");
+}
+
+#[test]
+fn cancels_orders_after_reconnect() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        read_frame(&mut stream); // place order
+        drop(stream);
+
+        let (mut stream, _) = accept_handshake(&listener);
+        let requests = [read_frame(&mut stream), read_frame(&mut stream), read_frame(&mut stream)];
+
+        write_frame(&mut stream, "3\013\0Cancelled\00\0100\00\01376327563\00\00\0100\0\00\0");
+
+        (stream, requests)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+
+    bus.set_cancel_orders_on_disconnect(true);
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::PlaceOrder);
+    request.push_field(&13);
+    let mut notifications = bus.send_order_message(13, &request).unwrap();
+
+    match notifications.next() {
+        Some(message) => assert_eq!(message.message_type(), IncomingMessages::ConnectionLost, "message.message_type()"),
+        None => assert!(false, "expected connection lost notification"),
+    }
+
+    match notifications.next() {
+        Some(message) => assert_eq!(message.peek_string(2), "Cancelled", "order status"),
+        None => assert!(false, "expected order status after reconnect"),
+    }
+
+    let (_stream, [global_cancel, open_orders, executions]) = server.join().unwrap();
+    assert_eq!(global_cancel, "58\01\0", "global cancel request");
+    assert_eq!(open_orders, "5\01\0", "open orders request");
+    assert!(executions.starts_with("7\03\0"), "executions request: {executions:?}");
+}
+
+#[test]
+fn connection_state_transitions() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let (accepted_send, accepted_recv) = channel::unbounded();
+    let (resume_send, resume_recv) = channel::unbounded::<()>();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = accept_handshake(&listener);
+        resume_recv.recv().unwrap();
+        drop(stream);
+
+        let (stream, _) = listener.accept().unwrap();
+        accepted_send.send(()).unwrap();
+        resume_recv.recv().unwrap();
+
+        serve_handshake(stream)
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    assert_eq!(bus.connection_state(), ConnectionState::Connecting, "after connect");
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+    assert_eq!(bus.connection_state(), ConnectionState::Connecting, "after handshake");
+
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+    assert_eq!(bus.connection_state(), ConnectionState::Connected, "after process_messages");
+
+    // server closes the connection and holds the handshake of the next one
+    resume_send.send(()).unwrap();
+    accepted_recv.recv().unwrap();
+    assert_eq!(bus.connection_state(), ConnectionState::Reconnecting, "while reconnecting");
+
+    resume_send.send(()).unwrap();
+    let (_stream, _) = server.join().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while bus.connection_state() != ConnectionState::Connected && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(bus.connection_state(), ConnectionState::Connected, "after reconnect");
+
+    bus.disconnect().unwrap();
+    assert_eq!(bus.connection_state(), ConnectionState::Disconnected, "after disconnect");
+}
+
+#[test]
+fn reconnect_fails_when_server_version_changes() {
+    use std::net::TcpListener;
+
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = accept_handshake(&listener);
+        drop(stream);
+
+        // negotiates another version than the first connection
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut prefix = [0_u8; 4];
+        stream.read_exact(&mut prefix).unwrap();
+        read_frame(&mut stream); // supported versions
+        write_frame(&mut stream, &format!("{}|20230405 22:20:39 PST|", server_versions::HISTORICAL_SCHEDULE));
+        stream
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    bus.read_message().unwrap();
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::RequestContractData);
+    let responses = bus.send_generic_message(9000, &request).unwrap();
+    drop(responses);
+
+    let _stream = server.join().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while bus.connection_state() != ConnectionState::Disconnected && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(bus.connection_state(), ConnectionState::Disconnected, "after server version changed");
+
+    // the socket is closed already, the remaining teardown still runs
+    let result = bus.disconnect();
+    assert!(result.is_ok(), "disconnect after failed reconnect: {result:?}");
+    assert!(bus.handles.is_empty(), "message processing threads still running");
+}
+
+#[test]
+fn response_iterator_receives_message_within_timeout() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, None, None, Duration::from_secs(10)).with_timeout(Duration::from_millis(500));
+
+    let delayed = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        sender.send(ResponseMessage::from("88\09000\01678323335\0")).unwrap();
+    });
+
+    match responses.recv_timeout() {
+        Ok(message) => assert_eq!(message.message_type(), IncomingMessages::HeadTimestamp, "message.message_type()"),
+        Err(err) => assert!(false, "error receiving message: {err}"),
+    }
+
+    delayed.join().unwrap();
+}
+
+#[test]
+fn response_iterator_try_next_does_not_wait() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, None, None, Duration::from_secs(10));
+
+    let started = Instant::now();
+    assert!(matches!(responses.try_next(), TryNext::Empty), "expected no message");
+    assert!(started.elapsed() < Duration::from_secs(1), "try_next waited {:?}", started.elapsed());
+
+    sender.send(ResponseMessage::from("88\09000\01678323335\0")).unwrap();
+    match responses.try_next() {
+        TryNext::Value(message) => assert_eq!(message.message_type(), IncomingMessages::HeadTimestamp, "message.message_type()"),
+        other => assert!(false, "expected queued message, got {other:?}"),
+    }
+    assert!(matches!(responses.try_next(), TryNext::Empty), "expected no message after queued one");
+
+    sender.send(ResponseMessage::from("88\09000\01678323335\0")).unwrap();
+    responses.cancel();
+    assert!(matches!(responses.try_next(), TryNext::Ended), "expected disconnected once cancelled");
+}
+
+#[test]
+fn subscription_try_next_separates_empty_from_disconnected() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_secs(10));
+    let mut subscription = DecodedSubscription::new(responses, |message| match message.next_int()? {
+        1 => Ok(Some(message.next_string()?)),
+        2 => Ok(None),
+        message_type => Err(Error::Simple(format!("unexpected message type {message_type}"))),
+    });
+
+    assert_eq!(subscription.try_next(), TryNext::Empty, "nothing queued");
+
+    sender.send(ResponseMessage::from("9\0unknown\0")).unwrap();
+    sender.send(ResponseMessage::from("1\0first\0")).unwrap();
+    assert_eq!(
+        subscription.try_next(),
+        TryNext::Value("first".to_owned()),
+        "skips messages that fail to decode"
+    );
+    assert_eq!(subscription.try_next(), TryNext::Empty, "nothing queued after value");
+
+    sender.send(ResponseMessage::from("2\0")).unwrap();
+    assert_eq!(subscription.try_next(), TryNext::Ended, "end marker");
+    assert_eq!(subscription.try_next(), TryNext::Ended, "after end marker");
+
+    let (sender, receiver) = channel::unbounded::<ResponseMessage>();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let responses = ResponseIterator::new(receiver, signals_send, Some(9001), None, Duration::from_secs(10));
+    let mut subscription = DecodedSubscription::new(responses, |message| message.next_string().map(Some));
+
+    drop(sender);
+    assert_eq!(subscription.try_next(), TryNext::Ended, "closed channel");
+}
+
+#[test]
+fn global_try_next_reports_disconnect() {
+    let globals = GlobalChannels::new();
+    let mut positions = globals.responses(&globals.recv_positions);
+
+    assert!(matches!(positions.try_next(), TryNext::Empty), "nothing queued");
+
+    globals.send_positions.send(ResponseMessage::from("62\0")).unwrap();
+    globals.disconnect();
+
+    assert!(
+        matches!(positions.try_next(), TryNext::Value(_)),
+        "queued message delivered after disconnect"
+    );
+    assert!(matches!(positions.try_next(), TryNext::Ended), "disconnected");
+}
+
+#[test]
+fn response_iterator_recv_deadline() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, None, None, Duration::from_secs(10));
+
+    let deadline = Instant::now() + Duration::from_millis(50);
+    assert!(
+        matches!(responses.recv_deadline(deadline), Err(Error::Timeout)),
+        "expected Error::Timeout"
+    );
+
+    sender.send(ResponseMessage::from("88\09000\01678323335\0")).unwrap();
+    match responses.recv_deadline(Instant::now() + Duration::from_secs(1)) {
+        Ok(message) => assert_eq!(message.message_type(), IncomingMessages::HeadTimestamp, "message.message_type()"),
+        Err(err) => assert!(false, "error receiving message: {err}"),
+    }
+}
+
+#[test]
+fn response_iterator_times_out() {
+    let (_sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, None, None, Duration::from_secs(10)).with_timeout(Duration::from_millis(50));
+
+    assert!(matches!(responses.recv_timeout(), Err(Error::Timeout)), "expected Error::Timeout");
+    assert!(responses.next().is_none(), "iterator should end after timeout");
+}
+
+#[test]
+fn decoded_subscription_decodes_until_end_marker() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, signals_recv) = channel::unbounded();
+
+    for frame in ["1\0first\0", "9\0unknown\0", "1\0second\0", "2\0", "1\0after end\0"] {
+        sender.send(ResponseMessage::from(frame)).unwrap();
+    }
+
+    let responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(100));
+    let subscription = DecodedSubscription::new(responses, |message| match message.next_int()? {
+        1 => Ok(Some(message.next_string()?)),
+        2 => Ok(None),
+        message_type => Err(Error::Simple(format!("unexpected message type {message_type}"))),
+    });
+
+    let values: Vec<String> = subscription.collect();
+    assert_eq!(values, vec!["first", "second"], "decoded values");
+
+    assert!(
+        matches!(signals_recv.try_recv(), Ok(Signal::Request(9000))),
+        "request should be released at the end marker"
+    );
+}
+
+// Returns the results of reads in order, then end of file.
+struct ScriptedReader {
+    reads: std::collections::VecDeque<std::io::Result<Vec<u8>>>,
+}
+
+impl ScriptedReader {
+    fn new() -> Self {
+        ScriptedReader {
+            reads: std::collections::VecDeque::new(),
+        }
+    }
+
+    fn frame(mut self, data: &[u8]) -> Self {
+        self.reads.push_back(Ok((data.len() as u32).to_be_bytes().to_vec()));
+        self.reads.push_back(Ok(data.to_vec()));
+        self
+    }
+
+    fn error(mut self, kind: ErrorKind) -> Self {
+        self.reads.push_back(Err(std::io::Error::from(kind)));
+        self
+    }
+}
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.reads.pop_front() {
+            Some(Ok(data)) => {
+                buf[..data.len()].copy_from_slice(&data);
+                Ok(data.len())
+            }
+            Some(Err(err)) => Err(err),
+            None => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn read_messages_stops_at_end_of_file() {
+    let reader = ScriptedReader::new()
+        .frame(b"49\01\01678890000\0")
+        .error(ErrorKind::WouldBlock)
+        .frame(&[0xff, 0xfe])
+        .frame(b"49\01\01678890001\0");
+    let shutdown_requested = AtomicBool::new(false);
+
+    let mut received = Vec::new();
+    let failure = read_messages(reader, &shutdown_requested, |message| received.push(message.peek_int(2).unwrap()));
+
+    assert_eq!(received, vec![1678890000, 1678890001], "messages received");
+    match failure {
+        Some(err) => assert_eq!(classify_read_error(&err), ReadFailure::ConnectionLost, "failure: {err}"),
+        None => assert!(false, "expected read to fail at end of file"),
+    }
+}
+
+#[test]
+fn read_messages_stops_on_fatal_error() {
+    let reader = ScriptedReader::new().error(ErrorKind::PermissionDenied).frame(b"49\01\01678890000\0");
+    let shutdown_requested = AtomicBool::new(false);
+
+    let mut received = 0;
+    let failure = read_messages(reader, &shutdown_requested, |_| received += 1);
+
+    assert_eq!(received, 0, "messages received after fatal error");
+    match failure {
+        Some(err) => assert_eq!(classify_read_error(&err), ReadFailure::Fatal, "failure: {err}"),
+        None => assert!(false, "expected read to fail"),
+    }
+}
+
+#[test]
+fn subscription_reports_timeouts() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_secs(10));
+    let mut subscription = DecodedSubscription::new(responses, |message| match message.next_int()? {
+        1 => Ok(Some(message.next_string()?)),
+        2 => Ok(None),
+        message_type => Err(Error::Simple(format!("unexpected message type {message_type}"))),
+    });
+
+    assert!(
+        matches!(subscription.next_timeout(Duration::from_millis(50)), Err(Error::Timeout)),
+        "expected Error::Timeout"
+    );
+
+    sender.send(ResponseMessage::from("1\0first\0")).unwrap();
+    assert_eq!(
+        subscription.next_timeout(Duration::from_millis(50)).unwrap(),
+        Some("first".to_owned()),
+        "subscription should remain active after a timeout"
+    );
+
+    subscription.set_timeout(Duration::from_millis(50));
+    let started = Instant::now();
+    assert_eq!(subscription.next(), None, "next should return None when no value arrives in time");
+    assert!(started.elapsed() < Duration::from_secs(5), "next should wait no longer than the timeout");
+
+    sender.send(ResponseMessage::from("2\0")).unwrap();
+    assert_eq!(
+        subscription.next_timeout(Duration::from_millis(50)).unwrap(),
+        None,
+        "subscription should end at the end marker"
+    );
+}
+
+#[test]
+fn disconnect_stops_message_processing() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default() // returns once client shuts down
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let started = Instant::now();
+    bus.disconnect().unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(1), "disconnect took {:?}", started.elapsed());
+    assert!(bus.handles.is_empty(), "message processing threads still running");
+
+    server.join().unwrap();
+}
+
+#[test]
+fn disconnect_ends_pending_requests() {
+    use std::net::TcpListener;
+
+    use crate::messages::OutgoingMessages;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default() // returns once client shuts down
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::RequestContractData);
+    let responses = bus.send_generic_message(9000, &request).unwrap();
+
+    let order = RequestMessage::new();
+    let order_responses = bus.send_order_message(13, &order).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::RequestPositions);
+    let positions = bus.request_positions(&request).unwrap();
+
+    let consumer = thread::spawn(move || {
+        let started = Instant::now();
+        let received = responses.count() + order_responses.count() + positions.count();
+        (received, started.elapsed())
+    });
+
+    thread::sleep(Duration::from_millis(100));
+    bus.disconnect().unwrap();
+
+    let (received, elapsed) = consumer.join().unwrap();
+    assert_eq!(received, 0, "messages received");
+    assert!(elapsed < Duration::from_secs(2), "pending requests ended after {elapsed:?}");
+
+    server.join().unwrap();
+}
+
+#[test]
+fn next_result_decodes_api_errors() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    sender.send(ResponseMessage::from("79\09000\00\0")).unwrap();
+    sender
+        .send(ResponseMessage::from(
+            "4\02\09000\0200\0No security definition has been found for the request\0\0",
+        ))
+        .unwrap();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(50));
+
+    assert!(matches!(responses.next_result(), Some(Ok(_))), "expected data message");
+
+    match responses.next_result() {
+        Some(Err(Error::Api {
+            code,
+            message,
+            advanced_order_reject,
+        })) => {
+            assert_eq!(code, 200, "error code");
+            assert_eq!(message, "No security definition has been found for the request", "error message");
+            assert_eq!(advanced_order_reject, None, "advanced order reject");
+        }
+        other => assert!(false, "expected api error, got: {other:?}"),
+    }
+
+    assert!(responses.next_result().is_none(), "expected end of responses");
+}
+
+#[test]
+fn next_result_unescapes_api_errors() {
+    let message = "4\02\09000\0200\0Aucune d\\u00e9finition de titre trouv\\u00e9e\0";
+
+    for (server_version, expected) in [
+        (server_versions::ENCODE_MSG_ASCII7, "Aucune définition de titre trouvée"),
+        // older servers do not escape messages
+        (server_versions::ENCODE_MSG_ASCII7 - 1, "Aucune d\\u00e9finition de titre trouv\\u00e9e"),
+    ] {
+        let (sender, receiver) = channel::unbounded();
+        let (signals_send, _signals_recv) = channel::unbounded();
+
+        sender.send(ResponseMessage::from(message)).unwrap();
+
+        let mut responses =
+            ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(50)).with_server_version(server_version);
+
+        match responses.next_result() {
+            Some(Err(Error::Api { code, message, .. })) => {
+                assert_eq!(code, 200, "error code");
+                assert_eq!(message, expected, "error message for server version {server_version}");
+            }
+            other => assert!(false, "expected api error, got: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn error_events_publish_notifications() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    let messages = [
+        "4\02\0-1\01100\0Connectivity between IB and Trader Workstation has been lost.\0",
+        "4\02\0-1\02104\0Market data farm connection is OK:usfarm\0",
+        "4\02\0-1\0200\0No security definition has been found for the request\0",
+    ];
+
+    for message in messages {
+        dispatch_message(
+            ResponseMessage::from(message),
+            server_versions::SIZE_RULES,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+    }
+
+    let notifications: Vec<Notification> = globals.recv_notifications.try_iter().collect();
+
+    assert_eq!(
+        notifications,
+        vec![
+            Notification::Error {
+                code: 1100,
+                message: "Connectivity between IB and Trader Workstation has been lost.".into(),
+                advanced_order_reject: None,
+            },
+            Notification::Info {
+                code: 2104,
+                message: "Market data farm connection is OK:usfarm".into()
+            },
+            Notification::Error {
+                code: 200,
+                message: "No security definition has been found for the request".into(),
+                advanced_order_reject: None,
+            },
+        ],
+        "notifications"
+    );
+}
+
+#[test]
+fn notifications_keep_the_latest_when_full() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    let published = NOTIFICATIONS_CAPACITY as i32 + 10;
+    for code in 0..published {
+        dispatch_message(
+            ResponseMessage::from(&format!("4\02\0-1\0{code}\0notification {code}\0")),
+            server_versions::SIZE_RULES,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+    }
+
+    let codes: Vec<i32> = globals
+        .recv_notifications
+        .try_iter()
+        .map(|notification| match notification {
+            Notification::Info { code, .. } | Notification::Error { code, .. } => code,
+        })
+        .collect();
+
+    assert_eq!(codes.len(), NOTIFICATIONS_CAPACITY, "notifications kept");
+    assert_eq!(codes.first(), Some(&10), "oldest notification kept");
+    assert_eq!(codes.last(), Some(&(published - 1)), "latest notification kept");
+}
+
+#[test]
+fn error_events_parse_advanced_order_reject() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    let message = "4\02\0-1\0201\0Order rejected - reason:Insufficient buying power\0{\"rejectReason\":\"Insufficient buying power\",\"rejectReasonId\":\"INSUFFICIENT_BP\"}\0";
+    dispatch_message(
+        ResponseMessage::from(message),
+        server_versions::ADVANCED_ORDER_REJECT,
+        &requests,
+        &orders,
+        &globals,
+        &executions,
+        &order_ids,
+    );
+
+    match globals.recv_notifications.try_recv() {
+        Ok(Notification::Error {
+            code,
+            advanced_order_reject: Some(reject),
+            ..
+        }) => {
+            assert_eq!(code, 201, "code");
+            assert_eq!(reject.reject_reason.as_deref(), Some("Insufficient buying power"), "reject.reject_reason");
+            assert_eq!(reject.details["rejectReasonId"], "INSUFFICIENT_BP", "reject.details");
+        }
+        other => assert!(false, "expected error notification with advanced order reject, got: {other:?}"),
+    }
+}
+
+#[test]
+fn next_result_decodes_advanced_order_reject() {
+    let (sender, receiver) = channel::unbounded();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let message = "4\02\09000\0202\0Order Canceled - reason:Price exceeds the percentage constraint\0{\"rejectReason\":\"Price exceeds the percentage constraint\",\"percentage\":3}\0";
+    sender.send(ResponseMessage::from(message)).unwrap();
+    sender.send(ResponseMessage::from(message)).unwrap();
+
+    let mut responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(50))
+        .with_server_version(server_versions::ADVANCED_ORDER_REJECT);
+
+    match responses.next_result() {
+        Some(Err(Error::Api {
+            code,
+            advanced_order_reject: Some(reject),
+            ..
+        })) => {
+            assert_eq!(code, 202, "error code");
+            assert_eq!(
+                reject.reject_reason.as_deref(),
+                Some("Price exceeds the percentage constraint"),
+                "reject.reject_reason"
+            );
+            assert_eq!(reject.details["percentage"], 3, "reject.details");
+        }
+        other => assert!(false, "expected api error with advanced order reject, got: {other:?}"),
+    }
+
+    // older servers do not send the advanced order reject field
+    let mut responses = responses.with_server_version(server_versions::ADVANCED_ORDER_REJECT - 1);
+    match responses.next_result() {
+        Some(Err(Error::Api {
+            code, advanced_order_reject, ..
+        })) => {
+            assert_eq!(code, 202, "error code");
+            assert_eq!(advanced_order_reject, None, "advanced order reject");
+        }
+        other => assert!(false, "expected api error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn error_events_unescape_messages() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    let message = "4\02\0-1\02104\0Connexion \\u00e9tablie:usfarm\0";
+
+    let globals = Arc::new(GlobalChannels::new());
+    dispatch_message(
+        ResponseMessage::from(message),
+        server_versions::ENCODE_MSG_ASCII7,
+        &requests,
+        &orders,
+        &globals,
+        &executions,
+        &order_ids,
+    );
+
+    match globals.recv_notifications.try_recv() {
+        Ok(notification) => assert_eq!(
+            notification,
+            Notification::Info {
+                code: 2104,
+                message: "Connexion établie:usfarm".into()
+            },
+            "notification"
+        ),
+        Err(err) => assert!(false, "expected notification: {err}"),
+    }
+
+    // older servers do not escape messages
+    let globals = Arc::new(GlobalChannels::new());
+    dispatch_message(
+        ResponseMessage::from(message),
+        server_versions::ENCODE_MSG_ASCII7 - 1,
+        &requests,
+        &orders,
+        &globals,
+        &executions,
+        &order_ids,
+    );
+
+    match globals.recv_notifications.try_recv() {
+        Ok(notification) => assert_eq!(
+            notification,
+            Notification::Info {
+                code: 2104,
+                message: "Connexion \\u00e9tablie:usfarm".into()
+            },
+            "notification"
+        ),
+        Err(err) => assert!(false, "expected notification: {err}"),
+    }
+}
+
+#[test]
+fn replays_recorded_session() {
+    use std::fs;
+    use std::net::TcpListener;
+
+    let recording_dir = env::temp_dir().join(format!("ibapi-replays-recorded-session-{}", std::process::id()));
+    let recording_dir = recording_dir.to_str().unwrap().to_owned();
+    let _ = fs::remove_dir_all(&recording_dir);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        read_frame(&mut stream); // contract details request
+        write_frame(&mut stream, "4\02\09000\0200\0No security definition has been found for the request\0");
+        write_frame(&mut stream, "52\01\09000\0");
+
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default() // returns once client shuts down
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    bus.add_observer(Arc::new(MessageRecorder::with_dir(&recording_dir)));
+
+    let live = run_session(&mut bus);
+
+    bus.disconnect().unwrap();
+    server.join().unwrap();
+
+    let mut replay = ReplayMessageBus::open(&recording_dir).unwrap();
+    let replayed = run_session(&mut replay);
+
+    fs::remove_dir_all(&recording_dir).unwrap();
+
+    assert_eq!(live.len(), 2, "live responses");
+    assert_eq!(replayed, live, "replayed responses");
+}
+
+#[derive(Default)]
+struct MessageCounter {
+    requests: Mutex<Vec<Option<i32>>>,
+    responses: Mutex<Vec<String>>,
+}
+
+impl MessageObserver for MessageCounter {
+    fn on_request(&self, request_id: Option<i32>, _message: &RequestMessage) {
+        self.requests.lock().unwrap().push(request_id);
+    }
+
+    fn on_response(&self, message: &ResponseMessage) {
+        self.responses.lock().unwrap().push(message.encode());
+    }
+}
+
+#[test]
+fn observers_see_sent_and_received_messages() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        read_frame(&mut stream); // contract details request
+        write_frame(&mut stream, "4\02\09000\0200\0No security definition has been found for the request\0");
+        write_frame(&mut stream, "52\01\09000\0");
+
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default() // returns once client shuts down
+    });
+
+    let counter = Arc::new(MessageCounter::default());
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    bus.add_observer(counter.clone());
+
+    let live = run_session(&mut bus);
+
+    bus.disconnect().unwrap();
+    server.join().unwrap();
+
+    // start api and contract details
+    assert_eq!(*counter.requests.lock().unwrap(), vec![None, Some(9000)], "observed requests");
+
+    // server version followed by the contract details responses
+    let responses = counter.responses.lock().unwrap();
+    assert_eq!(responses.len(), 3, "observed responses");
+    assert!(
+        responses[0].starts_with(&format!("{}\0", server_versions::SIZE_RULES)),
+        "observed server version: {:?}",
+        responses[0]
+    );
+    assert_eq!(responses[1..], live[..], "observed contract details responses");
+}
+
+// Performs the handshake and requests contract details. Returns the encoded responses.
+fn run_session(bus: &mut dyn MessageBus) -> Vec<String> {
+    use crate::client::encode_packet;
+    use crate::messages::OutgoingMessages;
+
+    bus.write(&("API\0".to_owned() + &encode_packet("v100..165"))).unwrap();
+    let mut ack = bus.read_message().unwrap();
+    let server_version = ack.next_int().unwrap();
+
+    bus.start_api(server_versions::SIZE_RULES, 100, "").unwrap();
+
+    bus.process_messages(server_version).unwrap();
+
+    let mut request = RequestMessage::new();
+    request.push_field(&OutgoingMessages::RequestContractData);
+    request.push_field(&8);
+    request.push_field(&9000);
+    let responses = bus.send_generic_message(9000, &request).unwrap();
+
+    responses.take(2).map(|message| message.encode()).collect()
+}
+
+#[test]
+fn heartbeat_detects_stale_connection() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // reads heartbeats without ever answering them
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut pings = Vec::new();
+
+        let mut header = [0_u8; 4];
+        while stream.read_exact(&mut header).is_ok() {
+            let mut data = vec![0_u8; u32::from_be_bytes(header) as usize];
+            if stream.read_exact(&mut data).is_err() {
+                break;
+            }
+            pings.push(String::from_utf8(data).unwrap());
+        }
+
+        // accepts the reconnect so the client can shut down cleanly
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default();
+
+        pings
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap().heartbeat(Duration::from_millis(50));
+    bus.process_messages(server_versions::SIZE_RULES).unwrap();
+
+    assert!(bus.is_healthy(), "connection should start healthy");
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while bus.is_healthy() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert!(!bus.is_healthy(), "stale connection not detected");
+
+    bus.disconnect().unwrap();
+
+    let pings = server.join().unwrap();
+    assert!(!pings.is_empty(), "no heartbeats sent");
+    assert_eq!(pings[0], "49\01\0", "heartbeat message");
+}
+
+// Sends 5 messages to a subscription buffering 2 without consuming them. Returns the messages queued and whether the subscription is still active.
+fn flood_bounded_subscription(overflow_policy: OverflowPolicy) -> (Vec<i32>, bool) {
+    let requests = SenderHash::<i32, ResponseMessage>::new();
+
+    let (sender, receiver) = channel::bounded(2);
+    requests.insert(9000, ResponseSender::bounded(sender, &receiver, overflow_policy));
+
+    for i in 0..5 {
+        requests.send(&9000, ResponseMessage::from(&format!("1\09000\0{i}"))).unwrap();
+    }
+
+    let received = receiver.try_iter().map(|message| message.peek_int(2).unwrap()).collect();
+    (received, requests.contains(&9000))
+}
+
+#[test]
+fn bounded_channels_honor_overflow_policy() {
+    assert_eq!(flood_bounded_subscription(OverflowPolicy::DropOldest), (vec![3, 4], true), "drop oldest");
+    assert_eq!(flood_bounded_subscription(OverflowPolicy::DropNewest), (vec![0, 1], true), "drop newest");
+    assert_eq!(flood_bounded_subscription(OverflowPolicy::Error), (vec![0, 1], false), "error");
+}
+
+#[test]
+fn bounded_channels_block_until_consumed() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+
+    let (sender, receiver) = channel::bounded(2);
+    requests.insert(9000, ResponseSender::bounded(sender, &receiver, OverflowPolicy::Block));
+
+    let producer = {
+        let requests = Arc::clone(&requests);
+        thread::spawn(move || {
+            for i in 0..5 {
+                requests.send(&9000, ResponseMessage::from(&format!("1\09000\0{i}"))).unwrap();
+            }
+        })
+    };
+
+    let received: Vec<i32> = receiver.iter().take(5).map(|message| message.peek_int(2).unwrap()).collect();
+    producer.join().unwrap();
+
+    assert_eq!(received, vec![0, 1, 2, 3, 4], "received");
+}
+
+#[test]
+fn bounded_channels_block_briefly_for_stalled_consumers() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+
+    // the first request is never consumed, its channel is full
+    let (stalled_sender, stalled_receiver) = channel::bounded(1);
+    requests.insert(9000, ResponseSender::bounded(stalled_sender, &stalled_receiver, OverflowPolicy::Block));
+    requests.send(&9000, ResponseMessage::from("1\09000\00\0")).unwrap();
+
+    let (sender, receiver) = channel::bounded(1);
+    requests.insert(9001, ResponseSender::bounded(sender, &receiver, OverflowPolicy::Block));
+
+    let dispatcher = {
+        let requests = Arc::clone(&requests);
+        thread::spawn(move || {
+            requests.send(&9000, ResponseMessage::from("1\09000\01\0")).unwrap();
+            requests.send(&9001, ResponseMessage::from("1\09001\00\0")).unwrap();
+        })
+    };
+
+    let started = Instant::now();
+    match receiver.recv_timeout(Duration::from_secs(2)) {
+        Ok(message) => assert_eq!(message.peek_int(1).unwrap(), 9001, "request id"),
+        Err(err) => assert!(false, "response of other request not delivered: {err}"),
+    }
+    assert!(started.elapsed() < Duration::from_secs(1), "delivered after {:?}", started.elapsed());
+    dispatcher.join().unwrap();
+
+    // the response to the stalled request is dropped, the request stays subscribed
+    assert_eq!(stalled_receiver.len(), 1, "stalled_receiver.len()");
+    assert_eq!(stalled_receiver.recv().unwrap().peek_int(2).unwrap(), 0, "queued response");
+    assert!(requests.contains(&9000), "stalled request should stay subscribed");
+}
+
+#[test]
+fn bounded_channels_end_subscription_on_error() {
+    let requests = SenderHash::<i32, ResponseMessage>::new();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let (sender, receiver) = channel::bounded(1);
+    requests.insert(9000, ResponseSender::bounded(sender, &receiver, OverflowPolicy::Error));
+    let mut responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(100));
+
+    requests.send(&9000, ResponseMessage::from("1\09000\00")).unwrap();
+    requests.send(&9000, ResponseMessage::from("1\09000\01")).unwrap();
+
+    assert!(responses.recv_timeout().is_ok(), "queued response delivered");
+    match responses.recv_timeout() {
+        Err(Error::Simple(_)) => (),
+        other => assert!(false, "expected subscription to end, got {other:?}"),
+    }
+}
+
+#[test]
+fn bounded_channels_deliver_queued_responses_before_ending() {
+    let requests = SenderHash::<i32, ResponseMessage>::new();
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    let (sender, receiver) = channel::bounded(2);
+    requests.insert(9000, ResponseSender::bounded(sender, &receiver, OverflowPolicy::Error));
+    let responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(100));
+
+    for value in 0..3 {
+        requests
+            .send(&9000, ResponseMessage::from(format!("1\09000\0{value}\0").as_str()))
+            .unwrap();
+    }
+
+    let values: Vec<i32> = responses
+        .map(|mut message| {
+            message.skip(); // message type
+            message.skip(); // request id
+            message.next_int().unwrap()
+        })
+        .collect();
+
+    assert_eq!(values, vec![0, 1], "queued responses before the iteration ends");
+    assert!(!requests.contains(&9000), "sender should be removed on overflow");
+}
+
+#[test]
+fn routes_option_computations_by_server_version() {
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+
+    // servers before PRICE_BASED_VOLATILITY send the message version ahead of the request id
+    let computations = [
+        (
+            server_versions::PRICE_BASED_VOLATILITY - 1,
+            "21\06\09000\013\00.3\00.6\05.4\0-1\00.03\00.12\0-0.04\0185.5\0",
+        ),
+        (
+            server_versions::PRICE_BASED_VOLATILITY,
+            "21\09000\013\01\00.3\00.6\05.4\0-1\00.03\00.12\0-0.04\0185.5\0",
+        ),
+    ];
+
+    for (server_version, message) in computations {
+        let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+        let (sender, receiver) = channel::unbounded();
+        requests.insert(9000, sender);
+
+        dispatch_message(
+            ResponseMessage::from(message),
+            server_version,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+
+        assert!(
+            receiver.try_recv().is_ok(),
+            "computation routed to request 9000 for server version {server_version}"
+        );
+    }
+}
+
+#[test]
+fn executions_route_commission_reports_and_end() {
+    let requests = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let orders = Arc::new(SenderHash::<i32, ResponseMessage>::new());
+    let globals = Arc::new(GlobalChannels::new());
+    let executions = SenderHash::<String, ResponseMessage>::new();
+    let order_ids = AtomicI32::new(-1);
+    let (signals_send, _signals_recv) = channel::unbounded();
+
+    // executions of order 13, placed by this client, and requested with Client::executions
+    let (sender, receiver) = channel::unbounded();
+    orders.insert(13, sender);
+    let order_responses = ResponseIterator::new(receiver, signals_send.clone(), None, Some(13), Duration::from_millis(100));
+
+    let (sender, receiver) = channel::unbounded();
+    requests.insert(9000, sender);
+    let execution_responses = ResponseIterator::new(receiver, signals_send, Some(9000), None, Duration::from_millis(100));
+
+    let messages = [
+        "11\0-1\013\076792991\0TSLA\0STK\0\00.0\0\0\0ISLAND\0USD\0TSLA\0NMS\000025b46.63f8f39c.01.01\020230224  12:04:56\0DU1236109\0ISLAND\0BOT\0100\0196.52\01376327563\0100\00\0100\0196.52\0\0\0\0\02\0\0",
+        "59\01\000025b46.63f8f39c.01.01\01.0\0USD\01.7976931348623157E308\01.7976931348623157E308\0\0\0",
+        "11\09000\013\076792991\0TSLA\0STK\0\00.0\0\0\0ISLAND\0USD\0TSLA\0NMS\000025b46.63f8f39c.01.02\020230224  12:04:56\0DU1236109\0ISLAND\0BOT\0100\0196.52\01376327563\0100\00\0100\0196.52\0\0\0\0\02\0\0",
+        "59\01\000025b46.63f8f39c.01.02\01.0\0USD\01.7976931348623157E308\01.7976931348623157E308\0\0\0",
+        "55\01\09000\0",
+    ];
+
+    for message in messages {
+        dispatch_message(
+            ResponseMessage::from(message),
+            server_versions::SIZE_RULES,
+            &requests,
+            &orders,
+            &globals,
+            &executions,
+            &order_ids,
+        );
+    }
+
+    let order_messages: Vec<IncomingMessages> = order_responses.messages.try_iter().map(|message| message.message_type()).collect();
+    assert_eq!(
+        order_messages,
+        vec![IncomingMessages::ExecutionData, IncomingMessages::CommissionsReport],
+        "order messages"
+    );
+
+    let execution_messages: Vec<IncomingMessages> = execution_responses.messages.try_iter().map(|message| message.message_type()).collect();
+    assert_eq!(
+        execution_messages,
+        vec![
+            IncomingMessages::ExecutionData,
+            IncomingMessages::CommissionsReport,
+            IncomingMessages::ExecutionDataEnd
+        ],
+        "execution messages"
+    );
+
+    assert_eq!(executions.len(), 0, "executions released once commission reports are routed");
+}
+
+#[test]
+fn malformed_messages_do_not_stop_processing() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        read_frame(&mut stream); // contract details request
+
+        // not utf-8
+        stream.write_all(&2_u32.to_be_bytes()).unwrap();
+        stream.write_all(&[0xff, 0xfe]).unwrap();
+
+        write_frame(&mut stream, "999\0"); // unknown message type
+        write_frame(&mut stream, "52\0"); // missing request id
+        write_frame(&mut stream, "9\0"); // missing order id
+        write_frame(&mut stream, "4\02\09000\0200\0No security definition has been found for the request\0");
+        write_frame(&mut stream, "52\01\09000\0");
+
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap_or_default() // returns once client shuts down
+    });
+
+    let mut bus = TcpMessageBus::connect(&address).unwrap();
+    let responses = run_session(&mut bus);
+
+    bus.disconnect().unwrap();
+    server.join().unwrap();
+
+    assert_eq!(responses.len(), 2, "responses after malformed messages");
+    assert!(responses[1].starts_with("52\01\09000\0"), "contract data end: {:?}", responses[1]);
+}
+
+#[test]
+fn message_stats_count_message_types() {
+    let stats = MessageStats::default();
+
+    for message in [
+        "3\013\0Filled\0",
+        "3\013\0Filled\0",
+        "53\01\0",
+        "1\02\09000\01\0185.50\0",
+        "999\0",
+        "-2\0",
+    ] {
+        stats.record(ResponseMessage::from(message).message_type());
+    }
+
+    let counts = stats.counts();
+    assert_eq!(counts.len(), 5, "counts.len()");
+    assert_eq!(counts.get(&IncomingMessages::OrderStatus), Some(&2), "OrderStatus");
+    assert_eq!(counts.get(&IncomingMessages::OpenOrderEnd), Some(&1), "OpenOrderEnd");
+    assert_eq!(counts.get(&IncomingMessages::TickPrice), Some(&1), "TickPrice");
+    assert_eq!(counts.get(&IncomingMessages::NotValid), Some(&1), "NotValid");
+    assert_eq!(counts.get(&IncomingMessages::ConnectionLost), Some(&1), "ConnectionLost");
+    assert_eq!(counts.get(&IncomingMessages::UserInfo), None, "UserInfo");
+}
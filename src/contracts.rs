@@ -1,12 +1,17 @@
+use std::collections::HashSet;
 use std::convert::From;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 use std::string::ToString;
+use std::time::{Duration, Instant};
 
 use log::{error, info};
 
+use crate::client::transport::ResponseIterator;
 use crate::encode_option_field;
 use crate::messages::IncomingMessages;
 use crate::messages::RequestMessage;
+use crate::orders::Action;
 use crate::Client;
 use crate::{server_versions, Error, ToField};
 
@@ -30,6 +35,8 @@ pub enum SecurityType {
     Option,
     /// Future
     Future,
+    /// Continuous future
+    ContinuousFuture,
     /// Index
     Index,
     /// Futures option
@@ -40,14 +47,53 @@ pub enum SecurityType {
     Spread,
     ///  Warrant
     Warrant,
+    /// Dutch warrant
+    DutchWarrant,
     /// Bond
     Bond,
+    /// Treasury bill
+    Bill,
+    /// Fixed income
+    Fixed,
+    /// Contract for difference
+    Cfd,
     /// Commodity
     Commodity,
+    /// Forward
+    Forward,
     /// News
     News,
     /// Mutual fund
     MutualFund,
+    /// Cryptocurrency
+    Crypto,
+    /// Security type not modelled above, e.g. one added in a newer TWS release. Holds the TWS code.
+    Other(String),
+}
+
+impl SecurityType {
+    /// Security types with a dedicated variant, see [SecurityType::Other] for the rest.
+    pub const ALL: [SecurityType; 19] = [
+        SecurityType::Stock,
+        SecurityType::Option,
+        SecurityType::Future,
+        SecurityType::ContinuousFuture,
+        SecurityType::Index,
+        SecurityType::FuturesOption,
+        SecurityType::ForexPair,
+        SecurityType::Spread,
+        SecurityType::Warrant,
+        SecurityType::DutchWarrant,
+        SecurityType::Bond,
+        SecurityType::Bill,
+        SecurityType::Fixed,
+        SecurityType::Cfd,
+        SecurityType::Commodity,
+        SecurityType::Forward,
+        SecurityType::News,
+        SecurityType::MutualFund,
+        SecurityType::Crypto,
+    ];
 }
 
 impl ToField for SecurityType {
@@ -62,22 +108,40 @@ impl ToField for Option<SecurityType> {
     }
 }
 
-impl ToString for SecurityType {
-    fn to_string(&self) -> String {
-        match self {
-            SecurityType::Stock => "STK".to_string(),
-            SecurityType::Option => "OPT".to_string(),
-            SecurityType::Future => "FUT".to_string(),
-            SecurityType::Index => "IND".to_string(),
-            SecurityType::FuturesOption => "FOP".to_string(),
-            SecurityType::ForexPair => "CASH".to_string(),
-            SecurityType::Spread => "BAG".to_string(),
-            SecurityType::Warrant => "WAR".to_string(),
-            SecurityType::Bond => "BOND".to_string(),
-            SecurityType::Commodity => "CMDTY".to_string(),
-            SecurityType::News => "NEWS".to_string(),
-            SecurityType::MutualFund => "FUND".to_string(),
-        }
+impl fmt::Display for SecurityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            SecurityType::Stock => "STK",
+            SecurityType::Option => "OPT",
+            SecurityType::Future => "FUT",
+            SecurityType::ContinuousFuture => "CONTFUT",
+            SecurityType::Index => "IND",
+            SecurityType::FuturesOption => "FOP",
+            SecurityType::ForexPair => "CASH",
+            SecurityType::Spread => "BAG",
+            SecurityType::Warrant => "WAR",
+            SecurityType::DutchWarrant => "IOPT",
+            SecurityType::Bond => "BOND",
+            SecurityType::Bill => "BILL",
+            SecurityType::Fixed => "FIXED",
+            SecurityType::Cfd => "CFD",
+            SecurityType::Commodity => "CMDTY",
+            SecurityType::Forward => "FWD",
+            SecurityType::News => "NEWS",
+            SecurityType::MutualFund => "FUND",
+            SecurityType::Crypto => "CRYPTO",
+            SecurityType::Other(code) => code,
+        };
+        write!(f, "{code}")
+    }
+}
+
+impl FromStr for SecurityType {
+    type Err = Error;
+
+    /// Parses the TWS code of a security type. Unknown codes are kept as [SecurityType::Other].
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(SecurityType::from(name))
     }
 }
 
@@ -87,20 +151,49 @@ impl SecurityType {
             "STK" => SecurityType::Stock,
             "OPT" => SecurityType::Option,
             "FUT" => SecurityType::Future,
+            "CONTFUT" => SecurityType::ContinuousFuture,
             "IND" => SecurityType::Index,
             "FOP" => SecurityType::FuturesOption,
             "CASH" => SecurityType::ForexPair,
             "BAG" => SecurityType::Spread,
             "WAR" => SecurityType::Warrant,
+            "IOPT" => SecurityType::DutchWarrant,
             "BOND" => SecurityType::Bond,
+            "BILL" => SecurityType::Bill,
+            "FIXED" => SecurityType::Fixed,
+            "CFD" => SecurityType::Cfd,
             "CMDTY" => SecurityType::Commodity,
+            "FWD" => SecurityType::Forward,
             "NEWS" => SecurityType::News,
             "FUND" => SecurityType::MutualFund,
-            &_ => todo!(),
+            "CRYPTO" => SecurityType::Crypto,
+            other => SecurityType::Other(other.to_owned()),
         }
     }
 }
 
+/// Right of an option contract, see [Contract::option].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Right {
+    Call,
+    Put,
+}
+
+impl fmt::Display for Right {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Right::Call => write!(f, "C"),
+            Right::Put => write!(f, "P"),
+        }
+    }
+}
+
+impl ToField for Right {
+    fn to_field(&self) -> String {
+        self.to_string()
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 /// Contract describes an instrument's definition
 pub struct Contract {
@@ -159,6 +252,64 @@ impl Contract {
         }
     }
 
+    /// Creates an option contract on a US stock, routed to SMART with a multiplier of 100.
+    ///
+    /// # Arguments
+    /// * `symbol` - symbol of the underlying. e.g. AAPL
+    /// * `expiry` - last trading day (YYYYMMDD) or contract month (YYYYMM).
+    /// * `strike` - strike price.
+    /// * `right`  - [Right::Call] or [Right::Put].
+    pub fn option(symbol: &str, expiry: &str, strike: f64, right: Right) -> Contract {
+        Contract {
+            symbol: symbol.to_string(),
+            security_type: SecurityType::Option,
+            last_trade_date_or_contract_month: expiry.to_string(),
+            strike,
+            right: right.to_string(),
+            multiplier: "100".to_string(),
+            currency: "USD".to_string(),
+            exchange: "SMART".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a futures contract for the given contract month (YYYYMM) or last trading day (YYYYMMDD) on an exchange. e.g. ES, 202312, CME
+    ///
+    /// Currency defaults to USD.
+    pub fn future(symbol: &str, expiry: &str, exchange: &str) -> Contract {
+        Contract {
+            symbol: symbol.to_string(),
+            security_type: SecurityType::Future,
+            last_trade_date_or_contract_month: expiry.to_string(),
+            exchange: exchange.to_string(),
+            currency: "USD".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a forex pair routed to IDEALPRO. e.g. EUR, USD for the EUR.USD pair.
+    pub fn forex(base: &str, quote: &str) -> Contract {
+        Contract {
+            symbol: base.to_string(),
+            security_type: SecurityType::ForexPair,
+            currency: quote.to_string(),
+            local_symbol: format!("{base}.{quote}"),
+            exchange: "IDEALPRO".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a cryptocurrency contract traded in USD on PAXOS. e.g. BTC
+    pub fn crypto(symbol: &str) -> Contract {
+        Contract {
+            symbol: symbol.to_string(),
+            security_type: SecurityType::Crypto,
+            currency: "USD".to_string(),
+            exchange: "PAXOS".to_string(),
+            ..Default::default()
+        }
+    }
+
     /// Creates futures contract from specified symbol
     pub fn futures(symbol: &str) -> Contract {
         Contract {
@@ -169,6 +320,20 @@ impl Contract {
         }
     }
 
+    /// Creates a combo (BAG) contract from the given legs, e.g. for spreads and pairs. Legs can be built with [ComboLegBuilder].
+    ///
+    /// The symbol can be the symbol of the first leg or the currency. The combo is routed to SMART.
+    pub fn combo(symbol: &str, currency: &str, legs: Vec<ComboLeg>) -> Contract {
+        Contract {
+            symbol: symbol.to_string(),
+            security_type: SecurityType::Spread,
+            currency: currency.to_string(),
+            exchange: "SMART".to_string(),
+            combo_legs: legs,
+            ..Default::default()
+        }
+    }
+
     /// Is Bag request
     pub fn is_bag(&self) -> bool {
         self.security_type == SecurityType::Spread
@@ -246,6 +411,86 @@ impl From<i32> for ComboLegOpenClose {
     }
 }
 
+/// Builds a [ComboLeg] for a BAG contract, see [Contract::combo].
+///
+/// # Examples
+///
+/// ```
+/// use ibapi::contracts::{ComboLegBuilder, Contract};
+/// use ibapi::orders::Action;
+///
+/// let legs = vec![
+///     ComboLegBuilder::new(55928698).action(Action::Buy).exchange("IPE").build(),
+///     ComboLegBuilder::new(55850663).action(Action::Sell).exchange("IPE").build(),
+/// ];
+///
+/// let contract = Contract::combo("WTI", "USD", legs);
+/// assert!(contract.is_bag());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ComboLegBuilder {
+    leg: ComboLeg,
+}
+
+impl ComboLegBuilder {
+    /// Starts a leg for the contract with the given IB contract id. The leg buys one contract unless configured otherwise.
+    pub fn new(contract_id: i32) -> Self {
+        ComboLegBuilder {
+            leg: ComboLeg {
+                contract_id,
+                ratio: 1,
+                action: Action::Buy.to_string(),
+                ..ComboLeg::default()
+            },
+        }
+    }
+
+    /// Relative number of contracts for the leg.
+    pub fn ratio(mut self, ratio: i32) -> Self {
+        self.leg.ratio = ratio;
+        self
+    }
+
+    /// Side of the leg.
+    pub fn action(mut self, action: Action) -> Self {
+        self.leg.action = action.to_string();
+        self
+    }
+
+    /// Exchange the leg is routed to.
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.leg.exchange = exchange.to_owned();
+        self
+    }
+
+    /// Whether the leg opens or closes a position. Only valid for institutional customers.
+    pub fn open_close(mut self, open_close: ComboLegOpenClose) -> Self {
+        self.leg.open_close = open_close;
+        self
+    }
+
+    /// Where shares to short are held: 1 = clearing broker, 2 = third party, see [Self::designated_location].
+    pub fn short_sale_slot(mut self, short_sale_slot: i32) -> Self {
+        self.leg.short_sale_slot = short_sale_slot;
+        self
+    }
+
+    /// Location of the shares to short when the short sale slot is 2.
+    pub fn designated_location(mut self, designated_location: &str) -> Self {
+        self.leg.designated_location = designated_location.to_owned();
+        self
+    }
+
+    pub fn exempt_code(mut self, exempt_code: i32) -> Self {
+        self.leg.exempt_code = exempt_code;
+        self
+    }
+
+    pub fn build(self) -> ComboLeg {
+        self.leg
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 /// Delta and underlying price for Delta-Neutral combo orders.
 /// Underlying (STK or FUT), delta and underlying price goes into this attribute.
@@ -382,23 +627,93 @@ pub(crate) fn contract_details(client: &Client, contract: &Contract) -> Result<V
     let request_id = client.next_request_id();
     let packet = encoders::request_contract_data(client.server_version(), request_id, contract)?;
 
-    let responses = client.send_request(request_id, packet)?;
+    collect_contract_details(client, request_id, packet)
+}
+
+// Sends the requests for all contracts before reading any response. The responses are routed to each request by request id,
+// so the requests are processed by the server concurrently. Each request succeeds or fails on its own.
+pub(crate) fn contract_details_batch(client: &Client, contracts: &[Contract]) -> Result<Vec<Result<Vec<ContractDetails>, Error>>, Error> {
+    for contract in contracts {
+        verify_contract(client, contract)?;
+    }
+
+    let mut requests = Vec::with_capacity(contracts.len());
+    for contract in contracts {
+        let request_id = client.next_request_id();
+        let packet = encoders::request_contract_data(client.server_version(), request_id, contract)?;
+        requests.push(client.send_request(request_id, packet)?);
+    }
 
+    let deadline = Instant::now() + CONTRACT_DETAILS_TIMEOUT;
+    Ok(requests
+        .iter_mut()
+        .map(|responses| read_contract_details(client.server_version(), responses, deadline))
+        .collect())
+}
+
+// Requests the single contract identified by contract id.
+pub(crate) fn contract_details_by_contract_id(client: &Client, contract_id: i32) -> Result<ContractDetails, Error> {
+    let request_id = client.next_request_id();
+    let packet = encoders::request_contract_data_by_contract_id(client.server_version(), request_id, contract_id)?;
+
+    let contract_details = collect_contract_details(client, request_id, packet)?;
+
+    single_contract(contract_details, &format!("contract id {contract_id}"))
+}
+
+// Requests the single contract matching the contract provided.
+pub(crate) fn resolve_contract(client: &Client, contract: &Contract) -> Result<ContractDetails, Error> {
+    let contract_details = contract_details(client, contract)?;
+
+    single_contract(contract_details, &format!("symbol {}", contract.symbol))
+}
+
+// Returns the only contract of the candidates. Rows repeating a contract id and exchange count once.
+fn single_contract(mut candidates: Vec<ContractDetails>, description: &str) -> Result<ContractDetails, Error> {
+    let mut seen = HashSet::new();
+    candidates.retain(|details| seen.insert((details.contract.contract_id, details.contract.exchange.clone())));
+
+    match candidates.len() {
+        1 => Ok(candidates.remove(0)),
+        0 => Err(Error::Simple(format!("no contract found for {description}"))),
+        _ => Err(Error::AmbiguousContract { candidates }),
+    }
+}
+
+// Time allowed for receiving all contract details of a request. Broad requests may match many contracts.
+const CONTRACT_DETAILS_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Collects contract details until the end of the response.
+fn collect_contract_details(client: &Client, request_id: i32, packet: RequestMessage) -> Result<Vec<ContractDetails>, Error> {
+    let mut responses = client.send_request(request_id, packet)?;
+
+    read_contract_details(client.server_version(), &mut responses, Instant::now() + CONTRACT_DETAILS_TIMEOUT)
+}
+
+// Reads contract details until the end marker. Fails with the contract details received so far when the end marker does not arrive before the deadline.
+fn read_contract_details(server_version: i32, responses: &mut ResponseIterator, deadline: Instant) -> Result<Vec<ContractDetails>, Error> {
     let mut contract_details: Vec<ContractDetails> = Vec::default();
 
-    // TODO create iterator
-    for mut message in responses {
+    loop {
+        let mut message = match responses.recv_deadline(deadline) {
+            Ok(message) => message,
+            Err(Error::Timeout) => return Err(Error::incomplete(contract_details)),
+            Err(err) => {
+                info!("error receiving contract details: {err}");
+                break;
+            }
+        };
+
         match message.message_type() {
             IncomingMessages::ContractData => {
-                let decoded = decoders::contract_details(client.server_version(), &mut message)?;
+                let decoded = decoders::contract_details(server_version, &mut message)?;
                 contract_details.push(decoded);
             }
             IncomingMessages::ContractDataEnd => {
                 break;
             }
             IncomingMessages::Error => {
-                error!("error: {message:?}");
-                return Err(Error::Simple(format!("contract_details {message:?}")));
+                return Err(message.api_error_for(server_version));
             }
             _ => {
                 error!("unexpected message: {:?}", message);
@@ -454,23 +769,19 @@ pub struct ContractDescription {
 // * `client` - [Client] with an active connection to gateway.
 // * `pattern` - Either start of ticker symbol or (for larger strings) company name.
 pub(crate) fn matching_symbols(client: &Client, pattern: &str) -> Result<Vec<ContractDescription>, Error> {
-    client.check_server_version(server_versions::REQ_MATCHING_SYMBOLS, "It does not support mathing symbols requests.")?;
+    client.check_server_version(server_versions::REQ_MATCHING_SYMBOLS, "It does not support matching symbols requests.")?;
 
     let request_id = client.next_request_id();
     let request = encoders::request_matching_symbols(request_id, pattern)?;
 
     let mut responses = client.send_request(request_id, request)?;
 
-    if let Some(mut message) = responses.next() {
+    if let Some(message) = responses.next_result() {
+        let mut message = message?;
         match message.message_type() {
             IncomingMessages::SymbolSamples => {
                 return decoders::contract_descriptions(client.server_version(), &mut message);
             }
-            IncomingMessages::Error => {
-                // TODO custom error
-                error!("unexpected error: {:?}", message);
-                return Err(Error::Simple(format!("unexpected error: {message:?}")));
-            }
             _ => {
                 info!("unexpected message: {:?}", message);
                 return Err(Error::Simple(format!("unexpected message: {message:?}")));
@@ -481,12 +792,259 @@ pub(crate) fn matching_symbols(client: &Client, pattern: &str) -> Result<Vec<Con
     Ok(Vec::default())
 }
 
+/// Option chain for an underlying on an exchange, received from [Client::option_chains].
+#[derive(Debug, Default)]
+pub struct OptionChain {
+    /// The exchange for which the option parameters apply
+    pub exchange: String,
+    /// The conid of the underlying security
+    pub underlying_contract_id: i32,
+    /// The option trading class
+    pub trading_class: String,
+    /// The option multiplier
+    pub multiplier: String,
+    /// Available expirations, formatted YYYYMMDD
+    pub expirations: Vec<String>,
+    /// Available strikes
+    pub strikes: Vec<f64>,
+}
+
+// Requests security definition option parameters for viewing a contract's option chain.
+pub(crate) fn option_chains(
+    client: &Client,
+    symbol: &str,
+    exchange: &str,
+    security_type: SecurityType,
+    underlying_contract_id: i32,
+) -> Result<Vec<OptionChain>, Error> {
+    client.check_server_version(
+        server_versions::SEC_DEF_OPT_PARAMS_REQ,
+        "It does not support security definition option parameters requests.",
+    )?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_option_chains(request_id, symbol, exchange, security_type, underlying_contract_id)?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    read_option_chains(client.server_version(), &mut responses, Instant::now() + OPTION_CHAINS_TIMEOUT)
+}
+
+// Time allowed for receiving all option chains of a request, one is sent per exchange.
+const OPTION_CHAINS_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Reads option chains until the end marker. Fails with the option chains received so far when the end marker does not arrive before the deadline.
+fn read_option_chains(server_version: i32, responses: &mut ResponseIterator, deadline: Instant) -> Result<Vec<OptionChain>, Error> {
+    let mut option_chains: Vec<OptionChain> = Vec::default();
+
+    loop {
+        let mut message = match responses.recv_deadline(deadline) {
+            Ok(message) => message,
+            Err(Error::Timeout) => return Err(Error::incomplete(option_chains)),
+            Err(err) => {
+                info!("error receiving option chains: {err}");
+                break;
+            }
+        };
+
+        match message.message_type() {
+            IncomingMessages::SecurityDefinitionOptionParameter => {
+                option_chains.push(decoders::option_chain(&mut message)?);
+            }
+            IncomingMessages::SecurityDefinitionOptionParameterEnd => {
+                break;
+            }
+            IncomingMessages::Error => {
+                return Err(message.api_error_for(server_version));
+            }
+            _ => {
+                error!("unexpected message: {:?}", message);
+            }
+        }
+    }
+
+    Ok(option_chains)
+}
+
+/// Option price and greeks computed by TWS, received from [Client::calculate_implied_volatility] and [Client::calculate_option_price].
+///
+/// Values TWS was unable to compute are reported as `None`.
+#[derive(Debug, Default)]
+pub struct OptionComputation {
+    /// The implied volatility calculated by the TWS option modeler, using the specified tick type value.
+    pub implied_vol: Option<f64>,
+    /// The option delta value.
+    pub delta: Option<f64>,
+    /// The option gamma value.
+    pub gamma: Option<f64>,
+    /// The option vega value.
+    pub vega: Option<f64>,
+    /// The option theta value.
+    pub theta: Option<f64>,
+    /// The option price.
+    pub opt_price: Option<f64>,
+    /// The present value of dividends expected on the option's underlying.
+    pub pv_dividend: Option<f64>,
+    /// The price of the underlying.
+    pub und_price: Option<f64>,
+}
+
+// Calculates an option's implied volatility based on the specified option and underlying prices.
+pub(crate) fn calculate_implied_volatility(
+    client: &Client,
+    contract: &Contract,
+    option_price: f64,
+    underlying_price: f64,
+) -> Result<OptionComputation, Error> {
+    client.check_server_version(
+        server_versions::REQ_CALC_IMPLIED_VOLAT,
+        "It does not support calculate implied volatility requests.",
+    )?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::calculate_implied_volatility(client.server_version(), request_id, contract, option_price, underlying_price)?;
+
+    let responses = client.send_request(request_id, message)?;
+
+    read_option_computation(client, responses, encoders::cancel_implied_volatility(request_id)?)
+}
+
+// Calculates an option's price based on the specified volatility and underlying price.
+pub(crate) fn calculate_option_price(
+    client: &Client,
+    contract: &Contract,
+    volatility: f64,
+    underlying_price: f64,
+) -> Result<OptionComputation, Error> {
+    client.check_server_version(
+        server_versions::REQ_CALC_OPTION_PRICE,
+        "It does not support calculate option price requests.",
+    )?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::calculate_option_price(client.server_version(), request_id, contract, volatility, underlying_price)?;
+
+    let responses = client.send_request(request_id, message)?;
+
+    read_option_computation(client, responses, encoders::cancel_option_price(request_id)?)
+}
+
+// Returns the first computation received. The server keeps calculating until cancelled, so cancel is sent once
+// the computation is read or none arrived in time. Requests ended by an error need no cancel.
+fn read_option_computation(client: &Client, mut responses: ResponseIterator, cancel: RequestMessage) -> Result<OptionComputation, Error> {
+    let computation = loop {
+        match responses.next_result() {
+            Some(Ok(mut message)) if message.message_type() == IncomingMessages::TickOptionComputation => {
+                break decoders::option_computation(client.server_version(), &mut message);
+            }
+            Some(Ok(message)) => error!("unexpected message: {:?}", message),
+            Some(Err(err)) => return Err(err),
+            None => break Err(Error::Simple("no option computation received".into())),
+        }
+    };
+
+    client.send_message(cancel)?;
+
+    computation
+}
+
+/// Fundamental data reports available from Refinitiv (formerly Reuters) through [Client::fundamental_data].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FundamentalReport {
+    /// Company overview
+    ReportSnapshot,
+    /// Financial summary
+    ReportsFinSummary,
+    /// Financial ratios
+    ReportRatios,
+    /// Financial statements
+    ReportsFinStatements,
+    /// Analyst estimates (RESC)
+    Resc,
+    /// Company calendar
+    CalendarReport,
+}
+
+impl fmt::Display for FundamentalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = match self {
+            FundamentalReport::ReportSnapshot => "ReportSnapshot",
+            FundamentalReport::ReportsFinSummary => "ReportsFinSummary",
+            FundamentalReport::ReportRatios => "ReportRatios",
+            FundamentalReport::ReportsFinStatements => "ReportsFinStatements",
+            FundamentalReport::Resc => "RESC",
+            FundamentalReport::CalendarReport => "CalendarReport",
+        };
+
+        write!(f, "{report}")
+    }
+}
+
+impl ToField for FundamentalReport {
+    fn to_field(&self) -> String {
+        self.to_string()
+    }
+}
+
+// Requests a fundamental data report for a contract. The report is returned as the raw XML sent by TWS.
+pub(crate) fn fundamental_data(client: &Client, contract: &Contract, report_type: FundamentalReport) -> Result<String, Error> {
+    client.check_server_version(server_versions::FUNDAMENTAL_DATA, "It does not support fundamental data requests.")?;
+
+    if !contract.trading_class.is_empty() || contract.contract_id > 0 {
+        client.check_server_version(
+            server_versions::TRADING_CLASS,
+            "It does not support contract_id nor trading_class parameters when requesting fundamental data.",
+        )?;
+    }
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_fundamental_data(client.server_version(), request_id, contract, report_type)?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    while let Some(message) = responses.next_result() {
+        let mut message = message?;
+        match message.message_type() {
+            IncomingMessages::FundamentalData => {
+                return decoders::fundamental_data(&mut message);
+            }
+            _ => {
+                error!("unexpected message: {:?}", message);
+            }
+        }
+    }
+
+    Err(Error::Simple("no fundamental data received".into()))
+}
+
+/// Minimum price increments of an instrument on an exchange, returned by [Client::market_rule].
 #[derive(Debug, Default)]
 pub struct MarketRule {
     pub market_rule_id: i32,
+    /// Increment bands ordered by their low edge.
     pub price_increments: Vec<PriceIncrement>,
 }
 
+impl MarketRule {
+    /// Returns the minimum price increment that applies at the given price.
+    pub fn increment(&self, price: f64) -> Option<f64> {
+        self.price_increments
+            .iter()
+            .take_while(|band| band.low_edge <= price.abs())
+            .last()
+            .map(|band| band.increment)
+    }
+
+    /// Rounds price to the nearest valid increment.
+    pub fn round_price(&self, price: f64) -> Option<f64> {
+        match self.increment(price) {
+            Some(increment) if increment > 0.0 => Some((price / increment).round() * increment),
+            _ => None,
+        }
+    }
+}
+
+/// Price increment applied from low_edge up to the low edge of the next band.
 #[derive(Debug, Default)]
 pub struct PriceIncrement {
     pub low_edge: f64,
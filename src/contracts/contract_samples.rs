@@ -65,3 +65,26 @@ pub fn smart_future_combo_contract() -> Contract {
         ..Contract::default()
     }
 }
+
+// Currency pairs are quoted as base.quote, e.g. EUR.USD, and traded on IDEALPRO.
+pub fn forex_pair() -> Contract {
+    Contract {
+        symbol: "EUR".to_owned(),
+        security_type: SecurityType::ForexPair,
+        currency: "USD".to_owned(),
+        local_symbol: "EUR.USD".to_owned(),
+        exchange: "IDEALPRO".to_owned(),
+        ..Contract::default()
+    }
+}
+
+// Cryptocurrencies are traded against USD on PAXOS.
+pub fn crypto_contract() -> Contract {
+    Contract {
+        symbol: "BTC".to_owned(),
+        security_type: SecurityType::Crypto,
+        currency: "USD".to_owned(),
+        exchange: "PAXOS".to_owned(),
+        ..Contract::default()
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{contracts::SecurityType, messages::ResponseMessage, orders::TagValue, server_versions, Error};
 
-use super::{Contract, ContractDescription, ContractDetails, MarketRule, PriceIncrement};
+use super::{Contract, ContractDescription, ContractDetails, MarketRule, OptionChain, OptionComputation, PriceIncrement};
 
 pub(crate) fn contract_details(server_version: i32, message: &mut ResponseMessage) -> Result<ContractDetails, Error> {
     message.skip(); // message type
@@ -67,20 +67,20 @@ pub(crate) fn contract_details(server_version: i32, message: &mut ResponseMessag
             contract.sec_id_list.push(TagValue { tag, value });
         }
     }
-    if server_version > server_versions::AGG_GROUP {
+    if server_version >= server_versions::AGG_GROUP {
         contract.agg_group = message.next_int()?;
     }
-    if server_version > server_versions::UNDERLYING_INFO {
+    if server_version >= server_versions::UNDERLYING_INFO {
         contract.under_symbol = message.next_string()?;
         contract.under_security_type = message.next_string()?;
     }
-    if server_version > server_versions::MARKET_RULES {
+    if server_version >= server_versions::MARKET_RULES {
         contract.market_rule_ids = message.next_string()?;
     }
-    if server_version > server_versions::REAL_EXPIRATION_DATE {
+    if server_version >= server_versions::REAL_EXPIRATION_DATE {
         contract.real_expiration_date = message.next_string()?;
     }
-    if server_version > server_versions::STOCK_TYPE {
+    if server_version >= server_versions::STOCK_TYPE {
         contract.stock_type = message.next_string()?;
     }
     if (server_versions::FRACTIONAL_SIZE_SUPPORT..server_versions::SIZE_RULES).contains(&server_version) {
@@ -95,7 +95,7 @@ pub(crate) fn contract_details(server_version: i32, message: &mut ResponseMessag
     Ok(contract)
 }
 
-fn read_last_trade_date(contract: &mut ContractDetails, last_trade_date_or_contract_month: &str, is_bond: bool) -> Result<(), Error> {
+pub(super) fn read_last_trade_date(contract: &mut ContractDetails, last_trade_date_or_contract_month: &str, is_bond: bool) -> Result<(), Error> {
     if last_trade_date_or_contract_month.is_empty() {
         return Ok(());
     }
@@ -166,6 +166,89 @@ pub(crate) fn contract_descriptions(server_version: i32, message: &mut ResponseM
     Ok(contract_descriptions)
 }
 
+pub(crate) fn option_chain(message: &mut ResponseMessage) -> Result<OptionChain, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let mut option_chain = OptionChain {
+        exchange: message.next_string()?,
+        underlying_contract_id: message.next_int()?,
+        trading_class: message.next_string()?,
+        multiplier: message.next_string()?,
+        ..Default::default()
+    };
+
+    let expirations_count = message.next_int()?;
+    for _ in 0..expirations_count {
+        option_chain.expirations.push(message.next_string()?);
+    }
+
+    let strikes_count = message.next_int()?;
+    for _ in 0..strikes_count {
+        option_chain.strikes.push(message.next_double()?);
+    }
+
+    Ok(option_chain)
+}
+
+pub(crate) fn option_computation(server_version: i32, message: &mut ResponseMessage) -> Result<OptionComputation, Error> {
+    message.skip(); // message type
+
+    let message_version = if server_version >= server_versions::PRICE_BASED_VOLATILITY {
+        server_version
+    } else {
+        message.next_int()?
+    };
+
+    message.skip(); // request id
+
+    let tick_type = message.next_int()?;
+
+    if server_version >= server_versions::PRICE_BASED_VOLATILITY {
+        message.skip(); // tick attribute
+    }
+
+    let mut computation = OptionComputation {
+        implied_vol: unless_sentinel(message.next_double()?, -1.0),
+        delta: unless_sentinel(message.next_double()?, -2.0),
+        ..Default::default()
+    };
+
+    if message_version >= 6 || tick_type == MODEL_OPTION_COMPUTATION || tick_type == DELAYED_MODEL_OPTION_COMPUTATION {
+        computation.opt_price = unless_sentinel(message.next_double()?, -1.0);
+        computation.pv_dividend = unless_sentinel(message.next_double()?, -1.0);
+    }
+
+    if message_version >= 6 {
+        computation.gamma = unless_sentinel(message.next_double()?, -2.0);
+        computation.vega = unless_sentinel(message.next_double()?, -2.0);
+        computation.theta = unless_sentinel(message.next_double()?, -2.0);
+        computation.und_price = unless_sentinel(message.next_double()?, -1.0);
+    }
+
+    Ok(computation)
+}
+
+const MODEL_OPTION_COMPUTATION: i32 = 13;
+const DELAYED_MODEL_OPTION_COMPUTATION: i32 = 83;
+
+// TWS reports values it could not compute with a sentinel (-1 or -2 depending on the field).
+fn unless_sentinel(value: f64, sentinel: f64) -> Option<f64> {
+    if value == sentinel {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+pub(crate) fn fundamental_data(message: &mut ResponseMessage) -> Result<String, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // request id
+
+    message.next_string()
+}
+
 pub(crate) fn market_rule(message: &mut ResponseMessage) -> Result<MarketRule, Error> {
     message.skip(); // message type
 
@@ -189,6 +272,65 @@ pub(crate) fn market_rule(message: &mut ResponseMessage) -> Result<MarketRule, E
 mod tests {
     use super::*;
 
+    #[test]
+    fn decode_option_computation() {
+        let mut message = ResponseMessage::from("21\09000\053\01\00.3\00.6\05.4\0-1\00.03\00.12\0-0.04\0185.5\0");
+
+        let computation = option_computation(server_versions::SIZE_RULES, &mut message).expect("error decoding option computation");
+
+        assert_eq!(computation.implied_vol, Some(0.3), "computation.implied_vol");
+        assert_eq!(computation.delta, Some(0.6), "computation.delta");
+        assert_eq!(computation.opt_price, Some(5.4), "computation.opt_price");
+        assert_eq!(computation.pv_dividend, None, "computation.pv_dividend");
+        assert_eq!(computation.gamma, Some(0.03), "computation.gamma");
+        assert_eq!(computation.vega, Some(0.12), "computation.vega");
+        assert_eq!(computation.theta, Some(-0.04), "computation.theta");
+        assert_eq!(computation.und_price, Some(185.5), "computation.und_price");
+    }
+
+    #[test]
+    fn decode_contract_details() {
+        let mut message = ResponseMessage::from(
+            "10\08\09001\0TSLA\0STK\0\00\0\0SMART\0USD\0TSLA\0NMS\0NMS\076792991\00.01\0100\0\0ACTIVETIM,AD,LMT,MKT,STP\0SMART,AMEX,NYSE\01\00\0TESLA INC\0NASDAQ\0\0Consumer, Cyclical\0Auto Manufacturers\0Auto-Cars/Light Trucks\0US/Eastern\020221229:0400-20221229:2000;20221230:0400-20221230:2000;20221231:CLOSED\020221229:0930-20221229:1600;20221230:0930-20221230:1600;20221231:CLOSED\0\0\01\0ISIN\0US88160R1014\01\0\0\026,26,26\0\0COMMON\00.0001\0",
+        );
+
+        // fractional size support is the last version sending the message version, md size multiplier and size min tick
+        let details = contract_details(server_versions::FRACTIONAL_SIZE_SUPPORT, &mut message).expect("error decoding contract details");
+
+        assert_eq!(details.contract.symbol, "TSLA", "details.contract.symbol");
+        assert_eq!(details.contract.security_type, SecurityType::Stock, "details.contract.security_type");
+        assert_eq!(details.contract.exchange, "SMART", "details.contract.exchange");
+        assert_eq!(details.contract.currency, "USD", "details.contract.currency");
+        assert_eq!(details.contract.local_symbol, "TSLA", "details.contract.local_symbol");
+        assert_eq!(details.market_name, "NMS", "details.market_name");
+        assert_eq!(details.contract.trading_class, "NMS", "details.contract.trading_class");
+        assert_eq!(details.contract.contract_id, 76792991, "details.contract.contract_id");
+        assert_eq!(details.min_tick, 0.01, "details.min_tick");
+        assert_eq!(details.order_types, "ACTIVETIM,AD,LMT,MKT,STP", "details.order_types");
+        assert_eq!(details.valid_exchanges, "SMART,AMEX,NYSE", "details.valid_exchanges");
+        assert_eq!(details.price_magnifier, 1, "details.price_magnifier");
+        assert_eq!(details.long_name, "TESLA INC", "details.long_name");
+        assert_eq!(details.contract.primary_exchange, "NASDAQ", "details.contract.primary_exchange");
+        assert_eq!(details.industry, "Consumer, Cyclical", "details.industry");
+        assert_eq!(details.category, "Auto Manufacturers", "details.category");
+        assert_eq!(details.subcategory, "Auto-Cars/Light Trucks", "details.subcategory");
+        assert_eq!(details.time_zone_id, "US/Eastern", "details.time_zone_id");
+        assert_eq!(
+            details.trading_hours, "20221229:0400-20221229:2000;20221230:0400-20221230:2000;20221231:CLOSED",
+            "details.trading_hours"
+        );
+        assert_eq!(
+            details.liquid_hours, "20221229:0930-20221229:1600;20221230:0930-20221230:1600;20221231:CLOSED",
+            "details.liquid_hours"
+        );
+        assert_eq!(details.sec_id_list.len(), 1, "details.sec_id_list.len()");
+        assert_eq!(details.sec_id_list[0].value, "US88160R1014", "details.sec_id_list[0].value");
+        assert_eq!(details.agg_group, 1, "details.agg_group");
+        assert_eq!(details.market_rule_ids, "26,26,26", "details.market_rule_ids");
+        assert_eq!(details.stock_type, "COMMON", "details.stock_type");
+        assert_eq!(details.min_size, 0.0, "details.min_size");
+    }
+
     #[test]
     fn decode_market_rule() {
         let mut message = ResponseMessage::from("93\026\01\00\00.01\0");
@@ -1,9 +1,29 @@
-use super::Contract;
+use super::{Contract, FundamentalReport, SecurityType};
 use crate::messages::OutgoingMessages;
 use crate::messages::RequestMessage;
+use crate::messages::{layout_field_count, FieldSpan, NOT_REMOVED};
 use crate::{server_versions, Error};
 
 pub(crate) fn request_contract_data(server_version: i32, request_id: i32, contract: &Contract) -> Result<RequestMessage, Error> {
+    encode_contract_data(server_version, request_id, contract, Some(contract.security_type.clone()))
+}
+
+// Requests the contract with the given contract id. All other fields are left blank so they don't narrow the match.
+pub(crate) fn request_contract_data_by_contract_id(server_version: i32, request_id: i32, contract_id: i32) -> Result<RequestMessage, Error> {
+    let contract = Contract {
+        contract_id,
+        ..Contract::default()
+    };
+
+    encode_contract_data(server_version, request_id, &contract, None)
+}
+
+fn encode_contract_data(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    security_type: Option<SecurityType>,
+) -> Result<RequestMessage, Error> {
     const VERSION: i32 = 8;
 
     let mut packet = RequestMessage::default();
@@ -20,7 +40,7 @@ pub(crate) fn request_contract_data(server_version: i32, request_id: i32, contra
     }
 
     packet.push_field(&contract.symbol);
-    packet.push_field(&contract.security_type);
+    packet.push_field(&security_type);
     packet.push_field(&contract.last_trade_date_or_contract_month);
     packet.push_field(&contract.strike);
     packet.push_field(&contract.right);
@@ -57,9 +77,27 @@ pub(crate) fn request_contract_data(server_version: i32, request_id: i32, contra
         packet.push_field(&contract.issuer_id);
     }
 
+    packet.verify_field_count(layout_field_count(CONTRACT_DATA_LAYOUT, server_version))?;
+
     Ok(packet)
 }
 
+// Fields of a contract data request by the server versions sending them.
+const CONTRACT_DATA_LAYOUT: &[FieldSpan] = &[
+    (0, NOT_REMOVED, 2), // message type and version
+    (server_versions::CONTRACT_DATA_CHAIN, NOT_REMOVED, 1),
+    (server_versions::CONTRACT_CONID, NOT_REMOVED, 1),
+    (0, NOT_REMOVED, 5), // symbol through right
+    (15, NOT_REMOVED, 1),
+    (server_versions::LINKING, NOT_REMOVED, 1),
+    (server_versions::PRIMARYEXCH, NOT_REMOVED, 1),
+    (0, NOT_REMOVED, 2), // currency and local symbol
+    (server_versions::TRADING_CLASS, NOT_REMOVED, 1),
+    (31, NOT_REMOVED, 1),
+    (server_versions::SEC_ID_TYPE, NOT_REMOVED, 2),
+    (server_versions::BOND_ISSUERID, NOT_REMOVED, 1),
+];
+
 pub(crate) fn request_matching_symbols(request_id: i32, pattern: &str) -> Result<RequestMessage, Error> {
     let mut message = RequestMessage::default();
 
@@ -70,6 +108,157 @@ pub(crate) fn request_matching_symbols(request_id: i32, pattern: &str) -> Result
     Ok(message)
 }
 
+pub(crate) fn request_option_chains(
+    request_id: i32,
+    symbol: &str,
+    exchange: &str,
+    security_type: SecurityType,
+    underlying_contract_id: i32,
+) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestSecurityDefinitionOptionalParameters);
+    message.push_field(&request_id);
+    message.push_field(&symbol);
+    message.push_field(&exchange);
+    message.push_field(&security_type);
+    message.push_field(&underlying_contract_id);
+
+    Ok(message)
+}
+
+pub(crate) fn calculate_implied_volatility(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    option_price: f64,
+    underlying_price: f64,
+) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 3;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::ReqCalcImpliedVolat);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    encode_calculation_contract(server_version, &mut message, contract);
+
+    message.push_field(&option_price);
+    message.push_field(&underlying_price);
+
+    if server_version >= server_versions::LINKING {
+        message.push_field(&0); // implied volatility options count
+        message.push_field(&""); // implied volatility options
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_implied_volatility(request_id: i32) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelImpliedVolatility);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+pub(crate) fn calculate_option_price(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    volatility: f64,
+    underlying_price: f64,
+) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 3;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::ReqCalcOptionPrice);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    encode_calculation_contract(server_version, &mut message, contract);
+
+    message.push_field(&volatility);
+    message.push_field(&underlying_price);
+
+    if server_version >= server_versions::LINKING {
+        message.push_field(&0); // option price options count
+        message.push_field(&""); // option price options
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_option_price(request_id: i32) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelOptionPrice);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+fn encode_calculation_contract(server_version: i32, message: &mut RequestMessage, contract: &Contract) {
+    message.push_field(&contract.contract_id);
+    message.push_field(&contract.symbol);
+    message.push_field(&contract.security_type);
+    message.push_field(&contract.last_trade_date_or_contract_month);
+    message.push_field(&contract.strike);
+    message.push_field(&contract.right);
+    message.push_field(&contract.multiplier);
+    message.push_field(&contract.exchange);
+    message.push_field(&contract.primary_exchange);
+    message.push_field(&contract.currency);
+    message.push_field(&contract.local_symbol);
+
+    if server_version >= server_versions::TRADING_CLASS {
+        message.push_field(&contract.trading_class);
+    }
+}
+
+pub(crate) fn request_fundamental_data(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    report_type: FundamentalReport,
+) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 2;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestFundamentalData);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    if server_version >= server_versions::TRADING_CLASS {
+        message.push_field(&contract.contract_id);
+    }
+
+    message.push_field(&contract.symbol);
+    message.push_field(&contract.security_type);
+    message.push_field(&contract.exchange);
+    message.push_field(&contract.primary_exchange);
+    message.push_field(&contract.currency);
+    message.push_field(&contract.local_symbol);
+    message.push_field(&report_type);
+
+    if server_version >= server_versions::LINKING {
+        message.push_field(&0); // fundamental data options count
+        message.push_field(&""); // fundamental data options
+    }
+
+    Ok(message)
+}
+
 pub(crate) fn request_market_rule(market_rule_id: i32) -> Result<RequestMessage, Error> {
     let mut message = RequestMessage::default();
 
@@ -96,4 +285,47 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn request_contract_data_field_count() {
+        let contract = Contract::stock("TSLA");
+
+        for (server_version, expected) in [(server_versions::SIZE_RULES, 18), (server_versions::BOND_ISSUERID, 19)] {
+            match super::request_contract_data(server_version, 9000, &contract) {
+                Ok(message) => assert_eq!(message.len(), expected, "fields for server version {server_version}"),
+                Err(err) => assert!(false, "error encoding contract data request: {err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn request_contract_data_samples() {
+        use crate::contracts::contract_samples;
+
+        let samples = [
+            (
+                contract_samples::forex_pair(),
+                Contract::forex("EUR", "USD"),
+                "9|8|9000|0|EUR|CASH||0|||IDEALPRO||USD|EUR.USD||0|||",
+            ),
+            (
+                contract_samples::crypto_contract(),
+                Contract::crypto("BTC"),
+                "9|8|9000|0|BTC|CRYPTO||0|||PAXOS||USD|||0|||",
+            ),
+        ];
+
+        for (sample, constructed, expected) in samples {
+            match (
+                super::request_contract_data(server_versions::SIZE_RULES, 9000, &sample),
+                super::request_contract_data(server_versions::SIZE_RULES, 9000, &constructed),
+            ) {
+                (Ok(sample), Ok(constructed)) => {
+                    assert_eq!(sample.encode_simple(), expected, "sample contract");
+                    assert_eq!(constructed.encode_simple(), expected, "constructed contract");
+                }
+                (sample, constructed) => assert!(false, "error encoding contract data request: {:?} {:?}", sample.err(), constructed.err()),
+            }
+        }
+    }
 }
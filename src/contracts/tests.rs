@@ -1,8 +1,10 @@
 use std::cell::RefCell;
+use std::net::TcpListener;
+use std::thread;
 
 use super::*;
 
-use crate::stubs::MessageBusStub;
+use crate::stubs::{accept_handshake, read_frame, write_frame, MessageBusStub};
 
 #[test]
 fn request_stock_contract_details() {
@@ -21,7 +23,7 @@ fn request_stock_contract_details() {
 
     let results = client.contract_details(&contract);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode_simple(), "9|8|9000|0|TSLA|STK||0|||SMART||USD|||0|||");
 
@@ -37,6 +39,9 @@ fn request_stock_contract_details() {
     assert_eq!(contracts[0].contract.security_type, SecurityType::Stock);
     assert_eq!(contracts[0].contract.currency, "USD");
     assert_eq!(contracts[0].contract.contract_id, 76792991);
+    assert_eq!(contracts[0].market_name, "NMS");
+    assert_eq!(contracts[0].contract.trading_class, "NMS");
+    assert_eq!(contracts[0].min_tick, 0.01);
     assert_eq!(contracts[0].order_types, "ACTIVETIM,AD,ADJUST,ALERT,ALGO,ALLOC,AON,AVGCOST,BASKET,BENCHPX,CASHQTY,COND,CONDORDER,DARKONLY,DARKPOLL,DAY,DEACT,DEACTDIS,DEACTEOD,DIS,DUR,GAT,GTC,GTD,GTT,HID,IBKRATS,ICE,IMB,IOC,LIT,LMT,LOC,MIDPX,MIT,MKT,MOC,MTL,NGCOMB,NODARK,NONALGO,OCA,OPG,OPGREROUT,PEGBENCH,PEGMID,POSTATS,POSTONLY,PREOPGRTH,PRICECHK,REL,REL2MID,RELPCTOFS,RPI,RTH,SCALE,SCALEODD,SCALERST,SIZECHK,SNAPMID,SNAPMKT,SNAPREL,STP,STPLMT,SWEEP,TRAIL,TRAILLIT,TRAILLMT,TRAILMIT,WHATIF");
     assert_eq!(
         contracts[0].valid_exchanges,
@@ -83,16 +88,680 @@ fn request_future_contract_details() {}
 
 #[test]
 fn test_read_last_trade_date() {
-    // let mut contract = ContractDetails::default();
+    let mut contract = ContractDetails::default();
 
     // handles blank string
-    // let result = read_last_trade_date(&mut contract, "", false);
-    // assert!(!result.is_err(), "unexpected error {:?}", result);
+    let result = decoders::read_last_trade_date(&mut contract, "", false);
+    assert!(result.is_ok(), "unexpected error {:?}", result);
+    assert_eq!(contract.contract.last_trade_date_or_contract_month, "");
 
     // handles non bond contracts
+    let result = decoders::read_last_trade_date(&mut contract, "20230616 16:00", false);
+    assert!(result.is_ok(), "unexpected error {:?}", result);
+    assert_eq!(contract.contract.last_trade_date_or_contract_month, "20230616");
+    assert_eq!(contract.last_trade_time, "16:00");
 
     // handles bond contracts
+    let result = decoders::read_last_trade_date(&mut contract, "20300515 16:00 US/Eastern", true);
+    assert!(result.is_ok(), "unexpected error {:?}", result);
+    assert_eq!(contract.maturity, "20300515");
+    assert_eq!(contract.last_trade_time, "16:00");
+    assert_eq!(contract.time_zone_id, "US/Eastern");
 }
 
 #[test]
-fn request_matching_symbols() {}
+fn request_matching_symbols() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "79|9000|3|76792991|TSLA|STK|NASDAQ.NMS|USD|6|CFD|OPT|IOPT|WAR|FUT|BAG|43661924|TL0|STK|IBIS|EUR|2|CFD|OPT|7184|TXLZF|STK|PINK|USD|0|"
+                .to_string(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.matching_symbols("TSLA");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "81|9000|TSLA|");
+
+    assert!(results.is_ok(), "failed to request matching symbols: {}", results.err().unwrap());
+
+    let contract_descriptions: Vec<ContractDescription> = results.unwrap().collect();
+
+    assert_eq!(contract_descriptions.len(), 3, "contract_descriptions.len()");
+
+    assert_eq!(
+        contract_descriptions[0].contract.contract_id, 76792991,
+        "contract_descriptions[0].contract.contract_id"
+    );
+    assert_eq!(
+        contract_descriptions[0].contract.symbol, "TSLA",
+        "contract_descriptions[0].contract.symbol"
+    );
+    assert_eq!(
+        contract_descriptions[0].contract.security_type,
+        SecurityType::Stock,
+        "contract_descriptions[0].contract.security_type"
+    );
+    assert_eq!(
+        contract_descriptions[0].contract.primary_exchange, "NASDAQ.NMS",
+        "contract_descriptions[0].contract.primary_exchange"
+    );
+    assert_eq!(
+        contract_descriptions[0].contract.currency, "USD",
+        "contract_descriptions[0].contract.currency"
+    );
+    assert_eq!(
+        contract_descriptions[0].derivative_security_types,
+        vec!["CFD", "OPT", "IOPT", "WAR", "FUT", "BAG"],
+        "contract_descriptions[0].derivative_security_types"
+    );
+
+    assert_eq!(
+        contract_descriptions[1].contract.symbol, "TL0",
+        "contract_descriptions[1].contract.symbol"
+    );
+    assert_eq!(
+        contract_descriptions[1].contract.currency, "EUR",
+        "contract_descriptions[1].contract.currency"
+    );
+    assert_eq!(
+        contract_descriptions[1].derivative_security_types,
+        vec!["CFD", "OPT"],
+        "contract_descriptions[1].derivative_security_types"
+    );
+
+    assert_eq!(
+        contract_descriptions[2].contract.symbol, "TXLZF",
+        "contract_descriptions[2].contract.symbol"
+    );
+    assert!(
+        contract_descriptions[2].derivative_security_types.is_empty(),
+        "contract_descriptions[2].derivative_security_types"
+    );
+}
+
+#[test]
+fn request_matching_symbols_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_MATCHING_SYMBOLS - 1);
+
+    let results = client.matching_symbols("TSLA");
+
+    assert!(matches!(results, Err(Error::ServerVersion(_, _, _))), "expected server version error");
+}
+
+#[test]
+fn contract_details_reports_api_error() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["4|2|9000|200|No security definition has been found for the request|".to_string()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("XYZXYZ");
+
+    match client.contract_details(&contract) {
+        Err(Error::Api { code, message, .. }) => {
+            assert_eq!(code, 200, "error code");
+            assert_eq!(message, "No security definition has been found for the request", "error message");
+        }
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected error for unknown symbol"),
+    }
+}
+
+#[test]
+fn contract_details_end_after_deadline() {
+    use std::time::{Duration, Instant};
+
+    use crossbeam::channel;
+
+    use crate::messages::ResponseMessage;
+
+    let (sender, receiver) = channel::unbounded();
+    let (signals, _signals) = channel::unbounded();
+    let mut responses = ResponseIterator::new(receiver, signals, None, None, Duration::from_secs(10));
+
+    // end marker is withheld, the sender stays connected
+    sender.send(ResponseMessage::from(&"10|9001|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS|76792991|0.01||ACTIVETIM,AD,ADJUST,ALERT,ALGO,ALLOC,AON,AVGCOST,BASKET,BENCHPX,CASHQTY,COND,CONDORDER,DARKONLY,DARKPOLL,DAY,DEACT,DEACTDIS,DEACTEOD,DIS,DUR,GAT,GTC,GTD,GTT,HID,IBKRATS,ICE,IMB,IOC,LIT,LMT,LOC,MIDPX,MIT,MKT,MOC,MTL,NGCOMB,NODARK,NONALGO,OCA,OPG,OPGREROUT,PEGBENCH,PEGMID,POSTATS,POSTONLY,PREOPGRTH,PRICECHK,REL,REL2MID,RELPCTOFS,RPI,RTH,SCALE,SCALEODD,SCALERST,SIZECHK,SNAPMID,SNAPMKT,SNAPREL,STP,STPLMT,SWEEP,TRAIL,TRAILLIT,TRAILLMT,TRAILMIT,WHATIF|SMART,AMEX,NYSE,CBOE,PHLX,ISE,CHX,ARCA,ISLAND,DRCTEDGE,BEX,BATS,EDGEA,CSFBALGO,JEFFALGO,BYX,IEX,EDGX,FOXRIVER,PEARL,NYSENAT,LTSE,MEMX,PSX|1|0|TESLA INC|NASDAQ||Consumer, Cyclical|Auto Manufacturers|Auto-Cars/Light Trucks|US/Eastern|20221229:0400-20221229:2000;20221230:0400-20221230:2000;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0400-20230103:2000|20221229:0930-20221229:1600;20221230:0930-20221230:1600;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0930-20230103:1600|||1|ISIN|US88160R1014|1|||26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26||COMMON|1|1|100||".replace('|', "\0"))).unwrap();
+
+    let started = Instant::now();
+    let result = read_contract_details(server_versions::SIZE_RULES, &mut responses, Instant::now() + Duration::from_millis(100));
+
+    assert!(started.elapsed() < Duration::from_secs(1), "returned after {:?}", started.elapsed());
+    match result {
+        Err(err) => match err.partial_results::<ContractDetails>() {
+            Some(received) => {
+                assert_eq!(received.len(), 1, "received.len()");
+                assert_eq!(received[0].contract.symbol, "TSLA", "received[0].contract.symbol");
+                assert!(err.partial_results::<OptionChain>().is_none(), "partial results of another type");
+            }
+            None => assert!(false, "unexpected error: {err}"),
+        },
+        Ok(_) => assert!(false, "expected incomplete contract details"),
+    }
+
+    drop(sender);
+}
+
+#[test]
+fn option_chains_end_after_deadline() {
+    use std::time::{Duration, Instant};
+
+    use crossbeam::channel;
+
+    use crate::messages::ResponseMessage;
+
+    let (sender, receiver) = channel::unbounded();
+    let (signals, _signals) = channel::unbounded();
+    let mut responses = ResponseIterator::new(receiver, signals, None, None, Duration::from_secs(10));
+
+    // end marker is withheld, the sender stays connected
+    sender
+        .send(ResponseMessage::from(
+            &"75|9000|SMART|265598|AAPL|100|2|20240621|20240719|4|170|175|180|185|".replace('|', "\0"),
+        ))
+        .unwrap();
+
+    let started = Instant::now();
+    let result = read_option_chains(server_versions::SIZE_RULES, &mut responses, Instant::now() + Duration::from_millis(100));
+
+    assert!(started.elapsed() < Duration::from_secs(1), "returned after {:?}", started.elapsed());
+    match result {
+        Err(err) => match err.partial_results::<OptionChain>() {
+            Some(received) => {
+                assert_eq!(received.len(), 1, "received.len()");
+                assert_eq!(received[0].exchange, "SMART", "received[0].exchange");
+            }
+            None => assert!(false, "unexpected error: {err}"),
+        },
+        Ok(_) => assert!(false, "expected incomplete option chains"),
+    }
+
+    drop(sender);
+}
+
+#[test]
+fn request_option_chains() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "75|9000|SMART|265598|AAPL|100|2|20240621|20240719|4|170|175|180|185|".to_string(),
+            "75|9000|CBOE|265598|AAPL|100|2|20240621|20240719|2|170|175|".to_string(),
+            "76|9000|".to_string(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.option_chains("AAPL", "", SecurityType::Stock, 265598);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "78|9000|AAPL||STK|265598|");
+
+    assert!(results.is_ok(), "failed to request option chains: {}", results.err().unwrap());
+
+    let option_chains: Vec<OptionChain> = results.unwrap().collect();
+
+    assert_eq!(option_chains.len(), 2, "option_chains.len()");
+
+    assert_eq!(option_chains[0].exchange, "SMART", "option_chains[0].exchange");
+    assert_eq!(option_chains[0].underlying_contract_id, 265598, "option_chains[0].underlying_contract_id");
+    assert_eq!(option_chains[0].trading_class, "AAPL", "option_chains[0].trading_class");
+    assert_eq!(option_chains[0].multiplier, "100", "option_chains[0].multiplier");
+    assert_eq!(option_chains[0].expirations, vec!["20240621", "20240719"], "option_chains[0].expirations");
+    assert_eq!(option_chains[0].strikes, vec![170.0, 175.0, 180.0, 185.0], "option_chains[0].strikes");
+
+    assert_eq!(option_chains[1].exchange, "CBOE", "option_chains[1].exchange");
+    assert_eq!(option_chains[1].strikes, vec![170.0, 175.0], "option_chains[1].strikes");
+}
+
+#[test]
+fn calculate_implied_volatility() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["21|9000|53|1|0.3|0.6|5.4|-1|0.03|0.12|-0.04|185.5|".to_string()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract {
+        symbol: "AAPL".to_string(),
+        security_type: SecurityType::Option,
+        last_trade_date_or_contract_month: "20240621".to_string(),
+        strike: 180.0,
+        right: "C".to_string(),
+        multiplier: "100".to_string(),
+        exchange: "SMART".to_string(),
+        currency: "USD".to_string(),
+        ..Default::default()
+    };
+
+    let results = client.calculate_implied_volatility(&contract, 5.4, 185.5);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "54|3|9000|0|AAPL|OPT|20240621|180|C|100|SMART||USD|||5.4|185.5|0||"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "56|1|9000|", "cancel implied volatility");
+
+    match results {
+        Ok(computation) => {
+            assert_eq!(computation.implied_vol, Some(0.3), "computation.implied_vol");
+            assert_eq!(computation.delta, Some(0.6), "computation.delta");
+            assert_eq!(computation.opt_price, Some(5.4), "computation.opt_price");
+            assert_eq!(computation.pv_dividend, None, "computation.pv_dividend");
+            assert_eq!(computation.und_price, Some(185.5), "computation.und_price");
+        }
+        Err(err) => assert!(false, "failed to calculate implied volatility: {err}"),
+    }
+}
+
+#[test]
+fn calculate_option_price() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["21|9000|53|1|0.25|0.55|4.75|0.12|0.03|0.11|-0.05|185.5|".to_string()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract {
+        symbol: "AAPL".to_string(),
+        security_type: SecurityType::Option,
+        last_trade_date_or_contract_month: "20240621".to_string(),
+        strike: 180.0,
+        right: "C".to_string(),
+        exchange: "SMART".to_string(),
+        currency: "USD".to_string(),
+        ..Default::default()
+    };
+
+    let results = client.calculate_option_price(&contract, 0.25, 185.5);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "55|3|9000|0|AAPL|OPT|20240621|180|C||SMART||USD|||0.25|185.5|0||"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "57|1|9000|", "cancel option price");
+
+    match results {
+        Ok(computation) => {
+            assert_eq!(computation.opt_price, Some(4.75), "computation.opt_price");
+            assert_eq!(computation.gamma, Some(0.03), "computation.gamma");
+            assert_eq!(computation.vega, Some(0.11), "computation.vega");
+            assert_eq!(computation.theta, Some(-0.05), "computation.theta");
+        }
+        Err(err) => assert!(false, "failed to calculate option price: {err}"),
+    }
+}
+
+#[test]
+fn calculate_option_price_cancels_unanswered_request() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.calculate_option_price(&Contract::default(), 0.25, 185.5);
+    assert!(results.is_err(), "expected error when no computation is received");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request and cancel");
+    assert_eq!(request_messages[1].encode_simple(), "57|1|9000|", "cancel option price");
+}
+
+#[test]
+fn calculate_implied_volatility_api_error_is_not_cancelled() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["4|2|9000|200|No security definition has been found for the request|".to_string()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.calculate_implied_volatility(&Contract::default(), 5.4, 185.5);
+    assert!(matches!(results, Err(Error::Api { code: 200, .. })), "expected api error: {results:?}");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 1, "no cancel for requests ended by an error");
+}
+
+#[test]
+fn calculate_option_price_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_CALC_IMPLIED_VOLAT);
+
+    let results = client.calculate_option_price(&Contract::default(), 0.25, 185.5);
+
+    assert!(matches!(results, Err(Error::ServerVersion(_, _, _))), "expected server version error");
+}
+
+#[test]
+fn request_fundamental_data() {
+    let report = r#"<?xml version="1.0" encoding="UTF-8"?><ReportSnapshot Major="1" Minor="0" Revision="1"><CoIDs><CoID Type="RepNo">AC317</CoID><CoID Type="CompanyName">Apple Inc.</CoID></CoIDs></ReportSnapshot>"#;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![format!("51|1|9000|{report}|")],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let results = client.fundamental_data(&contract, FundamentalReport::ReportSnapshot);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "52|2|9000|0|AAPL|STK|SMART||USD||ReportSnapshot|0||");
+
+    match results {
+        Ok(xml) => assert_eq!(xml, report, "fundamental data report"),
+        Err(err) => assert!(false, "failed to request fundamental data: {err}"),
+    }
+}
+
+#[test]
+fn request_contract_details_by_contract_id() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "10|9000|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS|76792991|0.01||ACTIVETIM,AD,ADJUST,ALERT,ALGO,ALLOC,AON,AVGCOST,BASKET,BENCHPX,CASHQTY,COND,CONDORDER,DARKONLY,DARKPOLL,DAY,DEACT,DEACTDIS,DEACTEOD,DIS,DUR,GAT,GTC,GTD,GTT,HID,IBKRATS,ICE,IMB,IOC,LIT,LMT,LOC,MIDPX,MIT,MKT,MOC,MTL,NGCOMB,NODARK,NONALGO,OCA,OPG,OPGREROUT,PEGBENCH,PEGMID,POSTATS,POSTONLY,PREOPGRTH,PRICECHK,REL,REL2MID,RELPCTOFS,RPI,RTH,SCALE,SCALEODD,SCALERST,SIZECHK,SNAPMID,SNAPMKT,SNAPREL,STP,STPLMT,SWEEP,TRAIL,TRAILLIT,TRAILLMT,TRAILMIT,WHATIF|SMART,AMEX,NYSE,CBOE,PHLX,ISE,CHX,ARCA,ISLAND,DRCTEDGE,BEX,BATS,EDGEA,CSFBALGO,JEFFALGO,BYX,IEX,EDGX,FOXRIVER,PEARL,NYSENAT,LTSE,MEMX,PSX|1|0|TESLA INC|NASDAQ||Consumer, Cyclical|Auto Manufacturers|Auto-Cars/Light Trucks|US/Eastern|20221229:0400-20221229:2000;20221230:0400-20221230:2000;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0400-20230103:2000|20221229:0930-20221229:1600;20221230:0930-20221230:1600;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0930-20230103:1600|||1|ISIN|US88160R1014|1|||26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26||COMMON|1|1|100||".to_owned(),
+            "52|1|9000||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.contract_details_by_contract_id(76792991);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    // only the contract id narrows the match
+    assert_eq!(request_messages[0].encode_simple(), "9|8|9000|76792991||||0||||||||0|||");
+
+    match results {
+        Ok(details) => {
+            assert_eq!(details.contract.contract_id, 76792991, "details.contract.contract_id");
+            assert_eq!(details.contract.symbol, "TSLA", "details.contract.symbol");
+            assert_eq!(details.contract.security_type, SecurityType::Stock, "details.contract.security_type");
+        }
+        Err(err) => assert!(false, "error requesting contract details: {err}"),
+    }
+}
+
+#[test]
+fn request_contract_details_by_contract_id_not_found() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["52|1|9000||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.contract_details_by_contract_id(76792991);
+
+    assert!(results.is_err(), "expected error when no contract matches");
+}
+
+// TSLA contract data listed on exchange.
+fn tsla_contract_data(exchange: &str) -> String {
+    "10|9000|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS|76792991|0.01||ACTIVETIM,AD,ADJUST,ALERT,ALGO,ALLOC,AON,AVGCOST,BASKET,BENCHPX,CASHQTY,COND,CONDORDER,DARKONLY,DARKPOLL,DAY,DEACT,DEACTDIS,DEACTEOD,DIS,DUR,GAT,GTC,GTD,GTT,HID,IBKRATS,ICE,IMB,IOC,LIT,LMT,LOC,MIDPX,MIT,MKT,MOC,MTL,NGCOMB,NODARK,NONALGO,OCA,OPG,OPGREROUT,PEGBENCH,PEGMID,POSTATS,POSTONLY,PREOPGRTH,PRICECHK,REL,REL2MID,RELPCTOFS,RPI,RTH,SCALE,SCALEODD,SCALERST,SIZECHK,SNAPMID,SNAPMKT,SNAPREL,STP,STPLMT,SWEEP,TRAIL,TRAILLIT,TRAILLMT,TRAILMIT,WHATIF|SMART,AMEX,NYSE,CBOE,PHLX,ISE,CHX,ARCA,ISLAND,DRCTEDGE,BEX,BATS,EDGEA,CSFBALGO,JEFFALGO,BYX,IEX,EDGX,FOXRIVER,PEARL,NYSENAT,LTSE,MEMX,PSX|1|0|TESLA INC|NASDAQ||Consumer, Cyclical|Auto Manufacturers|Auto-Cars/Light Trucks|US/Eastern|20221229:0400-20221229:2000;20221230:0400-20221230:2000;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0400-20230103:2000|20221229:0930-20221229:1600;20221230:0930-20221230:1600;20221231:CLOSED;20230101:CLOSED;20230102:CLOSED;20230103:0930-20230103:1600|||1|ISIN|US88160R1014|1|||26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26,26||COMMON|1|1|100||".replacen("||SMART|", &format!("||{exchange}|"), 1)
+}
+
+#[test]
+fn resolve_contract() {
+    // the same contract reported twice counts once
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![tsla_contract_data("SMART"), tsla_contract_data("SMART"), "52|1|9000||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    match client.resolve_contract(&Contract::stock("TSLA")) {
+        Ok(details) => {
+            assert_eq!(details.contract.contract_id, 76792991, "details.contract.contract_id");
+            assert_eq!(details.contract.exchange, "SMART", "details.contract.exchange");
+        }
+        Err(err) => assert!(false, "error resolving contract: {err}"),
+    }
+}
+
+#[test]
+fn resolve_contract_not_found() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["52|1|9000||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    match client.resolve_contract(&Contract::stock("TSLA")) {
+        Err(Error::Simple(_)) => {}
+        result => assert!(false, "expected error when no contract matches, got {result:?}"),
+    }
+}
+
+#[test]
+fn resolve_contract_ambiguous() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![tsla_contract_data("SMART"), tsla_contract_data("AMEX"), "52|1|9000||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    match client.resolve_contract(&Contract::stock("TSLA")) {
+        Err(Error::AmbiguousContract { candidates }) => {
+            let exchanges: Vec<&str> = candidates.iter().map(|details| details.contract.exchange.as_str()).collect();
+            assert_eq!(exchanges, vec!["SMART", "AMEX"], "candidate exchanges");
+        }
+        result => assert!(false, "expected ambiguous contract error, got {result:?}"),
+    }
+}
+
+#[test]
+fn request_market_rule() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["93|26|3|0|0.0001|1|0.01|10000|5||".to_string()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let market_rule = client.market_rule(26).expect("failed to request market rule");
+
+    assert_eq!(market_rule.market_rule_id, 26, "market_rule.market_rule_id");
+    assert_eq!(market_rule.price_increments.len(), 3, "market_rule.price_increments.len()");
+    assert_eq!(market_rule.price_increments[1].low_edge, 1.0, "price_increments[1].low_edge");
+    assert_eq!(market_rule.price_increments[1].increment, 0.01, "price_increments[1].increment");
+    assert_eq!(market_rule.price_increments[2].low_edge, 10000.0, "price_increments[2].low_edge");
+    assert_eq!(market_rule.price_increments[2].increment, 5.0, "price_increments[2].increment");
+
+    assert_eq!(market_rule.increment(0.5), Some(0.0001), "increment below 1");
+    assert_eq!(market_rule.increment(185.5), Some(0.01), "increment above 1");
+    assert_eq!(market_rule.increment(12000.0), Some(5.0), "increment above 10000");
+    assert_eq!(market_rule.round_price(12003.0), Some(12005.0), "round_price()");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "91|26|", "market rule request");
+}
+
+#[test]
+fn request_market_rule_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::MARKET_RULES - 1);
+
+    let result = client.market_rule(26);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error, got {result:?}"
+    );
+}
+
+#[test]
+fn security_type_wire_strings() {
+    let expected = [
+        (SecurityType::Stock, "STK"),
+        (SecurityType::Option, "OPT"),
+        (SecurityType::Future, "FUT"),
+        (SecurityType::ContinuousFuture, "CONTFUT"),
+        (SecurityType::Index, "IND"),
+        (SecurityType::FuturesOption, "FOP"),
+        (SecurityType::ForexPair, "CASH"),
+        (SecurityType::Spread, "BAG"),
+        (SecurityType::Warrant, "WAR"),
+        (SecurityType::DutchWarrant, "IOPT"),
+        (SecurityType::Bond, "BOND"),
+        (SecurityType::Bill, "BILL"),
+        (SecurityType::Fixed, "FIXED"),
+        (SecurityType::Cfd, "CFD"),
+        (SecurityType::Commodity, "CMDTY"),
+        (SecurityType::Forward, "FWD"),
+        (SecurityType::News, "NEWS"),
+        (SecurityType::MutualFund, "FUND"),
+        (SecurityType::Crypto, "CRYPTO"),
+    ];
+
+    assert_eq!(expected.len(), SecurityType::ALL.len(), "every security type is covered");
+
+    for (security_type, code) in expected {
+        assert_eq!(security_type.to_field(), code, "{security_type:?}.to_field()");
+        assert_eq!(code.parse::<SecurityType>().ok(), Some(security_type.clone()), "parse {code}");
+        assert!(SecurityType::ALL.contains(&security_type), "{security_type:?} in SecurityType::ALL");
+    }
+
+    assert_eq!(SecurityType::from("ICS"), SecurityType::Other("ICS".into()), "unknown security type");
+    assert_eq!(SecurityType::Other("ICS".into()).to_string(), "ICS", "unknown security type round trip");
+}
+
+#[test]
+fn contract_constructors() {
+    let contract = Contract::option("AAPL", "20231215", 180.0, Right::Call);
+    assert_eq!(contract.symbol, "AAPL", "option.symbol");
+    assert_eq!(contract.security_type, SecurityType::Option, "option.security_type");
+    assert_eq!(
+        contract.last_trade_date_or_contract_month, "20231215",
+        "option.last_trade_date_or_contract_month"
+    );
+    assert_eq!(contract.strike, 180.0, "option.strike");
+    assert_eq!(contract.right, "C", "option.right");
+    assert_eq!(contract.multiplier, "100", "option.multiplier");
+    assert_eq!(contract.exchange, "SMART", "option.exchange");
+    assert_eq!(contract.currency, "USD", "option.currency");
+    assert_eq!(Contract::option("AAPL", "20231215", 180.0, Right::Put).right, "P", "put option.right");
+
+    let contract = Contract::future("ES", "202312", "CME");
+    assert_eq!(contract.symbol, "ES", "future.symbol");
+    assert_eq!(contract.security_type, SecurityType::Future, "future.security_type");
+    assert_eq!(
+        contract.last_trade_date_or_contract_month, "202312",
+        "future.last_trade_date_or_contract_month"
+    );
+    assert_eq!(contract.exchange, "CME", "future.exchange");
+    assert_eq!(contract.currency, "USD", "future.currency");
+
+    let contract = Contract::forex("EUR", "USD");
+    assert_eq!(contract.symbol, "EUR", "forex.symbol");
+    assert_eq!(contract.security_type, SecurityType::ForexPair, "forex.security_type");
+    assert_eq!(contract.currency, "USD", "forex.currency");
+    assert_eq!(contract.local_symbol, "EUR.USD", "forex.local_symbol");
+    assert_eq!(contract.exchange, "IDEALPRO", "forex.exchange");
+
+    let contract = Contract::crypto("BTC");
+    assert_eq!(contract.symbol, "BTC", "crypto.symbol");
+    assert_eq!(contract.security_type, SecurityType::Crypto, "crypto.security_type");
+    assert_eq!(contract.currency, "USD", "crypto.currency");
+    assert_eq!(contract.exchange, "PAXOS", "crypto.exchange");
+}
+
+#[test]
+fn request_contract_details_batch() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // Answers the requests with interleaved responses, each request matching contracts on a distinct exchange.
+    // The last request is rejected.
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        write_frame(&mut stream, "9|1|90|");
+        write_frame(&mut stream, "15|1|DU1234567|");
+
+        let request_ids: Vec<String> = (0..4).map(|_| read_frame(&mut stream).split('\0').nth(2).unwrap().to_owned()).collect();
+
+        let contract_data =
+            |request: usize, exchange: &str| tsla_contract_data(exchange).replacen("10|9000|", &format!("10|{}|", request_ids[request]), 1);
+        let contract_data_end = |request: usize| format!("52|1|{}|", request_ids[request]);
+
+        for response in [
+            contract_data(2, "ISLAND"),
+            contract_data(0, "SMART"),
+            format!("4|2|{}|200|No security definition has been found for the request|", request_ids[3]),
+            contract_data(1, "NYSE"),
+            contract_data_end(2),
+            contract_data(1, "ARCA"),
+            contract_data_end(0),
+            contract_data_end(1),
+        ] {
+            write_frame(&mut stream, &response);
+        }
+
+        stream
+    });
+
+    let client = Client::builder().address(&address).client_id(100).connect().expect("connection failed");
+
+    let contracts = [
+        Contract::stock("TSLA"),
+        Contract::stock("TSLA"),
+        Contract::stock("TSLA"),
+        Contract::stock("XXXX"),
+    ];
+    let results = client.contract_details_batch(&contracts).expect("request failed");
+
+    let exchanges: Vec<Vec<&str>> = results[..3]
+        .iter()
+        .map(|result| {
+            let details = result.as_ref().expect("contract details");
+            details.iter().map(|details| details.contract.exchange.as_str()).collect()
+        })
+        .collect();
+    assert_eq!(
+        exchanges,
+        vec![vec!["SMART"], vec!["NYSE", "ARCA"], vec!["ISLAND"]],
+        "exchanges by request"
+    );
+
+    match &results[3] {
+        Err(Error::Api { code, .. }) => assert_eq!(*code, 200, "error code"),
+        result => assert!(false, "expected api error, got {result:?}"),
+    }
+
+    drop(server.join().unwrap());
+}
@@ -1,5 +1,9 @@
+use std::any::Any;
 use std::{num::ParseIntError, string::FromUtf8Error};
 
+use crate::contracts::ContractDetails;
+use crate::orders::AdvancedOrderReject;
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
@@ -14,10 +18,79 @@ pub enum Error {
     Parse(usize, String, String),
     ServerVersion(i32, i32, String),
     Simple(String),
+    Timeout,
+    ConnectionFailed,
+    /// The connection was lost while a subscription was active. Requests are not resent after reconnecting, so the subscription has to be requested again.
+    ConnectionReset,
+    /// Error reported by TWS. See [TWS API error codes](https://interactivebrokers.github.io/tws-api/message_codes.html).
+    Api {
+        code: i32,
+        message: String,
+        /// Details of an order rejection, sent by servers supporting advanced order rejects.
+        advanced_order_reject: Option<AdvancedOrderReject>,
+    },
+    /// The end of a response was not received in time. Holds the results received until then, see [Error::partial_results].
+    Incomplete {
+        /// Number of results received.
+        count: usize,
+        received: Box<dyn Any + Send + Sync>,
+    },
+    /// More than one contract matched a request expecting a single contract. Holds the matching contracts.
+    AmbiguousContract {
+        candidates: Vec<ContractDetails>,
+    },
+    /// The market data lines configured with [ClientBuilder::max_market_data_lines](crate::ClientBuilder::max_market_data_lines) are all in use.
+    TooManyLines {
+        limit: usize,
+    },
+    /// An encoder produced a different number of fields than the message layout of the negotiated server version.
+    EncodingMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// Order price is not a multiple of the contract's minimum tick. Returned by [Client::place_order_validated](crate::Client::place_order_validated).
+    InvalidPrice {
+        price: f64,
+        /// Nearest price aligned to the minimum tick.
+        suggested: f64,
+    },
 }
 
 impl std::error::Error for Error {}
 
+impl Error {
+    // Timeout of a response collected into a Vec, keeping the results received so far.
+    pub(crate) fn incomplete<T: Send + Sync + 'static>(received: Vec<T>) -> Error {
+        Error::Incomplete {
+            count: received.len(),
+            received: Box::new(received),
+        }
+    }
+
+    /// Results received before the response timed out, when this is an [Error::Incomplete] holding results of type `T`.
+    ///
+    /// ```no_run
+    /// use ibapi::contracts::{Contract, ContractDetails};
+    /// use ibapi::Client;
+    ///
+    /// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+    ///
+    /// match client.contract_details(&Contract::stock("TSLA")) {
+    ///     Ok(contracts) => println!("received {} contracts", contracts.count()),
+    ///     Err(err) => match err.partial_results::<ContractDetails>() {
+    ///         Some(received) => println!("timed out after {} contracts", received.len()),
+    ///         None => println!("request failed: {err}"),
+    ///     },
+    /// }
+    /// ```
+    pub fn partial_results<T: 'static>(&self) -> Option<&[T]> {
+        match self {
+            Error::Incomplete { received, .. } => received.downcast_ref::<Vec<T>>().map(Vec::as_slice),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -31,6 +104,22 @@ impl std::fmt::Display for Error {
             Error::ServerVersion(wanted, have, message) => write!(f, "server version {wanted} required, got {have}: {message}"),
 
             Error::Simple(ref err) => write!(f, "error occurred: {err}"),
+            Error::Timeout => write!(f, "timeout waiting for response"),
+            Error::ConnectionFailed => write!(f, "connection failed: the server may be rejecting connections from this host"),
+            Error::ConnectionReset => write!(f, "connection reset: the subscription has to be requested again"),
+            Error::Api { code, message, .. } => write!(f, "[{code}] {message}"),
+            Error::Incomplete { count, .. } => write!(f, "timeout waiting for end of response, received {count} results"),
+            Error::AmbiguousContract { candidates } => write!(f, "ambiguous contract: {} contracts matched", candidates.len()),
+            Error::TooManyLines { limit } => write!(f, "too many market data lines: all {limit} lines are in use"),
+            Error::EncodingMismatch { expected, actual } => {
+                write!(f, "encoded {actual} fields, the message layout of the server version has {expected}")
+            }
+            Error::InvalidPrice { price, suggested } => {
+                write!(
+                    f,
+                    "price {price} is not a multiple of the minimum tick, nearest valid price is {suggested}"
+                )
+            }
         }
     }
 }
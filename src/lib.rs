@@ -122,18 +122,25 @@ pub mod errors;
 /// APIs for retrieving market data
 pub mod market_data;
 mod messages;
-pub(crate) mod news;
+/// News headlines, articles and news providers.
+pub mod news;
 /// Data types for building and placing orders.
 pub mod orders;
+/// Server versions supported by TWS and the features they introduce.
+pub mod server_versions;
+/// Wall Street Horizon corporate event data, such as earnings dates.
+pub mod wsh;
 
-mod server_versions;
 pub(crate) mod stubs;
 
 #[doc(inline)]
 pub use errors::Error;
 
 #[doc(inline)]
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
+
+#[cfg(feature = "async")]
+pub use client::AsyncClient;
 
 // ToField
 
@@ -1,2 +1,3 @@
 pub mod historical;
 pub mod realtime;
+pub mod scanners;
@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 
 use log::error;
 use time::{Date, OffsetDateTime};
@@ -9,6 +10,9 @@ use crate::contracts::Contract;
 use crate::messages::{IncomingMessages, RequestMessage, ResponseMessage};
 use crate::{server_versions, Client, Error, ToField};
 
+// Historical data requests can legitimately take longer than the default response timeout.
+const HISTORICAL_DATA_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
 mod decoders;
 mod encoders;
 #[cfg(test)]
@@ -36,7 +40,8 @@ pub struct Bar {
     pub count: i32,
 }
 
-#[derive(Clone, Debug, Copy)]
+/// Size of the bars returned by [Client::historical_data]. Displayed as the bar size string expected by TWS, e.g. "5 mins".
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum BarSize {
     Sec,
     Sec5,
@@ -59,28 +64,83 @@ pub enum BarSize {
     Month,
 }
 
-impl ToString for BarSize {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Sec => "1 sec".into(),
-            Self::Sec5 => "5 secs".into(),
-            Self::Sec15 => "15 secs".into(),
-            Self::Sec30 => "30 secs".into(),
-            Self::Min => "1 min".into(),
-            Self::Min2 => "2 mins".into(),
-            Self::Min3 => "3 mins".into(),
-            Self::Min5 => "5 mins".into(),
-            Self::Min15 => "15 mins".into(),
-            Self::Min20 => "20 mins".into(),
-            Self::Min30 => "30 mins".into(),
-            Self::Hour => "1 hour".into(),
-            Self::Hour2 => "2 hours".into(),
-            Self::Hour3 => "3 hours".into(),
-            Self::Hour4 => "4 hours".into(),
-            Self::Hour8 => "8 hours".into(),
-            Self::Day => "1 day".into(),
-            Self::Week => "1 week".into(),
-            Self::Month => "1 month".into(),
+impl BarSize {
+    /// All bar sizes supported by TWS, from smallest to largest.
+    pub const ALL: [BarSize; 19] = [
+        Self::Sec,
+        Self::Sec5,
+        Self::Sec15,
+        Self::Sec30,
+        Self::Min,
+        Self::Min2,
+        Self::Min3,
+        Self::Min5,
+        Self::Min15,
+        Self::Min20,
+        Self::Min30,
+        Self::Hour,
+        Self::Hour2,
+        Self::Hour3,
+        Self::Hour4,
+        Self::Hour8,
+        Self::Day,
+        Self::Week,
+        Self::Month,
+    ];
+}
+
+impl fmt::Display for BarSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Sec => "1 sec",
+            Self::Sec5 => "5 secs",
+            Self::Sec15 => "15 secs",
+            Self::Sec30 => "30 secs",
+            Self::Min => "1 min",
+            Self::Min2 => "2 mins",
+            Self::Min3 => "3 mins",
+            Self::Min5 => "5 mins",
+            Self::Min15 => "15 mins",
+            Self::Min20 => "20 mins",
+            Self::Min30 => "30 mins",
+            Self::Hour => "1 hour",
+            Self::Hour2 => "2 hours",
+            Self::Hour3 => "3 hours",
+            Self::Hour4 => "4 hours",
+            Self::Hour8 => "8 hours",
+            Self::Day => "1 day",
+            Self::Week => "1 week",
+            Self::Month => "1 month",
+        };
+        write!(f, "{text}")
+    }
+}
+
+impl FromStr for BarSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1 sec" => Ok(Self::Sec),
+            "5 secs" => Ok(Self::Sec5),
+            "15 secs" => Ok(Self::Sec15),
+            "30 secs" => Ok(Self::Sec30),
+            "1 min" => Ok(Self::Min),
+            "2 mins" => Ok(Self::Min2),
+            "3 mins" => Ok(Self::Min3),
+            "5 mins" => Ok(Self::Min5),
+            "15 mins" => Ok(Self::Min15),
+            "20 mins" => Ok(Self::Min20),
+            "30 mins" => Ok(Self::Min30),
+            "1 hour" => Ok(Self::Hour),
+            "2 hours" => Ok(Self::Hour2),
+            "3 hours" => Ok(Self::Hour3),
+            "4 hours" => Ok(Self::Hour4),
+            "8 hours" => Ok(Self::Hour8),
+            "1 day" => Ok(Self::Day),
+            "1 week" => Ok(Self::Week),
+            "1 month" => Ok(Self::Month),
+            _ => Err(Error::Simple(format!("unsupported bar size: {s}"))),
         }
     }
 }
@@ -91,7 +151,10 @@ impl ToField for BarSize {
     }
 }
 
-#[derive(Clone, Debug, Copy)]
+/// Time span covered by a historical request. Displayed as the duration string expected by TWS, e.g. "3 D".
+///
+/// Durations are created with [Duration::days] and friends, or with [ToDuration], e.g. `3.days()`.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub struct Duration {
     value: i32,
     unit: char,
@@ -125,9 +188,30 @@ impl Duration {
     }
 }
 
-impl ToString for Duration {
-    fn to_string(&self) -> String {
-        format!("{} {}", self.value, self.unit)
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit)
+    }
+}
+
+impl FromStr for Duration {
+    type Err = Error;
+
+    /// Parses TWS duration strings such as "3 D".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Simple(format!("invalid duration: {s}"));
+
+        let (value, unit) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let value: i32 = value.parse().map_err(|_| invalid())?;
+
+        match unit {
+            "S" => Ok(Self::seconds(value)),
+            "D" => Ok(Self::days(value)),
+            "W" => Ok(Self::weeks(value)),
+            "M" => Ok(Self::months(value)),
+            "Y" => Ok(Self::years(value)),
+            _ => Err(invalid()),
+        }
     }
 }
 
@@ -136,6 +220,7 @@ impl ToField for Duration {
         self.to_string()
     }
 }
+
 pub trait ToDuration {
     fn seconds(&self) -> Duration;
     fn days(&self) -> Duration;
@@ -166,10 +251,11 @@ impl ToDuration for i32 {
     }
 }
 
-#[derive(Debug)]
-struct HistogramData {
+/// Number of trades at a price, see [Client::histogram_data].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramEntry {
     pub price: f64,
-    pub count: i32,
+    pub count: i64,
 }
 
 #[derive(Clone, Debug)]
@@ -251,6 +337,17 @@ pub struct TickAttributeLast {
     pub unreported: bool,
 }
 
+/// Historical tick of the type requested with [WhatToShow], see [Client::historical_ticks].
+#[derive(Debug)]
+pub enum HistoricalTick {
+    /// Returned for [WhatToShow::MidPoint].
+    Midpoint(TickMidpoint),
+    /// Returned for [WhatToShow::BidAsk].
+    BidAsk(TickBidAsk),
+    /// Returned for [WhatToShow::Trades].
+    Last(TickLast),
+}
+
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub enum WhatToShow {
     Trades,
@@ -264,19 +361,20 @@ pub enum WhatToShow {
     Schedule,
 }
 
-impl ToString for WhatToShow {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Trades => "TRADES".to_string(),
-            Self::MidPoint => "MIDPOINT".to_string(),
-            Self::Bid => "BID".to_string(),
-            Self::Ask => "ASK".to_string(),
-            Self::BidAsk => "BID_ASK".to_string(),
-            Self::HistoricalVolatility => "HISTORICAL_VOLATILITY".to_string(),
-            Self::OptionImpliedVolatility => "OPTION_IMPLIED_VOLATILITY".to_string(),
-            Self::FeeRate => "FEE_RATE".to_string(),
-            Self::Schedule => "SCHEDULE".to_string(),
-        }
+impl fmt::Display for WhatToShow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Trades => "TRADES",
+            Self::MidPoint => "MIDPOINT",
+            Self::Bid => "BID",
+            Self::Ask => "ASK",
+            Self::BidAsk => "BID_ASK",
+            Self::HistoricalVolatility => "HISTORICAL_VOLATILITY",
+            Self::OptionImpliedVolatility => "OPTION_IMPLIED_VOLATILITY",
+            Self::FeeRate => "FEE_RATE",
+            Self::Schedule => "SCHEDULE",
+        };
+        write!(f, "{text}")
     }
 }
 
@@ -304,19 +402,37 @@ pub(crate) fn head_timestamp(client: &Client, contract: &Contract, what_to_show:
 
     let mut messages = client.send_request(request_id, request)?;
 
-    if let Some(mut message) = messages.next() {
-        decoders::decode_head_timestamp(&mut message)
-    } else {
-        Err(Error::Simple("did not receive head timestamp message".into()))
+    match messages.next_result() {
+        Some(Ok(mut message)) => decoders::decode_head_timestamp(&mut message),
+        Some(Err(err)) => Err(err),
+        None => {
+            // server keeps unanswered requests open
+            if client.server_version() >= server_versions::CANCEL_HEADTIMESTAMP {
+                client.send_message(encoders::encode_cancel_head_timestamp(request_id)?)?;
+            }
+            Err(Error::Simple("did not receive head timestamp message".into()))
+        }
     }
 }
 
-/// Returns data histogram of specified contract
-fn _histogram_data(_client: &Client, _contract: &Contract, _use_rth: bool, _period: &str) -> Result<HistogramDataIterator, Error> {
-    // " S (seconds) - " D (days)
-    // " W (weeks) - " M (months)
-    // " Y (years)
-    Err(Error::NotImplemented)
+// Returns data histogram of specified contract
+pub(crate) fn histogram_data(client: &Client, contract: &Contract, use_rth: bool, period: Duration) -> Result<Vec<HistogramEntry>, Error> {
+    client.check_server_version(server_versions::REQ_HISTOGRAM, "It does not support histogram data requests.")?;
+
+    let request_id = client.next_request_id();
+    let request = encoders::encode_request_histogram_data(request_id, contract, use_rth, period)?;
+
+    let mut messages = client.send_request(request_id, request)?;
+
+    match messages.next_result() {
+        Some(Ok(mut message)) => decoders::decode_histogram_data(&mut message),
+        Some(Err(err)) => Err(err),
+        None => {
+            // server keeps unanswered requests open
+            client.send_message(encoders::encode_cancel_histogram_data(request_id)?)?;
+            Err(Error::Simple("did not receive histogram data message".into()))
+        }
+    }
 }
 
 // https://interactivebrokers.github.io/tws-api/historical_bars.html#hd_duration
@@ -357,16 +473,13 @@ pub(crate) fn historical_data(
         Vec::<crate::contracts::TagValue>::default(),
     )?;
 
-    let mut messages = client.send_request(request_id, request)?;
+    let mut messages = client.send_request(request_id, request)?.with_timeout(HISTORICAL_DATA_TIMEOUT);
 
-    if let Some(mut message) = messages.next() {
-        match message.message_type() {
-            IncomingMessages::HistoricalData => decoders::decode_historical_data(client.server_version, client.time_zone, &mut message),
-            IncomingMessages::Error => Err(Error::Simple(message.peek_string(4))),
-            _ => Err(Error::Simple(format!("unexpected message: {:?}", message.message_type()))),
-        }
-    } else {
-        Err(Error::Simple("did not receive historical data response".into()))
+    let mut message = messages.recv_timeout()?;
+    match message.message_type() {
+        IncomingMessages::HistoricalData => decoders::decode_historical_data(client.server_version, client.time_zone, &mut message),
+        IncomingMessages::Error => Err(message.api_error_for(client.server_version)),
+        _ => Err(Error::Simple(format!("unexpected message: {:?}", message.message_type()))),
     }
 }
 
@@ -402,16 +515,13 @@ pub(crate) fn historical_schedule(
         Vec::<crate::contracts::TagValue>::default(),
     )?;
 
-    let mut messages = client.send_request(request_id, request)?;
+    let mut messages = client.send_request(request_id, request)?.with_timeout(HISTORICAL_DATA_TIMEOUT);
 
-    if let Some(mut message) = messages.next() {
-        match message.message_type() {
-            IncomingMessages::HistoricalSchedule => decoders::decode_historical_schedule(&mut message),
-            IncomingMessages::Error => Err(Error::Simple(message.peek_string(4))),
-            _ => Err(Error::Simple(format!("unexpected message: {:?}", message.message_type()))),
-        }
-    } else {
-        Err(Error::Simple("did not receive historical schedule response".into()))
+    let mut message = messages.recv_timeout()?;
+    match message.message_type() {
+        IncomingMessages::HistoricalSchedule => decoders::decode_historical_schedule(&mut message),
+        IncomingMessages::Error => Err(message.api_error_for(client.server_version)),
+        _ => Err(Error::Simple(format!("unexpected message: {:?}", message.message_type()))),
     }
 }
 
@@ -479,6 +589,36 @@ pub(crate) fn historical_ticks_trade(
     Ok(TickIterator::new(messages))
 }
 
+// Requests historical ticks of the type selected by what_to_show. Pages until the server reports the request is done.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn historical_ticks(
+    client: &Client,
+    contract: &Contract,
+    start: Option<OffsetDateTime>,
+    end: Option<OffsetDateTime>,
+    number_of_ticks: i32,
+    what_to_show: WhatToShow,
+    use_rth: bool,
+    ignore_size: bool,
+) -> Result<Vec<HistoricalTick>, Error> {
+    match what_to_show {
+        WhatToShow::Trades => Ok(historical_ticks_trade(client, contract, start, end, number_of_ticks, use_rth)?
+            .map(HistoricalTick::Last)
+            .collect()),
+        WhatToShow::MidPoint => Ok(historical_ticks_mid_point(client, contract, start, end, number_of_ticks, use_rth)?
+            .map(HistoricalTick::Midpoint)
+            .collect()),
+        WhatToShow::BidAsk => Ok(
+            historical_ticks_bid_ask(client, contract, start, end, number_of_ticks, use_rth, ignore_size)?
+                .map(HistoricalTick::BidAsk)
+                .collect(),
+        ),
+        _ => Err(Error::Simple(format!(
+            "historical ticks are available for TRADES, MIDPOINT and BID_ASK, not {what_to_show}"
+        ))),
+    }
+}
+
 pub(crate) trait TickDecoder<T> {
     fn decode(message: &mut ResponseMessage) -> Result<(Vec<T>, bool), Error>;
     fn message_type() -> IncomingMessages;
@@ -543,7 +683,13 @@ impl<T: TickDecoder<T> + Debug> Iterator for TickIterator<T> {
             match self.messages.next() {
                 Some(mut message) => {
                     if message.message_type() == Self::Item::message_type() {
-                        let (ticks, done) = Self::Item::decode(&mut message).unwrap();
+                        let (ticks, done) = match Self::Item::decode(&mut message) {
+                            Ok(decoded) => decoded,
+                            Err(err) => {
+                                error!("error decoding ticks: {err}");
+                                return None;
+                            }
+                        };
 
                         self.buffer.append(&mut ticks.into());
                         self.done = done;
@@ -567,5 +713,3 @@ impl<T: TickDecoder<T> + Debug> Iterator for TickIterator<T> {
         }
     }
 }
-
-struct HistogramDataIterator {}
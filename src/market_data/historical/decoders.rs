@@ -13,6 +13,23 @@ pub(super) fn decode_head_timestamp(message: &mut ResponseMessage) -> Result<Off
     Ok(head_timestamp)
 }
 
+pub(super) fn decode_histogram_data(message: &mut ResponseMessage) -> Result<Vec<HistogramEntry>, Error> {
+    message.skip(); // message type
+    message.skip(); // request_id
+
+    let count = message.next_int()?;
+
+    let mut entries = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        entries.push(HistogramEntry {
+            price: message.next_double()?,
+            count: message.next_long()?,
+        });
+    }
+
+    Ok(entries)
+}
+
 pub(super) fn decode_historical_data(server_version: i32, time_zone: &Tz, message: &mut ResponseMessage) -> Result<HistoricalData, Error> {
     message.skip(); // message type
 
@@ -44,6 +44,56 @@ pub(super) fn encode_request_head_timestamp(
     Ok(packet)
 }
 
+// Encodes the cancel head timestamp request
+pub(super) fn encode_cancel_head_timestamp(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut packet = RequestMessage::default();
+
+    packet.push_field(&OutgoingMessages::CancelHeadTimestamp);
+    packet.push_field(&request_id);
+
+    Ok(packet)
+}
+
+// Encodes the histogram data request
+pub(super) fn encode_request_histogram_data(request_id: i32, contract: &Contract, use_rth: bool, period: Duration) -> Result<RequestMessage, Error> {
+    let mut packet = RequestMessage::default();
+
+    packet.push_field(&OutgoingMessages::RequestHistogramData);
+    packet.push_field(&request_id);
+    contract.push_fields(&mut packet);
+    packet.push_field(&use_rth);
+    packet.push_field(&histogram_period(&period));
+
+    Ok(packet)
+}
+
+// Histogram periods are spelled out, e.g. 3 days or 1 week.
+fn histogram_period(period: &Duration) -> String {
+    let unit = match period.unit {
+        'S' => "second",
+        'D' => "day",
+        'W' => "week",
+        'M' => "month",
+        _ => "year",
+    };
+
+    if period.value == 1 {
+        format!("{} {unit}", period.value)
+    } else {
+        format!("{} {unit}s", period.value)
+    }
+}
+
+// Encodes the cancel histogram data request
+pub(super) fn encode_cancel_histogram_data(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut packet = RequestMessage::default();
+
+    packet.push_field(&OutgoingMessages::CancelHistogramData);
+    packet.push_field(&request_id);
+
+    Ok(packet)
+}
+
 // Encodes the historical data request
 #[allow(clippy::too_many_arguments)]
 pub(super) fn encode_request_historical_data(
@@ -12,7 +12,7 @@ use super::*;
 fn test_head_timestamp() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
         request_messages: RefCell::new(vec![]),
-        response_messages: vec!["9|9000|1678323335|".to_owned()],
+        response_messages: vec!["88|9000|1678323335|".to_owned()],
     }));
 
     let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
@@ -27,7 +27,7 @@ fn test_head_timestamp() {
 
     assert_eq!(head_timestamp, OffsetDateTime::from_unix_timestamp(1678323335).unwrap(), "bar.date");
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     let head_timestamp_request = &request_messages[0];
     assert_eq!(
@@ -62,10 +62,219 @@ fn test_head_timestamp() {
     assert_eq!(head_timestamp_request[17], "2", "message.date_format");
 }
 
+#[test]
+fn test_head_timestamp_api_error() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["4|2|9000|162|Historical Market Data Service error message:No head time stamp|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("MSFT");
+    let result = client.head_timestamp(&contract, WhatToShow::Trades, true);
+
+    match result {
+        Err(Error::Api { code, .. }) => assert_eq!(code, 162, "error code"),
+        other => assert!(false, "expected api error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_head_timestamp_cancelled_without_response() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("MSFT");
+    let result = client.head_timestamp(&contract, WhatToShow::Trades, true);
+
+    assert!(result.is_err(), "expected error without head timestamp response");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+    assert_eq!(request_messages[1].encode_simple(), "90|9000|", "cancel head timestamp request");
+}
+
 #[test]
 fn test_histogram_data() {
-    let result = 2 + 2;
-    assert_eq!(result, 4);
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["89|9000|3|409.5|1200|410.0|3450|410.5|980|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("MSFT");
+
+    let entries = client.histogram_data(&contract, true, 3.days()).expect("histogram data request failed");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "88|9000|0|MSFT|STK||0|||SMART||USD|||0|1|3 days|");
+
+    assert_eq!(
+        entries,
+        vec![
+            HistogramEntry { price: 409.5, count: 1200 },
+            HistogramEntry { price: 410.0, count: 3450 },
+            HistogramEntry { price: 410.5, count: 980 },
+        ]
+    );
+}
+
+#[test]
+fn test_histogram_data_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_HISTOGRAM - 1);
+
+    let contract = Contract::stock("MSFT");
+
+    let result = client.histogram_data(&contract, true, 1.weeks());
+
+    assert!(result.is_err(), "expected error for unsupported server version");
+}
+
+#[test]
+fn test_historical_ticks_trades() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["98|9000|2|1681133400|0|185.5|100|NASDAQ|T|1681133401|2|185.55|200|ARCA||1|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("TSLA");
+    let start = Some(datetime!(2023-04-10 13:30 UTC));
+
+    let ticks = client
+        .historical_ticks(&contract, start, None, 100, WhatToShow::Trades, true, false)
+        .expect("historical ticks request failed");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "96|9000|0|TSLA|STK||0|||SMART||USD|||0|20230410 13:30:00 UTC||100|TRADES|1|0||"
+    );
+
+    assert_eq!(ticks.len(), 2, "ticks.len()");
+
+    match &ticks[0] {
+        HistoricalTick::Last(tick) => {
+            assert_eq!(tick.timestamp, datetime!(2023-04-10 13:30:00 UTC), "tick.timestamp");
+            assert_eq!(
+                tick.tick_attribute_last,
+                TickAttributeLast {
+                    past_limit: false,
+                    unreported: false
+                },
+                "tick.tick_attribute_last"
+            );
+            assert_eq!(tick.price, 185.5, "tick.price");
+            assert_eq!(tick.size, 100, "tick.size");
+            assert_eq!(tick.exchange, "NASDAQ", "tick.exchange");
+            assert_eq!(tick.special_conditions, "T", "tick.special_conditions");
+        }
+        tick => assert!(false, "expected trade tick, got {tick:?}"),
+    }
+
+    match &ticks[1] {
+        HistoricalTick::Last(tick) => {
+            assert_eq!(
+                tick.tick_attribute_last,
+                TickAttributeLast {
+                    past_limit: false,
+                    unreported: true
+                },
+                "tick.tick_attribute_last"
+            );
+            assert_eq!(tick.price, 185.55, "tick.price");
+            assert_eq!(tick.exchange, "ARCA", "tick.exchange");
+        }
+        tick => assert!(false, "expected trade tick, got {tick:?}"),
+    }
+}
+
+#[test]
+fn test_historical_ticks_bid_ask() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["97|9000|2|1681133400|1|185.45|185.5|300|200|1681133401|2|185.4|185.55|100|400|1|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("TSLA");
+    let end = Some(datetime!(2023-04-10 13:30 UTC));
+
+    let ticks = client
+        .historical_ticks(&contract, None, end, 100, WhatToShow::BidAsk, false, true)
+        .expect("historical ticks request failed");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "96|9000|0|TSLA|STK||0|||SMART||USD|||0||20230410 13:30:00 UTC|100|BID_ASK|0|1||"
+    );
+
+    assert_eq!(ticks.len(), 2, "ticks.len()");
+
+    match &ticks[0] {
+        HistoricalTick::BidAsk(tick) => {
+            assert_eq!(tick.timestamp, datetime!(2023-04-10 13:30:00 UTC), "tick.timestamp");
+            assert_eq!(
+                tick.tick_attribute_bid_ask,
+                TickAttributeBidAsk {
+                    bid_past_low: false,
+                    ask_past_high: true
+                },
+                "tick.tick_attribute_bid_ask"
+            );
+            assert_eq!(tick.price_bid, 185.45, "tick.price_bid");
+            assert_eq!(tick.price_ask, 185.5, "tick.price_ask");
+            assert_eq!(tick.size_bid, 300, "tick.size_bid");
+            assert_eq!(tick.size_ask, 200, "tick.size_ask");
+        }
+        tick => assert!(false, "expected bid/ask tick, got {tick:?}"),
+    }
+
+    match &ticks[1] {
+        HistoricalTick::BidAsk(tick) => {
+            assert_eq!(
+                tick.tick_attribute_bid_ask,
+                TickAttributeBidAsk {
+                    bid_past_low: true,
+                    ask_past_high: false
+                },
+                "tick.tick_attribute_bid_ask"
+            );
+            assert_eq!(tick.size_ask, 400, "tick.size_ask");
+        }
+        tick => assert!(false, "expected bid/ask tick, got {tick:?}"),
+    }
+}
+
+#[test]
+fn test_historical_ticks_unsupported_what_to_show() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("TSLA");
+
+    let result = client.historical_ticks(&contract, None, None, 100, WhatToShow::HistoricalVolatility, true, false);
+
+    assert!(result.is_err(), "expected error for unsupported what_to_show");
+    assert!(client.message_bus.lock().request_messages().is_empty(), "no request expected");
 }
 
 #[test]
@@ -107,7 +316,7 @@ fn test_historical_data() {
 
     // Assert Request
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     let head_timestamp_request = &request_messages[0];
     assert_eq!(
@@ -147,6 +356,29 @@ fn test_historical_data() {
     assert_eq!(head_timestamp_request[22], "", "message.chart_options");
 }
 
+#[test]
+fn test_historical_data_no_data() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "4|2|9000|162|Historical Market Data Service error message:HMDS query returned no data: MSFT@SMART Trades||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("MSFT");
+    let interval_end = datetime!(2023-04-15 16:31:22 UTC);
+
+    let result = client.historical_data(&contract, interval_end, 2.days(), BarSize::Hour, WhatToShow::Trades, true);
+
+    match result {
+        Err(Error::Api { code, .. }) => assert_eq!(code, 162, "error code"),
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(data) => assert!(false, "expected error, got: {data:?}"),
+    }
+}
+
 #[test]
 fn test_bar_size() {
     assert_eq!(BarSize::Sec.to_string(), "1 sec");
@@ -161,6 +393,17 @@ fn test_bar_size() {
     assert_eq!(BarSize::Min30.to_string(), "30 mins");
     assert_eq!(BarSize::Hour.to_string(), "1 hour");
     assert_eq!(BarSize::Day.to_string(), "1 day");
+
+    let expected = [
+        "1 sec", "5 secs", "15 secs", "30 secs", "1 min", "2 mins", "3 mins", "5 mins", "15 mins", "20 mins", "30 mins", "1 hour", "2 hours",
+        "3 hours", "4 hours", "8 hours", "1 day", "1 week", "1 month",
+    ];
+    for (bar_size, text) in BarSize::ALL.iter().zip(expected) {
+        assert_eq!(bar_size.to_string(), text, "{bar_size:?}.to_string()");
+        assert_eq!(text.parse::<BarSize>().ok(), Some(*bar_size), "parse {text}");
+    }
+
+    assert!("7 mins".parse::<BarSize>().is_err(), "unsupported bar size should not parse");
 }
 
 #[test]
@@ -189,4 +432,12 @@ fn test_duration() {
     assert_eq!(4.weeks().to_field(), "4 W");
     assert_eq!(5.months().to_field(), "5 M");
     assert_eq!(6.years().to_field(), "6 Y");
+
+    assert_eq!(Duration::days(3).to_string(), "3 D");
+
+    assert_eq!("3 D".parse::<Duration>().ok(), Some(3.days()), "parse 3 D");
+    assert_eq!("60 S".parse::<Duration>().ok(), Some(60.seconds()), "parse 60 S");
+    assert_eq!("2 Y".parse::<Duration>().ok(), Some(2.years()), "parse 2 Y");
+    assert!("3 X".parse::<Duration>().is_err(), "unknown unit should not parse");
+    assert!("D".parse::<Duration>().is_err(), "missing value should not parse");
 }
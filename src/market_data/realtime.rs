@@ -1,13 +1,24 @@
-use log::error;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, warn};
 use time::OffsetDateTime;
 
-use crate::client::transport::ResponseIterator;
+use crate::client::transport::{DecodedSubscription, ResponseIterator};
+use crate::client::{Subscription, TryNext};
 use crate::contracts::Contract;
-use crate::messages::IncomingMessages;
+use crate::messages::{IncomingMessages, ResponseMessage};
 use crate::orders::TagValue;
 use crate::server_versions;
 use crate::ToField;
 use crate::{Client, Error};
+#[cfg(feature = "async")]
+pub(crate) use asynchronous::realtime_bars_async;
+#[cfg(feature = "async")]
+pub use asynchronous::RealTimeBarStream;
+
+#[cfg(feature = "async")]
+mod asynchronous;
 
 mod decoders;
 mod encoders;
@@ -123,6 +134,440 @@ impl ToField for WhatToShow {
     }
 }
 
+/// Market data tick types. See [Available Tick Types](https://interactivebrokers.github.io/tws-api/tick_types.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TickType {
+    BidSize,
+    Bid,
+    Ask,
+    AskSize,
+    Last,
+    LastSize,
+    High,
+    Low,
+    Volume,
+    Close,
+    BidOption,
+    AskOption,
+    LastOption,
+    ModelOption,
+    Open,
+    Low13Week,
+    High13Week,
+    Low26Week,
+    High26Week,
+    Low52Week,
+    High52Week,
+    AverageVolume,
+    OpenInterest,
+    OptionHistoricalVolatility,
+    OptionImpliedVolatility,
+    OptionBidExchange,
+    OptionAskExchange,
+    OptionCallOpenInterest,
+    OptionPutOpenInterest,
+    OptionCallVolume,
+    OptionPutVolume,
+    IndexFuturePremium,
+    BidExchange,
+    AskExchange,
+    AuctionVolume,
+    AuctionPrice,
+    AuctionImbalance,
+    MarkPrice,
+    LastTimestamp,
+    Shortable,
+    FundamentalRatios,
+    RtVolume,
+    Halted,
+    /// Tick type not modeled by this library.
+    Other(i32),
+}
+
+impl From<i32> for TickType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::BidSize,
+            1 => Self::Bid,
+            2 => Self::Ask,
+            3 => Self::AskSize,
+            4 => Self::Last,
+            5 => Self::LastSize,
+            6 => Self::High,
+            7 => Self::Low,
+            8 => Self::Volume,
+            9 => Self::Close,
+            10 => Self::BidOption,
+            11 => Self::AskOption,
+            12 => Self::LastOption,
+            13 => Self::ModelOption,
+            14 => Self::Open,
+            15 => Self::Low13Week,
+            16 => Self::High13Week,
+            17 => Self::Low26Week,
+            18 => Self::High26Week,
+            19 => Self::Low52Week,
+            20 => Self::High52Week,
+            21 => Self::AverageVolume,
+            22 => Self::OpenInterest,
+            23 => Self::OptionHistoricalVolatility,
+            24 => Self::OptionImpliedVolatility,
+            25 => Self::OptionBidExchange,
+            26 => Self::OptionAskExchange,
+            27 => Self::OptionCallOpenInterest,
+            28 => Self::OptionPutOpenInterest,
+            29 => Self::OptionCallVolume,
+            30 => Self::OptionPutVolume,
+            31 => Self::IndexFuturePremium,
+            32 => Self::BidExchange,
+            33 => Self::AskExchange,
+            34 => Self::AuctionVolume,
+            35 => Self::AuctionPrice,
+            36 => Self::AuctionImbalance,
+            37 => Self::MarkPrice,
+            45 => Self::LastTimestamp,
+            46 => Self::Shortable,
+            47 => Self::FundamentalRatios,
+            48 => Self::RtVolume,
+            49 => Self::Halted,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+/// Generic ticks requested in addition to the default ticks of [Client::market_data].
+/// See [Generic Tick Types](https://interactivebrokers.github.io/tws-api/tick_types.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GenericTick {
+    /// Call and put option volume, for stocks.
+    OptionVolume,
+    /// Call and put option open interest, for stocks.
+    OptionOpenInterest,
+    /// 30 day historical volatility, for stocks.
+    HistoricalVolatility,
+    /// Average option volume, for stocks.
+    AverageOptionVolume,
+    /// 30 day option implied volatility, for stocks.
+    OptionImpliedVolatility,
+    /// Index future premium.
+    IndexFuturePremium,
+    /// 13, 26 and 52 week highs and lows, and average volume.
+    MiscellaneousStats,
+    /// Mark price used in P&L calculations.
+    MarkPrice,
+    /// Auction volume, price and imbalance.
+    AuctionValues,
+    /// Last trade price, size and time, total volume and VWAP.
+    RtVolume,
+    /// Shortable level of the contract.
+    Shortable,
+    /// Shares available to short.
+    Inventory,
+    /// Fundamental ratios of the company.
+    FundamentalRatios,
+    /// Number of trades for the day.
+    TradeCount,
+    /// Trades per minute.
+    TradeRate,
+    /// Shares traded per minute.
+    VolumeRate,
+    /// Last trade of the regular trading hours.
+    LastRthTrade,
+    /// Last trade price, size and time, excluding unreportable trades.
+    RtTradeVolume,
+    /// Historical volatility updated in real time.
+    RealtimeHistoricalVolatility,
+    /// Dividends of the past and next 12 months and the next dividend.
+    IbDividends,
+    /// Bond factor multiplier.
+    BondFactorMultiplier,
+    /// Total open interest, for futures.
+    FuturesOpenInterest,
+    /// Volume of the last 3, 5 and 10 minutes.
+    ShortTermVolume,
+}
+
+impl GenericTick {
+    /// Id of the generic tick sent in market data requests.
+    pub fn id(&self) -> &'static str {
+        match self {
+            GenericTick::OptionVolume => "100",
+            GenericTick::OptionOpenInterest => "101",
+            GenericTick::HistoricalVolatility => "104",
+            GenericTick::AverageOptionVolume => "105",
+            GenericTick::OptionImpliedVolatility => "106",
+            GenericTick::IndexFuturePremium => "162",
+            GenericTick::MiscellaneousStats => "165",
+            GenericTick::MarkPrice => "221",
+            GenericTick::AuctionValues => "225",
+            GenericTick::RtVolume => "233",
+            GenericTick::Shortable => "236",
+            GenericTick::Inventory => "256",
+            GenericTick::FundamentalRatios => "258",
+            GenericTick::TradeCount => "293",
+            GenericTick::TradeRate => "294",
+            GenericTick::VolumeRate => "295",
+            GenericTick::LastRthTrade => "318",
+            GenericTick::RtTradeVolume => "375",
+            GenericTick::RealtimeHistoricalVolatility => "411",
+            GenericTick::IbDividends => "456",
+            GenericTick::BondFactorMultiplier => "460",
+            GenericTick::FuturesOpenInterest => "588",
+            GenericTick::ShortTermVolume => "595",
+        }
+    }
+
+    /// Ids of the generic ticks, for the `generic_ticks` argument of [Client::market_data].
+    ///
+    /// ```
+    /// use ibapi::market_data::realtime::GenericTick;
+    ///
+    /// let ids = GenericTick::ids(&[GenericTick::RtVolume, GenericTick::Shortable]);
+    /// assert_eq!(ids, vec!["233", "236"]);
+    /// ```
+    pub fn ids(generic_ticks: &[GenericTick]) -> Vec<&'static str> {
+        generic_ticks.iter().map(GenericTick::id).collect()
+    }
+
+    /// Comma separated ids of the generic ticks, as sent in market data requests.
+    pub fn to_ids(generic_ticks: &[GenericTick]) -> String {
+        Self::ids(generic_ticks).join(",")
+    }
+}
+
+/// Type of market data returned by [Client::market_data]. See [Client::set_market_data_type].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MarketDataType {
+    /// Real-time streaming market data.
+    #[default]
+    Live = 1,
+    /// Last data recorded at market close.
+    Frozen = 2,
+    /// Delayed market data, for accounts without a market data subscription.
+    Delayed = 3,
+    /// Delayed data, or last delayed data recorded at market close.
+    DelayedFrozen = 4,
+}
+
+impl From<i32> for MarketDataType {
+    fn from(value: i32) -> Self {
+        match value {
+            2 => MarketDataType::Frozen,
+            3 => MarketDataType::Delayed,
+            4 => MarketDataType::DelayedFrozen,
+            _ => MarketDataType::Live,
+        }
+    }
+}
+
+impl ToField for MarketDataType {
+    fn to_field(&self) -> String {
+        (*self as i32).to_string()
+    }
+}
+
+/// Market data tick received from a [Client::market_data] subscription.
+#[derive(Debug)]
+pub enum TickTypes {
+    Price(TickPrice),
+    Size(TickSize),
+    String(TickString),
+    Generic(TickGeneric),
+}
+
+#[derive(Debug)]
+pub struct TickPrice {
+    pub tick_type: TickType,
+    pub price: f64,
+    /// Size associated with the price, for bid, ask and last ticks.
+    pub size: f64,
+    pub attributes: TickAttrib,
+    /// Tick is delayed market data, see [MarketDataType::Delayed].
+    pub delayed: bool,
+}
+
+/// Attributes of a [TickPrice].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickAttrib {
+    /// Whether an order at this price can be executed automatically.
+    pub can_auto_execute: bool,
+    /// Bid price is lower than the day's lowest value or ask price is higher than the day's highest value.
+    pub past_limit: bool,
+    /// Bid or ask price is a pre-open price.
+    pub pre_open: bool,
+}
+
+#[derive(Debug)]
+pub struct TickSize {
+    pub tick_type: TickType,
+    pub size: f64,
+    /// Tick is delayed market data, see [MarketDataType::Delayed].
+    pub delayed: bool,
+}
+
+#[derive(Debug)]
+pub struct TickString {
+    pub tick_type: TickType,
+    pub value: String,
+    /// Tick is delayed market data, see [MarketDataType::Delayed].
+    pub delayed: bool,
+}
+
+#[derive(Debug)]
+pub struct TickGeneric {
+    pub tick_type: TickType,
+    pub value: f64,
+}
+
+/// Consolidated quote returned by [Client::snapshot]. Fields not reported in the snapshot are None.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last: Option<f64>,
+    pub bid_size: Option<f64>,
+    pub ask_size: Option<f64>,
+    pub volume: Option<f64>,
+    pub close: Option<f64>,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+}
+
+impl Snapshot {
+    // Records the value of a price or size tick.
+    fn update(&mut self, tick_type: TickType, value: f64) {
+        let field = match tick_type {
+            TickType::Bid => &mut self.bid,
+            TickType::Ask => &mut self.ask,
+            TickType::Last => &mut self.last,
+            TickType::BidSize => &mut self.bid_size,
+            TickType::AskSize => &mut self.ask_size,
+            TickType::Volume => &mut self.volume,
+            TickType::Close => &mut self.close,
+            TickType::Open => &mut self.open,
+            TickType::High => &mut self.high,
+            TickType::Low => &mut self.low,
+            _ => return,
+        };
+        *field = Some(value);
+    }
+}
+
+/// Operation to apply to the order book row identified by [DepthUpdate::position].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl DepthOperation {
+    fn from(val: i32) -> Result<Self, Error> {
+        match val {
+            0 => Ok(DepthOperation::Insert),
+            1 => Ok(DepthOperation::Update),
+            2 => Ok(DepthOperation::Delete),
+            _ => Err(Error::Simple(format!("unexpected market depth operation: {val}"))),
+        }
+    }
+}
+
+/// Side of the order book a [DepthUpdate] applies to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthSide {
+    Ask,
+    Bid,
+}
+
+impl DepthSide {
+    fn from(val: i32) -> Result<Self, Error> {
+        match val {
+            0 => Ok(DepthSide::Ask),
+            1 => Ok(DepthSide::Bid),
+            _ => Err(Error::Simple(format!("unexpected market depth side: {val}"))),
+        }
+    }
+}
+
+/// Change to the order book received from a [Client::market_depth] subscription.
+#[derive(Clone, Debug)]
+pub struct DepthUpdate {
+    /// The order book's row being updated
+    pub position: i32,
+    /// Exchange holding the order if is_smart_depth is true, otherwise the MPID of the market maker. Empty for L1 updates.
+    pub market_maker: String,
+    /// How to refresh the row
+    pub operation: DepthOperation,
+    /// Side of the book the order belongs to
+    pub side: DepthSide,
+    /// Order's price
+    pub price: f64,
+    /// Order's size
+    pub size: f64,
+    /// Flag indicating if this is smart depth response
+    pub smart_depth: bool,
+}
+
+/// Row of an [OrderBook].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+    pub market_maker: String,
+}
+
+/// Aggregated view of the order book built from [DepthUpdate]s.
+///
+/// ```no_run
+/// use ibapi::Client;
+/// use ibapi::contracts::Contract;
+/// use ibapi::market_data::realtime::OrderBook;
+///
+/// let client = Client::connect("127.0.0.1:4002", 100).expect("connection failed");
+///
+/// let contract = Contract::stock("AAPL");
+/// let updates = client.market_depth(&contract, 5, false).expect("request failed");
+///
+/// let mut book = OrderBook::default();
+/// for update in updates {
+///     book.apply(&update);
+///     println!("bids: {:?} asks: {:?}", book.bids, book.asks);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OrderBook {
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+impl OrderBook {
+    /// Applies a [DepthUpdate] to the book. Updates for rows out of range are ignored.
+    pub fn apply(&mut self, update: &DepthUpdate) {
+        let rows = match update.side {
+            DepthSide::Bid => &mut self.bids,
+            DepthSide::Ask => &mut self.asks,
+        };
+
+        let position = update.position as usize;
+        let level = BookLevel {
+            price: update.price,
+            size: update.size,
+            market_maker: update.market_maker.clone(),
+        };
+
+        match update.operation {
+            DepthOperation::Insert if position <= rows.len() => rows.insert(position, level),
+            DepthOperation::Update if position < rows.len() => rows[position] = level,
+            DepthOperation::Delete if position < rows.len() => {
+                rows.remove(position);
+            }
+            operation => debug!("ignoring market depth {operation:?} at position {position}, rows: {}", rows.len()),
+        }
+    }
+}
+
 // === Implementation ===
 
 // Requests realtime bars.
@@ -151,13 +596,189 @@ pub(crate) fn realtime_bars<'a>(
     Ok(RealTimeBarIterator::new(client, request_id, responses))
 }
 
+// Requests streaming market data.
+pub(crate) fn market_data<'a>(
+    client: &'a Client,
+    contract: &Contract,
+    generic_ticks: &[&str],
+    snapshot: bool,
+    regulatory_snapshot: bool,
+) -> Result<MarketDataIterator<'a>, Error> {
+    let (request_id, responses) = request_market_data(client, contract, generic_ticks, snapshot, regulatory_snapshot)?;
+
+    Ok(MarketDataIterator::new(client, request_id, responses))
+}
+
+// Sends a market data request, holding a market data line until the caller releases it.
+fn request_market_data(
+    client: &Client,
+    contract: &Contract,
+    generic_ticks: &[&str],
+    snapshot: bool,
+    regulatory_snapshot: bool,
+) -> Result<(i32, ResponseIterator), Error> {
+    if contract.delta_neutral_contract.is_some() {
+        client.check_server_version(server_versions::DELTA_NEUTRAL, "It does not support delta-neutral orders.")?;
+    }
+
+    if contract.contract_id > 0 {
+        client.check_server_version(server_versions::REQ_MKT_DATA_CONID, "It does not support contract_id parameter.")?;
+    }
+
+    if !contract.trading_class.is_empty() {
+        client.check_server_version(
+            server_versions::TRADING_CLASS,
+            "It does not support trading_class parameter in request_market_data.",
+        )?;
+    }
+
+    let request_id = client.next_request_id();
+    let message = encoders::encode_request_market_data(
+        client.server_version(),
+        request_id,
+        contract,
+        generic_ticks,
+        snapshot,
+        regulatory_snapshot,
+    )?;
+
+    client.acquire_market_data_line()?;
+    match client.send_request(request_id, message) {
+        Ok(responses) => Ok((request_id, responses)),
+        Err(err) => {
+            client.release_market_data_line();
+            Err(err)
+        }
+    }
+}
+
+// Error code of the warning sent when delayed market data is displayed in place of a missing subscription.
+const DELAYED_MARKET_DATA_WARNING: i32 = 10167;
+
+// Requests a single snapshot of market data and consolidates its ticks.
+pub(crate) fn snapshot(client: &Client, contract: &Contract) -> Result<Snapshot, Error> {
+    let (request_id, mut responses) = request_market_data(client, contract, &[], true, false)?;
+
+    let result = read_snapshot(client.server_version(), &mut responses);
+
+    // The server ends the request itself once the snapshot is complete or rejected.
+    if !matches!(result, Ok(_) | Err(Error::Api { .. })) {
+        let message = encoders::encode_cancel_market_data(request_id)?;
+        if let Err(e) = client.send_message(message) {
+            error!("error cancelling market data: {e}");
+        }
+    }
+    client.release_market_data_line();
+
+    result
+}
+
+// Reads ticks until the end of the snapshot, returning Error::Timeout if it doesn't arrive in time.
+fn read_snapshot(server_version: i32, responses: &mut ResponseIterator) -> Result<Snapshot, Error> {
+    let mut snapshot = Snapshot::default();
+
+    loop {
+        let mut message = responses.recv_timeout()?;
+
+        match message.message_type() {
+            IncomingMessages::TickPrice => match decoders::decode_tick_price(server_version, &mut message) {
+                Ok(tick) => snapshot.update(tick.tick_type, tick.price),
+                Err(err) => error!("error decoding message {message:?}: {err}"),
+            },
+            IncomingMessages::TickSize => match decoders::decode_tick_size(&mut message) {
+                Ok(tick) => snapshot.update(tick.tick_type, tick.size),
+                Err(err) => error!("error decoding message {message:?}: {err}"),
+            },
+            IncomingMessages::TickSnapshotEnd => return Ok(snapshot),
+            IncomingMessages::Error => match message.api_error_for(server_version) {
+                Error::Api { code, message, .. } if code == DELAYED_MARKET_DATA_WARNING => warn!("{message}"),
+                err => return Err(err),
+            },
+            _ => debug!("ignoring message {message:?}"),
+        }
+    }
+}
+
+// Switches market data type returned by subsequent market data requests.
+pub(crate) fn set_market_data_type(client: &Client, market_data_type: MarketDataType) -> Result<(), Error> {
+    client.check_server_version(server_versions::REQ_MARKET_DATA_TYPE, "It does not support market data type requests.")?;
+
+    let message = encoders::encode_request_market_data_type(market_data_type)?;
+
+    client.send_message(message)
+}
+
+// Requests the exchanges represented by the bits of a bit-mapped exchange, i.e. the bbo exchange of a tick requested with SMART routing.
+pub(crate) fn smart_components(client: &Client, bbo_exchange: &str) -> Result<HashMap<i32, (char, String)>, Error> {
+    client.check_server_version(server_versions::REQ_SMART_COMPONENTS, "It does not support smart components request.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::encode_request_smart_components(request_id, bbo_exchange)?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    while let Some(message) = responses.next_result() {
+        let mut message = message?;
+        match message.message_type() {
+            IncomingMessages::SmartComponents => {
+                return decoders::decode_smart_components(&mut message);
+            }
+            _ => {
+                error!("unexpected message: {:?}", message);
+            }
+        }
+    }
+
+    Err(Error::Simple("no smart components received".into()))
+}
+
+// Requests market depth (order book).
+pub(crate) fn market_depth<'a>(
+    client: &'a Client,
+    contract: &Contract,
+    number_of_rows: i32,
+    is_smart_depth: bool,
+) -> Result<MarketDepthIterator<'a>, Error> {
+    if is_smart_depth {
+        client.check_server_version(server_versions::SMART_DEPTH, "It does not support SMART depth request.")?;
+    }
+
+    if !contract.primary_exchange.is_empty() {
+        client.check_server_version(
+            server_versions::MKT_DEPTH_PRIM_EXCHANGE,
+            "It does not support primary_exchange parameter in request_market_depth.",
+        )?;
+    }
+
+    if !contract.trading_class.is_empty() || contract.contract_id > 0 {
+        client.check_server_version(
+            server_versions::TRADING_CLASS,
+            "It does not support contract_id nor trading_class parameters in request_market_depth.",
+        )?;
+    }
+
+    let request_id = client.next_request_id();
+    let message = encoders::encode_request_market_depth(client.server_version(), request_id, contract, number_of_rows, is_smart_depth)?;
+
+    client.acquire_market_data_line()?;
+    let responses = match client.send_request(request_id, message) {
+        Ok(responses) => responses,
+        Err(err) => {
+            client.release_market_data_line();
+            return Err(err);
+        }
+    };
+
+    Ok(MarketDepthIterator::new(client, request_id, is_smart_depth, responses))
+}
+
 // Requests tick by tick AllLast ticks.
 pub(crate) fn tick_by_tick_all_last<'a>(
     client: &'a Client,
     contract: &Contract,
     number_of_ticks: i32,
     ignore_size: bool,
-) -> Result<impl Iterator<Item = Trade> + 'a, Error> {
+) -> Result<TradeIterator<'a>, Error> {
     validate_tick_by_tick_request(client, contract, number_of_ticks, ignore_size)?;
 
     let server_version = client.server_version();
@@ -166,11 +787,7 @@ pub(crate) fn tick_by_tick_all_last<'a>(
     let message = encoders::tick_by_tick(server_version, request_id, contract, "AllLast", number_of_ticks, ignore_size)?;
     let responses = client.send_request(request_id, message)?;
 
-    Ok(TradeIterator {
-        client,
-        request_id,
-        responses,
-    })
+    Ok(TradeIterator::new(client, request_id, responses))
 }
 
 // Validates that server supports the given request.
@@ -202,11 +819,7 @@ pub(crate) fn tick_by_tick_last<'a>(
     let message = encoders::tick_by_tick(server_version, request_id, contract, "Last", number_of_ticks, ignore_size)?;
     let responses = client.send_request(request_id, message)?;
 
-    Ok(TradeIterator {
-        client,
-        request_id,
-        responses,
-    })
+    Ok(TradeIterator::new(client, request_id, responses))
 }
 
 // Requests tick by tick BidAsk ticks.
@@ -224,11 +837,7 @@ pub(crate) fn tick_by_tick_bid_ask<'a>(
     let message = encoders::tick_by_tick(server_version, request_id, contract, "BidAsk", number_of_ticks, ignore_size)?;
     let responses = client.send_request(request_id, message)?;
 
-    Ok(BidAskIterator {
-        client,
-        request_id,
-        responses,
-    })
+    Ok(BidAskIterator::new(client, request_id, responses))
 }
 
 // Requests tick by tick MidPoint ticks.
@@ -246,24 +855,25 @@ pub(crate) fn tick_by_tick_midpoint<'a>(
     let message = encoders::tick_by_tick(server_version, request_id, contract, "MidPoint", number_of_ticks, ignore_size)?;
     let responses = client.send_request(request_id, message)?;
 
-    Ok(MidPointIterator {
-        client,
-        request_id,
-        responses,
-    })
+    Ok(MidPointIterator::new(client, request_id, responses))
 }
 
 // Iterators
 
 /// RealTimeBarIterator supports iteration over [RealTimeBar] ticks.
-pub(crate) struct RealTimeBarIterator<'a> {
+pub struct RealTimeBarIterator<'a> {
     client: &'a Client,
     request_id: i32,
-    responses: ResponseIterator,
+    responses: DecodedSubscription<Bar>,
 }
 
 impl<'a> RealTimeBarIterator<'a> {
     fn new(client: &'a Client, request_id: i32, responses: ResponseIterator) -> RealTimeBarIterator<'a> {
+        let responses = DecodedSubscription::new(responses, |message| match message.message_type() {
+            IncomingMessages::RealTimeBars => decoders::decode_realtime_bar(message).map(Some),
+            message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+        });
+
         RealTimeBarIterator {
             client,
             request_id,
@@ -271,58 +881,259 @@ impl<'a> RealTimeBarIterator<'a> {
         }
     }
 
-    /// Cancels request to stream realtime bars
-    fn cancel_realtime_bars(&mut self) {
+    /// Cancels the realtime bars subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        if self.responses.is_cancelled() {
+            return;
+        }
+
         let message = encoders::cancel_realtime_bars(self.request_id).unwrap();
-        self.client.send_message(message).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling realtime bars: {e}");
+        }
+
+        self.responses.cancel();
     }
 }
 
 impl<'a> Iterator for RealTimeBarIterator<'a> {
     type Item = Bar;
 
-    /// Advances the iterator and returns the next value.
+    /// Advances the iterator and returns the next value. Bars are streamed until the iterator is dropped.
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(mut message) = self.responses.next() {
-            match message.message_type() {
-                IncomingMessages::RealTimeBars => {
-                    let decoded = decoders::decode_realtime_bar(&mut message);
-
-                    if let Ok(bar) = decoded {
-                        return Some(bar);
-                    }
-
-                    error!("unexpected message: {:?}", decoded.err());
-                    None
-                }
-                _ => {
-                    error!("unexpected message: {message:?}");
-                    None
-                }
-            }
-        } else {
-            None
-        }
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for RealTimeBarIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
     }
 }
 
 impl<'a> Drop for RealTimeBarIterator<'a> {
     fn drop(&mut self) {
-        self.cancel_realtime_bars()
+        self.cancel()
     }
 }
 
-/// TradeIterator supports iteration over [Trade] ticks.
-pub(crate) struct TradeIterator<'a> {
+/// MarketDepthIterator supports iteration over [DepthUpdate]s.
+pub struct MarketDepthIterator<'a> {
     client: &'a Client,
     request_id: i32,
+    is_smart_depth: bool,
+    responses: DecodedSubscription<DepthUpdate>,
+}
+
+impl<'a> MarketDepthIterator<'a> {
+    fn new(client: &'a Client, request_id: i32, is_smart_depth: bool, responses: ResponseIterator) -> MarketDepthIterator<'a> {
+        let server_version = client.server_version();
+        let responses = DecodedSubscription::new(responses, move |message| match message.message_type() {
+            IncomingMessages::MarketDepth => decoders::decode_market_depth(message).map(Some),
+            IncomingMessages::MarketDepthL2 => decoders::decode_market_depth_l2(server_version, message).map(Some),
+            message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+        });
+
+        MarketDepthIterator {
+            client,
+            request_id,
+            is_smart_depth,
+            responses,
+        }
+    }
+
+    /// Cancels the market depth subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        if self.responses.is_cancelled() {
+            return;
+        }
+
+        let message = encoders::encode_cancel_market_depth(self.client.server_version(), self.request_id, self.is_smart_depth).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling market depth: {e}");
+        }
+
+        self.responses.cancel();
+        self.client.release_market_data_line();
+    }
+}
+
+impl<'a> Drop for MarketDepthIterator<'a> {
+    // Ensures market depth request is cancelled
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
+impl<'a> Iterator for MarketDepthIterator<'a> {
+    type Item = DepthUpdate;
+
+    /// Advances the iterator and returns the next value. Updates are streamed until the iterator is dropped.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for MarketDepthIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
+
+/// MarketDataIterator supports iteration over [TickTypes] ticks.
+pub struct MarketDataIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: DecodedSubscription<TickTypes>,
+}
+
+impl<'a> MarketDataIterator<'a> {
+    fn new(client: &'a Client, request_id: i32, responses: ResponseIterator) -> MarketDataIterator<'a> {
+        let server_version = client.server_version();
+        let responses = DecodedSubscription::new(responses, move |message| match message.message_type() {
+            IncomingMessages::TickPrice => decoders::decode_tick_price(server_version, message).map(|tick| Some(TickTypes::Price(tick))),
+            IncomingMessages::TickSize => decoders::decode_tick_size(message).map(|tick| Some(TickTypes::Size(tick))),
+            IncomingMessages::Tickstring => decoders::decode_tick_string(message).map(|tick| Some(TickTypes::String(tick))),
+            IncomingMessages::TickGeneric => decoders::decode_tick_generic(message).map(|tick| Some(TickTypes::Generic(tick))),
+            IncomingMessages::TickSnapshotEnd => Ok(None),
+            message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+        })
+        .ignoring(&[IncomingMessages::TickReqParams, IncomingMessages::MarketDataType]);
+
+        MarketDataIterator {
+            client,
+            request_id,
+            responses,
+        }
+    }
+
+    /// Cancels the market data subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        if self.responses.is_cancelled() {
+            return;
+        }
+
+        let message = encoders::encode_cancel_market_data(self.request_id).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling market data: {e}");
+        }
+
+        self.responses.cancel();
+        self.client.release_market_data_line();
+    }
+}
+
+impl<'a> Drop for MarketDataIterator<'a> {
+    // Ensures market data request is cancelled
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
+impl<'a> Iterator for MarketDataIterator<'a> {
+    type Item = TickTypes;
+
+    /// Advances the iterator and returns the next value.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for MarketDataIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
+    }
+}
+
+// Decodes the tick by tick messages of a subscription with decode.
+fn tick_by_tick_subscription<T: 'static>(
     responses: ResponseIterator,
+    decode: fn(&mut ResponseMessage) -> Result<T, Error>,
+) -> DecodedSubscription<T> {
+    DecodedSubscription::new(responses, move |message| match message.message_type() {
+        IncomingMessages::TickByTick => decode(message).map(Some),
+        message_type => Err(Error::Simple(format!("unexpected message: {message_type:?}"))),
+    })
+}
+
+/// Cancels the tick by tick request
+fn cancel_tick_by_tick<T>(client: &Client, request_id: i32, responses: &mut DecodedSubscription<T>) {
+    if responses.is_cancelled() {
+        return;
+    }
+
+    if client.server_version() >= server_versions::TICK_BY_TICK {
+        let message = encoders::cancel_tick_by_tick(request_id).unwrap();
+        if let Err(e) = client.send_message(message) {
+            error!("error cancelling tick by tick: {e}");
+        }
+    }
+
+    responses.cancel();
+}
+
+/// TradeIterator supports iteration over [Trade] ticks.
+pub struct TradeIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: DecodedSubscription<Trade>,
+}
+
+impl<'a> TradeIterator<'a> {
+    fn new(client: &'a Client, request_id: i32, responses: ResponseIterator) -> TradeIterator<'a> {
+        TradeIterator {
+            client,
+            request_id,
+            responses: tick_by_tick_subscription(responses, decoders::trade_tick),
+        }
+    }
+
+    /// Cancels the tick by tick subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        cancel_tick_by_tick(self.client, self.request_id, &mut self.responses);
+    }
 }
 
 impl<'a> Drop for TradeIterator<'a> {
     // Ensures tick by tick request is cancelled
     fn drop(&mut self) {
-        cancel_tick_by_tick(self.client, self.request_id);
+        self.cancel()
     }
 }
 
@@ -331,40 +1142,54 @@ impl<'a> Iterator for TradeIterator<'a> {
 
     /// Advances the iterator and returns the next value.
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.responses.next() {
-                Some(mut message) => match message.message_type() {
-                    IncomingMessages::TickByTick => match decoders::trade_tick(&mut message) {
-                        Ok(tick) => return Some(tick),
-                        Err(e) => error!("unexpected message {message:?}: {e:?}"),
-                    },
-                    _ => error!("unexpected message {message:?}"),
-                },
-                None => return None,
-            }
-        }
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for TradeIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
     }
 }
 
 /// BidAskIterator supports iteration over [BidAsk] ticks.
-pub(crate) struct BidAskIterator<'a> {
+pub struct BidAskIterator<'a> {
     client: &'a Client,
     request_id: i32,
-    responses: ResponseIterator,
+    responses: DecodedSubscription<BidAsk>,
 }
 
-/// Cancels the tick by tick request
-fn cancel_tick_by_tick(client: &Client, request_id: i32) {
-    if client.server_version() >= server_versions::TICK_BY_TICK {
-        let message = encoders::cancel_tick_by_tick(request_id).unwrap();
-        client.send_message(message).unwrap();
+impl<'a> BidAskIterator<'a> {
+    fn new(client: &'a Client, request_id: i32, responses: ResponseIterator) -> BidAskIterator<'a> {
+        BidAskIterator {
+            client,
+            request_id,
+            responses: tick_by_tick_subscription(responses, decoders::bid_ask_tick),
+        }
+    }
+
+    /// Cancels the tick by tick subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        cancel_tick_by_tick(self.client, self.request_id, &mut self.responses);
     }
 }
 
 impl<'a> Drop for BidAskIterator<'a> {
     // Ensures tick by tick request is cancelled
     fn drop(&mut self) {
-        cancel_tick_by_tick(self.client, self.request_id);
+        self.cancel()
     }
 }
 
@@ -373,32 +1198,54 @@ impl<'a> Iterator for BidAskIterator<'a> {
 
     /// Advances the iterator and returns the next value.
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.responses.next() {
-                Some(mut message) => match message.message_type() {
-                    IncomingMessages::TickByTick => match decoders::bid_ask_tick(&mut message) {
-                        Ok(tick) => return Some(tick),
-                        Err(e) => error!("unexpected message {message:?}: {e:?}"),
-                    },
-                    _ => error!("unexpected message {message:?}"),
-                },
-                None => return None,
-            }
-        }
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for BidAskIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
     }
 }
 
 /// MidPointIterator supports iteration over [MidPoint] ticks.
-pub(crate) struct MidPointIterator<'a> {
+pub struct MidPointIterator<'a> {
     client: &'a Client,
     request_id: i32,
-    responses: ResponseIterator,
+    responses: DecodedSubscription<MidPoint>,
+}
+
+impl<'a> MidPointIterator<'a> {
+    fn new(client: &'a Client, request_id: i32, responses: ResponseIterator) -> MidPointIterator<'a> {
+        MidPointIterator {
+            client,
+            request_id,
+            responses: tick_by_tick_subscription(responses, decoders::mid_point_tick),
+        }
+    }
+
+    /// Cancels the tick by tick subscription. The iterator returns None afterwards.
+    pub fn cancel(&mut self) {
+        cancel_tick_by_tick(self.client, self.request_id, &mut self.responses);
+    }
 }
 
 impl<'a> Drop for MidPointIterator<'a> {
     // Ensures tick by tick request is cancelled
     fn drop(&mut self) {
-        cancel_tick_by_tick(self.client, self.request_id);
+        self.cancel()
     }
 }
 
@@ -407,17 +1254,24 @@ impl<'a> Iterator for MidPointIterator<'a> {
 
     /// Advances the iterator and returns the next value.
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.responses.next() {
-                Some(mut message) => match message.message_type() {
-                    IncomingMessages::TickByTick => match decoders::mid_point_tick(&mut message) {
-                        Ok(tick) => return Some(tick),
-                        Err(e) => error!("unexpected message {message:?}: {e:?}"),
-                    },
-                    _ => error!("unexpected message {message:?}"),
-                },
-                None => return None,
-            }
-        }
+        self.responses.next()
+    }
+}
+
+impl<'a> Subscription for MidPointIterator<'a> {
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.responses.set_timeout(timeout)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Self::Item>, Error> {
+        self.responses.next_timeout(timeout)
+    }
+
+    fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<Self::Item>, Error> {
+        self.responses.recv_deadline(deadline)
+    }
+
+    fn try_next(&mut self) -> TryNext<Self::Item> {
+        self.responses.try_next()
     }
 }
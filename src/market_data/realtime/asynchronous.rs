@@ -0,0 +1,78 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use log::error;
+
+use super::{decoders, encoders, Bar, BarSize, WhatToShow};
+use crate::client::transport::asynchronous::ResponseStream;
+use crate::contracts::Contract;
+use crate::messages::IncomingMessages;
+use crate::orders::TagValue;
+use crate::{server_versions, AsyncClient, Error};
+
+// Requests realtime bars.
+pub(crate) async fn realtime_bars_async<'a>(
+    client: &'a AsyncClient,
+    contract: &Contract,
+    bar_size: &BarSize,
+    what_to_show: &WhatToShow,
+    use_rth: bool,
+    options: Vec<TagValue>,
+) -> Result<RealTimeBarStream<'a>, Error> {
+    client.check_server_version(server_versions::REAL_TIME_BARS, "It does not support real time bars.")?;
+
+    if !contract.trading_class.is_empty() || contract.contract_id > 0 {
+        client.check_server_version(
+            server_versions::TRADING_CLASS,
+            "It does not support ConId nor TradingClass parameters in reqRealTimeBars.",
+        )?;
+    }
+
+    let request_id = client.next_request_id();
+    let packet = encoders::encode_request_realtime_bars(client.server_version(), request_id, contract, bar_size, what_to_show, use_rth, options)?;
+
+    let responses = client.send_request(request_id, packet).await?;
+
+    Ok(RealTimeBarStream {
+        client,
+        request_id,
+        responses,
+    })
+}
+
+/// Stream of realtime [Bar]s returned by [AsyncClient::realtime_bars]. Dropping the stream cancels the subscription.
+pub struct RealTimeBarStream<'a> {
+    client: &'a AsyncClient,
+    request_id: i32,
+    responses: ResponseStream,
+}
+
+impl Stream for RealTimeBarStream<'_> {
+    type Item = Bar;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.responses).poll_next(cx) {
+                Poll::Ready(Some(mut message)) => match message.message_type() {
+                    IncomingMessages::RealTimeBars => match decoders::decode_realtime_bar(&mut message) {
+                        Ok(bar) => return Poll::Ready(Some(bar)),
+                        Err(e) => error!("error decoding realtime bar {message:?}: {e:?}"),
+                    },
+                    _ => error!("unexpected message {message:?}"),
+                },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for RealTimeBarStream<'_> {
+    fn drop(&mut self) {
+        match encoders::cancel_realtime_bars(self.request_id) {
+            Ok(message) => self.client.send_detached(message),
+            Err(e) => error!("error cancelling realtime bars: {e}"),
+        }
+    }
+}
@@ -1,7 +1,108 @@
+use std::collections::HashMap;
+
 use crate::messages::ResponseMessage;
-use crate::Error;
+use crate::{server_versions, Error};
+
+use super::{
+    Bar, BidAsk, BidAskAttribute, DepthOperation, DepthSide, DepthUpdate, MidPoint, TickAttrib, TickGeneric, TickPrice, TickSize, TickString,
+    TickType, Trade, TradeAttribute,
+};
+
+// Delayed market data is sent with its own tick ids. Maps them onto the tick type of live data, flagging the tick as delayed.
+fn decode_tick_type(tick_id: i32) -> (TickType, bool) {
+    let live_tick_id = match tick_id {
+        66 => 1,  // delayed bid
+        67 => 2,  // delayed ask
+        68 => 4,  // delayed last
+        69 => 0,  // delayed bid size
+        70 => 3,  // delayed ask size
+        71 => 5,  // delayed last size
+        72 => 6,  // delayed high
+        73 => 7,  // delayed low
+        74 => 8,  // delayed volume
+        75 => 9,  // delayed close
+        76 => 14, // delayed open
+        88 => 45, // delayed last timestamp
+        _ => return (TickType::from(tick_id), false),
+    };
+
+    (TickType::from(live_tick_id), true)
+}
+
+pub(crate) fn decode_tick_price(server_version: i32, message: &mut ResponseMessage) -> Result<TickPrice, Error> {
+    message.skip(); // message type
+
+    let message_version = message.next_int()?;
+    message.skip(); // message request id
+
+    let (tick_type, delayed) = decode_tick_type(message.next_int()?);
+    let price = message.next_double()?;
+
+    let size = if message_version >= 2 { message.next_double()? } else { 0.0 };
 
-use super::{Bar, BidAsk, BidAskAttribute, MidPoint, Trade, TradeAttribute};
+    let mut attributes = TickAttrib::default();
+    if message_version >= 3 {
+        let mask = message.next_int()?;
+
+        if server_version >= server_versions::PAST_LIMIT {
+            attributes.can_auto_execute = mask & 0x1 != 0;
+            attributes.past_limit = mask & 0x2 != 0;
+
+            if server_version >= server_versions::PRE_OPEN_BID_ASK {
+                attributes.pre_open = mask & 0x4 != 0;
+            }
+        } else {
+            attributes.can_auto_execute = mask == 1;
+        }
+    }
+
+    Ok(TickPrice {
+        tick_type,
+        price,
+        size,
+        attributes,
+        delayed,
+    })
+}
+
+pub(crate) fn decode_tick_size(message: &mut ResponseMessage) -> Result<TickSize, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // message request id
+
+    let (tick_type, delayed) = decode_tick_type(message.next_int()?);
+
+    Ok(TickSize {
+        tick_type,
+        size: message.next_double()?,
+        delayed,
+    })
+}
+
+pub(crate) fn decode_tick_string(message: &mut ResponseMessage) -> Result<TickString, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // message request id
+
+    let (tick_type, delayed) = decode_tick_type(message.next_int()?);
+
+    Ok(TickString {
+        tick_type,
+        value: message.next_string()?,
+        delayed,
+    })
+}
+
+pub(crate) fn decode_tick_generic(message: &mut ResponseMessage) -> Result<TickGeneric, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // message request id
+
+    Ok(TickGeneric {
+        tick_type: TickType::from(message.next_int()?),
+        value: message.next_double()?,
+    })
+}
 
 pub(crate) fn decode_realtime_bar(message: &mut ResponseMessage) -> Result<Bar, Error> {
     message.skip(); // message type
@@ -20,6 +121,44 @@ pub(crate) fn decode_realtime_bar(message: &mut ResponseMessage) -> Result<Bar,
     })
 }
 
+pub(crate) fn decode_market_depth(message: &mut ResponseMessage) -> Result<DepthUpdate, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // message request id
+
+    Ok(DepthUpdate {
+        position: message.next_int()?,
+        market_maker: String::default(),
+        operation: DepthOperation::from(message.next_int()?)?,
+        side: DepthSide::from(message.next_int()?)?,
+        price: message.next_double()?,
+        size: message.next_double()?,
+        smart_depth: false,
+    })
+}
+
+pub(crate) fn decode_market_depth_l2(server_version: i32, message: &mut ResponseMessage) -> Result<DepthUpdate, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // message request id
+
+    let mut update = DepthUpdate {
+        position: message.next_int()?,
+        market_maker: message.next_string()?,
+        operation: DepthOperation::from(message.next_int()?)?,
+        side: DepthSide::from(message.next_int()?)?,
+        price: message.next_double()?,
+        size: message.next_double()?,
+        smart_depth: false,
+    };
+
+    if server_version >= server_versions::SMART_DEPTH {
+        update.smart_depth = message.next_bool()?;
+    }
+
+    Ok(update)
+}
+
 pub(crate) fn trade_tick(message: &mut ResponseMessage) -> Result<Trade, Error> {
     message.skip(); // message type
     message.skip(); // message request id
@@ -94,6 +233,25 @@ pub(crate) fn mid_point_tick(message: &mut ResponseMessage) -> Result<MidPoint,
     })
 }
 
+// Decodes the map of bit number to exchange letter and exchange name.
+pub(crate) fn decode_smart_components(message: &mut ResponseMessage) -> Result<HashMap<i32, (char, String)>, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let count = message.next_int()?;
+    let mut components = HashMap::with_capacity(count.max(0) as usize);
+
+    for _ in 0..count {
+        let bit_number = message.next_int()?;
+        let exchange = message.next_string()?;
+        let exchange_letter = message.next_string()?.chars().next().unwrap_or_default();
+
+        components.insert(bit_number, (exchange_letter, exchange));
+    }
+
+    Ok(components)
+}
+
 #[cfg(test)]
 mod tests {
     use time::OffsetDateTime;
@@ -139,6 +297,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_tick_price_attributes() {
+        let mut message = ResponseMessage::from("1\06\09000\01\0185.50\0300\02\0");
+
+        let results = decode_tick_price(server_versions::SIZE_RULES, &mut message);
+
+        if let Ok(tick) = results {
+            assert_eq!(tick.tick_type, TickType::Bid, "tick.tick_type");
+            assert_eq!(tick.attributes.can_auto_execute, false, "tick.attributes.can_auto_execute");
+            assert_eq!(tick.attributes.past_limit, true, "tick.attributes.past_limit");
+            assert_eq!(tick.attributes.pre_open, false, "tick.attributes.pre_open");
+        } else if let Err(err) = results {
+            assert!(false, "error decoding tick price: {err}");
+        }
+
+        // before PAST_LIMIT the mask only flags whether the tick can auto execute
+        let mut message = ResponseMessage::from("1\06\09000\01\0185.50\0300\01\0");
+
+        match decode_tick_price(server_versions::PAST_LIMIT - 1, &mut message) {
+            Ok(tick) => assert_eq!(
+                tick.attributes,
+                TickAttrib {
+                    can_auto_execute: true,
+                    past_limit: false,
+                    pre_open: false
+                },
+                "tick.attributes"
+            ),
+            Err(err) => assert!(false, "error decoding tick price: {err}"),
+        }
+    }
+
+    #[test]
+    fn decode_delayed_ticks() {
+        let mut message = ResponseMessage::from("1\06\09000\066\0185.50\0300\00\0");
+        match decode_tick_price(server_versions::SIZE_RULES, &mut message) {
+            Ok(tick) => {
+                assert_eq!(tick.tick_type, TickType::Bid, "tick.tick_type");
+                assert_eq!(tick.price, 185.50, "tick.price");
+                assert_eq!(tick.delayed, true, "tick.delayed");
+            }
+            Err(err) => assert!(false, "error decoding tick price: {err}"),
+        }
+
+        let mut message = ResponseMessage::from("1\06\09000\01\0185.50\0300\00\0");
+        match decode_tick_price(server_versions::SIZE_RULES, &mut message) {
+            Ok(tick) => assert_eq!(tick.delayed, false, "tick.delayed"),
+            Err(err) => assert!(false, "error decoding tick price: {err}"),
+        }
+
+        let mut message = ResponseMessage::from("2\06\09000\070\0400\0");
+        match decode_tick_size(&mut message) {
+            Ok(tick) => {
+                assert_eq!(tick.tick_type, TickType::AskSize, "tick.tick_type");
+                assert_eq!(tick.size, 400.0, "tick.size");
+                assert_eq!(tick.delayed, true, "tick.delayed");
+            }
+            Err(err) => assert!(false, "error decoding tick size: {err}"),
+        }
+
+        let mut message = ResponseMessage::from("46\06\09000\088\01678745793\0");
+        match decode_tick_string(&mut message) {
+            Ok(tick) => {
+                assert_eq!(tick.tick_type, TickType::LastTimestamp, "tick.tick_type");
+                assert_eq!(tick.delayed, true, "tick.delayed");
+            }
+            Err(err) => assert!(false, "error decoding tick string: {err}"),
+        }
+
+        let delayed_ticks = [
+            (66, TickType::Bid),
+            (67, TickType::Ask),
+            (68, TickType::Last),
+            (69, TickType::BidSize),
+            (70, TickType::AskSize),
+            (71, TickType::LastSize),
+            (72, TickType::High),
+            (73, TickType::Low),
+            (74, TickType::Volume),
+            (75, TickType::Close),
+            (76, TickType::Open),
+        ];
+        for (tick_id, tick_type) in delayed_ticks {
+            assert_eq!(decode_tick_type(tick_id), (tick_type, true), "tick id {tick_id}");
+        }
+    }
+
     #[test]
     fn decode_mid_point() {
         let mut message = ResponseMessage::from("99\09000\04\01678746113\03896.875\0");
@@ -1,4 +1,4 @@
-use super::{BarSize, WhatToShow};
+use super::{BarSize, MarketDataType, WhatToShow};
 use crate::contracts::Contract;
 use crate::messages::OutgoingMessages;
 use crate::messages::RequestMessage;
@@ -52,6 +52,178 @@ pub(crate) fn encode_request_realtime_bars(
     Ok(packet)
 }
 
+pub(crate) fn encode_request_market_data(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    generic_ticks: &[&str],
+    snapshot: bool,
+    regulatory_snapshot: bool,
+) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 11;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestMarketData);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    if server_version >= server_versions::REQ_MKT_DATA_CONID {
+        message.push_field(&contract.contract_id);
+    }
+
+    message.push_field(&contract.symbol);
+    message.push_field(&contract.security_type);
+    message.push_field(&contract.last_trade_date_or_contract_month);
+    message.push_field(&contract.strike);
+    message.push_field(&contract.right);
+    message.push_field(&contract.multiplier);
+    message.push_field(&contract.exchange);
+    message.push_field(&contract.primary_exchange);
+    message.push_field(&contract.currency);
+    message.push_field(&contract.local_symbol);
+
+    if server_version >= server_versions::TRADING_CLASS {
+        message.push_field(&contract.trading_class);
+    }
+
+    if contract.is_bag() {
+        message.push_field(&contract.combo_legs.len());
+
+        for leg in &contract.combo_legs {
+            message.push_field(&leg.contract_id);
+            message.push_field(&leg.ratio);
+            message.push_field(&leg.action);
+            message.push_field(&leg.exchange);
+        }
+    }
+
+    if server_version >= server_versions::DELTA_NEUTRAL {
+        if let Some(delta_neutral_contract) = &contract.delta_neutral_contract {
+            message.push_field(&true);
+            message.push_field(&delta_neutral_contract.contract_id);
+            message.push_field(&delta_neutral_contract.delta);
+            message.push_field(&delta_neutral_contract.price);
+        } else {
+            message.push_field(&false);
+        }
+    }
+
+    message.push_field(&generic_ticks.join(","));
+    message.push_field(&snapshot);
+
+    if server_version >= server_versions::REQ_SMART_COMPONENTS {
+        message.push_field(&regulatory_snapshot);
+    }
+
+    if server_version >= server_versions::LINKING {
+        message.push_field(&""); // market data options -- internal use only
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn encode_cancel_market_data(request_id: i32) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 2;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelMarketData);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+pub(crate) fn encode_request_market_data_type(market_data_type: MarketDataType) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestMarketDataType);
+    message.push_field(&VERSION);
+    message.push_field(&market_data_type);
+
+    Ok(message)
+}
+
+pub(crate) fn encode_request_smart_components(request_id: i32, bbo_exchange: &str) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestSmartComponents);
+    message.push_field(&request_id);
+    message.push_field(&bbo_exchange);
+
+    Ok(message)
+}
+
+pub(crate) fn encode_request_market_depth(
+    server_version: i32,
+    request_id: i32,
+    contract: &Contract,
+    number_of_rows: i32,
+    is_smart_depth: bool,
+) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 5;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestMarketDepth);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    if server_version >= server_versions::TRADING_CLASS {
+        message.push_field(&contract.contract_id);
+    }
+
+    message.push_field(&contract.symbol);
+    message.push_field(&contract.security_type);
+    message.push_field(&contract.last_trade_date_or_contract_month);
+    message.push_field(&contract.strike);
+    message.push_field(&contract.right);
+    message.push_field(&contract.multiplier);
+    message.push_field(&contract.exchange);
+
+    if server_version >= server_versions::MKT_DEPTH_PRIM_EXCHANGE {
+        message.push_field(&contract.primary_exchange);
+    }
+
+    message.push_field(&contract.currency);
+    message.push_field(&contract.local_symbol);
+
+    if server_version >= server_versions::TRADING_CLASS {
+        message.push_field(&contract.trading_class);
+    }
+
+    message.push_field(&number_of_rows);
+
+    if server_version >= server_versions::SMART_DEPTH {
+        message.push_field(&is_smart_depth);
+    }
+
+    if server_version >= server_versions::LINKING {
+        message.push_field(&""); // market depth options
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn encode_cancel_market_depth(server_version: i32, request_id: i32, is_smart_depth: bool) -> Result<RequestMessage, Error> {
+    const VERSION: i32 = 1;
+
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelMarketDepth);
+    message.push_field(&VERSION);
+    message.push_field(&request_id);
+
+    if server_version >= server_versions::SMART_DEPTH {
+        message.push_field(&is_smart_depth);
+    }
+
+    Ok(message)
+}
+
 pub(crate) fn cancel_realtime_bars(request_id: i32) -> Result<RequestMessage, Error> {
     const VERSION: i32 = 1;
 
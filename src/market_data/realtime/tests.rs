@@ -46,7 +46,7 @@ fn realtime_bars() {
     // Should trigger cancel realtime bars
     drop(bars);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     // Verify Requests
     let realtime_bars_request = &request_messages[0];
@@ -88,6 +88,93 @@ fn realtime_bars() {
     assert_eq!(cancel_request[2], "9000", "message.request_id");
 }
 
+#[test]
+fn realtime_bars_stream() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "50|3|9000|1678323335|4028.75|4029.00|4028.25|4028.50|2|4026.75|1|".to_owned(),
+            "50|3|9000|1678323340|4028.50|4028.75|4028.25|4028.25|5|4028.50|3|".to_owned(),
+            "50|3|9000|1678323345|4028.25|4028.50|4027.75|4028.00|4|4028.10|2|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = contract_samples::future_with_local_symbol();
+
+    let bars = client.realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, true);
+    assert!(bars.is_ok(), "failed to request realtime bars: {}", bars.err().unwrap());
+
+    let bars: Vec<Bar> = bars.unwrap().collect();
+    assert_eq!(bars.len(), 3, "bars.len()");
+
+    for pair in bars.windows(2) {
+        assert!(
+            pair[0].date < pair[1].date,
+            "bar timestamps should increase: {} >= {}",
+            pair[0].date,
+            pair[1].date
+        );
+    }
+
+    assert_eq!(bars[2].close, 4028.00, "bars[2].close");
+    assert_eq!(bars[2].volume, 4.0, "bars[2].volume");
+    assert_eq!(bars[2].count, 2, "bars[2].count");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+    assert_eq!(request_messages[1].encode_simple(), "51|1|9000|", "cancel realtime bars");
+}
+
+#[test]
+fn realtime_bars_end_when_connection_lost() {
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::stubs::{accept_handshake, read_frame, write_frame};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // Sends a bar and closes the connection, then accepts the reconnect.
+    let server = thread::spawn(move || {
+        let (mut stream, _) = accept_handshake(&listener);
+        write_frame(&mut stream, "9|1|90|");
+        write_frame(&mut stream, "15|1|DU1234567|");
+
+        read_frame(&mut stream); // realtime bars request
+        write_frame(&mut stream, "50|3|9000|1678323335|4028.75|4029.00|4028.25|4028.50|2|4026.75|1|");
+        drop(stream);
+
+        let (stream, _) = accept_handshake(&listener);
+        stream
+    });
+
+    let client = Client::builder().address(&address).client_id(100).connect().expect("connection failed");
+
+    let contract = contract_samples::future_with_local_symbol();
+    let mut bars = client
+        .realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, true)
+        .expect("failed to request realtime bars");
+
+    match bars.next_timeout(Duration::from_secs(5)) {
+        Ok(Some(bar)) => assert_eq!(bar.close, 4028.50, "bar.close"),
+        result => assert!(false, "expected a real time bar, got {result:?}"),
+    }
+
+    // the request is not resent after reconnecting, so the consumer has to subscribe again
+    match bars.next_timeout(Duration::from_secs(5)) {
+        Err(Error::ConnectionReset) => (),
+        result => assert!(false, "expected connection reset, got {result:?}"),
+    }
+    assert!(bars.next().is_none(), "no bars after the connection was reset");
+
+    let _stream = server.join().unwrap();
+}
+
 #[test]
 fn what_to_show() {
     assert_eq!(WhatToShow::Trades.to_string(), "TRADES");
@@ -95,3 +182,482 @@ fn what_to_show() {
     assert_eq!(WhatToShow::Bid.to_string(), "BID");
     assert_eq!(WhatToShow::Ask.to_string(), "ASK");
 }
+
+#[test]
+fn market_data_lines_limit_subscriptions() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let mut client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.max_market_data_lines = Some(2);
+
+    let contract = Contract::stock("AAPL");
+
+    let first = client.market_data(&contract, &[], false, false).expect("first subscription failed");
+    let second = client.market_depth(&contract, 5, false).expect("second subscription failed");
+    assert_eq!(client.active_market_data_lines(), 2, "active_market_data_lines()");
+
+    let third = client.market_data(&contract, &[], false, false);
+    assert!(
+        matches!(third, Err(Error::TooManyLines { limit: 2 })),
+        "expected too many lines error: {:?}",
+        third.err()
+    );
+    assert_eq!(
+        client.message_bus.lock().request_messages().len(),
+        2,
+        "refused request should not be sent"
+    );
+
+    drop(first);
+    assert_eq!(client.active_market_data_lines(), 1, "active_market_data_lines() after drop");
+
+    let third = client.market_data(&contract, &[], false, false);
+    assert!(third.is_ok(), "failed to subscribe after a line was released: {:?}", third.err());
+
+    drop(second);
+    drop(third);
+    assert_eq!(client.active_market_data_lines(), 0, "active_market_data_lines() after all dropped");
+}
+
+#[test]
+fn market_data() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "81|9000|0.01|9c0001|3|".to_owned(),
+            "1|6|9000|1|185.50|300|1|".to_owned(),
+            "2|6|9000|0|300|".to_owned(),
+            "46|6|9000|45|1678323335|".to_owned(),
+            "45|6|9000|46|3.0|".to_owned(),
+            "57|1|9000|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let ticks = client.market_data(&contract, &["233", "236"], false, false);
+    assert!(ticks.is_ok(), "failed to request market data: {}", ticks.err().unwrap());
+
+    let ticks: Vec<TickTypes> = ticks.unwrap().collect();
+    assert_eq!(ticks.len(), 4, "ticks.len()");
+
+    if let TickTypes::Price(tick) = &ticks[0] {
+        assert_eq!(tick.tick_type, TickType::Bid, "tick.tick_type");
+        assert_eq!(tick.price, 185.50, "tick.price");
+        assert_eq!(tick.size, 300.0, "tick.size");
+        assert_eq!(tick.attributes.can_auto_execute, true, "tick.attributes.can_auto_execute");
+    } else {
+        assert!(false, "expected tick price, got: {:?}", ticks[0]);
+    }
+
+    if let TickTypes::Size(tick) = &ticks[1] {
+        assert_eq!(tick.tick_type, TickType::BidSize, "tick.tick_type");
+        assert_eq!(tick.size, 300.0, "tick.size");
+    } else {
+        assert!(false, "expected tick size, got: {:?}", ticks[1]);
+    }
+
+    if let TickTypes::String(tick) = &ticks[2] {
+        assert_eq!(tick.tick_type, TickType::LastTimestamp, "tick.tick_type");
+        assert_eq!(tick.value, "1678323335", "tick.value");
+    } else {
+        assert!(false, "expected tick string, got: {:?}", ticks[2]);
+    }
+
+    if let TickTypes::Generic(tick) = &ticks[3] {
+        assert_eq!(tick.tick_type, TickType::Shortable, "tick.tick_type");
+        assert_eq!(tick.value, 3.0, "tick.value");
+    } else {
+        assert!(false, "expected tick generic, got: {:?}", ticks[3]);
+    }
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "1|11|9000|0|AAPL|STK||0|||SMART||USD|||0|233,236|0|0||",
+        "market data request"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "2|2|9000|", "cancel market data request");
+}
+
+#[test]
+fn snapshot() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "1|6|9000|1|185.50|300|1|".to_owned(),
+            "1|6|9000|2|185.60|200|1|".to_owned(),
+            "1|6|9000|4|185.55|100|0|".to_owned(),
+            "2|6|9000|0|300|".to_owned(),
+            "2|6|9000|8|25000|".to_owned(),
+            "1|6|9000|9|184.20|0|0|".to_owned(),
+            "1|6|9000|6|186.10|0|0|".to_owned(),
+            "1|6|9000|7|184.00|0|0|".to_owned(),
+            "46|6|9000|45|1678323335|".to_owned(),
+            "57|1|9000|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let snapshot = client.snapshot(&contract).expect("failed to request snapshot");
+
+    assert_eq!(
+        snapshot,
+        Snapshot {
+            bid: Some(185.50),
+            ask: Some(185.60),
+            last: Some(185.55),
+            bid_size: Some(300.0),
+            ask_size: None,
+            volume: Some(25000.0),
+            close: Some(184.20),
+            open: None,
+            high: Some(186.10),
+            low: Some(184.00),
+        }
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "1|11|9000|0|AAPL|STK||0|||SMART||USD|||0||1|0||",
+        "snapshot request"
+    );
+}
+
+#[test]
+fn snapshot_api_error() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["4|2|9000|354|Requested market data is not subscribed.|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    match client.snapshot(&contract) {
+        Err(Error::Api { code, message, .. }) => {
+            assert_eq!(code, 354, "error code");
+            assert_eq!(message, "Requested market data is not subscribed.", "error message");
+        }
+        result => assert!(false, "expected api error, got {result:?}"),
+    }
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 1, "rejected snapshot should not be cancelled");
+}
+
+#[test]
+fn snapshot_without_end() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["1|6|9000|1|185.50|300|1|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let result = client.snapshot(&contract);
+    assert!(result.is_err(), "expected error for snapshot without end, got {result:?}");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[1].encode_simple(), "2|2|9000|", "cancel market data");
+}
+
+#[test]
+fn generic_tick_ids() {
+    let generic_ticks = [
+        GenericTick::OptionVolume,
+        GenericTick::OptionOpenInterest,
+        GenericTick::HistoricalVolatility,
+        GenericTick::OptionImpliedVolatility,
+        GenericTick::MiscellaneousStats,
+        GenericTick::MarkPrice,
+        GenericTick::RtVolume,
+        GenericTick::Shortable,
+        GenericTick::FundamentalRatios,
+    ];
+
+    assert_eq!(GenericTick::to_ids(&generic_ticks), "100,101,104,106,165,221,233,236,258");
+    assert_eq!(GenericTick::to_ids(&[]), "");
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+    let ids = GenericTick::ids(&[GenericTick::RtVolume, GenericTick::Shortable]);
+    let _ticks = client.market_data(&contract, &ids, false, false).expect("failed to request market data");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "1|11|9000|0|AAPL|STK||0|||SMART||USD|||0|233,236|0|0||",
+        "market data request"
+    );
+}
+
+#[test]
+fn tick_by_tick_all_last() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "99|9000|2|1678740829|3895.25|7|2|NASDAQ||".to_owned(),
+            "99|9000|2|1678740830|3895.50|1|1|ISLAND|I|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let trades = client.tick_by_tick_all_last(&contract, 0, false);
+    assert!(trades.is_ok(), "failed to request tick by tick: {}", trades.err().unwrap());
+
+    let trades: Vec<Trade> = trades.unwrap().collect();
+    assert_eq!(trades.len(), 2, "trades.len()");
+
+    assert_eq!(trades[0].tick_type, "2", "trades[0].tick_type");
+    assert_eq!(trades[0].time, OffsetDateTime::from_unix_timestamp(1678740829).unwrap(), "trades[0].time");
+    assert_eq!(trades[0].price, 3895.25, "trades[0].price");
+    assert_eq!(trades[0].size, 7, "trades[0].size");
+    assert!(!trades[0].trade_attribute.past_limit, "trades[0].trade_attribute.past_limit");
+    assert!(trades[0].trade_attribute.unreported, "trades[0].trade_attribute.unreported");
+    assert_eq!(trades[0].exchange, "NASDAQ", "trades[0].exchange");
+    assert_eq!(trades[0].special_conditions, "", "trades[0].special_conditions");
+
+    assert!(trades[1].trade_attribute.past_limit, "trades[1].trade_attribute.past_limit");
+    assert!(!trades[1].trade_attribute.unreported, "trades[1].trade_attribute.unreported");
+    assert_eq!(trades[1].special_conditions, "I", "trades[1].special_conditions");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "97|9000|0|AAPL|STK||0|||SMART||USD|||AllLast|0|0|",
+        "request tick by tick"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "98|9000|", "cancel tick by tick");
+}
+
+#[test]
+fn tick_by_tick_bid_ask() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["99|9000|3|1678745793|3895.50|3896.00|9|11|3|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let bid_asks = client.tick_by_tick_bid_ask(&contract, 0, false);
+    assert!(bid_asks.is_ok(), "failed to request tick by tick: {}", bid_asks.err().unwrap());
+
+    let bid_asks: Vec<BidAsk> = bid_asks.unwrap().collect();
+    assert_eq!(bid_asks.len(), 1, "bid_asks.len()");
+
+    assert_eq!(
+        bid_asks[0].time,
+        OffsetDateTime::from_unix_timestamp(1678745793).unwrap(),
+        "bid_asks[0].time"
+    );
+    assert_eq!(bid_asks[0].bid_price, 3895.50, "bid_asks[0].bid_price");
+    assert_eq!(bid_asks[0].ask_price, 3896.00, "bid_asks[0].ask_price");
+    assert_eq!(bid_asks[0].bid_size, 9, "bid_asks[0].bid_size");
+    assert_eq!(bid_asks[0].ask_size, 11, "bid_asks[0].ask_size");
+    assert!(bid_asks[0].bid_ask_attribute.bid_past_low, "bid_asks[0].bid_ask_attribute.bid_past_low");
+    assert!(bid_asks[0].bid_ask_attribute.ask_past_high, "bid_asks[0].bid_ask_attribute.ask_past_high");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "97|9000|0|AAPL|STK||0|||SMART||USD|||BidAsk|0|0|",
+        "request tick by tick"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "98|9000|", "cancel tick by tick");
+}
+
+#[test]
+fn market_depth() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "12|1|9000|0|0|1|185.50|100|".to_owned(),
+            "13|1|9000|0|NSDQ|0|0|185.60|200|1|".to_owned(),
+            "13|1|9000|1|ARCA|0|0|185.70|300|1|".to_owned(),
+            "13|1|9000|0|NSDQ|1|0|185.65|250|1|".to_owned(),
+            "13|1|9000|1|ARCA|2|0|185.70|300|1|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let updates = client.market_depth(&contract, 5, true);
+    assert!(updates.is_ok(), "failed to request market depth: {}", updates.err().unwrap());
+
+    let updates: Vec<DepthUpdate> = updates.unwrap().collect();
+    assert_eq!(updates.len(), 5, "updates.len()");
+
+    assert_eq!(updates[0].operation, DepthOperation::Insert, "updates[0].operation");
+    assert_eq!(updates[0].side, DepthSide::Bid, "updates[0].side");
+    assert_eq!(updates[0].price, 185.50, "updates[0].price");
+    assert_eq!(updates[0].market_maker, "", "updates[0].market_maker");
+    assert!(!updates[0].smart_depth, "updates[0].smart_depth");
+
+    assert_eq!(updates[1].market_maker, "NSDQ", "updates[1].market_maker");
+    assert_eq!(updates[1].side, DepthSide::Ask, "updates[1].side");
+    assert!(updates[1].smart_depth, "updates[1].smart_depth");
+    assert_eq!(updates[3].operation, DepthOperation::Update, "updates[3].operation");
+    assert_eq!(updates[4].operation, DepthOperation::Delete, "updates[4].operation");
+
+    let mut book = OrderBook::default();
+    for update in &updates {
+        book.apply(update);
+    }
+
+    assert_eq!(
+        book.bids,
+        vec![BookLevel {
+            price: 185.50,
+            size: 100.0,
+            market_maker: "".into()
+        }],
+        "book.bids"
+    );
+    assert_eq!(
+        book.asks,
+        vec![BookLevel {
+            price: 185.65,
+            size: 250.0,
+            market_maker: "NSDQ".into()
+        }],
+        "book.asks"
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "10|5|9000|0|AAPL|STK||0|||SMART||USD|||5|1||",
+        "request market depth"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "11|1|9000|1|", "cancel market depth");
+}
+
+#[test]
+fn set_market_data_type() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    assert_eq!(client.market_data_type(), MarketDataType::Live, "default market data type");
+
+    let result = client.set_market_data_type(MarketDataType::Delayed);
+    assert!(result.is_ok(), "failed to set market data type: {}", result.err().unwrap());
+
+    assert_eq!(client.market_data_type(), MarketDataType::Delayed, "market data type");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "59|1|3|", "request market data type");
+}
+
+#[test]
+fn cancel_subscriptions() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "50|3|9000|1678323335|4028.75|4029.00|4028.25|4028.50|2|4026.75|1|".to_owned(),
+            "50|3|9000|1678323340|4028.50|4028.75|4028.25|4028.25|5|4028.50|3|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = contract_samples::future_with_local_symbol();
+
+    let mut bars = client
+        .realtime_bars(&contract, BarSize::Sec5, WhatToShow::Trades, true)
+        .expect("failed to request realtime bars");
+    assert!(bars.next().is_some(), "expected a real time bar");
+
+    bars.cancel();
+
+    assert!(bars.next().is_none(), "no bars expected after cancel");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+    assert_eq!(request_messages[1].encode_simple(), "51|1|9000|", "cancel realtime bars");
+
+    // cancel is only sent once
+    bars.cancel();
+    drop(bars);
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len() after drop");
+
+    let mut ticks = client.market_data(&contract, &[], false, false).expect("failed to request market data");
+    ticks.cancel();
+
+    assert!(ticks.next().is_none(), "no ticks expected after cancel");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[3].encode_simple(), "2|2|9001|", "cancel market data request");
+}
+
+#[test]
+fn smart_components() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["82|9000|3|0|NYSE|N|1|ARCA|P|5|ISLAND|Q|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.smart_components("a6");
+    assert!(results.is_ok(), "failed to request smart components: {}", results.err().unwrap());
+
+    let components = results.unwrap();
+    assert_eq!(components.len(), 3, "components.len()");
+    assert_eq!(components[&0], ('N', "NYSE".to_owned()), "components[0]");
+    assert_eq!(components[&1], ('P', "ARCA".to_owned()), "components[1]");
+    assert_eq!(components[&5], ('Q', "ISLAND".to_owned()), "components[5]");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "83|9000|a6|", "smart components request");
+}
+
+#[test]
+fn smart_components_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_SMART_COMPONENTS - 1);
+
+    let results = client.smart_components("a6");
+    assert!(matches!(results, Err(Error::ServerVersion(_, _, _))), "expected server version error");
+}
@@ -1 +1,201 @@
-// request_scanner_sub
\ No newline at end of file
+use log::error;
+
+use crate::client::transport::ResponseIterator;
+use crate::contracts::{ContractDetails, TagValue};
+use crate::messages::IncomingMessages;
+use crate::{server_versions, Client, Error};
+
+mod decoders;
+mod encoders;
+#[cfg(test)]
+mod tests;
+
+// === Models ===
+
+/// Defines a market scanner request for [Client::scanner_subscription].
+///
+/// Valid values for `instrument`, `location_code` and `scan_code` can be discovered with [Client::scanner_parameters].
+#[derive(Clone, Debug)]
+pub struct ScannerSubscription {
+    /// The number of rows to be returned for the query. -1 for the server default.
+    pub number_of_rows: i32,
+    /// The instrument's type for the scan, i.e. STK
+    pub instrument: String,
+    /// The request's location, i.e. STK.US.MAJOR
+    pub location_code: String,
+    /// The request's scan type, i.e. TOP_PERC_GAIN
+    pub scan_code: String,
+    /// Filters out contracts which price is below this value
+    pub above_price: Option<f64>,
+    /// Filters out contracts which price is above this value
+    pub below_price: Option<f64>,
+    /// Filters out contracts which volume is below this value
+    pub above_volume: Option<i32>,
+    /// Filters out contracts which market cap is below this value
+    pub market_cap_above: Option<f64>,
+    /// Filters out contracts which market cap is above this value
+    pub market_cap_below: Option<f64>,
+    /// Filters out contracts which Moody's rating is below this value
+    pub moody_rating_above: String,
+    /// Filters out contracts which Moody's rating is above this value
+    pub moody_rating_below: String,
+    /// Filters out contracts with a S&P rating below this value
+    pub sp_rating_above: String,
+    /// Filters out contracts with a S&P rating above this value
+    pub sp_rating_below: String,
+    /// Filter out contracts with a maturity date earlier than this value
+    pub maturity_date_above: String,
+    /// Filter out contracts with a maturity date older than this value
+    pub maturity_date_below: String,
+    /// Filter out contracts with a coupon rate lower than this value
+    pub coupon_rate_above: Option<f64>,
+    /// Filter out contracts with a coupon rate higher than this value
+    pub coupon_rate_below: Option<f64>,
+    /// Filters out convertible bonds
+    pub exclude_convertible: bool,
+    /// Filters out contracts with an average option volume below this value
+    pub average_option_volume_above: Option<i32>,
+    /// Pass comma separated scanner setting pairs, i.e. Annual,true
+    pub scanner_setting_pairs: String,
+    /// Filters out stock types, i.e. ALL, CORP, ADR, ETF, REIT or CEF
+    pub stock_type_filter: String,
+    /// Additional filters, as listed in the scanner parameters XML
+    pub filter_options: Vec<TagValue>,
+}
+
+impl Default for ScannerSubscription {
+    fn default() -> Self {
+        ScannerSubscription {
+            number_of_rows: -1,
+            instrument: String::default(),
+            location_code: String::default(),
+            scan_code: String::default(),
+            above_price: None,
+            below_price: None,
+            above_volume: None,
+            market_cap_above: None,
+            market_cap_below: None,
+            moody_rating_above: String::default(),
+            moody_rating_below: String::default(),
+            sp_rating_above: String::default(),
+            sp_rating_below: String::default(),
+            maturity_date_above: String::default(),
+            maturity_date_below: String::default(),
+            coupon_rate_above: None,
+            coupon_rate_below: None,
+            exclude_convertible: false,
+            average_option_volume_above: None,
+            scanner_setting_pairs: String::default(),
+            stock_type_filter: String::default(),
+            filter_options: Vec::default(),
+        }
+    }
+}
+
+/// A ranked row of a market scan result.
+#[derive(Debug, Default)]
+pub struct ScannerData {
+    /// The ranking within the response of this bar.
+    pub rank: i32,
+    /// The contract matching the scan.
+    pub contract_details: ContractDetails,
+    /// Varies based on query.
+    pub distance: String,
+    /// Varies based on query.
+    pub benchmark: String,
+    /// Varies based on query.
+    pub projection: String,
+    /// Describes the combo legs when the scanner is returning EFP.
+    pub legs: String,
+}
+
+// === API ===
+
+// Requests an XML list of scanner parameters valid in TWS.
+pub(crate) fn scanner_parameters(client: &Client) -> Result<String, Error> {
+    let message = encoders::encode_scanner_parameters()?;
+
+    let mut messages = client.request_scanner_parameters(message)?;
+
+    match messages.next() {
+        Some(mut message) => decoders::decode_scanner_parameters(&mut message),
+        None => Err(Error::Simple("no scanner parameters received".into())),
+    }
+}
+
+// Starts a subscription to market scan results based on the provided parameters.
+pub(crate) fn scanner_subscription<'a>(client: &'a Client, subscription: &ScannerSubscription) -> Result<ScannerDataIterator<'a>, Error> {
+    if !subscription.filter_options.is_empty() {
+        client.check_server_version(
+            server_versions::SCANNER_GENERIC_OPTS,
+            "It does not support API scanner subscription generic filter options.",
+        )?
+    }
+
+    let request_id = client.next_request_id();
+    let message = encoders::encode_scanner_subscription(client.server_version(), request_id, subscription)?;
+
+    let responses = client.send_request(request_id, message)?;
+
+    Ok(ScannerDataIterator {
+        client,
+        request_id,
+        responses,
+        error: None,
+    })
+}
+
+/// ScannerDataIterator supports iteration over market scan results. Each item is a complete, ranked scan result.
+///
+/// Iteration ends when a scan result can't be decoded, see [ScannerDataIterator::error].
+pub struct ScannerDataIterator<'a> {
+    client: &'a Client,
+    request_id: i32,
+    responses: ResponseIterator,
+    error: Option<Error>,
+}
+
+impl<'a> ScannerDataIterator<'a> {
+    /// Returns the error that ended the iteration, if a scan result couldn't be decoded.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+}
+
+impl<'a> Drop for ScannerDataIterator<'a> {
+    // Ensures scanner subscription is cancelled
+    fn drop(&mut self) {
+        let message = encoders::encode_cancel_scanner_subscription(self.request_id).unwrap();
+        if let Err(e) = self.client.send_message(message) {
+            error!("error cancelling scanner subscription: {e}");
+        }
+    }
+}
+
+impl<'a> Iterator for ScannerDataIterator<'a> {
+    type Item = Vec<ScannerData>;
+
+    /// Advances the iterator and returns the next scan result. Results are streamed as the scan is refreshed, until the iterator is dropped.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        loop {
+            match self.responses.next() {
+                Some(mut message) => match message.message_type() {
+                    IncomingMessages::ScannerData => match decoders::decode_scanner_data(&mut message) {
+                        Ok(rows) => return Some(rows),
+                        Err(e) => {
+                            error!("error decoding scanner data {message:?}: {e:?}");
+                            self.error = Some(e);
+                            return None;
+                        }
+                    },
+                    _ => error!("unexpected message {message:?}"),
+                },
+                None => return None,
+            }
+        }
+    }
+}
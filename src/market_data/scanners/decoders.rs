@@ -0,0 +1,63 @@
+use crate::contracts::{Contract, ContractDetails, SecurityType};
+use crate::messages::ResponseMessage;
+use crate::Error;
+
+use super::ScannerData;
+
+pub(super) fn decode_scanner_parameters(message: &mut ResponseMessage) -> Result<String, Error> {
+    message.skip(); // message type
+    message.skip(); // message version
+
+    message.next_string()
+}
+
+pub(super) fn decode_scanner_data(message: &mut ResponseMessage) -> Result<Vec<ScannerData>, Error> {
+    message.skip(); // message type
+
+    let message_version = message.next_int()?;
+    message.skip(); // request id
+
+    let number_of_elements = message.next_int()?;
+    let mut rows = Vec::with_capacity(number_of_elements.max(0) as usize);
+
+    for _ in 0..number_of_elements {
+        let rank = message.next_int()?;
+
+        let mut contract = Contract::default();
+        if message_version >= 3 {
+            contract.contract_id = message.next_int()?;
+        }
+        contract.symbol = message.next_string()?;
+        contract.security_type = SecurityType::from(&message.next_string()?);
+        contract.last_trade_date_or_contract_month = message.next_string()?;
+        contract.strike = message.next_double()?;
+        contract.right = message.next_string()?;
+        contract.exchange = message.next_string()?;
+        contract.currency = message.next_string()?;
+        contract.local_symbol = message.next_string()?;
+
+        let market_name = message.next_string()?;
+        contract.trading_class = message.next_string()?;
+
+        let mut row = ScannerData {
+            rank,
+            contract_details: ContractDetails {
+                contract,
+                market_name,
+                ..Default::default()
+            },
+            distance: message.next_string()?,
+            benchmark: message.next_string()?,
+            projection: message.next_string()?,
+            ..Default::default()
+        };
+
+        if message_version >= 2 {
+            row.legs = message.next_string()?;
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
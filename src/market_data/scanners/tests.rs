@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+
+use crate::contracts::SecurityType;
+use crate::stubs::MessageBusStub;
+
+use super::*;
+
+#[test]
+fn scanner_parameters() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><ScanParameterResponse><InstrumentList varName="instrumentList"><Instrument><name>US Stocks</name><type>STK</type></Instrument></InstrumentList></ScanParameterResponse>"#;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![format!("19|1|{xml}|")],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.scanner_parameters();
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "24|1|", "scanner parameters request");
+
+    match results {
+        Ok(parameters) => assert_eq!(parameters, xml, "scanner parameters"),
+        Err(err) => assert!(false, "failed to request scanner parameters: {err}"),
+    }
+}
+
+#[test]
+fn scanner_subscription() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "20|3|9000|3|0|76792991|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS|||||1|265598|AAPL|STK||0||SMART|USD|AAPL|NMS|NMS|||||2|272093|MSFT|STK||0||SMART|USD|MSFT|NMS|NMS|||||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let subscription = ScannerSubscription {
+        number_of_rows: 3,
+        instrument: "STK".to_string(),
+        location_code: "STK.US.MAJOR".to_string(),
+        scan_code: "TOP_PERC_GAIN".to_string(),
+        above_price: Some(5.0),
+        ..Default::default()
+    };
+
+    let results = client.scanner_subscription(&subscription);
+    assert!(results.is_ok(), "failed to request scanner subscription: {}", results.err().unwrap());
+
+    let mut results = results.unwrap();
+    let scans: Vec<Vec<ScannerData>> = results.by_ref().collect();
+    assert_eq!(scans.len(), 1, "scans.len()");
+    assert!(results.error().is_none(), "unexpected error: {:?}", results.error());
+
+    let rows = &scans[0];
+    assert_eq!(rows.len(), 3, "rows.len()");
+
+    assert_eq!(rows[0].rank, 0, "rows[0].rank");
+    assert_eq!(rows[0].contract_details.contract.contract_id, 76792991, "rows[0].contract.contract_id");
+    assert_eq!(rows[0].contract_details.contract.symbol, "TSLA", "rows[0].contract.symbol");
+    assert_eq!(
+        rows[0].contract_details.contract.security_type,
+        SecurityType::Stock,
+        "rows[0].contract.security_type"
+    );
+    assert_eq!(rows[0].contract_details.contract.exchange, "SMART", "rows[0].contract.exchange");
+    assert_eq!(rows[0].contract_details.contract.trading_class, "NMS", "rows[0].contract.trading_class");
+    assert_eq!(rows[0].contract_details.market_name, "NMS", "rows[0].market_name");
+
+    assert_eq!(rows[1].rank, 1, "rows[1].rank");
+    assert_eq!(rows[1].contract_details.contract.symbol, "AAPL", "rows[1].contract.symbol");
+
+    assert_eq!(rows[2].rank, 2, "rows[2].rank");
+    assert_eq!(rows[2].contract_details.contract.contract_id, 272093, "rows[2].contract.contract_id");
+    assert_eq!(rows[2].contract_details.contract.symbol, "MSFT", "rows[2].contract.symbol");
+
+    drop(results);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "22|9000|3|STK|STK.US.MAJOR|TOP_PERC_GAIN|5|||||||||||||0||||||",
+        "scanner subscription request"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "23|1|9000|", "cancel scanner subscription request");
+}
+
+#[test]
+fn scanner_subscription_decode_error() {
+    // the row is missing the legs field
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "20|3|9000|2|0|76792991|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS||||1|265598|AAPL|STK||0||SMART|USD|AAPL|NMS|NMS|||||".to_owned(),
+            "20|3|9000|1|0|76792991|TSLA|STK||0||SMART|USD|TSLA|NMS|NMS|||||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let mut results = client
+        .scanner_subscription(&ScannerSubscription::default())
+        .expect("failed to request scanner subscription");
+
+    assert!(results.next().is_none(), "expected iteration to end on decode error");
+    assert!(results.error().is_some(), "expected decode error");
+    assert!(results.next().is_none(), "expected iteration to stay ended");
+}
@@ -3,15 +3,18 @@ use std::str::{self, FromStr};
 
 use time::OffsetDateTime;
 
-use crate::{Error, ToField};
+use crate::orders::AdvancedOrderReject;
+use crate::{server_versions, Error, ToField};
 
 const INFINITY_STR: &str = "Infinity";
 const UNSET_DOUBLE: &str = "1.7976931348623157E308";
 const UNSET_INTEGER: &str = "2147483647";
 const UNSET_LONG: &str = "9223372036854775807";
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum IncomingMessages {
+    /// Sentinel delivered to active subscriptions when the connection to TWS is lost. Never sent by TWS.
+    ConnectionLost = -2,
     NotValid = -1,
     TickPrice = 1,
     TickSize = 2,
@@ -100,6 +103,7 @@ pub enum IncomingMessages {
 impl From<i32> for IncomingMessages {
     fn from(value: i32) -> IncomingMessages {
         match value {
+            -2 => IncomingMessages::ConnectionLost,
             1 => IncomingMessages::TickPrice,
             2 => IncomingMessages::TickSize,
             3 => IncomingMessages::OrderStatus,
@@ -195,24 +199,123 @@ pub fn order_id_index(kind: IncomingMessages) -> Option<usize> {
     }
 }
 
-pub fn request_id_index(kind: IncomingMessages) -> Option<usize> {
+// Index of the request id field, or None for messages that are not routed by request id.
+pub(crate) fn find_request_id_index(kind: &IncomingMessages, server_version: i32) -> Option<usize> {
     match kind {
+        // sent with a message version ahead of the request id before PRICE_BASED_VOLATILITY
+        IncomingMessages::TickOptionComputation if server_version < server_versions::PRICE_BASED_VOLATILITY => Some(2),
         IncomingMessages::ContractData
         | IncomingMessages::TickByTick
         | IncomingMessages::SymbolSamples
         | IncomingMessages::OpenOrder
         | IncomingMessages::ExecutionData
         | IncomingMessages::HeadTimestamp
+        | IncomingMessages::HistogramData
         | IncomingMessages::HistoricalData
         | IncomingMessages::HistoricalSchedule
         | IncomingMessages::HistoricalTick
         | IncomingMessages::HistoricalTickBidAsk
-        | IncomingMessages::HistoricalTickLast => Some(1),
-        IncomingMessages::ContractDataEnd | IncomingMessages::RealTimeBars | IncomingMessages::Error | IncomingMessages::ExecutionDataEnd => Some(2),
-        _ => panic!("could not determine request id index for {kind:?}"),
+        | IncomingMessages::HistoricalTickLast
+        | IncomingMessages::TickReqParams
+        | IncomingMessages::PnL
+        | IncomingMessages::PnLSingle
+        | IncomingMessages::SecurityDefinitionOptionParameter
+        | IncomingMessages::SecurityDefinitionOptionParameterEnd
+        | IncomingMessages::TickOptionComputation
+        | IncomingMessages::HistoricalNews
+        | IncomingMessages::HistoricalNewsEnd
+        | IncomingMessages::NewsArticle
+        | IncomingMessages::SmartComponents
+        | IncomingMessages::WshMetaData
+        | IncomingMessages::WshEventData => Some(1),
+        IncomingMessages::ContractDataEnd
+        | IncomingMessages::RealTimeBars
+        | IncomingMessages::Error
+        | IncomingMessages::ExecutionDataEnd
+        | IncomingMessages::TickPrice
+        | IncomingMessages::TickSize
+        | IncomingMessages::Tickstring
+        | IncomingMessages::TickGeneric
+        | IncomingMessages::TickEFP
+        | IncomingMessages::TickSnapshotEnd
+        | IncomingMessages::MarketDataType
+        | IncomingMessages::AccountSummary
+        | IncomingMessages::AccountSummaryEnd
+        | IncomingMessages::MarketDepth
+        | IncomingMessages::MarketDepthL2
+        | IncomingMessages::FundamentalData
+        | IncomingMessages::ScannerData => Some(2),
+        _ => None,
     }
 }
 
+// Decodes the backslash escapes TWS uses for non ASCII text, mirroring Regex.Unescape in the C# client. e.g. \u00e9 -> é
+pub(crate) fn unescape(text: &str) -> String {
+    if !text.contains('\\') {
+        return text.to_owned();
+    }
+
+    let mut unescaped = String::with_capacity(text.len());
+    let mut units: Vec<u16> = Vec::new(); // pending \uXXXX code units, decoded together so surrogate pairs combine
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            flush_code_units(&mut unescaped, &mut units);
+            unescaped.push(c);
+            continue;
+        }
+
+        let escaped = chars.next();
+
+        if escaped == Some('u') {
+            let hex: String = chars.by_ref().take(4).collect();
+            match u16::from_str_radix(&hex, 16) {
+                Ok(unit) if hex.len() == 4 => units.push(unit),
+                _ => {
+                    flush_code_units(&mut unescaped, &mut units);
+                    unescaped.push_str("\\u");
+                    unescaped.push_str(&hex);
+                }
+            }
+            continue;
+        }
+
+        flush_code_units(&mut unescaped, &mut units);
+
+        match escaped {
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => unescaped.push(char::from(byte)),
+                    _ => {
+                        unescaped.push_str("\\x");
+                        unescaped.push_str(&hex);
+                    }
+                }
+            }
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\x0C'),
+            Some('v') => unescaped.push('\x0B'),
+            Some('a') => unescaped.push('\x07'),
+            Some('e') => unescaped.push('\x1B'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    flush_code_units(&mut unescaped, &mut units);
+
+    unescaped
+}
+
+fn flush_code_units(unescaped: &mut String, units: &mut Vec<u16>) {
+    let decoded = char::decode_utf16(units.drain(..)).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER));
+    unescaped.extend(decoded);
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Copy, Clone)]
 pub enum OutgoingMessages {
@@ -304,8 +407,24 @@ impl ToField for OutgoingMessages {
     }
 }
 
+/// Fields of a message sent to servers from version `.0` up to, but excluding, version `.1`.
+pub(crate) type FieldSpan = (i32, i32, usize);
+
+/// Upper bound of a [FieldSpan] still sent to the latest server versions.
+pub(crate) const NOT_REMOVED: i32 = i32::MAX;
+
+// Number of fields of a message layout sent to server_version.
+pub(crate) fn layout_field_count(layout: &[FieldSpan], server_version: i32) -> usize {
+    layout
+        .iter()
+        .filter(|(from, until, _)| (*from..*until).contains(&server_version))
+        .map(|(_, _, fields)| fields)
+        .sum()
+}
+
+/// Message sent to TWS or Gateway. See [MessageObserver](crate::client::MessageObserver).
 #[derive(Default, Debug, Clone)]
-pub(crate) struct RequestMessage {
+pub struct RequestMessage {
     fields: Vec<String>,
 }
 
@@ -314,18 +433,59 @@ impl RequestMessage {
         Self::default()
     }
 
-    pub fn push_field<T: ToField>(&mut self, val: &T) -> &RequestMessage {
+    pub(crate) fn push_field<T: ToField>(&mut self, val: &T) -> &RequestMessage {
         let field = val.to_field();
         self.fields.push(field);
         self
     }
 
+    /// Pushes val when the server version is at least min_version, otherwise the field is not sent.
+    pub(crate) fn push_if_server_version<T: ToField>(&mut self, server_version: i32, min_version: i32, val: &T) -> &RequestMessage {
+        if server_version >= min_version {
+            self.push_field(val);
+        }
+        self
+    }
+
+    /// Pushes val as 1 or 0.
+    pub(crate) fn push_bool(&mut self, val: bool) -> &RequestMessage {
+        self.push_field(&val)
+    }
+
+    /// Pushes val, sending None and the unset double sentinel (f64::MAX) as an empty field.
+    pub(crate) fn push_optional_f64(&mut self, val: Option<f64>) -> &RequestMessage {
+        match val {
+            Some(val) if val != f64::MAX => self.push_field(&val),
+            _ => self.push_field(&""),
+        }
+    }
+
+    /// Checks that the encoder pushed the number of fields expected for the negotiated server version.
+    pub(crate) fn verify_field_count(&self, expected: usize) -> Result<(), Error> {
+        let actual = self.fields.len();
+
+        debug_assert_eq!(actual, expected, "fields encoded for message type {:?}", self.fields.first());
+
+        if actual != expected {
+            return Err(Error::EncodingMismatch { expected, actual });
+        }
+
+        Ok(())
+    }
+
+    /// Fields of the message, each terminated by a null character, as sent on the wire.
     pub fn encode(&self) -> String {
         let mut data = self.fields.join("\0");
         data.push('\0');
         data
     }
 
+    /// Number of fields pushed.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.fields.len()
+    }
+
     #[cfg(test)]
     pub(crate) fn encode_simple(&self) -> String {
         let mut data = self.fields.join("|");
@@ -342,10 +502,11 @@ impl Index<usize> for RequestMessage {
     }
 }
 
+/// Message received from TWS or Gateway. See [MessageObserver](crate::client::MessageObserver).
 #[derive(Clone, Default, Debug)]
-pub(crate) struct ResponseMessage {
-    pub i: usize,
-    pub fields: Vec<String>,
+pub struct ResponseMessage {
+    pub(crate) i: usize,
+    pub(crate) fields: Vec<String>,
 }
 
 impl ResponseMessage {
@@ -358,8 +519,14 @@ impl ResponseMessage {
         }
     }
 
+    /// Request id of the message, as laid out by servers of [server_versions::max_version].
     pub fn request_id(&self) -> Option<i32> {
-        if let Some(i) = request_id_index(self.message_type()) {
+        self.request_id_for(server_versions::max_version())
+    }
+
+    // Request id of the message, as laid out by server_version.
+    pub(crate) fn request_id_for(&self, server_version: i32) -> Option<i32> {
+        if let Some(i) = find_request_id_index(&self.message_type(), server_version) {
             if let Ok(request_id) = self.peek_int(i) {
                 return Some(request_id);
             }
@@ -384,31 +551,45 @@ impl ResponseMessage {
         }
     }
 
+    /// Fields of the message, starting with the message type.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Index of the next field consumed by the next_* methods.
+    pub fn position(&self) -> usize {
+        self.i
+    }
+
+    /// Number of fields not yet consumed by the next_* methods.
+    pub fn remaining(&self) -> usize {
+        self.fields.len().saturating_sub(self.i)
+    }
+
     pub fn peek_int(&self, i: usize) -> Result<i32, Error> {
-        let field = &self.fields[i];
+        let field = self.field(i)?;
         match field.parse() {
             Ok(val) => Ok(val),
             Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
+    /// Returns the field at index i, or an empty string if the message is too short.
     pub fn peek_string(&self, i: usize) -> String {
-        self.fields[i].to_owned()
+        self.fields.get(i).cloned().unwrap_or_default()
     }
 
     pub fn next_int(&mut self) -> Result<i32, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         match field.parse() {
             Ok(val) => Ok(val),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_optional_int(&mut self) -> Result<Option<i32>, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         if field.is_empty() || field == UNSET_INTEGER {
             return Ok(None);
@@ -416,30 +597,27 @@ impl ResponseMessage {
 
         match field.parse::<i32>() {
             Ok(val) => Ok(Some(val)),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_bool(&mut self) -> Result<bool, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (_, field) = self.next_field()?;
 
         Ok(field == "1")
     }
 
     pub fn next_long(&mut self) -> Result<i64, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         match field.parse() {
             Ok(val) => Ok(val),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_optional_long(&mut self) -> Result<Option<i64>, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         if field.is_empty() || field == UNSET_LONG {
             return Ok(None);
@@ -447,35 +625,35 @@ impl ResponseMessage {
 
         match field.parse::<i64>() {
             Ok(val) => Ok(Some(val)),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_date_time(&mut self) -> Result<OffsetDateTime, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         if field.is_empty() {
             return Err(Error::Simple("expected timestamp and found empty string".into()));
         }
 
         // from_unix_timestamp
-        let timestamp: i64 = field.parse()?;
+        let timestamp: i64 = match field.parse() {
+            Ok(val) => val,
+            Err(err) => return Err(Error::Parse(i, field.into(), err.to_string())),
+        };
         match OffsetDateTime::from_unix_timestamp(timestamp) {
             Ok(val) => Ok(val),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_string(&mut self) -> Result<String, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (_, field) = self.next_field()?;
         Ok(String::from(field))
     }
 
     pub fn next_double(&mut self) -> Result<f64, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         if field.is_empty() || field == "0" || field == "0.0" {
             return Ok(0.0);
@@ -483,13 +661,12 @@ impl ResponseMessage {
 
         match field.parse() {
             Ok(val) => Ok(val),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
         }
     }
 
     pub fn next_optional_double(&mut self) -> Result<Option<f64>, Error> {
-        let field = &self.fields[self.i];
-        self.i += 1;
+        let (i, field) = self.next_field()?;
 
         if field.is_empty() || field == UNSET_DOUBLE {
             return Ok(None);
@@ -501,10 +678,60 @@ impl ResponseMessage {
 
         match field.parse() {
             Ok(val) => Ok(Some(val)),
-            Err(err) => Err(Error::Parse(self.i, field.into(), err.to_string())),
+            Err(err) => Err(Error::Parse(i, field.into(), err.to_string())),
+        }
+    }
+
+    // Field at index i. Truncated messages report the missing position instead of panicking.
+    fn field(&self, i: usize) -> Result<&str, Error> {
+        match self.fields.get(i) {
+            Some(field) => Ok(field),
+            None => Err(Error::Parse(
+                i,
+                String::default(),
+                format!("field missing, message has {} fields", self.fields.len()),
+            )),
+        }
+    }
+
+    // Returns the index and value of the next field and advances past it.
+    fn next_field(&mut self) -> Result<(usize, &str), Error> {
+        let i = self.i;
+        self.i += 1;
+        Ok((i, self.field(i)?))
+    }
+
+    /// Decodes an Error message into [Error::Api], as sent by servers of [server_versions::max_version].
+    pub fn api_error(&self) -> Error {
+        self.api_error_for(server_versions::max_version())
+    }
+
+    // Decodes an Error message into Error::Api, as sent by server_version.
+    pub(crate) fn api_error_for(&self, server_version: i32) -> Error {
+        let mut message = self.peek_string(4);
+
+        let mut advanced_order_reject_json = String::default();
+        if server_version >= server_versions::ADVANCED_ORDER_REJECT {
+            advanced_order_reject_json = self.peek_string(5);
+        }
+
+        if server_version >= server_versions::ENCODE_MSG_ASCII7 {
+            message = unescape(&message);
+            advanced_order_reject_json = unescape(&advanced_order_reject_json);
+        }
+
+        Error::Api {
+            code: self.peek_int(3).unwrap_or(-1),
+            message,
+            advanced_order_reject: AdvancedOrderReject::parse(&advanced_order_reject_json),
         }
     }
 
+    /// Sentinel message delivered to subscribers when the connection to TWS is lost.
+    pub fn connection_lost() -> ResponseMessage {
+        ResponseMessage::from(&(IncomingMessages::ConnectionLost as i32).to_string())
+    }
+
     pub fn from(fields: &str) -> ResponseMessage {
         ResponseMessage {
             i: 0,
@@ -520,6 +747,7 @@ impl ResponseMessage {
         self.i = 0;
     }
 
+    /// Fields of the message, each terminated by a null character, as received on the wire.
     pub fn encode(&self) -> String {
         let mut data = self.fields.join("\0");
         data.push('\0');
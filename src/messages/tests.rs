@@ -1,5 +1,6 @@
 use crate::contracts::{ComboLegOpenClose, SecurityType};
-use crate::orders::{Action, OrderCondition, OrderOpenClose, Rule80A};
+use crate::orders::{Action, OrderOpenClose, Rule80A};
+use crate::server_versions;
 
 use super::*;
 
@@ -68,21 +69,6 @@ fn test_message_encodes_rule_80_a() {
     assert_eq!("I\0A\0W\0J\0U\0M\0K\0Y\0N\0\0", message.encode());
 }
 
-#[test]
-fn test_message_encodes_order_condition() {
-    let mut message = RequestMessage::new();
-
-    message.push_field(&OrderCondition::Price);
-    message.push_field(&OrderCondition::Time);
-    message.push_field(&OrderCondition::Margin);
-    message.push_field(&OrderCondition::Execution);
-    message.push_field(&OrderCondition::Volume);
-    message.push_field(&OrderCondition::PercentChange);
-
-    assert_eq!(6, message.fields.len());
-    assert_eq!("1\03\04\05\06\07\0", message.encode());
-}
-
 #[test]
 fn test_message_encodes_action() {
     let mut message = RequestMessage::new();
@@ -255,23 +241,242 @@ fn test_order_id_index() {
 
 #[test]
 fn test_request_id_index() {
-    assert_eq!(request_id_index(IncomingMessages::ContractData), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::TickByTick), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::SymbolSamples), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::OpenOrder), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::ExecutionData), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::HeadTimestamp), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::HistoricalData), Some(1));
-    assert_eq!(request_id_index(IncomingMessages::HistoricalSchedule), Some(1));
-
-    assert_eq!(request_id_index(IncomingMessages::ContractDataEnd), Some(2));
-    assert_eq!(request_id_index(IncomingMessages::RealTimeBars), Some(2));
-    assert_eq!(request_id_index(IncomingMessages::Error), Some(2));
-    assert_eq!(request_id_index(IncomingMessages::ExecutionDataEnd), Some(2));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ContractData, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::TickByTick, server_versions::SIZE_RULES), Some(1));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::SymbolSamples, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::OpenOrder, server_versions::SIZE_RULES), Some(1));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ExecutionData, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HeadTimestamp, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HistogramData, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HistoricalData, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HistoricalSchedule, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::PnL, server_versions::SIZE_RULES), Some(1));
+    assert_eq!(find_request_id_index(&IncomingMessages::PnLSingle, server_versions::SIZE_RULES), Some(1));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::SecurityDefinitionOptionParameter, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::SecurityDefinitionOptionParameterEnd, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::TickOptionComputation, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HistoricalNews, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HistoricalNewsEnd, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::NewsArticle, server_versions::SIZE_RULES),
+        Some(1)
+    );
+
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ContractDataEnd, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::RealTimeBars, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::Error, server_versions::SIZE_RULES), Some(2));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ExecutionDataEnd, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::TickPrice, server_versions::SIZE_RULES), Some(2));
+    assert_eq!(find_request_id_index(&IncomingMessages::TickSize, server_versions::SIZE_RULES), Some(2));
+    assert_eq!(find_request_id_index(&IncomingMessages::Tickstring, server_versions::SIZE_RULES), Some(2));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::TickGeneric, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::TickSnapshotEnd, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::AccountSummary, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::AccountSummaryEnd, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::MarketDepth, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::MarketDepthL2, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::FundamentalData, server_versions::SIZE_RULES),
+        Some(2)
+    );
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ScannerData, server_versions::SIZE_RULES),
+        Some(2)
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_request_id_index_invalid() {
-    assert_eq!(request_id_index(IncomingMessages::NotValid), None);
+    assert_eq!(find_request_id_index(&IncomingMessages::NotValid, server_versions::SIZE_RULES), None);
+    assert_eq!(ResponseMessage::from("999\0").request_id(), None, "request id of unknown message");
+}
+
+#[test]
+fn test_find_request_id_index() {
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::HeadTimestamp, server_versions::SIZE_RULES),
+        Some(1)
+    );
+    assert_eq!(find_request_id_index(&IncomingMessages::TickPrice, server_versions::SIZE_RULES), Some(2));
+    assert_eq!(
+        find_request_id_index(&IncomingMessages::ManagedAccounts, server_versions::SIZE_RULES),
+        None
+    );
+}
+
+#[test]
+fn test_request_id_of_option_computation() {
+    // message version 6 precedes the request id before PRICE_BASED_VOLATILITY
+    let message = ResponseMessage::from("21\06\09000\013\00.3\00.6\05.4\0-1\00.03\00.12\0-0.04\0185.5\0");
+    assert_eq!(
+        message.request_id_for(server_versions::PRICE_BASED_VOLATILITY - 1),
+        Some(9000),
+        "request id before PRICE_BASED_VOLATILITY"
+    );
+
+    let message = ResponseMessage::from("21\09000\013\01\00.3\00.6\05.4\0-1\00.03\00.12\0-0.04\0185.5\0");
+    assert_eq!(
+        message.request_id_for(server_versions::PRICE_BASED_VOLATILITY),
+        Some(9000),
+        "request id since PRICE_BASED_VOLATILITY"
+    );
+}
+
+#[test]
+fn test_unescape() {
+    assert_eq!(unescape("Order rejected"), "Order rejected", "no escapes");
+    assert_eq!(unescape("Ordre rejet\\u00e9"), "Ordre rejeté", "unicode escape");
+    assert_eq!(
+        unescape("\\u8ba2\\u5355\\u88ab\\u62d2\\u7edd"),
+        "订单被拒绝",
+        "consecutive unicode escapes"
+    );
+    assert_eq!(unescape("\\ud83d\\ude00"), "😀", "surrogate pair");
+    assert_eq!(unescape("caf\\xe9"), "café", "hex escape");
+    assert_eq!(unescape("line\\nbreak\\ttab"), "line\nbreak\ttab", "control escapes");
+    assert_eq!(unescape("1\\.5\\\\2"), "1.5\\2", "escaped literals");
+    assert_eq!(unescape("\\u00zz"), "\\u00zz", "invalid unicode escape");
+    assert_eq!(unescape("trailing\\"), "trailing\\", "trailing backslash");
+}
+
+#[test]
+fn test_push_optional_and_version_gated_fields() {
+    let mut message = RequestMessage::default();
+
+    message.push_optional_f64(None);
+    message.push_optional_f64(Some(f64::MAX));
+    message.push_optional_f64(Some(1.5));
+    message.push_bool(true);
+    message.push_bool(false);
+    message.push_if_server_version(server_versions::SIZE_RULES, server_versions::ADVANCED_ORDER_REJECT, &"skipped");
+    message.push_if_server_version(server_versions::SIZE_RULES, server_versions::SIZE_RULES, &"sent");
+
+    assert_eq!(message.encode_simple(), "||1.5|1|0|sent|");
+}
+
+#[test]
+fn test_remaining_fields() {
+    let mut message = ResponseMessage::from("9\01\0120\0");
+
+    assert_eq!(message.remaining(), 4, "remaining before decoding");
+
+    message.skip(); // message type
+    message.skip(); // message version
+    assert_eq!(message.next_int().unwrap(), 120, "next_int()");
+
+    assert_eq!(message.remaining(), 1, "remaining after decoding");
+}
+
+#[test]
+fn test_field_accessors() {
+    let mut message = ResponseMessage::from("9\01\0120");
+
+    assert_eq!(message.fields(), ["9", "1", "120"], "fields()");
+    assert_eq!(message.position(), 0, "position before decoding");
+
+    message.skip(); // message type
+    message.skip(); // message version
+    assert_eq!(message.position(), 2, "position after decoding");
+}
+
+#[test]
+fn test_truncated_message_reports_missing_field() {
+    let mut message = ResponseMessage::from("9\01");
+
+    message.skip(); // message type
+    message.skip(); // message version
+
+    match message.next_int() {
+        Err(Error::Parse(i, value, _)) => {
+            assert_eq!(i, 2, "field index");
+            assert_eq!(value, "", "field value");
+        }
+        result => assert!(false, "expected parse error, got {result:?}"),
+    }
+
+    assert!(message.next_string().is_err(), "next_string() past end of message");
+    assert!(message.next_double().is_err(), "next_double() past end of message");
+    assert!(message.peek_int(5).is_err(), "peek_int() past end of message");
+    assert_eq!(message.peek_string(5), "", "peek_string() past end of message");
+}
+
+#[test]
+fn test_parse_error_reports_field_position() {
+    let mut message = ResponseMessage::from("1\06\09000\01\0abc\0");
+
+    message.skip(); // message type
+    message.skip(); // message version
+    message.skip(); // request id
+    message.skip(); // tick type
+
+    match message.next_double() {
+        Err(Error::Parse(i, value, _)) => {
+            assert_eq!(i, 4, "field index");
+            assert_eq!(value, "abc", "field value");
+        }
+        result => assert!(false, "expected parse error, got {result:?}"),
+    }
 }
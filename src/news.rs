@@ -1,30 +1,154 @@
-use crate::{Client, Error};
+use std::time::{Duration, Instant};
 
-#[derive(Clone, Debug)]
+use log::{error, info};
+use time::OffsetDateTime;
+
+use crate::client::transport::ResponseIterator;
+use crate::messages::IncomingMessages;
+use crate::{server_versions, Client, Error};
+
+mod decoders;
+mod encoders;
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Default)]
 pub struct NewsProvider {
+    /// Provider code, i.e. BRFG
     pub code: String,
+    /// Provider name, i.e. Briefing.com General Market Columns
     pub name: String,
 }
 
-// https://interactivebrokers.github.io/tws-api/news.html
+/// Historical news headline, received from [Client::historical_news].
+#[derive(Clone, Debug)]
+pub struct NewsArticle {
+    /// Time the headline was published
+    pub time: OffsetDateTime,
+    /// Code of the news provider
+    pub provider_code: String,
+    /// Article id, used to request the article body with [Client::news_article]
+    pub article_id: String,
+    /// Headline of the article
+    pub headline: String,
+}
+
+/// Format of a news article body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArticleType {
+    /// Plain text or HTML
+    Text,
+    /// Binary data, i.e. a PDF, encoded in Base64
+    Binary,
+}
+
+/// News article body, received from [Client::news_article].
+#[derive(Clone, Debug)]
+pub struct NewsArticleBody {
+    /// Format of the article text
+    pub article_type: ArticleType,
+    /// Body of the article
+    pub article_text: String,
+}
+
+// Requests news providers which the user has subscribed to.
+pub(crate) fn news_providers(client: &Client) -> Result<Vec<NewsProvider>, Error> {
+    client.check_server_version(server_versions::REQ_NEWS_PROVIDERS, "It does not support news providers requests.")?;
+
+    let message = encoders::request_news_providers()?;
+
+    let mut messages = client.request_news_providers(message)?;
+
+    if let Some(mut message) = messages.next() {
+        decoders::decode_news_providers(&mut message)
+    } else {
+        Ok(Vec::default())
+    }
+}
+
+// Requests historical news headlines.
+pub(crate) fn historical_news(
+    client: &Client,
+    contract_id: i32,
+    provider_codes: &[&str],
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    total_results: i32,
+) -> Result<Vec<NewsArticle>, Error> {
+    client.check_server_version(server_versions::REQ_HISTORICAL_NEWS, "It does not support historical news requests.")?;
 
-/// Historical News Headlines
+    let request_id = client.next_request_id();
+    let message = encoders::request_historical_news(
+        client.server_version(),
+        request_id,
+        contract_id,
+        provider_codes,
+        start,
+        end,
+        total_results,
+    )?;
 
-/// Requesting News Articles
+    let mut responses = client.send_request(request_id, message)?;
 
-/// Requests news providers which the user has subscribed to.
-pub fn news_providers(client: &Client) -> Result<Vec<NewsProvider>, Error> {
-    // request = RequestNewsProvidersRequest::new()
-    // packet = request.encode()
-    // client.send_packet(packet)
-    // packet = client.receive_packet(request_id)
-    // ReceiveNewsProvidersResponse::decode(packet)
-    print!("client: {client:?}");
-    Err(Error::NotImplemented)
+    read_historical_news(client.server_version(), &mut responses, Instant::now() + HISTORICAL_NEWS_TIMEOUT)
 }
 
-// :reqNewsArticle below.
+// Time allowed for receiving all headlines of a request.
+const HISTORICAL_NEWS_TIMEOUT: Duration = Duration::from_secs(30);
 
-// reqHistoricalNews
+// Reads headlines until the end marker. Fails with the headlines received so far when the end marker does not arrive before the deadline.
+fn read_historical_news(server_version: i32, responses: &mut ResponseIterator, deadline: Instant) -> Result<Vec<NewsArticle>, Error> {
+    let mut articles: Vec<NewsArticle> = Vec::default();
 
-//reqNewsArticle s
+    loop {
+        let mut message = match responses.recv_deadline(deadline) {
+            Ok(message) => message,
+            Err(Error::Timeout) => return Err(Error::incomplete(articles)),
+            Err(err) => {
+                info!("error receiving historical news: {err}");
+                break;
+            }
+        };
+
+        match message.message_type() {
+            IncomingMessages::HistoricalNews => {
+                articles.push(decoders::decode_historical_news(&mut message)?);
+            }
+            IncomingMessages::HistoricalNewsEnd => {
+                break;
+            }
+            IncomingMessages::Error => {
+                return Err(message.api_error_for(server_version));
+            }
+            _ => {
+                error!("unexpected message: {:?}", message);
+            }
+        }
+    }
+
+    Ok(articles)
+}
+
+// Requests the body of a news article.
+pub(crate) fn news_article(client: &Client, provider_code: &str, article_id: &str) -> Result<NewsArticleBody, Error> {
+    client.check_server_version(server_versions::REQ_NEWS_ARTICLE, "It does not support news article requests.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_news_article(client.server_version(), request_id, provider_code, article_id)?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    while let Some(message) = responses.next_result() {
+        let mut message = message?;
+        match message.message_type() {
+            IncomingMessages::NewsArticle => {
+                return decoders::decode_news_article(&mut message);
+            }
+            _ => {
+                error!("unexpected message: {:?}", message);
+            }
+        }
+    }
+
+    Err(Error::Simple(format!("no news article received for {provider_code}:{article_id}")))
+}
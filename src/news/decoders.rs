@@ -0,0 +1,59 @@
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+
+use crate::messages::ResponseMessage;
+use crate::Error;
+
+use super::{ArticleType, NewsArticle, NewsArticleBody, NewsProvider};
+
+pub(crate) fn decode_news_providers(message: &mut ResponseMessage) -> Result<Vec<NewsProvider>, Error> {
+    message.skip(); // message type
+
+    let news_providers_count = message.next_int()?;
+
+    if news_providers_count < 1 {
+        return Ok(Vec::default());
+    }
+
+    let mut news_providers: Vec<NewsProvider> = Vec::with_capacity(news_providers_count as usize);
+
+    for _ in 0..news_providers_count {
+        news_providers.push(NewsProvider {
+            code: message.next_string()?,
+            name: message.next_string()?,
+        });
+    }
+
+    Ok(news_providers)
+}
+
+pub(crate) fn decode_historical_news(message: &mut ResponseMessage) -> Result<NewsArticle, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let time_format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond]");
+    let time = PrimitiveDateTime::parse(&message.next_string()?, time_format)?;
+
+    Ok(NewsArticle {
+        time: time.assume_utc(),
+        provider_code: message.next_string()?,
+        article_id: message.next_string()?,
+        headline: message.next_string()?,
+    })
+}
+
+pub(crate) fn decode_news_article(message: &mut ResponseMessage) -> Result<NewsArticleBody, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let article_type = match message.next_int()? {
+        0 => ArticleType::Text,
+        1 => ArticleType::Binary,
+        kind => return Err(Error::Simple(format!("unexpected article type: {kind}"))),
+    };
+
+    Ok(NewsArticleBody {
+        article_type,
+        article_text: message.next_string()?,
+    })
+}
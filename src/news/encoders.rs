@@ -0,0 +1,64 @@
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::messages::{OutgoingMessages, RequestMessage};
+use crate::{server_versions, Error};
+
+pub(crate) fn request_news_providers() -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestNewsProviders);
+
+    Ok(message)
+}
+
+pub(crate) fn request_historical_news(
+    server_version: i32,
+    request_id: i32,
+    contract_id: i32,
+    provider_codes: &[&str],
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    total_results: i32,
+) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestHistoricalNews);
+    message.push_field(&request_id);
+    message.push_field(&contract_id);
+    message.push_field(&provider_codes.join("+"));
+    message.push_field(&format_date_time(start)?);
+    message.push_field(&format_date_time(end)?);
+    message.push_field(&total_results);
+
+    if server_version >= server_versions::NEWS_QUERY_ORIGINS {
+        message.push_field(&""); // historical news options
+    }
+
+    Ok(message)
+}
+
+// Formats date time as yyyy-MM-dd HH:mm:ss.0 in UTC, the format expected by historical news requests.
+fn format_date_time(date_time: OffsetDateTime) -> Result<String, Error> {
+    let date_time_format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second].0");
+
+    match date_time.to_offset(UtcOffset::UTC).format(date_time_format) {
+        Ok(formatted) => Ok(formatted),
+        Err(err) => Err(Error::Simple(format!("error formatting date time {date_time}: {err}"))),
+    }
+}
+
+pub(crate) fn request_news_article(server_version: i32, request_id: i32, provider_code: &str, article_id: &str) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestNewsArticle);
+    message.push_field(&request_id);
+    message.push_field(&provider_code);
+    message.push_field(&article_id);
+
+    if server_version >= server_versions::NEWS_QUERY_ORIGINS {
+        message.push_field(&""); // news article options
+    }
+
+    Ok(message)
+}
@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+
+use time::macros::datetime;
+
+use crate::stubs::MessageBusStub;
+
+use super::*;
+
+#[test]
+fn news_providers() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "85|3|BRFG|Briefing.com General Market Columns|BRFUPDN|Briefing.com Analyst Actions|DJNL|Dow Jones Newsletters|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.news_providers();
+    assert!(results.is_ok(), "failed to request news providers: {}", results.err().unwrap());
+
+    let news_providers = results.unwrap();
+    assert_eq!(news_providers.len(), 3, "news_providers.len()");
+
+    assert_eq!(news_providers[0].code, "BRFG", "news_providers[0].code");
+    assert_eq!(news_providers[0].name, "Briefing.com General Market Columns", "news_providers[0].name");
+    assert_eq!(news_providers[2].code, "DJNL", "news_providers[2].code");
+    assert_eq!(news_providers[2].name, "Dow Jones Newsletters", "news_providers[2].name");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "85|", "news providers request");
+}
+
+#[test]
+fn historical_news() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "86|9000|2024-03-14 20:05:00.0|BRFG|BRFG$1a2b3c4d|Apple shares slip after EU fine|".to_owned(),
+            "86|9000|2024-03-14 13:30:00.0|DJNL|DJNL$5e6f7a8b|Tech stocks lead early gains|".to_owned(),
+            "87|9000|0|".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let start = datetime!(2024-03-01 0:00 UTC);
+    let end = datetime!(2024-03-15 0:00 UTC);
+
+    let results = client.historical_news(265598, &["BRFG", "DJNL"], start, end, 10);
+    assert!(results.is_ok(), "failed to request historical news: {}", results.err().unwrap());
+
+    let articles = results.unwrap();
+    assert_eq!(articles.len(), 2, "articles.len()");
+
+    assert_eq!(articles[0].time, datetime!(2024-03-14 20:05 UTC), "articles[0].time");
+    assert_eq!(articles[0].provider_code, "BRFG", "articles[0].provider_code");
+    assert_eq!(articles[0].article_id, "BRFG$1a2b3c4d", "articles[0].article_id");
+    assert_eq!(articles[0].headline, "Apple shares slip after EU fine", "articles[0].headline");
+
+    assert_eq!(articles[1].provider_code, "DJNL", "articles[1].provider_code");
+    assert_eq!(articles[1].headline, "Tech stocks lead early gains", "articles[1].headline");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "86|9000|265598|BRFG+DJNL|2024-03-01 00:00:00.0|2024-03-15 00:00:00.0|10||",
+        "historical news request"
+    );
+}
+
+#[test]
+fn historical_news_end_after_deadline() {
+    use std::time::{Duration, Instant};
+
+    use crossbeam::channel;
+
+    use crate::messages::ResponseMessage;
+
+    let (sender, receiver) = channel::unbounded();
+    let (signals, _signals) = channel::unbounded();
+    let mut responses = ResponseIterator::new(receiver, signals, None, None, Duration::from_secs(10));
+
+    // end marker is withheld, the sender stays connected
+    sender
+        .send(ResponseMessage::from(
+            &"86|9000|2024-03-14 20:05:00.0|BRFG|BRFG$1a2b3c4d|Apple shares slip after EU fine|".replace('|', "\0"),
+        ))
+        .unwrap();
+
+    let started = Instant::now();
+    let result = read_historical_news(server_versions::SIZE_RULES, &mut responses, Instant::now() + Duration::from_millis(100));
+
+    assert!(started.elapsed() < Duration::from_secs(1), "returned after {:?}", started.elapsed());
+    match result {
+        Err(err) => match err.partial_results::<NewsArticle>() {
+            Some(received) => {
+                assert_eq!(received.len(), 1, "received.len()");
+                assert_eq!(received[0].provider_code, "BRFG", "received[0].provider_code");
+            }
+            None => assert!(false, "unexpected error: {err}"),
+        },
+        Ok(_) => assert!(false, "expected incomplete historical news"),
+    }
+
+    drop(sender);
+}
+
+#[test]
+fn news_article() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["83|9000|0|<p>Apple shares slipped on Thursday.</p>|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.news_article("BRFG", "BRFG$1a2b3c4d");
+    assert!(results.is_ok(), "failed to request news article: {}", results.err().unwrap());
+
+    let article = results.unwrap();
+    assert_eq!(article.article_type, ArticleType::Text, "article.article_type");
+    assert_eq!(article.article_text, "<p>Apple shares slipped on Thursday.</p>", "article.article_text");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "84|9000|BRFG|BRFG$1a2b3c4d||",
+        "news article request"
+    );
+}
+
+#[test]
+fn historical_news_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::REQ_NEWS_PROVIDERS);
+
+    let results = client.historical_news(265598, &["BRFG"], datetime!(2024-03-01 0:00 UTC), datetime!(2024-03-15 0:00 UTC), 10);
+
+    assert!(matches!(results, Err(Error::ServerVersion(_, _, _))), "expected server version error");
+}
@@ -1,10 +1,12 @@
+use std::collections::HashMap;
 use std::convert::From;
 use std::fmt::{self, Debug};
 
-use log::{error, info};
+use log::{debug, error, info};
+use serde::Deserialize;
 
 use crate::client::transport::{GlobalResponseIterator, ResponseIterator};
-use crate::contracts::{ComboLeg, ComboLegOpenClose, Contract, DeltaNeutralContract, SecurityType};
+use crate::contracts::{ComboLeg, ComboLegOpenClose, Contract, ContractDetails, DeltaNeutralContract, SecurityType};
 use crate::messages::{IncomingMessages, OutgoingMessages};
 use crate::messages::{RequestMessage, ResponseMessage};
 use crate::Client;
@@ -12,7 +14,7 @@ use crate::{encode_option_field, ToField};
 use crate::{server_versions, Error};
 
 mod decoders;
-mod encoders;
+pub(crate) mod encoders;
 
 /// Make sure to test using only your paper trading account when applicable. A good way of finding out if an order type/exchange combination
 /// is possible is by trying to place such order manually using the TWS.
@@ -34,7 +36,7 @@ pub struct Order {
     /// The API client id which placed the order.
     pub client_id: i32,
     /// The Host order identifier.
-    pub perm_id: i32,
+    pub perm_id: i64,
     /// Identifies the side.
     /// Generally available values are BUY and SELL.
     /// Additionally, SSHORT and SLONG are available in some institutional-accounts only.
@@ -263,7 +265,7 @@ pub struct Order {
     /// Valid values are:
     /// IB, Away, and PTA (post trade allocation).
     pub clearing_intent: String,
-    /// The algorithm strategy.
+    /// The algorithm strategy, see [AlgoStrategy].
     /// As of API verion 9.6, the following algorithms are supported:
     /// ArrivalPx - Arrival Price
     /// DarkIce - Dark Ice
@@ -363,6 +365,8 @@ pub struct Order {
     pub advanced_error_override: String,
     /// Used by brokers and advisors when manually entering, modifying or cancelling orders at the direction of a client. Only used when allocating orders to specific groups or accounts. Excluding "All" group.
     pub manual_order_time: String,
+    /// Whether the order was entered manually (1) or by an automated system (0), for CME tagging requirements.
+    pub manual_order_indicator: Option<i32>,
     /// Defines the minimum trade quantity to fill. For IBKRATS orders.
     pub min_trade_qty: Option<i32>,
     /// Defines the minimum size to compete. For IBKRATS orders.
@@ -532,6 +536,7 @@ impl Default for Order {
             parent_perm_id: None,
             advanced_error_override: "".to_owned(),
             manual_order_time: "".to_owned(),
+            manual_order_indicator: None,
             min_trade_qty: None,
             min_compete_size: None,
             compete_against_best_offset: None,
@@ -632,6 +637,38 @@ impl Action {
     }
 }
 
+/// IB algorithms for [Order::algo_strategy], configured by [Order::algo_params].
+/// See [IB Algos](https://interactivebrokers.github.io/tws-api/ibalgos.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlgoStrategy {
+    /// Adaptive algo, combining smart routing with user defined priority settings.
+    Adaptive,
+    /// Arrival Price, achieves the bid/ask midpoint at the time the order is submitted.
+    ArrivalPrice,
+    /// Dark Ice, displays a different size than the order size.
+    DarkIce,
+    /// Percentage of Volume, participates in volume at a user defined rate.
+    PercentOfVolume,
+    /// TWAP (Time Weighted Average Price)
+    Twap,
+    /// VWAP (Volume Weighted Average Price)
+    Vwap,
+}
+
+impl fmt::Display for AlgoStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AlgoStrategy::Adaptive => "Adaptive",
+            AlgoStrategy::ArrivalPrice => "ArrivalPx",
+            AlgoStrategy::DarkIce => "DarkIce",
+            AlgoStrategy::PercentOfVolume => "PctVol",
+            AlgoStrategy::Twap => "Twap",
+            AlgoStrategy::Vwap => "Vwap",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Rule80A {
     Individual,
@@ -690,6 +727,19 @@ impl Rule80A {
     }
 }
 
+/// Tells how to handle remaining orders in an OCA group when one order or part of an order executes. See [Order::oca_type].
+///
+/// Types "with block" give the orders overfill protection: only one order in the group is routed at a time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OcaType {
+    /// Cancel all remaining orders with block.
+    CancelWithBlock = 1,
+    /// Remaining orders are proportionately reduced in size with block.
+    ReduceWithBlock = 2,
+    /// Remaining orders are proportionately reduced in size with no block.
+    ReduceNonBlock = 3,
+}
+
 pub enum AuctionStrategy {
     Match,
     Improvement,
@@ -701,38 +751,72 @@ pub struct OrderComboLeg {
     price: Option<f64>,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Condition determining when an order is activated or canceled, see [Order::conditions].
+///
+/// `is_conjunction` combines the condition with the one that follows using AND when true, OR otherwise. It is ignored for the last condition.
+#[derive(Clone, Debug, PartialEq)]
 pub enum OrderCondition {
-    Price = 1,
-    Time = 3,
-    Margin = 4,
-    Execution = 5,
-    Volume = 6,
-    PercentChange = 7,
+    /// The price of a contract is above or below a price.
+    Price {
+        contract_id: i32,
+        exchange: String,
+        price: f64,
+        /// Method used to trigger the condition, see [Order::trigger_method].
+        trigger_method: i32,
+        is_more: bool,
+        is_conjunction: bool,
+    },
+    /// The time is after or before a time, formatted as yyyymmdd hh:mm:ss with an optional time zone.
+    Time { time: String, is_more: bool, is_conjunction: bool },
+    /// The margin cushion of the account is above or below a percentage.
+    Margin { percent: i32, is_more: bool, is_conjunction: bool },
+    /// A trade in a contract of the symbol and security type was executed on the exchange.
+    Execution {
+        symbol: String,
+        security_type: String,
+        exchange: String,
+        is_conjunction: bool,
+    },
+    /// The traded volume of a contract is above or below a volume.
+    Volume {
+        contract_id: i32,
+        exchange: String,
+        volume: i32,
+        is_more: bool,
+        is_conjunction: bool,
+    },
+    /// The change in the price of a contract since the last close is above or below a percentage.
+    PercentChange {
+        contract_id: i32,
+        exchange: String,
+        change_percent: f64,
+        is_more: bool,
+        is_conjunction: bool,
+    },
 }
 
-impl ToField for OrderCondition {
-    fn to_field(&self) -> String {
-        (*self as u8).to_string()
-    }
-}
-
-impl ToField for Option<OrderCondition> {
-    fn to_field(&self) -> String {
-        encode_option_field(self)
+impl OrderCondition {
+    /// Type of the condition as identified by TWS.
+    pub fn condition_type(&self) -> i32 {
+        match self {
+            OrderCondition::Price { .. } => 1,
+            OrderCondition::Time { .. } => 3,
+            OrderCondition::Margin { .. } => 4,
+            OrderCondition::Execution { .. } => 5,
+            OrderCondition::Volume { .. } => 6,
+            OrderCondition::PercentChange { .. } => 7,
+        }
     }
-}
 
-impl From<i32> for OrderCondition {
-    fn from(val: i32) -> Self {
-        match val {
-            1 => OrderCondition::Price,
-            3 => OrderCondition::Time,
-            4 => OrderCondition::Volume,
-            5 => OrderCondition::Execution,
-            6 => OrderCondition::Volume,
-            7 => OrderCondition::PercentChange,
-            _ => panic!("OrderCondition({val}) is unsupported"),
+    /// Returns true if the condition is combined with the following condition using AND.
+    pub fn is_conjunction(&self) -> bool {
+        match self {
+            OrderCondition::Price { is_conjunction, .. }
+            | OrderCondition::Time { is_conjunction, .. }
+            | OrderCondition::Margin { is_conjunction, .. }
+            | OrderCondition::Execution { is_conjunction, .. }
+            | OrderCondition::Volume { is_conjunction, .. }
+            | OrderCondition::PercentChange { is_conjunction, .. } => *is_conjunction,
         }
     }
 }
@@ -794,6 +878,34 @@ pub struct OrderState {
     pub completed_status: String,
 }
 
+/// Structured details TWS sends with some order rejections, e.g. error codes 201 and 202.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct AdvancedOrderReject {
+    /// Description of why the order was rejected.
+    #[serde(default, rename = "rejectReason")]
+    pub reject_reason: Option<String>,
+    /// Remaining fields of the reject details, keyed by their JSON name.
+    #[serde(flatten)]
+    pub details: HashMap<String, serde_json::Value>,
+}
+
+impl AdvancedOrderReject {
+    // Parses the advanced order reject JSON of an error message. Empty when the rejection has no details.
+    pub(crate) fn parse(json: &str) -> Option<AdvancedOrderReject> {
+        if json.is_empty() {
+            return None;
+        }
+
+        match serde_json::from_str(json) {
+            Ok(reject) => Some(reject),
+            Err(err) => {
+                error!("error parsing advanced order reject {json:?}: {err}");
+                None
+            }
+        }
+    }
+}
+
 /// For institutional customers only. Valid values are O (open) and C (close).
 /// Available for institutional clients to determine if this order is to open or close a position.
 /// When Action = "BUY" and OpenClose = "O" this will open a new position.
@@ -836,6 +948,7 @@ impl OrderOpenClose {
 }
 
 /// Represents the commission generated by an execution.
+/// Reported after the [ExecutionData] it belongs to, matched by execution id.
 #[derive(Clone, Debug, Default)]
 pub struct CommissionReport {
     /// the execution's id this commission belongs to.
@@ -898,7 +1011,7 @@ pub struct Execution {
     /// The order's execution price excluding commissions.
     pub price: f64,
     /// The TWS order identifier. The PermId can be 0 for trades originating outside IB.
-    pub perm_id: i32,
+    pub perm_id: i64,
     /// Identifies whether an execution occurred because of an IB-initiated liquidation.
     pub liquidation: i32,
     /// Cumulative quantity.
@@ -966,17 +1079,8 @@ impl From<CommissionReport> for OrderNotification {
 pub struct OrderStatus {
     /// The order's client id.
     pub order_id: i32,
-    /// The current status of the order. Possible values:
-    ///     ApiPending - indicates order has not yet been sent to IB server, for instance if there is a delay in receiving the security definition. Uncommonly received.
-    ///     PendingSubmit - indicates that you have transmitted the order, but have not yet received confirmation that it has been accepted by the order destination.
-    ///     PendingCancel - indicates that you have sent a request to cancel the order but have not yet received cancel confirmation from the order destination. At this point, your order is not confirmed canceled. It is not guaranteed that the cancellation will be successful.
-    ///     PreSubmitted - indicates that a simulated order type has been accepted by the IB system and that this order has yet to be elected. The order is held in the IB system until the election criteria are met. At that time the order is transmitted to the order destination as specified .
-    ///     Submitted - indicates that your order has been accepted by the system.
-    ///     ApiCancelled - after an order has been submitted and before it has been acknowledged, an API client client can request its cancelation, producing this state.
-    ///     Cancelled - indicates that the balance of your order has been confirmed canceled by the IB system. This could occur unexpectedly when IB or the destination has rejected your order.
-    ///     Filled - indicates that the order has been completely filled. Market orders executions will not always trigger a Filled status.
-    ///     Inactive - indicates that the order was received by the system but is no longer active because it was rejected or canceled.    
-    pub status: String,
+    /// The current status of the order. See [Status].
+    pub status: Status,
     /// Number of filled positions.
     pub filled: f64,
     /// The remnant positions.
@@ -984,7 +1088,7 @@ pub struct OrderStatus {
     /// Average filling price.
     pub average_fill_price: f64,
     /// The order's permId used by the TWS to identify orders.
-    pub perm_id: i32,
+    pub perm_id: i64,
     /// Parent's id. Used for bracket and auto trailing stop orders.
     pub parent_id: i32,
     /// Price at which the last positions were filled.
@@ -997,6 +1101,90 @@ pub struct OrderStatus {
     pub market_cap_price: f64,
 }
 
+impl OrderStatus {
+    /// Returns true if the order has been completely filled.
+    pub fn is_filled(&self) -> bool {
+        self.status == Status::Filled
+    }
+
+    /// Returns the fraction of the order that has been filled, between 0.0 and 1.0.
+    pub fn fill_ratio(&self) -> f64 {
+        let total = self.filled + self.remaining;
+        if total > 0.0 {
+            self.filled / total
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Status of an order as reported by [OrderStatus].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// Indicates order has not yet been sent to IB server, for instance if there is a delay in receiving the security definition. Uncommonly received.
+    ApiPending,
+    /// Indicates that you have transmitted the order, but have not yet received confirmation that it has been accepted by the order destination.
+    PendingSubmit,
+    /// Indicates that you have sent a request to cancel the order but have not yet received cancel confirmation from the order destination.
+    /// At this point, your order is not confirmed canceled. It is not guaranteed that the cancellation will be successful.
+    PendingCancel,
+    /// Indicates that a simulated order type has been accepted by the IB system and that this order has yet to be elected.
+    /// The order is held in the IB system until the election criteria are met. At that time the order is transmitted to the order destination as specified.
+    PreSubmitted,
+    /// Indicates that your order has been accepted by the system.
+    Submitted,
+    /// After an order has been submitted and before it has been acknowledged, an API client can request its cancelation, producing this state.
+    ApiCancelled,
+    /// Indicates that the balance of your order has been confirmed canceled by the IB system. This could occur unexpectedly when IB or the destination has rejected your order.
+    Cancelled,
+    /// Indicates that the order has been completely filled. Market orders executions will not always trigger a Filled status.
+    Filled,
+    /// Indicates that the order was received by the system but is no longer active because it was rejected or canceled.
+    Inactive,
+    /// Status not known to this library.
+    Unknown(String),
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Status::Unknown(String::default())
+    }
+}
+
+impl From<&str> for Status {
+    fn from(name: &str) -> Self {
+        match name {
+            "ApiPending" => Status::ApiPending,
+            "PendingSubmit" => Status::PendingSubmit,
+            "PendingCancel" => Status::PendingCancel,
+            "PreSubmitted" => Status::PreSubmitted,
+            "Submitted" => Status::Submitted,
+            "ApiCancelled" => Status::ApiCancelled,
+            "Cancelled" => Status::Cancelled,
+            "Filled" => Status::Filled,
+            "Inactive" => Status::Inactive,
+            other => Status::Unknown(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::ApiPending => write!(f, "ApiPending"),
+            Status::PendingSubmit => write!(f, "PendingSubmit"),
+            Status::PendingCancel => write!(f, "PendingCancel"),
+            Status::PreSubmitted => write!(f, "PreSubmitted"),
+            Status::Submitted => write!(f, "Submitted"),
+            Status::ApiCancelled => write!(f, "ApiCancelled"),
+            Status::Cancelled => write!(f, "Cancelled"),
+            Status::Filled => write!(f, "Filled"),
+            Status::Inactive => write!(f, "Inactive"),
+            Status::Unknown(status) => write!(f, "{status}"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Notice(String);
 
@@ -1009,12 +1197,7 @@ impl fmt::Display for Notice {
 // Submits an Order.
 // After the order is submitted correctly, events will be returned concerning the order's activity.
 // https://interactivebrokers.github.io/tws-api/order_submission.html
-pub(crate) fn place_order(
-    client: &Client,
-    order_id: i32,
-    contract: &Contract,
-    order: &Order,
-) -> Result<impl Iterator<Item = OrderNotification>, Error> {
+pub(crate) fn place_order(client: &Client, order_id: i32, contract: &Contract, order: &Order) -> Result<OrderNotificationIterator, Error> {
     verify_order(client, order, order_id)?;
     verify_order_contract(client, contract, order_id)?;
 
@@ -1028,6 +1211,102 @@ pub(crate) fn place_order(
     })
 }
 
+// Places an order after checking its prices are multiples of the contract's minimum tick.
+pub(crate) fn place_order_validated(
+    client: &Client,
+    order_id: i32,
+    contract_details: &ContractDetails,
+    order: &Order,
+) -> Result<OrderNotificationIterator, Error> {
+    verify_price_increments(order, contract_details.min_tick)?;
+    place_order(client, order_id, &contract_details.contract, order)
+}
+
+// TWS rejects prices not aligned to the minimum tick with error 110. Catches them before the round trip.
+fn verify_price_increments(order: &Order, min_tick: f64) -> Result<(), Error> {
+    const TOLERANCE: f64 = 1e-6;
+
+    if min_tick <= 0.0 {
+        return Ok(());
+    }
+
+    let prices = [order.limit_price, order.aux_price, order.trail_stop_price];
+    for price in prices.into_iter().flatten().filter(|price| *price != f64::MAX) {
+        let ticks = price / min_tick;
+        if (ticks - ticks.round()).abs() > TOLERANCE {
+            return Err(Error::InvalidPrice {
+                price,
+                suggested: ticks.round() * min_tick,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// Places a bracket order. Each order is assigned the next order id and children are linked to the parent.
+pub(crate) fn place_bracket_order(client: &Client, contract: &Contract, orders: [Order; 3]) -> Result<Vec<OrderNotificationIterator>, Error> {
+    let [mut parent, mut take_profit, mut stop_loss] = orders;
+
+    parent.order_id = client.next_order_id();
+    take_profit.order_id = client.next_order_id();
+    stop_loss.order_id = client.next_order_id();
+
+    take_profit.parent_id = parent.order_id;
+    stop_loss.parent_id = parent.order_id;
+
+    let mut notifications = Vec::with_capacity(3);
+    for order in [parent, take_profit, stop_loss] {
+        notifications.push(place_order(client, order.order_id, contract, &order)?);
+    }
+
+    Ok(notifications)
+}
+
+// Places orders linked in an OCA group. Each order is assigned the next order id.
+pub(crate) fn place_oca_orders(client: &Client, contract: &Contract, orders: Vec<Order>) -> Result<Vec<OrderNotificationIterator>, Error> {
+    if orders.iter().any(|order| order.oca_group.is_empty()) {
+        return Err(Error::Simple("all orders must be assigned to an OCA group".into()));
+    }
+
+    let mut notifications = Vec::with_capacity(orders.len());
+    for mut order in orders {
+        order.order_id = client.next_order_id();
+        notifications.push(place_order(client, order.order_id, contract, &order)?);
+    }
+
+    Ok(notifications)
+}
+
+// Modifies an [Order] previously placed by this client by re-sending it with the same order id.
+// Notifications keep arriving on the iterator returned by place_order, which must not have been dropped.
+pub(crate) fn modify_order(client: &Client, order_id: i32, contract: &Contract, order: &Order) -> Result<(), Error> {
+    verify_order(client, order, order_id)?;
+    verify_order_contract(client, contract, order_id)?;
+
+    let message = encoders::encode_place_order(client.server_version(), order_id, contract, order)?;
+
+    client.resend_order(order_id, message)
+}
+
+// Previews an [Order] by sending it with what_if set. TWS replies with the margin and commission impact instead of placing it.
+pub(crate) fn what_if_order(client: &Client, order_id: i32, contract: &Contract, order: &Order) -> Result<OrderState, Error> {
+    let order = Order {
+        what_if: true,
+        ..order.clone()
+    };
+
+    for notification in place_order(client, order_id, contract, &order)? {
+        match notification {
+            OrderNotification::OpenOrder(open_order) => return Ok(open_order.order_state),
+            OrderNotification::Message(message) => return Err(Error::Simple(message)),
+            notification => debug!("unexpected what-if notification: {notification:?}"),
+        }
+    }
+
+    Err(Error::Simple("no response from server".into()))
+}
+
 // Supports iteration over OrderNotification
 pub(crate) struct OrderNotificationIterator {
     server_version: i32,
@@ -1087,6 +1366,11 @@ impl Iterator for OrderNotificationIterator {
 fn verify_order(client: &Client, order: &Order, _order_id: i32) -> Result<(), Error> {
     let is_bag_order: bool = false; // StringsAreEqual(Constants.BagSecType, contract.SecType)
 
+    // older servers only accept whole quantities, the encoder would truncate the fraction
+    if order.total_quantity.fract() != 0.0 {
+        client.check_server_version(server_versions::FRACTIONAL_POSITIONS, "It does not support fractional quantities.")?
+    }
+
     if order.scale_init_level_size.is_some() || order.scale_price_increment.is_some() {
         client.check_server_version(server_versions::SCALE_ORDERS, "It does not support Scale orders.")?
     }
@@ -1196,6 +1480,13 @@ fn verify_order(client: &Client, order: &Order, _order_id: i32) -> Result<(), Er
         client.check_server_version(server_versions::DECISION_MAKER, "It does not support MIFID II execution parameters")?
     }
 
+    if order.order_type == "PEG BENCH" || !order.conditions.is_empty() || !order.adjusted_order_type.is_empty() {
+        client.check_server_version(
+            server_versions::PEGGED_TO_BENCHMARK,
+            "It does not support Pegged-to-benchmark orders, conditions, or adjusted orders",
+        )?
+    }
+
     if order.dont_use_auto_price_for_hedge {
         client.check_server_version(
             server_versions::AUTO_PRICE_FOR_HEDGE,
@@ -1238,6 +1529,13 @@ fn verify_order(client: &Client, order: &Order, _order_id: i32) -> Result<(), Er
         client.check_server_version(server_versions::MANUAL_ORDER_TIME, "It does not support manual order time attribute")?
     }
 
+    if order.manual_order_indicator.is_some() {
+        client.check_server_version(
+            server_versions::CME_TAGGING_FIELDS,
+            "It does not support manual order indicator attribute",
+        )?
+    }
+
     if order.min_trade_qty.is_some()
         || order.min_compete_size.is_some()
         || order.compete_against_best_offset.is_some()
@@ -1289,16 +1587,34 @@ fn verify_order_contract(client: &Client, contract: &Contract, _order_id: i32) -
     Ok(())
 }
 
+/// Attributes of an order cancellation, see [Client::cancel_order_with].
+#[derive(Clone, Debug, Default)]
+pub struct OrderCancel {
+    /// Time the cancellation was decided manually, e.g. 20240315 09:30:00. Leave blank for automated cancellations.
+    pub manual_order_cancel_time: String,
+    /// Identifies the person or system cancelling the order, for CME tagging requirements.
+    pub ext_operator: String,
+    /// Whether the cancellation was manual (1) or automated (0), for CME tagging requirements.
+    pub manual_order_indicator: Option<i32>,
+}
+
 // Cancels an open [Order].
-pub(crate) fn cancel_order(client: &Client, order_id: i32, manual_order_cancel_time: &str) -> Result<CancelOrderResultIterator, Error> {
-    if !manual_order_cancel_time.is_empty() {
+pub(crate) fn cancel_order(client: &Client, order_id: i32, order_cancel: &OrderCancel) -> Result<CancelOrderResultIterator, Error> {
+    if !order_cancel.manual_order_cancel_time.is_empty() {
         client.check_server_version(
             server_versions::MANUAL_ORDER_TIME,
             "It does not support manual order cancel time attribute",
         )?
     }
 
-    let message = encoders::encode_cancel_order(client.server_version(), order_id, manual_order_cancel_time)?;
+    if !order_cancel.ext_operator.is_empty() || order_cancel.manual_order_indicator.is_some() {
+        client.check_server_version(
+            server_versions::CME_TAGGING_FIELDS,
+            "It does not support ext operator and manual order indicator parameters in cancel order",
+        )?
+    }
+
+    let message = encoders::encode_cancel_order(client.server_version(), order_id, order_cancel)?;
 
     let messages = client.send_order(order_id, message)?;
 
@@ -1340,7 +1656,7 @@ impl Iterator for CancelOrderResultIterator {
                         return Some(CancelOrderResult::Notice(Notice(message)));
                     }
                     message => {
-                        error!("unexpected messsage: {message:?}");
+                        error!("unexpected message: {message:?}");
                     }
                 }
             } else {
@@ -1354,7 +1670,7 @@ impl Iterator for CancelOrderResultIterator {
 pub(crate) fn global_cancel(client: &Client) -> Result<(), Error> {
     client.check_server_version(server_versions::REQ_GLOBAL_CANCEL, "It does not support global cancel requests.")?;
 
-    let message = encoders::encode_global_cancel()?;
+    let message = encoders::encode_global_cancel(client.server_version())?;
 
     let request_id = client.next_request_id();
     client.send_order(request_id, message)?;
@@ -1364,7 +1680,13 @@ pub(crate) fn global_cancel(client: &Client) -> Result<(), Error> {
 
 // Gets next valid order id
 pub(crate) fn next_valid_order_id(client: &Client) -> Result<i32, Error> {
-    let message = encoders::encode_next_valid_order_id()?;
+    request_ids(client, 0)
+}
+
+// Requests the next valid order id and updates the order id counter of the client with it.
+// The number of ids requested is ignored by TWS, a single next valid id is always returned.
+pub(crate) fn request_ids(client: &Client, num_ids: i32) -> Result<i32, Error> {
+    let message = encoders::encode_request_ids(num_ids)?;
 
     let mut messages = client.request_next_order_id(message)?;
 
@@ -1546,7 +1868,7 @@ pub(crate) struct ExecutionDataIterator {
 impl Iterator for ExecutionDataIterator {
     type Item = ExecutionDataResult;
 
-    /// Returns the next [OrderDataResult]. Waits up to x seconds for next [OrderDataResult].
+    /// Returns the next [ExecutionDataResult]. Ends when ExecutionDataEnd is received.
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if let Some(mut message) = self.messages.next() {
@@ -1567,7 +1889,7 @@ impl Iterator for ExecutionDataIterator {
                         return None;
                     }
                     message => {
-                        error!("order data iterator unexpected messsage: {message:?}");
+                        error!("execution data iterator unexpected message: {message:?}");
                     }
                 }
             } else {
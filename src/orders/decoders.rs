@@ -122,7 +122,7 @@ impl OrderDecoder {
     }
 
     fn read_perm_id(&mut self) -> Result<(), Error> {
-        self.order.perm_id = self.message.next_int()?;
+        self.order.perm_id = self.message.next_long()?;
         Ok(())
     }
 
@@ -474,8 +474,8 @@ impl OrderDecoder {
         if self.server_version >= server_versions::PEGGED_TO_BENCHMARK {
             let conditions_count = self.message.next_int()?;
             for _ in 0..conditions_count {
-                let order_condition = self.message.next_int()?;
-                self.order.conditions.push(OrderCondition::from(order_condition));
+                let condition = decode_condition(&mut self.message)?;
+                self.order.conditions.push(condition);
             }
             if conditions_count > 0 {
                 self.order.conditions_ignore_rth = self.message.next_bool()?;
@@ -733,11 +733,11 @@ pub(crate) fn decode_order_status(server_version: i32, message: &mut ResponseMes
 
     let mut order_status = OrderStatus {
         order_id: message.next_int()?,
-        status: message.next_string()?,
+        status: Status::from(message.next_string()?.as_str()),
         filled: message.next_double()?,
         remaining: message.next_double()?,
         average_fill_price: message.next_double()?,
-        perm_id: message.next_int()?,
+        perm_id: message.next_long()?,
         parent_id: message.next_int()?,
         last_fill_price: message.next_double()?,
         client_id: message.next_int()?,
@@ -784,7 +784,7 @@ pub(crate) fn decode_execution_data(server_version: i32, message: &mut ResponseM
     execution.side = message.next_string()?;
     execution.shares = message.next_double()?;
     execution.price = message.next_double()?;
-    execution.perm_id = message.next_int()?;
+    execution.perm_id = message.next_long()?;
     execution.client_id = message.next_int()?;
     execution.liquidation = message.next_int()?;
     execution.cumulative_quantity = message.next_double()?;
@@ -889,3 +889,53 @@ pub(crate) fn decode_completed_order(server_version: i32, message: ResponseMessa
 
     Ok(decoder.into_order_data())
 }
+
+// Decodes a condition of an order, the reverse of encoders::encode_condition. Fields are read in the order they are listed.
+pub(crate) fn decode_condition(message: &mut ResponseMessage) -> Result<OrderCondition, Error> {
+    let condition_type = message.next_int()?;
+    let is_conjunction = message.next_string()? == "a";
+
+    let condition = match condition_type {
+        1 => OrderCondition::Price {
+            is_more: message.next_bool()?,
+            price: message.next_double()?,
+            contract_id: message.next_int()?,
+            exchange: message.next_string()?,
+            trigger_method: message.next_int()?,
+            is_conjunction,
+        },
+        3 => OrderCondition::Time {
+            is_more: message.next_bool()?,
+            time: message.next_string()?,
+            is_conjunction,
+        },
+        4 => OrderCondition::Margin {
+            is_more: message.next_bool()?,
+            percent: message.next_int()?,
+            is_conjunction,
+        },
+        5 => OrderCondition::Execution {
+            security_type: message.next_string()?,
+            exchange: message.next_string()?,
+            symbol: message.next_string()?,
+            is_conjunction,
+        },
+        6 => OrderCondition::Volume {
+            is_more: message.next_bool()?,
+            volume: message.next_int()?,
+            contract_id: message.next_int()?,
+            exchange: message.next_string()?,
+            is_conjunction,
+        },
+        7 => OrderCondition::PercentChange {
+            is_more: message.next_bool()?,
+            change_percent: message.next_double()?,
+            contract_id: message.next_int()?,
+            exchange: message.next_string()?,
+            is_conjunction,
+        },
+        _ => return Err(Error::Simple(format!("unsupported order condition type: {condition_type}"))),
+    };
+
+    Ok(condition)
+}
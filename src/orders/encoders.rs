@@ -1,3 +1,4 @@
+use crate::messages::{layout_field_count, FieldSpan, NOT_REMOVED};
 use crate::Error;
 
 use super::*;
@@ -14,9 +15,7 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
 
     message.push_field(&order_id);
 
-    if server_version >= server_versions::PLACE_ORDER_CONID {
-        message.push_field(&contract.contract_id);
-    }
+    message.push_if_server_version(server_version, server_versions::PLACE_ORDER_CONID, &contract.contract_id);
     message.push_field(&contract.symbol);
     message.push_field(&contract.security_type);
     message.push_field(&contract.last_trade_date_or_contract_month);
@@ -27,9 +26,7 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
     message.push_field(&contract.primary_exchange);
     message.push_field(&contract.currency);
     message.push_field(&contract.local_symbol);
-    if server_version >= server_versions::TRADING_CLASS {
-        message.push_field(&contract.trading_class);
-    }
+    message.push_if_server_version(server_version, server_versions::TRADING_CLASS, &contract.trading_class);
     if server_version >= server_versions::SEC_ID_TYPE {
         message.push_field(&contract.security_id_type);
         message.push_field(&contract.security_id);
@@ -47,12 +44,12 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
     if server_version < server_versions::ORDER_COMBO_LEGS_PRICE {
         message.push_field(&f64_max_to_zero(order.limit_price));
     } else {
-        message.push_field(&order.limit_price);
+        message.push_optional_f64(order.limit_price);
     }
     if server_version < server_versions::TRAILING_PERCENT {
         message.push_field(&f64_max_to_zero(order.aux_price));
     } else {
-        message.push_field(&order.aux_price);
+        message.push_optional_f64(order.aux_price);
     }
 
     // extended order fields
@@ -125,16 +122,12 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         message.push_field(&order.fa_profile);
     }
 
-    if server_version >= server_versions::MODELS_SUPPORT {
-        message.push_field(&order.model_code);
-    }
+    message.push_if_server_version(server_version, server_versions::MODELS_SUPPORT, &order.model_code);
 
     message.push_field(&order.short_sale_slot);
     message.push_field(&order.designated_location);
 
-    if server_version >= server_versions::SSHORTX_OLD {
-        message.push_field(&order.exempt_code);
-    }
+    message.push_if_server_version(server_version, server_versions::SSHORTX_OLD, &order.exempt_code);
 
     message.push_field(&order.oca_type);
     message.push_field(&order.rule_80_a);
@@ -142,9 +135,9 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
     message.push_field(&order.all_or_none);
     message.push_field(&order.min_qty);
     message.push_field(&order.percent_offset);
-    message.push_field(&false);
-    message.push_field(&false);
-    message.push_field(&Option::<f64>::None);
+    message.push_bool(false); // e_trade_only
+    message.push_bool(false); // firm_quote_only
+    message.push_optional_f64(None); // nbbo_price_cap
     message.push_field(&order.auction_strategy);
     message.push_field(&order.starting_price);
     message.push_field(&order.stock_ref_price);
@@ -178,9 +171,7 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
     message.push_field(&order.reference_price_type);
 
     message.push_field(&order.trail_stop_price);
-    if server_version >= server_versions::TRAILING_PERCENT {
-        message.push_field(&order.trailing_percent);
-    }
+    message.push_if_server_version(server_version, server_versions::TRAILING_PERCENT, &order.trailing_percent);
 
     if server_version >= server_versions::SCALE_ORDERS {
         if server_version >= server_versions::SCALE_ORDERS2 {
@@ -216,18 +207,14 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         }
     }
 
-    if server_version >= server_versions::OPT_OUT_SMART_ROUTING {
-        message.push_field(&order.opt_out_smart_routing);
-    }
+    message.push_if_server_version(server_version, server_versions::OPT_OUT_SMART_ROUTING, &order.opt_out_smart_routing);
 
     if server_version >= server_versions::PTA_ORDERS {
         message.push_field(&order.clearing_account);
         message.push_field(&order.clearing_intent);
     }
 
-    if server_version >= server_versions::NOT_HELD {
-        message.push_field(&order.not_held);
-    }
+    message.push_if_server_version(server_version, server_versions::NOT_HELD, &order.not_held);
 
     if server_version >= server_versions::DELTA_NEUTRAL {
         if let Some(delta_neutral_contract) = &contract.delta_neutral_contract {
@@ -251,21 +238,13 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         }
     }
 
-    if server_version >= server_versions::ALGO_ID {
-        message.push_field(&order.algo_id);
-    }
+    message.push_if_server_version(server_version, server_versions::ALGO_ID, &order.algo_id);
 
-    if server_version >= server_versions::WHAT_IF_ORDERS {
-        message.push_field(&order.what_if);
-    }
+    message.push_if_server_version(server_version, server_versions::WHAT_IF_ORDERS, &order.what_if);
 
-    if server_version >= server_versions::LINKING {
-        message.push_field(&order.order_misc_options);
-    }
+    message.push_if_server_version(server_version, server_versions::LINKING, &order.order_misc_options);
 
-    if server_version >= server_versions::ORDER_SOLICITED {
-        message.push_field(&order.solicited);
-    }
+    message.push_if_server_version(server_version, server_versions::ORDER_SOLICITED, &order.solicited);
 
     if server_version >= server_versions::RANDOMIZE_SIZE_AND_PRICE {
         message.push_field(&order.randomize_size);
@@ -285,9 +264,7 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
 
         if !order.conditions.is_empty() {
             for condition in &order.conditions {
-                // verify
-                // https://github.com/InteractiveBrokers/tws-api/blob/817a905d52299028ac5af08581c8ffde7644cea9/source/csharpclient/client/EClient.cs#L1187
-                message.push_field(condition);
+                encode_condition(&mut message, condition);
             }
 
             message.push_field(&order.conditions_ignore_rth);
@@ -303,18 +280,14 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         message.push_field(&order.adjustable_trailing_unit);
     }
 
-    if server_version >= server_versions::EXT_OPERATOR {
-        message.push_field(&order.ext_operator);
-    }
+    message.push_if_server_version(server_version, server_versions::EXT_OPERATOR, &order.ext_operator);
 
     if server_version >= server_versions::SOFT_DOLLAR_TIER {
         message.push_field(&order.soft_dollar_tier.name);
         message.push_field(&order.soft_dollar_tier.value);
     }
 
-    if server_version >= server_versions::CASH_QTY {
-        message.push_field(&order.cash_qty);
-    }
+    message.push_if_server_version(server_version, server_versions::CASH_QTY, &order.cash_qty);
 
     if server_version >= server_versions::DECISION_MAKER {
         message.push_field(&order.mifid2_decision_maker);
@@ -326,41 +299,27 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         message.push_field(&order.mifid2_execution_algo);
     }
 
-    if server_version >= server_versions::AUTO_PRICE_FOR_HEDGE {
-        message.push_field(&order.dont_use_auto_price_for_hedge);
-    }
+    message.push_if_server_version(
+        server_version,
+        server_versions::AUTO_PRICE_FOR_HEDGE,
+        &order.dont_use_auto_price_for_hedge,
+    );
 
-    if server_version >= server_versions::ORDER_CONTAINER {
-        message.push_field(&order.is_oms_container);
-    }
+    message.push_if_server_version(server_version, server_versions::ORDER_CONTAINER, &order.is_oms_container);
 
-    if server_version >= server_versions::D_PEG_ORDERS {
-        message.push_field(&order.discretionary_up_to_limit_price);
-    }
+    message.push_if_server_version(server_version, server_versions::D_PEG_ORDERS, &order.discretionary_up_to_limit_price);
 
-    if server_version >= server_versions::PRICE_MGMT_ALGO {
-        message.push_field(&order.use_price_mgmt_algo);
-    }
+    message.push_if_server_version(server_version, server_versions::PRICE_MGMT_ALGO, &order.use_price_mgmt_algo);
 
-    if server_version >= server_versions::DURATION {
-        message.push_field(&order.duration);
-    }
+    message.push_if_server_version(server_version, server_versions::DURATION, &order.duration);
 
-    if server_version >= server_versions::POST_TO_ATS {
-        message.push_field(&order.post_to_ats);
-    }
+    message.push_if_server_version(server_version, server_versions::POST_TO_ATS, &order.post_to_ats);
 
-    if server_version >= server_versions::AUTO_CANCEL_PARENT {
-        message.push_field(&order.auto_cancel_parent);
-    }
+    message.push_if_server_version(server_version, server_versions::AUTO_CANCEL_PARENT, &order.auto_cancel_parent);
 
-    if server_version >= server_versions::ADVANCED_ORDER_REJECT {
-        message.push_field(&order.advanced_error_override);
-    }
+    message.push_if_server_version(server_version, server_versions::ADVANCED_ORDER_REJECT, &order.advanced_error_override);
 
-    if server_version >= server_versions::MANUAL_ORDER_TIME {
-        message.push_field(&order.manual_order_time);
-    }
+    message.push_if_server_version(server_version, server_versions::MANUAL_ORDER_TIME, &order.manual_order_time);
 
     if server_version >= server_versions::PEGBEST_PEGMID_OFFSETS {
         if contract.exchange == "IBKRATS" {
@@ -382,44 +341,264 @@ pub(crate) fn encode_place_order(server_version: i32, order_id: i32, contract: &
         }
     }
 
+    message.push_if_server_version(server_version, server_versions::CME_TAGGING_FIELDS, &order.manual_order_indicator);
+
+    let expected = layout_field_count(PLACE_ORDER_LAYOUT, server_version) + place_order_variable_fields(server_version, contract, order);
+    message.verify_field_count(expected)?;
+
     Ok(message)
 }
 
-pub(crate) fn encode_cancel_order(server_version: i32, order_id: i32, manual_order_cancel_time: &str) -> Result<RequestMessage, Error> {
+// Fields of a place order message by the server versions sending them.
+// Fields depending on the contract and order are counted by place_order_variable_fields.
+const PLACE_ORDER_LAYOUT: &[FieldSpan] = &[
+    (0, NOT_REMOVED, 1), // message type
+    (0, server_versions::ORDER_CONTAINER, 1),
+    (0, NOT_REMOVED, 1), // order id
+    (server_versions::PLACE_ORDER_CONID, NOT_REMOVED, 1),
+    (0, NOT_REMOVED, 10), // symbol through local symbol
+    (server_versions::TRADING_CLASS, NOT_REMOVED, 1),
+    (server_versions::SEC_ID_TYPE, NOT_REMOVED, 2),
+    (0, NOT_REMOVED, 5),  // action through aux price
+    (0, NOT_REMOVED, 14), // tif through hidden
+    (0, NOT_REMOVED, 7),  // shares allocation through fa percentage
+    (0, server_versions::FA_PROFILE_DESUPPORT, 1),
+    (server_versions::MODELS_SUPPORT, NOT_REMOVED, 1),
+    (0, NOT_REMOVED, 2), // short sale slot and designated location
+    (server_versions::SSHORTX_OLD, NOT_REMOVED, 1),
+    (0, NOT_REMOVED, 20), // oca type through delta neutral aux price
+    (0, NOT_REMOVED, 3),  // continuous update through trail stop price
+    (server_versions::TRAILING_PERCENT, NOT_REMOVED, 1),
+    (server_versions::SCALE_ORDERS, NOT_REMOVED, 3),
+    (server_versions::SCALE_TABLE, NOT_REMOVED, 3),
+    (server_versions::HEDGE_ORDERS, NOT_REMOVED, 1),
+    (server_versions::OPT_OUT_SMART_ROUTING, NOT_REMOVED, 1),
+    (server_versions::PTA_ORDERS, NOT_REMOVED, 2),
+    (server_versions::NOT_HELD, NOT_REMOVED, 1),
+    (server_versions::DELTA_NEUTRAL, NOT_REMOVED, 1),
+    (server_versions::ALGO_ORDERS, NOT_REMOVED, 1),
+    (server_versions::ALGO_ID, NOT_REMOVED, 1),
+    (server_versions::WHAT_IF_ORDERS, NOT_REMOVED, 1),
+    (server_versions::LINKING, NOT_REMOVED, 1),
+    (server_versions::ORDER_SOLICITED, NOT_REMOVED, 1),
+    (server_versions::RANDOMIZE_SIZE_AND_PRICE, NOT_REMOVED, 2),
+    (server_versions::PEGGED_TO_BENCHMARK, NOT_REMOVED, 8), // conditions count and adjusted order fields
+    (server_versions::EXT_OPERATOR, NOT_REMOVED, 1),
+    (server_versions::SOFT_DOLLAR_TIER, NOT_REMOVED, 2),
+    (server_versions::CASH_QTY, NOT_REMOVED, 1),
+    (server_versions::DECISION_MAKER, NOT_REMOVED, 2),
+    (server_versions::MIFID_EXECUTION, NOT_REMOVED, 2),
+    (server_versions::AUTO_PRICE_FOR_HEDGE, NOT_REMOVED, 1),
+    (server_versions::ORDER_CONTAINER, NOT_REMOVED, 1),
+    (server_versions::D_PEG_ORDERS, NOT_REMOVED, 1),
+    (server_versions::PRICE_MGMT_ALGO, NOT_REMOVED, 1),
+    (server_versions::DURATION, NOT_REMOVED, 1),
+    (server_versions::POST_TO_ATS, NOT_REMOVED, 1),
+    (server_versions::AUTO_CANCEL_PARENT, NOT_REMOVED, 1),
+    (server_versions::ADVANCED_ORDER_REJECT, NOT_REMOVED, 1),
+    (server_versions::MANUAL_ORDER_TIME, NOT_REMOVED, 1),
+    (server_versions::CME_TAGGING_FIELDS, NOT_REMOVED, 1),
+];
+
+// Number of place order fields sent depending on the contract and order.
+fn place_order_variable_fields(server_version: i32, contract: &Contract, order: &Order) -> usize {
+    let mut fields = 0;
+
+    if contract.is_bag() {
+        let mut leg_fields = 5;
+        if server_version >= server_versions::SSHORT_COMBO_LEGS {
+            leg_fields += 2;
+        }
+        if server_version >= server_versions::SSHORTX_OLD {
+            leg_fields += 1;
+        }
+        fields += 1 + contract.combo_legs.len() * leg_fields;
+
+        if server_version >= server_versions::ORDER_COMBO_LEGS_PRICE {
+            fields += 1 + order.order_combo_legs.len();
+        }
+        if server_version >= server_versions::SMART_COMBO_ROUTING_PARAMS {
+            fields += 1 + 2 * order.smart_combo_routing_params.len();
+        }
+    }
+
+    if order.is_delta_neutral() {
+        if server_version >= server_versions::DELTA_NEUTRAL_CONID {
+            fields += 4;
+        }
+        if server_version >= server_versions::DELTA_NEUTRAL_OPEN_CLOSE {
+            fields += 4;
+        }
+    }
+
+    if server_version >= server_versions::SCALE_ORDERS3 && order.is_scale_order() {
+        fields += 7;
+    }
+
+    if server_version >= server_versions::HEDGE_ORDERS && !order.hedge_type.is_empty() {
+        fields += 1;
+    }
+
+    if server_version >= server_versions::DELTA_NEUTRAL && contract.delta_neutral_contract.is_some() {
+        fields += 3;
+    }
+
+    if server_version >= server_versions::ALGO_ORDERS && !order.algo_strategy.is_empty() {
+        fields += 1 + 2 * order.algo_params.len();
+    }
+
+    if server_version >= server_versions::PEGGED_TO_BENCHMARK {
+        if order.order_type == "PEG BENCH" {
+            fields += 5;
+        }
+
+        if !order.conditions.is_empty() {
+            fields += order.conditions.iter().map(condition_field_count).sum::<usize>() + 2;
+        }
+    }
+
+    if server_version >= server_versions::PEGBEST_PEGMID_OFFSETS {
+        if contract.exchange == "IBKRATS" {
+            fields += 1;
+        }
+        if order.order_type == "PEG BEST" {
+            fields += 2;
+            if order.compete_against_best_offset == COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID {
+                fields += 2;
+            }
+        } else if order.order_type == "PEG MID" {
+            fields += 2;
+        }
+    }
+
+    fields
+}
+
+// Number of fields encode_condition sends for the condition, including its type and conjunction.
+fn condition_field_count(condition: &OrderCondition) -> usize {
+    let fields = match condition {
+        OrderCondition::Price { .. } => 5,
+        OrderCondition::Time { .. } => 2,
+        OrderCondition::Margin { .. } => 2,
+        OrderCondition::Execution { .. } => 3,
+        OrderCondition::Volume { .. } => 4,
+        OrderCondition::PercentChange { .. } => 4,
+    };
+    2 + fields
+}
+
+pub(crate) fn encode_cancel_order(server_version: i32, order_id: i32, order_cancel: &OrderCancel) -> Result<RequestMessage, Error> {
     const VERSION: i32 = 1;
 
     let mut message = RequestMessage::default();
 
     message.push_field(&OutgoingMessages::CancelOrder);
-    message.push_field(&VERSION);
+    if server_version < server_versions::CME_TAGGING_FIELDS {
+        message.push_field(&VERSION);
+    }
     message.push_field(&order_id);
 
-    if server_version >= server_versions::MANUAL_ORDER_TIME {
-        message.push_field(&manual_order_cancel_time);
+    message.push_if_server_version(server_version, server_versions::MANUAL_ORDER_TIME, &order_cancel.manual_order_cancel_time);
+
+    if server_version >= server_versions::CME_TAGGING_FIELDS {
+        message.push_field(&order_cancel.ext_operator);
+        message.push_field(&order_cancel.manual_order_indicator);
     }
 
     Ok(message)
 }
 
-pub(crate) fn encode_global_cancel() -> Result<RequestMessage, Error> {
+pub(crate) fn encode_global_cancel(server_version: i32) -> Result<RequestMessage, Error> {
     const VERSION: i32 = 1;
 
     let mut message = RequestMessage::default();
 
     message.push_field(&OutgoingMessages::RequestGlobalCancel);
-    message.push_field(&VERSION);
+    if server_version < server_versions::CME_TAGGING_FIELDS {
+        message.push_field(&VERSION);
+    } else {
+        let order_cancel = OrderCancel::default();
+        message.push_field(&order_cancel.ext_operator);
+        message.push_field(&order_cancel.manual_order_indicator);
+    }
 
     Ok(message)
 }
 
-pub(crate) fn encode_next_valid_order_id() -> Result<RequestMessage, Error> {
+// Encodes the condition type followed by its fields.
+// https://github.com/InteractiveBrokers/tws-api/blob/817a905d52299028ac5af08581c8ffde7644cea9/source/csharpclient/client/EClient.cs#L1187
+pub(crate) fn encode_condition(message: &mut RequestMessage, condition: &OrderCondition) {
+    message.push_field(&condition.condition_type());
+    message.push_field(&if condition.is_conjunction() { "a" } else { "o" });
+
+    match condition {
+        OrderCondition::Price {
+            contract_id,
+            exchange,
+            price,
+            trigger_method,
+            is_more,
+            ..
+        } => {
+            message.push_field(is_more);
+            message.push_field(price);
+            message.push_field(contract_id);
+            message.push_field(exchange);
+            message.push_field(trigger_method);
+        }
+        OrderCondition::Time { time, is_more, .. } => {
+            message.push_field(is_more);
+            message.push_field(time);
+        }
+        OrderCondition::Margin { percent, is_more, .. } => {
+            message.push_field(is_more);
+            message.push_field(percent);
+        }
+        OrderCondition::Execution {
+            symbol,
+            security_type,
+            exchange,
+            ..
+        } => {
+            message.push_field(security_type);
+            message.push_field(exchange);
+            message.push_field(symbol);
+        }
+        OrderCondition::Volume {
+            contract_id,
+            exchange,
+            volume,
+            is_more,
+            ..
+        } => {
+            message.push_field(is_more);
+            message.push_field(volume);
+            message.push_field(contract_id);
+            message.push_field(exchange);
+        }
+        OrderCondition::PercentChange {
+            contract_id,
+            exchange,
+            change_percent,
+            is_more,
+            ..
+        } => {
+            message.push_field(is_more);
+            message.push_field(change_percent);
+            message.push_field(contract_id);
+            message.push_field(exchange);
+        }
+    }
+}
+
+pub(crate) fn encode_request_ids(num_ids: i32) -> Result<RequestMessage, Error> {
     const VERSION: i32 = 1;
 
     let mut message = RequestMessage::default();
 
     message.push_field(&OutgoingMessages::RequestIds);
     message.push_field(&VERSION);
-    message.push_field(&0);
+    message.push_field(&num_ids);
 
     Ok(message)
 }
@@ -475,9 +654,7 @@ pub(crate) fn encode_executions(server_version: i32, request_id: i32, filter: &E
     message.push_field(&OutgoingMessages::RequestExecutions);
     message.push_field(&VERSION);
 
-    if server_version >= server_versions::EXECUTION_DATA_CHAIN {
-        message.push_field(&request_id);
-    }
+    message.push_if_server_version(server_version, server_versions::EXECUTION_DATA_CHAIN, &request_id);
 
     message.push_field(&filter.client_id);
     message.push_field(&filter.account_code);
@@ -14,3 +14,29 @@ fn f64_max_to_zero() {
     assert_eq!(super::f64_max_to_zero(Some(0.0)), Some(0.0));
     assert_eq!(super::f64_max_to_zero(Some(50.0)), Some(50.0));
 }
+
+#[test]
+fn place_order_field_count() {
+    let contract = Contract::stock("TSLA");
+    let order = order_builder::limit_order(Action::Buy, 100.0, 196.52);
+
+    for (server_version, expected) in [(server_versions::SIZE_RULES, 113), (server_versions::PEGGED_TO_BENCHMARK, 98)] {
+        match encode_place_order(server_version, 13, &contract, &order) {
+            Ok(message) => assert_eq!(message.len(), expected, "fields for server version {server_version}"),
+            Err(err) => assert!(false, "error encoding place order: {err}"),
+        }
+    }
+
+    // algo strategy, count and one tag value pair
+    let mut order = order;
+    order.algo_strategy = "Adaptive".to_owned();
+    order.algo_params = vec![TagValue {
+        tag: "adaptivePriority".to_owned(),
+        value: "Normal".to_owned(),
+    }];
+
+    match encode_place_order(server_versions::SIZE_RULES, 13, &contract, &order) {
+        Ok(message) => assert_eq!(message.len(), 113 + 3, "fields with algo params"),
+        Err(err) => assert!(false, "error encoding place order: {err}"),
+    }
+}
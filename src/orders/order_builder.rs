@@ -1,4 +1,4 @@
-use super::{Action, Order, OrderComboLeg, TagValue};
+use super::{Action, AlgoStrategy, OcaType, Order, OrderComboLeg, OrderCondition, TagValue};
 
 /// An auction order is entered into the electronic trading system during the pre-market opening period for execution at the
 /// Calculated Opening Price (COP). If your order is not filled on the open, the order is re-submitted as a limit order with
@@ -42,6 +42,18 @@ pub fn market_order(action: Action, quantity: f64) -> Order {
     }
 }
 
+/// A Market order for an amount of cash instead of a number of shares, e.g. to invest $500 in fractional shares of a stock.
+/// The cash amount is in the currency of the contract.
+/// Requires TWS or IBG 963+
+pub fn market_order_cash(action: Action, cash_qty: f64) -> Order {
+    Order {
+        action,
+        order_type: "MKT".to_owned(),
+        cash_qty: Some(cash_qty),
+        ..Order::default()
+    }
+}
+
 /// A Market if Touched (MIT) is an order to buy (or sell) a contract below (or above) the market. Its purpose is to take advantage
 /// of sudden or unexpected changes in share or other prices and provides investors with a trigger price to set an order in motion.
 /// Investors may be waiting for excessive strength (or weakness) to cease, which might be represented by a specific price point.
@@ -390,7 +402,7 @@ pub fn bracket_order(
     limit_price: f64,
     take_profit_limit_price: f64,
     stop_loss_price: f64,
-) -> Vec<Order> {
+) -> [Order; 3] {
     //This will be our main or "parent" order
     let parent = Order {
         order_id: parent_order_id,
@@ -427,7 +439,90 @@ pub fn bracket_order(
         ..Order::default()
     };
 
-    vec![parent, take_profit, stop_loss]
+    [parent, take_profit, stop_loss]
+}
+
+/// Returns a copy of `order` with a new limit price. Use with [Client::modify_order](crate::Client::modify_order).
+pub fn with_limit_price(order: &Order, limit_price: f64) -> Order {
+    Order {
+        limit_price: Some(limit_price),
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` with a new total quantity. Use with [Client::modify_order](crate::Client::modify_order).
+/// The quantity is the size of the whole order, including any portion already filled.
+pub fn with_quantity(order: &Order, quantity: f64) -> Order {
+    Order {
+        total_quantity: quantity,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` that can also trigger or fill outside of regular trading hours.
+pub fn with_outside_rth(order: &Order) -> Order {
+    Order {
+        outside_rth: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` that is not visible in the market depth. Only applies to orders routed to NASDAQ.
+pub fn with_hidden(order: &Order) -> Order {
+    Order {
+        hidden: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` that must be filled in its entirety in a single execution.
+pub fn with_all_or_none(order: &Order) -> Order {
+    Order {
+        all_or_none: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` as a Sweep-to-Fill order, filled at the best prices across exchanges.
+pub fn with_sweep_to_fill(order: &Order) -> Order {
+    Order {
+        sweep_to_fill: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` as an ISE Block order.
+pub fn with_block_order(order: &Order) -> Order {
+    Order {
+        block_order: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` leaving the broker time and price discretion. Only applies to orders routed to IBUSOPT.
+pub fn with_not_held(order: &Order) -> Order {
+    Order {
+        not_held: true,
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` that becomes active at `good_after_time`, formatted as "yyyymmdd hh:mm:ss {optional time zone}".
+pub fn with_good_after_time(order: &Order, good_after_time: &str) -> Order {
+    Order {
+        good_after_time: good_after_time.to_owned(),
+        ..order.clone()
+    }
+}
+
+/// Returns a copy of `order` that stays active until `good_till_date`, formatted as "yyyymmdd hh:mm:ss {optional time zone}".
+/// The time in force is set to GTD.
+pub fn with_good_till_date(order: &Order, good_till_date: &str) -> Order {
+    Order {
+        tif: "GTD".to_owned(),
+        good_till_date: good_till_date.to_owned(),
+        ..order.clone()
+    }
 }
 
 /// Products:CFD, FUT, FOP, OPT, STK, WAR
@@ -675,6 +770,15 @@ pub fn one_cancels_all(oca_group: &str, mut oca_orders: Vec<Order>, oca_type: i3
     oca_orders
 }
 
+/// Assigns orders to the One-Cancels-All group oca_group, see [one_cancels_all]. Placing the orders, e.g. with
+/// [Client::place_oca_orders](crate::Client::place_oca_orders), creates a linked group.
+pub fn oca_group(orders: &mut [Order], oca_group: &str, oca_type: OcaType) {
+    for order in orders {
+        order.oca_group = oca_group.to_owned();
+        order.oca_type = oca_type as i32;
+    }
+}
+
 /// Specific to US options, investors are able to create and enter Volatility-type orders for options and combinations rather than price orders.
 /// Option traders may wish to trade and position for movements in the price of the option determined by its implied volatility. Because
 /// implied volatility is a key determinant of the premium on an option, traders position in specific contract months in an effort to take
@@ -704,6 +808,12 @@ pub fn market_f_hedge(parent_order_id: i32, action: Action) -> Order {
     order
 }
 
+/// The Pegged to Benchmark order is similar to the Pegged to Stock order for options, except that it can be pegged to a benchmark
+/// other than the stock. The price of the order moves by pegged_change_amount whenever the price of the reference contract changes
+/// by reference_change_amount, decreasing it instead when pegged_change_amount_decrease is set. The order stays active as long as the
+/// reference contract trades within the lower and upper range.
+/// Requires a server supporting pegged-to-benchmark orders.
+/// Products: STK, OPT
 #[allow(clippy::too_many_arguments)]
 pub fn pegged_to_benchmark(
     action: Action,
@@ -788,7 +898,7 @@ pub fn attach_adjustable_to_trail(
     order.trigger_price = Some(trigger_price); // When trigger price is penetrated
     order.adjusted_order_type = "TRAIL".to_owned(); // The parent order will be turned into a TRAIL order
     order.adjusted_stop_price = Some(adjusted_stop_price); // With a stop price of ...
-    order.adjustable_trailing_unit = trail_unit; // trailing by and amount (0) or a percent (100) ...
+    order.adjustable_trailing_unit = trail_unit; // trailing by an amount (0) or a percent (1) ...
     order.adjusted_trailing_amount = Some(adjusted_trail_amount); // of ...
 
     order
@@ -801,106 +911,67 @@ pub fn what_if_limit_order(action: Action, quantity: f64, limit_price: f64) -> O
     order
 }
 
-// https://github.com/InteractiveBrokers/tws-api/blob/07e54ceecda2c9cbd6ffb5f524894f0c837a9ecb/source/csharpclient/client/ContractCondition.cs
-// pub fn price_condition(contract_id: i32, exchange: &str, price: f64, is_more: bool, is_conjunction: bool) -> PriceCondition
-// {
-//     //! [price_condition]
-//     //Conditions have to be created via the OrderCondition.Create
-//     PriceCondition priceCondition = (PriceCondition)OrderCondition.Create(OrderConditionType.Price);
-//     //When this contract...
-//     priceCondition.ConId = conId;
-//     //traded on this exchange
-//     priceCondition.Exchange = exchange;
-//     //has a price above/below
-//     priceCondition.IsMore = isMore;
-//     //this quantity
-//     priceCondition.Price = price;
-//     //AND | OR next condition (will be ignored if no more conditions are added)
-//     priceCondition.IsConjunctionConnection = isConjunction;
-//     //! [price_condition]
-//     return priceCondition;
-// }
-
-//     public static ExecutionCondition ExecutionCondition(string symbol, string secType, string exchange, bool isConjunction)
-//     {
-//         //! [execution_condition]
-//         ExecutionCondition execCondition = (ExecutionCondition)OrderCondition.Create(OrderConditionType.Execution);
-//         //When an execution on symbol
-//         execCondition.Symbol = symbol;
-//         //at exchange
-//         execCondition.Exchange = exchange;
-//         //for this secType
-//         execCondition.SecType = secType;
-//         //AND | OR next condition (will be ignored if no more conditions are added)
-//         execCondition.IsConjunctionConnection = isConjunction;
-//         //! [execution_condition]
-//         return execCondition;
-//     }
-
-//     public static MarginCondition MarginCondition(int percent, bool isMore, bool isConjunction)
-//     {
-//         //! [margin_condition]
-//         MarginCondition marginCondition = (MarginCondition)OrderCondition.Create(OrderConditionType.Margin);
-//         //If margin is above/below
-//         marginCondition.IsMore = isMore;
-//         //given percent
-//         marginCondition.Percent = percent;
-//         //AND | OR next condition (will be ignored if no more conditions are added)
-//         marginCondition.IsConjunctionConnection = isConjunction;
-//         //! [margin_condition]
-//         return marginCondition;
-//     }
-
-//     public static PercentChangeCondition PercentageChangeCondition(double pctChange, int conId, string exchange, bool isMore, bool isConjunction)
-//     {
-//         //! [percentage_condition]
-//         PercentChangeCondition pctChangeCondition = (PercentChangeCondition)OrderCondition.Create(OrderConditionType.PercentCange);
-//         //If there is a price percent change measured against last close price above or below...
-//         pctChangeCondition.IsMore = isMore;
-//         //this amount...
-//         pctChangeCondition.ChangePercent = pctChange;
-//         //on this contract
-//         pctChangeCondition.ConId = conId;
-//         //when traded on this exchange...
-//         pctChangeCondition.Exchange = exchange;
-//         //AND | OR next condition (will be ignored if no more conditions are added)
-//         pctChangeCondition.IsConjunctionConnection = isConjunction;
-//         //! [percentage_condition]
-//         return pctChangeCondition;
-//     }
-
-//     public static TimeCondition TimeCondition(string time, bool isMore, bool isConjunction)
-//     {
-//         //! [time_condition]
-//         TimeCondition timeCondition = (TimeCondition)OrderCondition.Create(OrderConditionType.Time);
-//         //Before or after...
-//         timeCondition.IsMore = isMore;
-//         //this time..
-//         timeCondition.Time = time;
-//         //AND | OR next condition (will be ignored if no more conditions are added)
-//         timeCondition.IsConjunctionConnection = isConjunction;
-//         //! [time_condition]
-//         return timeCondition;
-//     }
-
-//     public static VolumeCondition VolumeCondition(int conId, string exchange, bool isMore, int volume, bool isConjunction)
-//     {
-//         //! [volume_condition]
-//         VolumeCondition volCond = (VolumeCondition)OrderCondition.Create(OrderConditionType.Volume);
-//         //Whenever contract...
-//         volCond.ConId = conId;
-//         //When traded at
-//         volCond.Exchange = exchange;
-//         //reaches a volume higher/lower
-//         volCond.IsMore = isMore;
-//         //than this...
-//         volCond.Volume = volume;
-//         //AND | OR next condition (will be ignored if no more conditions are added)
-//         volCond.IsConjunctionConnection = isConjunction;
-//         //! [volume_condition]
-//         return volCond;
-
-//     }
+/// Condition met when the price of the contract traded on the exchange is above (`is_more`) or below the price.
+pub fn price_condition(contract_id: i32, exchange: &str, price: f64, is_more: bool, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::Price {
+        contract_id,
+        exchange: exchange.to_owned(),
+        price,
+        trigger_method: 0,
+        is_more,
+        is_conjunction,
+    }
+}
+
+/// Condition met when a trade in the symbol of the security type is executed on the exchange.
+pub fn execution_condition(symbol: &str, security_type: &str, exchange: &str, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::Execution {
+        symbol: symbol.to_owned(),
+        security_type: security_type.to_owned(),
+        exchange: exchange.to_owned(),
+        is_conjunction,
+    }
+}
+
+/// Condition met when the margin cushion of the account is above (`is_more`) or below the percent.
+pub fn margin_condition(percent: i32, is_more: bool, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::Margin {
+        percent,
+        is_more,
+        is_conjunction,
+    }
+}
+
+/// Condition met when the change in price of the contract, measured against the last close price, is above (`is_more`) or below the percentage.
+pub fn percentage_change_condition(change_percent: f64, contract_id: i32, exchange: &str, is_more: bool, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::PercentChange {
+        contract_id,
+        exchange: exchange.to_owned(),
+        change_percent,
+        is_more,
+        is_conjunction,
+    }
+}
+
+/// Condition met after (`is_more`) or before the time, formatted as yyyymmdd hh:mm:ss with an optional time zone.
+pub fn time_condition(time: &str, is_more: bool, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::Time {
+        time: time.to_owned(),
+        is_more,
+        is_conjunction,
+    }
+}
+
+/// Condition met when the volume traded of the contract on the exchange is above (`is_more`) or below the volume.
+pub fn volume_condition(contract_id: i32, exchange: &str, is_more: bool, volume: i32, is_conjunction: bool) -> OrderCondition {
+    OrderCondition::Volume {
+        contract_id,
+        exchange: exchange.to_owned(),
+        volume,
+        is_more,
+        is_conjunction,
+    }
+}
 
 pub fn limit_ibkrats(action: Action, quantity: f64, limit_price: f64) -> Order {
     Order {
@@ -985,3 +1056,50 @@ pub fn peg_mid_order(
         ..Order::default()
     }
 }
+
+/// VWAP (Volume Weighted Average Price) algo order. Seeks to achieve the volume weighted average price between start_time and end_time,
+/// e.g. 09:30:00 US/Eastern. The order may continue past end_time to complete, and takes liquidity when needed.
+/// Products: STK
+pub fn vwap(action: Action, quantity: f64, limit_price: f64, max_pct_vol: f64, start_time: &str, end_time: &str) -> Order {
+    Order {
+        action,
+        order_type: "LMT".to_owned(),
+        total_quantity: quantity,
+        limit_price: Some(limit_price),
+        algo_strategy: AlgoStrategy::Vwap.to_string(),
+        algo_params: vec![
+            tag_value("maxPctVol", max_pct_vol),
+            tag_value("startTime", start_time),
+            tag_value("endTime", end_time),
+            tag_value("allowPastEndTime", 1),
+            tag_value("noTakeLiq", 0),
+        ],
+        ..Order::default()
+    }
+}
+
+/// Percentage of Volume algo order. Participates in the volume of the contract at pct_vol, e.g. 0.1 for 10%, between start_time and end_time.
+/// Products: STK
+pub fn percent_of_volume(action: Action, quantity: f64, limit_price: f64, pct_vol: f64, start_time: &str, end_time: &str) -> Order {
+    Order {
+        action,
+        order_type: "LMT".to_owned(),
+        total_quantity: quantity,
+        limit_price: Some(limit_price),
+        algo_strategy: AlgoStrategy::PercentOfVolume.to_string(),
+        algo_params: vec![
+            tag_value("pctVol", pct_vol),
+            tag_value("startTime", start_time),
+            tag_value("endTime", end_time),
+            tag_value("noTakeLiq", 0),
+        ],
+        ..Order::default()
+    }
+}
+
+fn tag_value(tag: &str, value: impl ToString) -> TagValue {
+    TagValue {
+        tag: tag.to_owned(),
+        value: value.to_string(),
+    }
+}
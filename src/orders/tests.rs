@@ -35,7 +35,7 @@ fn place_order() {
 
     let result = client.place_order(order_id, &contract, &order);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(
         request_messages[0].encode().replace('\0', "|"),
@@ -194,7 +194,7 @@ fn place_order() {
 
     if let Some(OrderNotification::OrderStatus(order_status)) = notifications.next() {
         assert_eq!(order_status.order_id, 13, "order_status.order_id");
-        assert_eq!(order_status.status, "PreSubmitted", "order_status.status");
+        assert_eq!(order_status.status, Status::PreSubmitted, "order_status.status");
         assert_eq!(order_status.filled, 0.0, "order_status.filled");
         assert_eq!(order_status.remaining, 100.0, "order_status.remaining");
         assert_eq!(order_status.average_fill_price, 0.0, "order_status.average_fill_price");
@@ -260,7 +260,7 @@ fn place_order() {
 
     if let Some(OrderNotification::OrderStatus(order_status)) = notifications.next() {
         assert_eq!(order_status.order_id, 13, "order_status.order_id");
-        assert_eq!(order_status.status, "Filled", "order_status.status");
+        assert_eq!(order_status.status, Status::Filled, "order_status.status");
         assert_eq!(order_status.filled, 100.0, "order_status.filled");
         assert_eq!(order_status.remaining, 0.0, "order_status.remaining");
         assert_eq!(order_status.average_fill_price, 196.52, "order_status.average_fill_price");
@@ -294,6 +294,333 @@ fn place_order() {
     }
 }
 
+#[test]
+fn place_order_with_conditions() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("AAPL");
+
+    let mut order = order_builder::limit_order(super::Action::Buy, 100.0, 150.0);
+    order.conditions = vec![
+        order_builder::price_condition(265598, "SMART", 145.5, false, true),
+        order_builder::time_condition("20240315 10:30:00 US/Eastern", true, false),
+    ];
+    order.conditions_ignore_rth = true;
+
+    let result = client.place_order(13, &contract, &order);
+    assert!(result.is_ok(), "failed to place order: {}", result.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    let encoded = request_messages[0].encode_simple();
+
+    assert!(
+        encoded.contains("|2|1|a|0|145.5|265598|SMART|0|3|o|1|20240315 10:30:00 US/Eastern|1|0|"),
+        "conditions not encoded: {encoded}"
+    );
+}
+
+#[test]
+fn place_order_with_each_condition_type() {
+    let conditions = vec![
+        order_builder::price_condition(265598, "SMART", 145.5, true, true),
+        order_builder::time_condition("20240315 10:30:00 US/Eastern", true, false),
+        order_builder::margin_condition(30, false, true),
+        order_builder::execution_condition("AAPL", "STK", "SMART", false),
+        order_builder::volume_condition(265598, "SMART", true, 100000, true),
+        order_builder::percentage_change_condition(2.5, 265598, "SMART", false, false),
+    ];
+
+    for condition in conditions {
+        let mut order = order_builder::limit_order(super::Action::Buy, 100.0, 150.0);
+        order.conditions = vec![condition.clone()];
+
+        let result = encode_placed_order(server_versions::SIZE_RULES, &order);
+        assert!(result.is_ok(), "failed to place order with {condition:?}: {}", result.err().unwrap());
+    }
+}
+
+#[test]
+fn order_conditions_round_trip() {
+    let conditions = vec![
+        order_builder::price_condition(265598, "SMART", 145.5, true, true),
+        order_builder::time_condition("20240315 10:30:00 US/Eastern", true, false),
+        order_builder::margin_condition(30, false, true),
+        order_builder::execution_condition("AAPL", "STK", "SMART", false),
+        order_builder::volume_condition(265598, "SMART", true, 100000, true),
+        order_builder::percentage_change_condition(2.5, 265598, "SMART", false, false),
+    ];
+
+    let mut request = RequestMessage::default();
+    for condition in &conditions {
+        encoders::encode_condition(&mut request, condition);
+    }
+
+    let mut message = ResponseMessage::from(&request.encode());
+    for (i, condition) in conditions.iter().enumerate() {
+        match decoders::decode_condition(&mut message) {
+            Ok(decoded) => assert_eq!(&decoded, condition, "conditions[{i}]"),
+            Err(err) => assert!(false, "error decoding conditions[{i}]: {err}"),
+        }
+    }
+}
+
+// Places the order and returns the encoded request.
+fn encode_placed_order(server_version: i32, order: &Order) -> Result<String, Error> {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_version);
+
+    let _notifications = client.place_order(13, &Contract::stock("AAPL"), order)?;
+
+    let request_messages = client.message_bus.lock().request_messages();
+    Ok(request_messages[0].encode_simple())
+}
+
+#[test]
+fn place_pegged_orders() {
+    let order = order_builder::pegged_to_midpoint(super::Action::Buy, 100.0, 0.02, 150.0);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(encoded.contains("|BUY|100|PEG MID|150|0.02|"), "PEG MID not encoded: {encoded}"),
+        Err(err) => assert!(false, "failed to place PEG MID order: {err}"),
+    }
+
+    let order = order_builder::relative_pegged_to_primary(super::Action::Buy, 100.0, 151.0, 0.01);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(encoded.contains("|BUY|100|REL|151|0.01|"), "REL not encoded: {encoded}"),
+        Err(err) => assert!(false, "failed to place REL order: {err}"),
+    }
+
+    let order = order_builder::pegged_to_benchmark(super::Action::Buy, 100.0, 10.0, true, 0.1, 1.0, 208813720, "ISLAND", 150.0, 140.0, 160.0);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => {
+            assert!(encoded.contains("|BUY|100|PEG BENCH|"), "PEG BENCH not encoded: {encoded}");
+            assert!(
+                encoded.contains("|208813720|1|0.1|1|ISLAND|0|"),
+                "pegged to benchmark fields not encoded: {encoded}"
+            );
+        }
+        Err(err) => assert!(false, "failed to place PEG BENCH order: {err}"),
+    }
+
+    // benchmark fields are only sent for PEG BENCH orders
+    let order = order_builder::pegged_to_midpoint(super::Action::Buy, 100.0, 0.02, 150.0);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(!encoded.contains("ISLAND"), "benchmark fields encoded for PEG MID: {encoded}"),
+        Err(err) => assert!(false, "failed to place PEG MID order: {err}"),
+    }
+}
+
+#[test]
+fn place_pegged_to_benchmark_not_supported() {
+    let order = order_builder::pegged_to_benchmark(super::Action::Buy, 100.0, 10.0, true, 0.1, 1.0, 208813720, "ISLAND", 150.0, 140.0, 160.0);
+
+    let result = encode_placed_order(server_versions::PEGGED_TO_BENCHMARK - 1, &order);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+}
+
+#[test]
+fn place_adjustable_stop_orders() {
+    let mut parent = order_builder::stop(super::Action::Buy, 100.0, 90.0);
+    parent.order_id = 13;
+
+    let order = order_builder::attach_adjustable_to_stop(&parent, 85.0, 95.0, 92.0);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => {
+            assert!(encoded.contains("|SELL|100|STP||85|"), "attached stop not encoded: {encoded}");
+            assert!(encoded.contains("|0|STP|95||92|||0|"), "adjusted stop not encoded: {encoded}");
+        }
+        Err(err) => assert!(false, "failed to place adjustable stop: {err}"),
+    }
+
+    let order = order_builder::attach_adjustable_to_stop_limit(&parent, 85.0, 95.0, 92.0, 91.5);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(
+            encoded.contains("|0|STP LMT|95||92|91.5||0|"),
+            "adjusted stop limit not encoded: {encoded}"
+        ),
+        Err(err) => assert!(false, "failed to place adjustable stop limit: {err}"),
+    }
+
+    let order = order_builder::attach_adjustable_to_trail(&parent, 85.0, 95.0, 92.0, 1.5, 1);
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(encoded.contains("|0|TRAIL|95||92||1.5|1|"), "adjusted trail not encoded: {encoded}"),
+        Err(err) => assert!(false, "failed to place adjustable trail: {err}"),
+    }
+
+    let order = order_builder::attach_adjustable_to_stop(&parent, 85.0, 95.0, 92.0);
+    let result = encode_placed_order(server_versions::PEGGED_TO_BENCHMARK - 1, &order);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+}
+
+#[test]
+fn place_cash_quantity_order() {
+    let order = order_builder::market_order_cash(super::Action::Buy, 500.0);
+    let without_cash_qty = Order {
+        cash_qty: None,
+        ..order.clone()
+    };
+
+    match (
+        encode_placed_order(server_versions::SIZE_RULES, &order),
+        encode_placed_order(server_versions::SIZE_RULES, &without_cash_qty),
+    ) {
+        (Ok(encoded), Ok(without)) => {
+            assert!(encoded.contains("|BUY|0|MKT|"), "market order not encoded: {encoded}");
+            assert_eq!(encoded.replacen("|500|", "||", 1), without, "cash quantity field");
+        }
+        (result, _) => assert!(false, "failed to place cash quantity order: {result:?}"),
+    }
+
+    // the field is not sent to older servers, which reject cash quantities
+    let contract = Contract::stock("AAPL");
+    let old_version = server_versions::CASH_QTY - 1;
+    match (
+        encoders::encode_place_order(old_version, 13, &contract, &order),
+        encoders::encode_place_order(old_version, 13, &contract, &without_cash_qty),
+    ) {
+        (Ok(encoded), Ok(without)) => assert_eq!(encoded.encode_simple(), without.encode_simple(), "cash quantity sent to old server"),
+        (result, _) => assert!(false, "failed to encode cash quantity order: {:?}", result.err()),
+    }
+
+    let result = encode_placed_order(old_version, &order);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+}
+
+#[test]
+fn place_order_with_manual_order_indicator() {
+    let order = Order {
+        manual_order_indicator: Some(1),
+        ..order_builder::limit_order(super::Action::Buy, 100.0, 185.5)
+    };
+    let without_indicator = Order {
+        manual_order_indicator: None,
+        ..order.clone()
+    };
+
+    match (
+        encode_placed_order(server_versions::CME_TAGGING_FIELDS, &order),
+        encode_placed_order(server_versions::CME_TAGGING_FIELDS, &without_indicator),
+    ) {
+        (Ok(encoded), Ok(without)) => {
+            assert!(encoded.ends_with("|1|"), "manual order indicator not encoded last: {encoded}");
+            assert_eq!(format!("{}|", &encoded[..encoded.len() - 2]), without, "manual order indicator field");
+        }
+        (result, _) => assert!(false, "failed to place order with manual order indicator: {result:?}"),
+    }
+
+    // older servers don't receive the field, and reject it when set
+    let result = encode_placed_order(server_versions::CME_TAGGING_FIELDS - 1, &without_indicator);
+    assert!(result.is_ok(), "failed to place order: {result:?}");
+
+    let result = encode_placed_order(server_versions::CME_TAGGING_FIELDS - 1, &order);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+}
+
+#[test]
+fn place_fractional_quantity_order() {
+    let order = order_builder::market_order(super::Action::Buy, 2.5);
+    match encode_placed_order(server_versions::FRACTIONAL_POSITIONS, &order) {
+        Ok(encoded) => assert!(encoded.contains("|BUY|2.5|MKT|"), "fractional quantity not encoded: {encoded}"),
+        Err(err) => assert!(false, "failed to place fractional quantity order: {err}"),
+    }
+
+    // whole quantities are sent as integers to older servers
+    let order = order_builder::market_order(super::Action::Buy, 100.0);
+    match encode_placed_order(server_versions::FRACTIONAL_POSITIONS - 1, &order) {
+        Ok(encoded) => assert!(encoded.contains("|BUY|100|MKT|"), "quantity not encoded: {encoded}"),
+        Err(err) => assert!(false, "failed to place order: {err}"),
+    }
+
+    let order = order_builder::market_order(super::Action::Buy, 2.5);
+    let result = encode_placed_order(server_versions::FRACTIONAL_POSITIONS - 1, &order);
+    assert!(
+        matches!(result, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error: {result:?}"
+    );
+}
+
+#[test]
+fn place_order_with_flags() {
+    let order = order_builder::market_order(super::Action::Buy, 100.0);
+    let encoded = encode_placed_order(server_versions::SIZE_RULES, &order).expect("failed to place order");
+
+    // field slots of the flags, see the place_order fixture
+    let flagged_orders = [
+        (order_builder::with_block_order(&order), vec![(29, "1")]),
+        (order_builder::with_sweep_to_fill(&order), vec![(30, "1")]),
+        (order_builder::with_outside_rth(&order), vec![(33, "1")]),
+        (order_builder::with_hidden(&order), vec![(34, "1")]),
+        (
+            order_builder::with_good_after_time(&order, "20230224 09:30:00 US/Eastern"),
+            vec![(37, "20230224 09:30:00 US/Eastern")],
+        ),
+        (
+            order_builder::with_good_till_date(&order, "20230224 16:00:00 US/Eastern"),
+            vec![(21, "GTD"), (38, "20230224 16:00:00 US/Eastern")],
+        ),
+        (order_builder::with_all_or_none(&order), vec![(50, "1")]),
+        (order_builder::with_not_held(&order), vec![(81, "1")]),
+    ];
+
+    let fields: Vec<&str> = encoded.split('|').collect();
+    for (flagged, expected) in flagged_orders {
+        match encode_placed_order(server_versions::SIZE_RULES, &flagged) {
+            Ok(flagged_encoded) => {
+                let changed: Vec<(usize, &str)> = flagged_encoded
+                    .split('|')
+                    .enumerate()
+                    .filter(|(i, field)| fields.get(*i) != Some(field))
+                    .collect();
+                assert_eq!(changed, expected, "changed fields of {flagged_encoded}");
+            }
+            Err(err) => assert!(false, "failed to place flagged order: {err}"),
+        }
+    }
+}
+
+#[test]
+fn place_algo_orders() {
+    let order = order_builder::vwap(super::Action::Buy, 100.0, 150.0, 0.2, "09:30:00 US/Eastern", "16:00:00 US/Eastern");
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(
+            encoded.contains("|Vwap|5|maxPctVol|0.2|startTime|09:30:00 US/Eastern|endTime|16:00:00 US/Eastern|allowPastEndTime|1|noTakeLiq|0|"),
+            "VWAP params not encoded: {encoded}"
+        ),
+        Err(err) => assert!(false, "failed to place VWAP order: {err}"),
+    }
+
+    let order = order_builder::percent_of_volume(super::Action::Sell, 100.0, 150.0, 0.1, "", "");
+    match encode_placed_order(server_versions::SIZE_RULES, &order) {
+        Ok(encoded) => assert!(
+            encoded.contains("|PctVol|4|pctVol|0.1|startTime||endTime||noTakeLiq|0|"),
+            "percent of volume params not encoded: {encoded}"
+        ),
+        Err(err) => assert!(false, "failed to place percent of volume order: {err}"),
+    }
+
+    assert_eq!(AlgoStrategy::ArrivalPrice.to_string(), "ArrivalPx");
+}
+
 #[test]
 fn cancel_order() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
@@ -309,7 +636,7 @@ fn cancel_order() {
     let order_id = 41;
     let results = client.cancel_order(order_id, "");
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode(), "4\01\041\0");
 
@@ -319,7 +646,7 @@ fn cancel_order() {
 
     if let Some(CancelOrderResult::OrderStatus(order_status)) = results.next() {
         assert_eq!(order_status.order_id, 41, "order_status.order_id");
-        assert_eq!(order_status.status, "Cancelled", "order_status.status");
+        assert_eq!(order_status.status, Status::Cancelled, "order_status.status");
         assert_eq!(order_status.filled, 0.0, "order_status.filled");
         assert_eq!(order_status.remaining, 100.0, "order_status.remaining");
         assert_eq!(order_status.average_fill_price, 0.0, "order_status.average_fill_price");
@@ -336,6 +663,97 @@ fn cancel_order() {
     }
 }
 
+#[test]
+fn cancel_order_with_manual_order_cancel_time() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["3|41|Cancelled|0|100|0|71270927|0|0|100||0||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::MANUAL_ORDER_TIME);
+
+    let results = client.cancel_order(41, "20240315 09:30:00");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode(), "4\01\041\020240315 09:30:00\0");
+
+    assert!(results.is_ok(), "failed to cancel order: {}", results.err().unwrap());
+
+    if let Some(CancelOrderResult::OrderStatus(order_status)) = results.unwrap().next() {
+        assert_eq!(order_status.status, Status::Cancelled, "order_status.status");
+    } else {
+        assert!(false, "expected order status after cancel");
+    }
+}
+
+#[test]
+fn cancel_order_manual_order_cancel_time_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.cancel_order(41, "20240315 09:30:00");
+
+    assert!(
+        matches!(results, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error for manual order cancel time"
+    );
+    assert!(client.message_bus.lock().request_messages().is_empty(), "no request expected");
+}
+
+#[test]
+fn cancel_order_with_cme_tagging_fields() {
+    let order_cancel = OrderCancel {
+        manual_order_cancel_time: "20240315 09:30:00".to_owned(),
+        ext_operator: "trader-7".to_owned(),
+        manual_order_indicator: Some(1),
+    };
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["3|41|Cancelled|0|100|0|71270927|0|0|100||0||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::CME_TAGGING_FIELDS);
+
+    let results = client.cancel_order_with(41, &order_cancel);
+    assert!(results.is_ok(), "failed to cancel order: {}", results.err().unwrap());
+
+    // the message version is no longer sent
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "4|41|20240315 09:30:00|trader-7|1|");
+
+    // older servers don't receive the tagging fields, and reject them when set
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["3|41|Cancelled|0|100|0|71270927|0|0|100||0||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::MANUAL_ORDER_TIME);
+
+    let results = client.cancel_order_with(41, &order_cancel);
+    assert!(
+        matches!(results, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error for ext operator"
+    );
+
+    let results = client.cancel_order_with(
+        41,
+        &OrderCancel {
+            manual_order_cancel_time: "20240315 09:30:00".to_owned(),
+            ..OrderCancel::default()
+        },
+    );
+    assert!(results.is_ok(), "failed to cancel order: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "4|1|41|20240315 09:30:00|");
+}
+
 #[test]
 fn global_cancel() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
@@ -347,10 +765,24 @@ fn global_cancel() {
 
     let results = super::global_cancel(&mut client);
 
-    let request_messages = client.message_bus.borrow_mut().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode(), "58\01\0");
     assert!(results.is_ok(), "failed to cancel order: {}", results.err().unwrap());
+
+    // the message version is replaced by the ext operator and manual order indicator
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::CME_TAGGING_FIELDS);
+
+    let results = super::global_cancel(&client);
+    assert!(results.is_ok(), "failed to cancel orders: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "58|||");
 }
 
 #[test]
@@ -364,7 +796,7 @@ fn next_valid_order_id() {
 
     let results = super::next_valid_order_id(&mut client);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode(), "8\01\00\0");
 
@@ -372,6 +804,28 @@ fn next_valid_order_id() {
     assert_eq!(43, results.unwrap(), "next order id");
 }
 
+#[test]
+fn request_ids() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["9|1|43||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.request_ids(5);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "8|1|5|", "request ids");
+
+    assert!(results.is_ok(), "failed to request ids: {}", results.err().unwrap());
+    assert_eq!(results.unwrap(), 43, "next valid order id");
+
+    assert_eq!(client.next_order_id(), 43, "client.next_order_id()");
+    assert_eq!(client.next_order_id(), 44, "client.next_order_id()");
+}
+
 #[test]
 fn completed_orders() {
     let message_bus = RefCell::new(Box::new(MessageBusStub{
@@ -387,7 +841,7 @@ fn completed_orders() {
     let api_only = true;
     let results = super::completed_orders(&mut client, api_only);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode(), "99\01\0");
 
@@ -508,22 +962,95 @@ fn completed_orders() {
     }
 }
 
+#[test]
+fn completed_orders_end_at_marker() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub{
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "101|265598|AAPL|STK||0|?||SMART|USD|AAPL|NMS|BUY|0|MKT|0.0|0.0|DAY||DU1236109||0||1824933227|0|0|0|||||||||||0||-1||||||2147483647|0|0||3|0||0|None||0|0|0||0|0||||0|0|0|2147483647|2147483647||||IB|0|0||0|Filled|0|0|0|1.7976931348623157E308|1.7976931348623157E308|0|1|0||100|2147483647|0|Not an insider or substantial shareholder|0|0|9223372036854775807|20230306 12:28:30 America/Los_Angeles|Filled Size: 100|".to_owned(),
+            "101|76792991|TSLA|STK||0|?||SMART|USD|TSLA|NMS|BUY|0|MKT|0.0|0.0|DAY||DU1236109||0||1824933227|0|0|0|||||||||||0||-1||||||2147483647|0|0||3|0||0|None||0|0|0||0|0||||0|0|0|2147483647|2147483647||||IB|0|0||0|Cancelled|0|0|0|1.7976931348623157E308|1.7976931348623157E308|0|1|0||100|2147483647|0|Not an insider or substantial shareholder|0|0|9223372036854775807|20230306 12:28:30 America/Los_Angeles|Cancelled by Trader|".to_owned(),
+            "102|".to_owned(),
+            "3|13|Filled|100|0|196.52|1376327563|0|196.52|100||0||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let results = client.completed_orders(false);
+    assert!(results.is_ok(), "failed to request completed orders: {}", results.err().unwrap());
+
+    let completed: Vec<(String, String)> = results
+        .unwrap()
+        .map(|result| match result {
+            OrderDataResult::OrderData(order_data) => (order_data.contract.symbol.clone(), order_data.order_state.completed_status.clone()),
+            OrderDataResult::OrderStatus(status) => panic!("unexpected order status: {status:?}"),
+        })
+        .collect();
+
+    assert_eq!(
+        completed,
+        vec![
+            ("AAPL".to_owned(), "Filled Size: 100".to_owned()),
+            ("TSLA".to_owned(), "Cancelled by Trader".to_owned())
+        ],
+        "completed orders"
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages[0].encode_simple(), "99|0|", "completed orders request");
+}
+
 #[test]
 fn open_orders() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
         request_messages: RefCell::new(vec![]),
-        response_messages: vec!["9|1|43||".to_owned()],
+        response_messages: vec![
+            "5|13|76792991|TSLA|STK||0|?||SMART|USD|TSLA|NMS|BUY|100|MKT|0.0|0.0|DAY||DU1236109||0||100|1376327563|0|0|0||1376327563.0/DU1236109/100||||||||||0||-1|0||||||2147483647|0|0|0||3|0|0||0|0||0|None||0||||?|0|0||0|0||||||0|0|0|2147483647|2147483647|||0||IB|0|0||0|0|Filled|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.0|||USD||0|0|0|None|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|0||||0|1|0|0|0|||0||".to_owned(),
+            "3|13|Filled|100|0|196.52|1376327563|0|196.52|100||0||".to_owned(),
+            "53|1|".to_owned(),
+        ],
     }));
 
-    let mut client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
 
-    let results = super::open_orders(&mut client);
+    let results = client.open_orders();
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode_simple(), "5|1|");
 
-    assert!(results.is_ok(), "failed to request completed orders: {}", results.err().unwrap());
+    assert!(results.is_ok(), "failed to request open orders: {}", results.err().unwrap());
+
+    let results: Vec<OrderDataResult> = results.unwrap().collect();
+    assert_eq!(results.len(), 2, "results.len()");
+
+    if let OrderDataResult::OrderData(order_data) = &results[0] {
+        assert_eq!(order_data.order_id, 13, "order_data.order_id");
+        assert_eq!(order_data.contract.symbol, "TSLA", "order_data.contract.symbol");
+        assert_eq!(order_data.contract.contract_id, 76792991, "order_data.contract.contract_id");
+        assert_eq!(order_data.order.action, Action::Buy, "order_data.order.action");
+        assert_eq!(order_data.order.total_quantity, 100.0, "order_data.order.total_quantity");
+        assert_eq!(order_data.order.order_type, "MKT", "order_data.order.order_type");
+        assert_eq!(order_data.order_state.status, "Filled", "order_data.order_state.status");
+        assert_eq!(order_data.order_state.commission, Some(1.0), "order_data.order_state.commission");
+        assert_eq!(
+            order_data.order_state.commission_currency, "USD",
+            "order_data.order_state.commission_currency"
+        );
+        assert_eq!(
+            order_data.order_state.initial_margin_before, None,
+            "order_data.order_state.initial_margin_before"
+        );
+    } else {
+        assert!(false, "expected open order data, got: {:?}", results[0]);
+    }
+
+    if let OrderDataResult::OrderStatus(order_status) = &results[1] {
+        assert_eq!(order_status.order_id, 13, "order_status.order_id");
+        assert_eq!(order_status.status, Status::Filled, "order_status.status");
+    } else {
+        assert!(false, "expected order status, got: {:?}", results[1]);
+    }
 }
 
 #[test]
@@ -537,7 +1064,7 @@ fn all_open_orders() {
 
     let results = client.all_open_orders();
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode_simple(), "16|1|");
 
@@ -556,18 +1083,22 @@ fn auto_open_orders() {
     let api_only = true;
     let results = client.auto_open_orders(api_only);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(request_messages[0].encode_simple(), "15|1|1|");
 
-    assert!(results.is_ok(), "failed to request completed orders: {}", results.err().unwrap());
+    assert!(results.is_ok(), "failed to request auto open orders: {}", results.err().unwrap());
 }
 
 #[test]
 fn executions() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
         request_messages: RefCell::new(vec![]),
-        response_messages: vec!["9|1|43||".to_owned()],
+        response_messages: vec![
+            "11|9000|13|76792991|TSLA|STK||0.0|||ISLAND|USD|TSLA|NMS|00025b46.63f8f39c.01.01|20230224  12:04:56|DU1236109|ISLAND|BOT|100|196.52|1376327563|100|0|100|196.52|||||2||".to_owned(),
+            "59|1|00025b46.63f8f39c.01.01|1.0|USD|1.7976931348623157E308|1.7976931348623157E308|||".to_owned(),
+            "55|1|9000|".to_owned(),
+        ],
     }));
 
     let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
@@ -583,15 +1114,41 @@ fn executions() {
     };
     let results = client.executions(filter);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(
         request_messages[0].encode_simple(),
         "7|3|9000|100|xyz|yyyymmdd hh:mm:ss EST|TSLA|STK|ISLAND|BUY|"
     );
 
-    assert!(results.is_ok(), "failed to request completed orders: {}", results.err().unwrap());
-    // assert_eq!(43, results.unwrap(), "next order id");
+    assert!(results.is_ok(), "failed to request executions: {}", results.err().unwrap());
+
+    let results: Vec<ExecutionDataResult> = results.unwrap().collect();
+    assert_eq!(results.len(), 2, "results.len()");
+
+    if let ExecutionDataResult::ExecutionData(execution_data) = &results[0] {
+        let execution = &execution_data.execution;
+
+        assert_eq!(execution_data.request_id, 9000, "execution_data.request_id");
+        assert_eq!(execution_data.contract.symbol, "TSLA", "execution_data.contract.symbol");
+        assert_eq!(execution.order_id, 13, "execution.order_id");
+        assert_eq!(execution.execution_id, "00025b46.63f8f39c.01.01", "execution.execution_id");
+        assert_eq!(execution.exchange, "ISLAND", "execution.exchange");
+        assert_eq!(execution.side, "BOT", "execution.side");
+        assert_eq!(execution.shares, 100.0, "execution.shares");
+        assert_eq!(execution.price, 196.52, "execution.price");
+        assert_eq!(execution.last_liquidity, Liquidity::RemovedLiquidity, "execution.last_liquidity");
+    } else {
+        assert!(false, "expected execution data, got: {:?}", results[0]);
+    }
+
+    if let ExecutionDataResult::CommissionReport(report) = &results[1] {
+        assert_eq!(report.execution_id, "00025b46.63f8f39c.01.01", "report.execution_id");
+        assert_eq!(report.commission, 1.0, "report.commission");
+        assert_eq!(report.currency, "USD", "report.currency");
+    } else {
+        assert!(false, "expected commission report, got: {:?}", results[1]);
+    }
 }
 
 #[test]
@@ -609,7 +1166,7 @@ fn encode_limit_order() {
 
     let results = client.place_order(order_id, &contract, &order);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(
         request_messages[0].encode_simple(),
@@ -619,6 +1176,89 @@ fn encode_limit_order() {
     assert!(results.is_ok(), "failed to place order: {}", results.err().unwrap());
 }
 
+#[test]
+fn modify_limit_order() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.set_next_order_id(12);
+
+    let order_id = client.next_order_id();
+    let contract = contract_samples::future_with_local_symbol();
+    let order = order_builder::limit_order(super::Action::Buy, 10.0, 500.00);
+
+    let results = client.place_order(order_id, &contract, &order);
+    assert!(results.is_ok(), "failed to place order: {}", results.err().unwrap());
+
+    let order = order_builder::with_limit_price(&order, 501.00);
+
+    let results = client.modify_order(order_id, &contract, &order);
+    assert!(results.is_ok(), "failed to modify order: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    let placed = request_messages[0].encode_simple();
+    let modified = request_messages[1].encode_simple();
+
+    let differences: Vec<(&str, &str)> = placed.split('|').zip(modified.split('|')).filter(|(a, b)| a != b).collect();
+
+    assert_eq!(placed.split('|').count(), modified.split('|').count(), "field count");
+    assert_eq!(differences, vec![("500", "501")], "only limit price should change");
+}
+
+#[test]
+fn modify_order_not_placed() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.set_next_order_id(12);
+
+    let contract = contract_samples::future_with_local_symbol();
+    let order = order_builder::limit_order(super::Action::Buy, 10.0, 500.00);
+
+    let results = client.modify_order(12, &contract, &order);
+
+    assert!(results.is_err(), "expected error modifying order that was not placed");
+    assert!(client.message_bus.lock().request_messages().is_empty(), "no request expected");
+}
+
+#[test]
+fn place_bracket_order() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.set_next_order_id(100);
+
+    let contract = contract_samples::future_with_local_symbol();
+
+    let results = client.place_bracket_order(&contract, super::Action::Buy, 10.0, 500.00, 510.00, 495.00);
+    assert!(results.is_ok(), "failed to place bracket order: {}", results.err().unwrap());
+    assert_eq!(results.unwrap().len(), 3, "notification iterators");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 3, "request_messages.len()");
+
+    // order id, action, order type, limit price, aux price, transmit, parent id
+    let fields = |i: usize| {
+        let encoded = request_messages[i].encode_simple();
+        let fields: Vec<String> = encoded.split('|').map(String::from).collect();
+        [1, 16, 18, 19, 20, 27, 28].map(|index| fields[index].clone())
+    };
+
+    assert_eq!(fields(0), ["100", "BUY", "LMT", "500", "", "0", "0"], "parent order");
+    assert_eq!(fields(1), ["101", "SELL", "LMT", "510", "", "0", "100"], "take profit order");
+    assert_eq!(fields(2), ["102", "SELL", "STP", "", "495", "1", "100"], "stop loss order");
+}
+
 #[test]
 fn encode_combo_market_order() {
     let message_bus = RefCell::new(Box::new(MessageBusStub {
@@ -634,7 +1274,7 @@ fn encode_combo_market_order() {
 
     let results = client.place_order(order_id, &contract, &order);
 
-    let request_messages = client.message_bus.borrow().request_messages();
+    let request_messages = client.message_bus.lock().request_messages();
 
     assert_eq!(
         request_messages[0].encode_simple(),
@@ -643,3 +1283,271 @@ fn encode_combo_market_order() {
 
     assert!(results.is_ok(), "failed to place order: {}", results.err().unwrap());
 }
+
+#[test]
+fn decode_order_status() {
+    let mut message = ResponseMessage::from("3\013\0PreSubmitted\00\0100\00\01376327563\00\00\0100\0\00\0\0");
+
+    let results = decoders::decode_order_status(server_versions::SIZE_RULES, &mut message);
+    assert!(results.is_ok(), "failed to decode order status: {}", results.err().unwrap());
+
+    let order_status = results.unwrap();
+    assert_eq!(order_status.order_id, 13, "order_status.order_id");
+    assert_eq!(order_status.status, Status::PreSubmitted, "order_status.status");
+    assert_eq!(order_status.filled, 0.0, "order_status.filled");
+    assert_eq!(order_status.remaining, 100.0, "order_status.remaining");
+    assert_eq!(order_status.fill_ratio(), 0.0, "order_status.fill_ratio()");
+    assert!(!order_status.is_filled(), "order_status.is_filled()");
+
+    let mut message = ResponseMessage::from("3\013\0Filled\0100\00\0196.52\01376327563\00\0196.52\0100\0\00\0\0");
+
+    let results = decoders::decode_order_status(server_versions::SIZE_RULES, &mut message);
+    assert!(results.is_ok(), "failed to decode order status: {}", results.err().unwrap());
+
+    let order_status = results.unwrap();
+    assert_eq!(order_status.status, Status::Filled, "order_status.status");
+    assert_eq!(order_status.filled, 100.0, "order_status.filled");
+    assert_eq!(order_status.remaining, 0.0, "order_status.remaining");
+    assert_eq!(order_status.average_fill_price, 196.52, "order_status.average_fill_price");
+    assert_eq!(order_status.last_fill_price, 196.52, "order_status.last_fill_price");
+    assert_eq!(order_status.perm_id, 1376327563, "order_status.perm_id");
+    assert_eq!(order_status.client_id, 100, "order_status.client_id");
+    assert_eq!(order_status.fill_ratio(), 1.0, "order_status.fill_ratio()");
+    assert!(order_status.is_filled(), "order_status.is_filled()");
+
+    // perm ids exceed i32 on servers sending them as long
+    let mut message = ResponseMessage::from("3\013\0Filled\0100\00\0196.52\03000000000\00\0196.52\0100\0\00\0\0");
+
+    let results = decoders::decode_order_status(server_versions::PERM_ID_AS_LONG, &mut message);
+    assert!(results.is_ok(), "failed to decode order status: {}", results.err().unwrap());
+    assert_eq!(results.unwrap().perm_id, 3_000_000_000, "order_status.perm_id");
+}
+
+#[test]
+fn decode_commission_report() {
+    let mut message = ResponseMessage::from("59\01\000025b46.63f8f39c.01.01\01.0\0USD\01.7976931348623157E308\01.7976931348623157E308\0\0\0");
+
+    match decoders::decode_commission_report(server_versions::SIZE_RULES, &mut message) {
+        Ok(report) => {
+            assert_eq!(report.execution_id, "00025b46.63f8f39c.01.01", "report.execution_id");
+            assert_eq!(report.commission, 1.0, "report.commission");
+            assert_eq!(report.currency, "USD", "report.currency");
+            assert_eq!(report.realized_pnl, None, "report.realized_pnl");
+            assert_eq!(report.yields, None, "report.yields");
+            assert_eq!(report.yield_redemption_date, "", "report.yield_redemption_date");
+        }
+        Err(err) => assert!(false, "failed to decode commission report: {err}"),
+    }
+
+    // bond executions report the yield, closing executions the realized pnl
+    let mut message = ResponseMessage::from("59\01\00000e0d5.6574d1e9.01.01\02.5\0USD\0150.75\00.045\020301231\0");
+
+    match decoders::decode_commission_report(server_versions::SIZE_RULES, &mut message) {
+        Ok(report) => {
+            assert_eq!(report.execution_id, "0000e0d5.6574d1e9.01.01", "report.execution_id");
+            assert_eq!(report.commission, 2.5, "report.commission");
+            assert_eq!(report.realized_pnl, Some(150.75), "report.realized_pnl");
+            assert_eq!(report.yields, Some(0.045), "report.yields");
+            assert_eq!(report.yield_redemption_date, "20301231", "report.yield_redemption_date");
+        }
+        Err(err) => assert!(false, "failed to decode commission report: {err}"),
+    }
+}
+
+#[test]
+fn order_status_from_str() {
+    assert_eq!(Status::from("ApiCancelled"), Status::ApiCancelled);
+    assert_eq!(Status::from("Inactive"), Status::Inactive);
+    assert_eq!(Status::from("Rejected"), Status::Unknown("Rejected".to_owned()));
+    assert_eq!(Status::Submitted.to_string(), "Submitted");
+}
+
+#[test]
+fn what_if_order() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            "5|13|76792991|TSLA|STK||0|?||SMART|USD|TSLA|NMS|BUY|100|MKT|0.0|0.0|DAY||DU1236109||0||100|1376327563|0|0|0||1376327563.0/DU1236109/100||||||||||0||-1|0||||||2147483647|0|0|0||3|0|0||0|0||0|None||0||||?|0|0||0|0||||||0|0|0|2147483647|2147483647|||0||IB|0|0||0|1|PreSubmitted|25000.5|20000.25|100000|1965.2|1786.6|0|26965.7|21786.85|100000|1|1|1.5|USD||0|0|0|None|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|1.7976931348623157E308|0||||0|1|0|0|0|||0||".to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("TSLA");
+    let order = order_builder::market_order(super::Action::Buy, 100.0);
+
+    let result = client.what_if_order(13, &contract, &order);
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    let what_if = Order { what_if: true, ..order };
+    let expected = encoders::encode_place_order(server_versions::SIZE_RULES, 13, &contract, &what_if).unwrap();
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        expected.encode_simple(),
+        "request sends what-if order"
+    );
+
+    match result {
+        Ok(order_state) => {
+            assert_eq!(order_state.status, "PreSubmitted", "order_state.status");
+            assert_eq!(order_state.initial_margin_before, Some(25000.5), "order_state.initial_margin_before");
+            assert_eq!(
+                order_state.maintenance_margin_before,
+                Some(20000.25),
+                "order_state.maintenance_margin_before"
+            );
+            assert_eq!(order_state.equity_with_loan_before, Some(100000.0), "order_state.equity_with_loan_before");
+            assert_eq!(order_state.initial_margin_change, Some(1965.2), "order_state.initial_margin_change");
+            assert_eq!(
+                order_state.maintenance_margin_change,
+                Some(1786.6),
+                "order_state.maintenance_margin_change"
+            );
+            assert_eq!(order_state.equity_with_loan_change, Some(0.0), "order_state.equity_with_loan_change");
+            assert_eq!(order_state.initial_margin_after, Some(26965.7), "order_state.initial_margin_after");
+            assert_eq!(
+                order_state.maintenance_margin_after,
+                Some(21786.85),
+                "order_state.maintenance_margin_after"
+            );
+            assert_eq!(order_state.commission, Some(1.0), "order_state.commission");
+            assert_eq!(order_state.minimum_commission, Some(1.0), "order_state.minimum_commission");
+            assert_eq!(order_state.maximum_commission, Some(1.5), "order_state.maximum_commission");
+            assert_eq!(order_state.commission_currency, "USD", "order_state.commission_currency");
+            assert_eq!(order_state.warning_text, "", "order_state.warning_text");
+        }
+        Err(err) => assert!(false, "failed to preview order: {err}"),
+    }
+}
+
+#[test]
+fn place_order_validated() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["3|13|PreSubmitted|0|100|0|1376327563|0|0|100||0||".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract_details = ContractDetails {
+        contract: Contract::stock("TSLA"),
+        min_tick: 0.01,
+        ..ContractDetails::default()
+    };
+
+    // aligned limit price is placed
+    let order = order_builder::limit_order(super::Action::Buy, 100.0, 196.53);
+    let result = client.place_order_validated(13, &contract_details, &order);
+    assert!(result.is_ok(), "failed to place aligned order: {:?}", result.err());
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 1, "aligned order should be sent");
+
+    // misaligned limit price is rejected before it is sent
+    let order = order_builder::limit_order(super::Action::Buy, 100.0, 196.534);
+    match client.place_order_validated(14, &contract_details, &order) {
+        Err(Error::InvalidPrice { price, suggested }) => {
+            assert_eq!(price, 196.534, "price");
+            assert!((suggested - 196.53).abs() < 1e-9, "suggested {suggested} should be 196.53");
+        }
+        Err(err) => assert!(false, "unexpected error: {err}"),
+        Ok(_) => assert!(false, "expected misaligned limit price to be rejected"),
+    }
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 1, "misaligned order should not be sent");
+}
+
+#[test]
+fn place_oca_orders() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+    client.set_next_order_id(100);
+
+    let contract = Contract::stock("MSFT");
+
+    let mut orders = vec![
+        order_builder::limit_order(super::Action::Sell, 100.0, 310.0),
+        order_builder::limit_order(super::Action::Sell, 100.0, 315.0),
+    ];
+    order_builder::oca_group(&mut orders, "MSFT-exit", OcaType::ReduceWithBlock);
+
+    let results = client.place_oca_orders(&contract, orders);
+    assert!(results.is_ok(), "failed to place oca orders: {}", results.err().unwrap());
+    assert_eq!(results.unwrap().len(), 2, "notification iterators");
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+
+    // order id, limit price, oca group, oca type
+    let fields = |i: usize| {
+        let encoded = request_messages[i].encode_simple();
+        let fields: Vec<String> = encoded.split('|').map(String::from).collect();
+        [1, 19, 22, 47].map(|index| fields[index].clone())
+    };
+
+    assert_eq!(fields(0), ["100", "310", "MSFT-exit", "2"], "first order");
+    assert_eq!(fields(1), ["101", "315", "MSFT-exit", "2"], "second order");
+}
+
+#[test]
+fn place_oca_orders_requires_group() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let contract = Contract::stock("MSFT");
+    let orders = vec![order_builder::limit_order(super::Action::Sell, 100.0, 310.0)];
+
+    let results = client.place_oca_orders(&contract, orders);
+    assert!(results.is_err(), "expected orders without OCA group to be rejected");
+    assert!(client.message_bus.lock().request_messages().is_empty(), "no orders should be sent");
+}
+
+#[test]
+fn encode_combo_built_from_legs() {
+    use crate::contracts::ComboLegBuilder;
+
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::SIZE_RULES);
+
+    let legs = vec![
+        ComboLegBuilder::new(55928698).ratio(1).action(Action::Buy).exchange("IPE").build(),
+        ComboLegBuilder::new(55850663).ratio(1).action(Action::Sell).exchange("IPE").build(),
+    ];
+    let contract = Contract::combo("WTI", "USD", legs);
+    let order = order_builder::combo_market_order(Action::Sell, 150.0, true);
+
+    let results = client.place_order(12, &contract, &order);
+    assert!(results.is_ok(), "failed to place order: {}", results.err().unwrap());
+
+    let results = client.place_order(12, &contract_samples::smart_future_combo_contract(), &order);
+    assert!(results.is_ok(), "failed to place order: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        request_messages[1].encode_simple(),
+        "built combo should encode like the sample combo"
+    );
+    assert!(
+        request_messages[0]
+            .encode_simple()
+            .contains("|2|55928698|1|BUY|IPE|0|0||0|55850663|1|SELL|IPE|0|0||0|"),
+        "combo legs: {}",
+        request_messages[0].encode_simple()
+    );
+}
@@ -1,125 +1,208 @@
-#![allow(dead_code)]
-//! The known server versions.
+//! The server versions known to the API, see [Client::server_version](crate::Client::server_version).
+//!
+//! Each constant is the first server version supporting the named feature.
 
-//shouldn't these all be deprecated?
-//  pub const HISTORICAL_DATA: i32 = 24;
-//  pub const CURRENT_TIME: i32 = 33;
-pub const REAL_TIME_BARS: i32 = 34;
-pub const SCALE_ORDERS: i32 = 35;
-//  pub const SNAPSHOT_MKT_DATA: i32 = 35;
-pub const SSHORT_COMBO_LEGS: i32 = 35;
-pub const WHAT_IF_ORDERS: i32 = 36;
-pub const CONTRACT_CONID: i32 = 37;
+macro_rules! server_versions {
+    ($($name:ident = $version:literal;)*) => {
+        $(pub const $name: i32 = $version;)*
 
-pub const PTA_ORDERS: i32 = 39;
-pub const FUNDAMENTAL_DATA: i32 = 40;
-pub const DELTA_NEUTRAL: i32 = 40;
-pub const CONTRACT_DATA_CHAIN: i32 = 40;
-pub const SCALE_ORDERS2: i32 = 40;
-pub const ALGO_ORDERS: i32 = 41;
-pub const EXECUTION_DATA_CHAIN: i32 = 42;
-pub const NOT_HELD: i32 = 44;
-pub const SEC_ID_TYPE: i32 = 45;
-pub const PLACE_ORDER_CONID: i32 = 46;
-pub const REQ_MKT_DATA_CONID: i32 = 47;
-pub const REQ_CALC_IMPLIED_VOLAT: i32 = 49;
-pub const REQ_CALC_OPTION_PRICE: i32 = 50;
-pub const SSHORTX_OLD: i32 = 51;
-pub const SSHORTX: i32 = 52;
-pub const REQ_GLOBAL_CANCEL: i32 = 53;
-pub const HEDGE_ORDERS: i32 = 54;
-pub const REQ_MARKET_DATA_TYPE: i32 = 55;
-pub const OPT_OUT_SMART_ROUTING: i32 = 56;
-pub const SMART_COMBO_ROUTING_PARAMS: i32 = 57;
-pub const DELTA_NEUTRAL_CONID: i32 = 58;
-pub const SCALE_ORDERS3: i32 = 60;
-pub const ORDER_COMBO_LEGS_PRICE: i32 = 61;
-pub const TRAILING_PERCENT: i32 = 62;
-pub const DELTA_NEUTRAL_OPEN_CLOSE: i32 = 66;
-pub const POSITIONS: i32 = 67;
-pub const ACCOUNT_SUMMARY: i32 = 67;
-pub const TRADING_CLASS: i32 = 68;
-pub const SCALE_TABLE: i32 = 69;
-pub const LINKING: i32 = 70;
-pub const ALGO_ID: i32 = 71;
-pub const OPTIONAL_CAPABILITIES: i32 = 72;
-pub const ORDER_SOLICITED: i32 = 73;
-pub const LINKING_AUTH: i32 = 74;
-pub const PRIMARYEXCH: i32 = 75;
-pub const RANDOMIZE_SIZE_AND_PRICE: i32 = 76;
-pub const FRACTIONAL_POSITIONS: i32 = 101;
-pub const PEGGED_TO_BENCHMARK: i32 = 102;
-pub const MODELS_SUPPORT: i32 = 103;
-pub const SEC_DEF_OPT_PARAMS_REQ: i32 = 104;
-pub const EXT_OPERATOR: i32 = 105;
-pub const SOFT_DOLLAR_TIER: i32 = 106;
-pub const REQ_FAMILY_CODES: i32 = 107;
-pub const REQ_MATCHING_SYMBOLS: i32 = 108;
-pub const PAST_LIMIT: i32 = 109;
-pub const MD_SIZE_MULTIPLIER: i32 = 110;
-pub const CASH_QTY: i32 = 111;
-pub const REQ_MKT_DEPTH_EXCHANGES: i32 = 112;
-pub const TICK_NEWS: i32 = 113;
-pub const REQ_SMART_COMPONENTS: i32 = 114;
-pub const REQ_NEWS_PROVIDERS: i32 = 115;
-pub const REQ_NEWS_ARTICLE: i32 = 116;
-pub const REQ_HISTORICAL_NEWS: i32 = 117;
-pub const REQ_HEAD_TIMESTAMP: i32 = 118;
-pub const REQ_HISTOGRAM: i32 = 119;
-pub const SERVICE_DATA_TYPE: i32 = 120;
-pub const AGG_GROUP: i32 = 121;
-pub const UNDERLYING_INFO: i32 = 122;
-pub const CANCEL_HEADTIMESTAMP: i32 = 123;
-pub const SYNT_REALTIME_BARS: i32 = 124;
-pub const CFD_REROUTE: i32 = 125;
-pub const MARKET_RULES: i32 = 126;
-pub const PNL: i32 = 127;
-pub const NEWS_QUERY_ORIGINS: i32 = 128;
-pub const UNREALIZED_PNL: i32 = 129;
-pub const HISTORICAL_TICKS: i32 = 130;
-pub const MARKET_CAP_PRICE: i32 = 131;
-pub const PRE_OPEN_BID_ASK: i32 = 132;
-pub const REAL_EXPIRATION_DATE: i32 = 134;
-pub const REALIZED_PNL: i32 = 135;
-pub const LAST_LIQUIDITY: i32 = 136;
-pub const TICK_BY_TICK: i32 = 137;
-pub const DECISION_MAKER: i32 = 138;
-pub const MIFID_EXECUTION: i32 = 139;
-pub const TICK_BY_TICK_IGNORE_SIZE: i32 = 140;
-pub const AUTO_PRICE_FOR_HEDGE: i32 = 141;
-pub const WHAT_IF_EXT_FIELDS: i32 = 142;
-pub const SCANNER_GENERIC_OPTS: i32 = 143;
-pub const API_BIND_ORDER: i32 = 144;
-pub const ORDER_CONTAINER: i32 = 145;
-pub const SMART_DEPTH: i32 = 146;
-pub const REMOVE_NULL_ALL_CASTING: i32 = 147;
-pub const D_PEG_ORDERS: i32 = 148;
-pub const MKT_DEPTH_PRIM_EXCHANGE: i32 = 149;
-pub const COMPLETED_ORDERS: i32 = 150;
-pub const PRICE_MGMT_ALGO: i32 = 151;
-pub const STOCK_TYPE: i32 = 152;
-pub const ENCODE_MSG_ASCII7: i32 = 153;
-pub const SEND_ALL_FAMILY_CODES: i32 = 154;
-pub const NO_DEFAULT_OPEN_CLOSE: i32 = 155;
-pub const PRICE_BASED_VOLATILITY: i32 = 156;
-pub const REPLACE_FA_END: i32 = 157;
-pub const DURATION: i32 = 158;
-pub const MARKET_DATA_IN_SHARES: i32 = 159;
-pub const POST_TO_ATS: i32 = 160;
-pub const WSHE_CALENDAR: i32 = 161;
-pub const AUTO_CANCEL_PARENT: i32 = 162;
-pub const FRACTIONAL_SIZE_SUPPORT: i32 = 163;
-pub const SIZE_RULES: i32 = 164;
-pub const HISTORICAL_SCHEDULE: i32 = 165;
-pub const ADVANCED_ORDER_REJECT: i32 = 166;
-pub const USER_INFO: i32 = 167;
-pub const CRYPTO_AGGREGATED_TRADES: i32 = 168;
-pub const MANUAL_ORDER_TIME: i32 = 169;
-pub const PEGBEST_PEGMID_OFFSETS: i32 = 170;
-pub const WSH_EVENT_DATA_FILTERS: i32 = 171;
-pub const IPO_PRICES: i32 = 172;
-pub const WSH_EVENT_DATA_FILTERS_DATE: i32 = 173;
-pub const INSTRUMENT_TIMEZONE: i32 = 174;
-pub const HMDS_MARKET_DATA_IN_SHARES: i32 = 175;
-pub const BOND_ISSUERID: i32 = 176;
-pub const FA_PROFILE_DESUPPORT: i32 = 177;
+        // Names of the known server versions in ascending order of version.
+        const NAMES: &[(&str, i32)] = &[$((stringify!($name), $version)),*];
+    };
+}
+
+server_versions! {
+    REAL_TIME_BARS = 34;
+    SCALE_ORDERS = 35;
+    SSHORT_COMBO_LEGS = 35;
+    WHAT_IF_ORDERS = 36;
+    CONTRACT_CONID = 37;
+    PTA_ORDERS = 39;
+    FUNDAMENTAL_DATA = 40;
+    DELTA_NEUTRAL = 40;
+    CONTRACT_DATA_CHAIN = 40;
+    SCALE_ORDERS2 = 40;
+    ALGO_ORDERS = 41;
+    EXECUTION_DATA_CHAIN = 42;
+    NOT_HELD = 44;
+    SEC_ID_TYPE = 45;
+    PLACE_ORDER_CONID = 46;
+    REQ_MKT_DATA_CONID = 47;
+    REQ_CALC_IMPLIED_VOLAT = 49;
+    REQ_CALC_OPTION_PRICE = 50;
+    SSHORTX_OLD = 51;
+    SSHORTX = 52;
+    REQ_GLOBAL_CANCEL = 53;
+    HEDGE_ORDERS = 54;
+    REQ_MARKET_DATA_TYPE = 55;
+    OPT_OUT_SMART_ROUTING = 56;
+    SMART_COMBO_ROUTING_PARAMS = 57;
+    DELTA_NEUTRAL_CONID = 58;
+    SCALE_ORDERS3 = 60;
+    ORDER_COMBO_LEGS_PRICE = 61;
+    TRAILING_PERCENT = 62;
+    DELTA_NEUTRAL_OPEN_CLOSE = 66;
+    POSITIONS = 67;
+    ACCOUNT_SUMMARY = 67;
+    TRADING_CLASS = 68;
+    SCALE_TABLE = 69;
+    LINKING = 70;
+    ALGO_ID = 71;
+    OPTIONAL_CAPABILITIES = 72;
+    ORDER_SOLICITED = 73;
+    LINKING_AUTH = 74;
+    PRIMARYEXCH = 75;
+    RANDOMIZE_SIZE_AND_PRICE = 76;
+    FRACTIONAL_POSITIONS = 101;
+    PEGGED_TO_BENCHMARK = 102;
+    MODELS_SUPPORT = 103;
+    SEC_DEF_OPT_PARAMS_REQ = 104;
+    EXT_OPERATOR = 105;
+    SOFT_DOLLAR_TIER = 106;
+    REQ_FAMILY_CODES = 107;
+    REQ_MATCHING_SYMBOLS = 108;
+    PAST_LIMIT = 109;
+    MD_SIZE_MULTIPLIER = 110;
+    CASH_QTY = 111;
+    REQ_MKT_DEPTH_EXCHANGES = 112;
+    TICK_NEWS = 113;
+    REQ_SMART_COMPONENTS = 114;
+    REQ_NEWS_PROVIDERS = 115;
+    REQ_NEWS_ARTICLE = 116;
+    REQ_HISTORICAL_NEWS = 117;
+    REQ_HEAD_TIMESTAMP = 118;
+    REQ_HISTOGRAM = 119;
+    SERVICE_DATA_TYPE = 120;
+    AGG_GROUP = 121;
+    UNDERLYING_INFO = 122;
+    CANCEL_HEADTIMESTAMP = 123;
+    SYNT_REALTIME_BARS = 124;
+    CFD_REROUTE = 125;
+    MARKET_RULES = 126;
+    PNL = 127;
+    NEWS_QUERY_ORIGINS = 128;
+    UNREALIZED_PNL = 129;
+    HISTORICAL_TICKS = 130;
+    MARKET_CAP_PRICE = 131;
+    PRE_OPEN_BID_ASK = 132;
+    REAL_EXPIRATION_DATE = 134;
+    REALIZED_PNL = 135;
+    LAST_LIQUIDITY = 136;
+    TICK_BY_TICK = 137;
+    DECISION_MAKER = 138;
+    MIFID_EXECUTION = 139;
+    TICK_BY_TICK_IGNORE_SIZE = 140;
+    AUTO_PRICE_FOR_HEDGE = 141;
+    WHAT_IF_EXT_FIELDS = 142;
+    SCANNER_GENERIC_OPTS = 143;
+    API_BIND_ORDER = 144;
+    ORDER_CONTAINER = 145;
+    SMART_DEPTH = 146;
+    REMOVE_NULL_ALL_CASTING = 147;
+    D_PEG_ORDERS = 148;
+    MKT_DEPTH_PRIM_EXCHANGE = 149;
+    COMPLETED_ORDERS = 150;
+    PRICE_MGMT_ALGO = 151;
+    STOCK_TYPE = 152;
+    ENCODE_MSG_ASCII7 = 153;
+    SEND_ALL_FAMILY_CODES = 154;
+    NO_DEFAULT_OPEN_CLOSE = 155;
+    PRICE_BASED_VOLATILITY = 156;
+    REPLACE_FA_END = 157;
+    DURATION = 158;
+    MARKET_DATA_IN_SHARES = 159;
+    POST_TO_ATS = 160;
+    WSHE_CALENDAR = 161;
+    AUTO_CANCEL_PARENT = 162;
+    FRACTIONAL_SIZE_SUPPORT = 163;
+    SIZE_RULES = 164;
+    HISTORICAL_SCHEDULE = 165;
+    ADVANCED_ORDER_REJECT = 166;
+    USER_INFO = 167;
+    CRYPTO_AGGREGATED_TRADES = 168;
+    MANUAL_ORDER_TIME = 169;
+    PEGBEST_PEGMID_OFFSETS = 170;
+    WSH_EVENT_DATA_FILTERS = 171;
+    IPO_PRICES = 172;
+    WSH_EVENT_DATA_FILTERS_DATE = 173;
+    INSTRUMENT_TIMEZONE = 174;
+    HMDS_MARKET_DATA_IN_SHARES = 175;
+    BOND_ISSUERID = 176;
+    FA_PROFILE_DESUPPORT = 177;
+    PERM_ID_AS_LONG = 178;
+    CME_TAGGING_FIELDS = 179;
+    CME_TAGGING_FIELDS_IN_OPEN_ORDER = 180;
+    ERROR_TIME = 181;
+    FULL_ORDER_PREVIEW_FIELDS = 182;
+    HISTORICAL_DATA_END = 183;
+}
+
+/// Lowest server version the client connects to.
+pub const fn min_version() -> i32 {
+    100
+}
+
+/// Highest server version requested by the client during the handshake.
+pub const fn max_version() -> i32 {
+    CME_TAGGING_FIELDS
+}
+
+/// Returns the name of the feature introduced in server version, for logging.
+/// When several features share a version the first one is returned.
+///
+/// ```
+/// use ibapi::server_versions;
+///
+/// assert_eq!(server_versions::name_of(server_versions::SIZE_RULES), Some("SIZE_RULES"));
+/// assert_eq!(server_versions::name_of(0), None);
+/// ```
+pub fn name_of(version: i32) -> Option<&'static str> {
+    NAMES.iter().find(|(_, v)| *v == version).map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_server_versions() {
+        assert_eq!(REAL_TIME_BARS, 34);
+        assert_eq!(FRACTIONAL_POSITIONS, 101);
+        assert_eq!(ENCODE_MSG_ASCII7, 153);
+        assert_eq!(SIZE_RULES, 164);
+        assert_eq!(ADVANCED_ORDER_REJECT, 166);
+        assert_eq!(CME_TAGGING_FIELDS, 179);
+
+        assert!(min_version() <= max_version(), "min_version() <= max_version()");
+    }
+
+    #[test]
+    fn max_version_covers_encoded_fields() {
+        // fields gated on versions above max_version() are never sent or decoded
+        for version in [
+            ADVANCED_ORDER_REJECT,
+            MANUAL_ORDER_TIME,
+            PEGBEST_PEGMID_OFFSETS,
+            BOND_ISSUERID,
+            FA_PROFILE_DESUPPORT,
+            PERM_ID_AS_LONG,
+            CME_TAGGING_FIELDS,
+        ] {
+            assert!(version <= max_version(), "{} above max_version()", name_of(version).unwrap());
+        }
+    }
+
+    #[test]
+    fn name_of_server_version() {
+        assert_eq!(name_of(SIZE_RULES), Some("SIZE_RULES"));
+        assert_eq!(name_of(ENCODE_MSG_ASCII7), Some("ENCODE_MSG_ASCII7"));
+        assert_eq!(name_of(SCALE_ORDERS), Some("SCALE_ORDERS"), "first of shared version");
+        assert_eq!(name_of(77), None);
+        assert_eq!(name_of(-1), None);
+    }
+
+    #[test]
+    fn names_are_ordered() {
+        assert!(NAMES.windows(2).all(|w| w[0].1 <= w[1].1), "names out of order");
+    }
+}
@@ -1,12 +1,18 @@
 use std::cell::RefCell;
+#[cfg(test)]
+use std::io::{Read, Write};
+#[cfg(test)]
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::AtomicI32;
 use std::sync::Arc;
 use std::time::Duration;
 
 use crossbeam::channel;
 
 use crate::client::transport::{GlobalResponseIterator, MessageBus, ResponseIterator};
-use crate::messages::{RequestMessage, ResponseMessage};
-use crate::Error;
+use crate::client::Notification;
+use crate::messages::{OutgoingMessages, RequestMessage, ResponseMessage};
+use crate::{Error, ToField};
 
 pub(crate) struct MessageBusStub {
     pub request_messages: RefCell<Vec<RequestMessage>>,
@@ -38,6 +44,18 @@ impl MessageBus for MessageBusStub {
         mock_request(self, request_id, message)
     }
 
+    fn resend_order_message(&mut self, order_id: i32, message: &RequestMessage) -> Result<(), Error> {
+        // orders count as placed once a place order message was sent for them
+        let prefix = format!("{}\0{order_id}\0", OutgoingMessages::PlaceOrder.to_field());
+        let placed = |request: &RequestMessage| request.encode().starts_with(&prefix);
+        if !self.request_messages.borrow().iter().any(placed) {
+            return Err(Error::Simple(format!("order id {order_id} has not been placed by this client")));
+        }
+
+        self.request_messages.borrow_mut().push(message.clone());
+        Ok(())
+    }
+
     fn request_next_order_id(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         mock_global_request(self, message)
     }
@@ -54,10 +72,30 @@ impl MessageBus for MessageBusStub {
         mock_global_request(self, message)
     }
 
+    fn request_account_updates(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        mock_global_request(self, message)
+    }
+
     fn request_family_codes(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
         mock_global_request(self, message)
     }
 
+    fn request_news_providers(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        mock_global_request(self, message)
+    }
+
+    fn request_scanner_parameters(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        mock_global_request(self, message)
+    }
+
+    fn request_fa(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        mock_global_request(self, message)
+    }
+
+    fn request_current_time(&mut self, message: &RequestMessage) -> Result<GlobalResponseIterator, Error> {
+        mock_global_request(self, message)
+    }
+
     fn write(&mut self, _packet: &str) -> Result<(), Error> {
         Ok(())
     }
@@ -65,6 +103,19 @@ impl MessageBus for MessageBusStub {
     fn process_messages(&mut self, _server_version: i32) -> Result<(), Error> {
         Ok(())
     }
+
+    fn disconnect(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn order_ids(&self) -> Arc<AtomicI32> {
+        Arc::new(AtomicI32::new(-1))
+    }
+
+    fn notifications(&self) -> channel::Receiver<Notification> {
+        let (_sender, receiver) = channel::unbounded();
+        receiver
+    }
 }
 
 fn mock_request(stub: &mut MessageBusStub, _request_id: i32, message: &RequestMessage) -> Result<ResponseIterator, Error> {
@@ -91,3 +142,43 @@ fn mock_global_request(stub: &mut MessageBusStub, message: &RequestMessage) -> R
 
     Ok(GlobalResponseIterator::new(Arc::new(receiver)))
 }
+
+// Accepts a connection and performs the server side of the handshake. Returns the StartApi message.
+#[cfg(test)]
+pub(crate) fn accept_handshake(listener: &TcpListener) -> (TcpStream, String) {
+    let (stream, _) = listener.accept().unwrap();
+    serve_handshake(stream)
+}
+
+// Performs the server side of the handshake on an accepted connection, negotiating server version SIZE_RULES. Returns the StartApi message.
+#[cfg(test)]
+pub(crate) fn serve_handshake(mut stream: TcpStream) -> (TcpStream, String) {
+    let mut prefix = [0_u8; 4];
+    stream.read_exact(&mut prefix).unwrap();
+    assert_eq!(&prefix, b"API\0", "handshake prefix");
+
+    read_frame(&mut stream); // supported versions
+    write_frame(&mut stream, &format!("{}\020230405 22:20:39 PST\0", crate::server_versions::SIZE_RULES));
+
+    let start_api = read_frame(&mut stream);
+    (stream, start_api)
+}
+
+// Reads a length prefixed frame sent by the client.
+#[cfg(test)]
+pub(crate) fn read_frame(stream: &mut TcpStream) -> String {
+    let mut header = [0_u8; 4];
+    stream.read_exact(&mut header).unwrap();
+
+    let mut data = vec![0_u8; u32::from_be_bytes(header) as usize];
+    stream.read_exact(&mut data).unwrap();
+
+    String::from_utf8(data).unwrap()
+}
+
+// Writes a length prefixed frame to the client, fields may be separated by | for readability.
+#[cfg(test)]
+pub(crate) fn write_frame(stream: &mut TcpStream, data: &str) {
+    stream.write_all(&(data.len() as u32).to_be_bytes()).unwrap();
+    stream.write_all(data.replace('|', "\0").as_bytes()).unwrap();
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use log::error;
+use serde::Deserialize;
+use time::Date;
+
+use crate::messages::IncomingMessages;
+use crate::{server_versions, Client, Error};
+
+mod decoders;
+mod encoders;
+#[cfg(test)]
+mod tests;
+
+/// Corporate event from the Wall Street Horizon calendar, received from [Client::wsh_event_data].
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct WshEvent {
+    /// Type of the event, e.g. wshe_ed for earnings dates.
+    #[serde(default)]
+    pub event_type: String,
+    /// Remaining fields of the event, keyed by their JSON name.
+    #[serde(flatten)]
+    pub details: HashMap<String, serde_json::Value>,
+}
+
+// Requests the Wall Street Horizon event types and filters available, as JSON.
+pub(crate) fn wsh_meta_data(client: &Client) -> Result<String, Error> {
+    client.check_server_version(server_versions::WSHE_CALENDAR, "It does not support WSHE Calendar API.")?;
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_wsh_meta_data(request_id)?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    let meta_data = loop {
+        match responses.next_result() {
+            Some(Ok(mut message)) if message.message_type() == IncomingMessages::WshMetaData => {
+                break decoders::decode_wsh_meta_data(&mut message);
+            }
+            Some(Ok(message)) => error!("unexpected message: {:?}", message),
+            Some(Err(err)) => break Err(err),
+            None => break Err(Error::Simple("no wsh meta data received".into())),
+        }
+    };
+
+    // TWS rejects further requests until the previous one is cancelled, also after an error
+    client.send_message(encoders::cancel_wsh_meta_data(request_id)?)?;
+
+    meta_data
+}
+
+// Requests Wall Street Horizon corporate events.
+//
+// # Arguments
+// * `contract_id`      - Contract id of the company, required unless filter is given.
+// * `filter`           - JSON filter of the events returned, see wsh_meta_data.
+// * `fill_watchlist`   - Include events of the companies in the watchlist.
+// * `fill_portfolio`   - Include events of the companies in the portfolio.
+// * `fill_competitors` - Include events of the competitors of the company.
+// * `start_date`       - Earliest date of the events returned.
+// * `end_date`         - Latest date of the events returned.
+// * `total_limit`      - Maximum number of events returned.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn wsh_event_data(
+    client: &Client,
+    contract_id: Option<i32>,
+    filter: &str,
+    fill_watchlist: bool,
+    fill_portfolio: bool,
+    fill_competitors: bool,
+    start_date: Option<Date>,
+    end_date: Option<Date>,
+    total_limit: Option<i32>,
+) -> Result<Vec<WshEvent>, Error> {
+    client.check_server_version(server_versions::WSHE_CALENDAR, "It does not support WSHE Calendar API.")?;
+
+    if !filter.is_empty() || fill_watchlist || fill_portfolio || fill_competitors {
+        client.check_server_version(server_versions::WSH_EVENT_DATA_FILTERS, "It does not support WSH event data filters.")?;
+    }
+
+    if start_date.is_some() || end_date.is_some() || total_limit.is_some() {
+        client.check_server_version(
+            server_versions::WSH_EVENT_DATA_FILTERS_DATE,
+            "It does not support WSH event data date filters.",
+        )?;
+    }
+
+    let request_id = client.next_request_id();
+    let message = encoders::request_wsh_event_data(
+        client.server_version(),
+        request_id,
+        contract_id,
+        filter,
+        fill_watchlist,
+        fill_portfolio,
+        fill_competitors,
+        start_date,
+        end_date,
+        total_limit,
+    )?;
+
+    let mut responses = client.send_request(request_id, message)?;
+
+    let events = loop {
+        match responses.next_result() {
+            Some(Ok(mut message)) if message.message_type() == IncomingMessages::WshEventData => {
+                break decoders::decode_wsh_event_data(&mut message);
+            }
+            Some(Ok(message)) => error!("unexpected message: {:?}", message),
+            Some(Err(err)) => break Err(err),
+            None => break Err(Error::Simple("no wsh event data received".into())),
+        }
+    };
+
+    // TWS rejects further requests until the previous one is cancelled, also after an error
+    client.send_message(encoders::cancel_wsh_event_data(request_id)?)?;
+
+    events
+}
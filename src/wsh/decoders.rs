@@ -0,0 +1,32 @@
+use serde_json::Value;
+
+use crate::messages::ResponseMessage;
+use crate::Error;
+
+use super::WshEvent;
+
+pub(crate) fn decode_wsh_meta_data(message: &mut ResponseMessage) -> Result<String, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    message.next_string()
+}
+
+// Events are sent as a JSON array, or a single JSON object when only one event matches.
+pub(crate) fn decode_wsh_event_data(message: &mut ResponseMessage) -> Result<Vec<WshEvent>, Error> {
+    message.skip(); // message type
+    message.skip(); // request id
+
+    let json = message.next_string()?;
+    if json.is_empty() {
+        return Ok(Vec::default());
+    }
+
+    let parsed: Result<Vec<WshEvent>, serde_json::Error> = match serde_json::from_str(&json) {
+        Ok(Value::Array(events)) => events.into_iter().map(serde_json::from_value).collect(),
+        Ok(event) => serde_json::from_value(event).map(|event| vec![event]),
+        Err(err) => Err(err),
+    };
+
+    parsed.map_err(|err| Error::Simple(format!("error parsing wsh event data {json:?}: {err}")))
+}
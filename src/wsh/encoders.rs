@@ -0,0 +1,78 @@
+use time::macros::format_description;
+use time::Date;
+
+use crate::messages::{OutgoingMessages, RequestMessage};
+use crate::{server_versions, Error};
+
+pub(crate) fn request_wsh_meta_data(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestWshMetaData);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_wsh_meta_data(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelWshMetaData);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn request_wsh_event_data(
+    server_version: i32,
+    request_id: i32,
+    contract_id: Option<i32>,
+    filter: &str,
+    fill_watchlist: bool,
+    fill_portfolio: bool,
+    fill_competitors: bool,
+    start_date: Option<Date>,
+    end_date: Option<Date>,
+    total_limit: Option<i32>,
+) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::RequestWshEventData);
+    message.push_field(&request_id);
+    message.push_field(&contract_id);
+
+    if server_version >= server_versions::WSH_EVENT_DATA_FILTERS {
+        message.push_field(&filter);
+        message.push_field(&fill_watchlist);
+        message.push_field(&fill_portfolio);
+        message.push_field(&fill_competitors);
+    }
+
+    if server_version >= server_versions::WSH_EVENT_DATA_FILTERS_DATE {
+        message.push_field(&format_date(start_date)?);
+        message.push_field(&format_date(end_date)?);
+        message.push_field(&total_limit);
+    }
+
+    Ok(message)
+}
+
+pub(crate) fn cancel_wsh_event_data(request_id: i32) -> Result<RequestMessage, Error> {
+    let mut message = RequestMessage::default();
+
+    message.push_field(&OutgoingMessages::CancelWshEventData);
+    message.push_field(&request_id);
+
+    Ok(message)
+}
+
+// Formats the date as yyyymmdd, empty when not given.
+fn format_date(date: Option<Date>) -> Result<String, Error> {
+    match date {
+        Some(date) => match date.format(format_description!("[year][month][day]")) {
+            Ok(formatted) => Ok(formatted),
+            Err(err) => Err(Error::Simple(format!("error formatting date {date}: {err}"))),
+        },
+        None => Ok(String::default()),
+    }
+}
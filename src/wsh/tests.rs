@@ -0,0 +1,170 @@
+use std::cell::RefCell;
+
+use time::macros::date;
+
+use crate::messages::ResponseMessage;
+use crate::stubs::MessageBusStub;
+
+use super::*;
+
+#[test]
+fn wsh_meta_data() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![r#"104|9000|{"meta_data":{"event_types":[{"tag":"wshe_ed","name":"Earnings Date"}]}}|"#.to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::WSHE_CALENDAR);
+
+    let results = client.wsh_meta_data();
+    assert!(results.is_ok(), "failed to request wsh meta data: {}", results.err().unwrap());
+
+    assert_eq!(
+        results.unwrap(),
+        r#"{"meta_data":{"event_types":[{"tag":"wshe_ed","name":"Earnings Date"}]}}"#,
+        "meta data"
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "100|9000|", "wsh meta data request");
+    assert_eq!(request_messages[1].encode_simple(), "101|9000|", "cancel wsh meta data");
+}
+
+#[test]
+fn wsh_event_data() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![
+            r#"105|9000|[{"event_type":"wshe_ed","data":{"earnings_date":"20240425","time_of_day":"AMC"}},{"event_type":"wshe_div","data":{"ex_date":"20240510"}}]|"#
+                .to_owned(),
+        ],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::WSH_EVENT_DATA_FILTERS_DATE);
+
+    let results = client.wsh_event_data(
+        Some(265598),
+        "",
+        false,
+        false,
+        false,
+        Some(date!(2024 - 04 - 01)),
+        Some(date!(2024 - 06 - 30)),
+        Some(10),
+    );
+    assert!(results.is_ok(), "failed to request wsh event data: {}", results.err().unwrap());
+
+    let events = results.unwrap();
+    assert_eq!(events.len(), 2, "events.len()");
+
+    assert_eq!(events[0].event_type, "wshe_ed", "events[0].event_type");
+    assert_eq!(events[0].details["data"]["earnings_date"], "20240425", "events[0] earnings date");
+    assert_eq!(events[1].event_type, "wshe_div", "events[1].event_type");
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(
+        request_messages[0].encode_simple(),
+        "102|9000|265598||0|0|0|20240401|20240630|10|",
+        "wsh event data request"
+    );
+    assert_eq!(request_messages[1].encode_simple(), "103|9000|", "cancel wsh event data");
+}
+
+#[test]
+fn wsh_event_data_without_filters() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![r#"105|9000|[{"event_type":"wshe_ed","data":{"earnings_date":"20240425"}}]|"#.to_owned()],
+    }));
+
+    // older servers take the contract id only
+    let client = Client::stubbed(message_bus, server_versions::WSHE_CALENDAR);
+
+    let results = client.wsh_event_data(Some(265598), "", false, false, false, None, None, None);
+    assert!(results.is_ok(), "failed to request wsh event data: {}", results.err().unwrap());
+
+    let request_messages = client.message_bus.lock().request_messages();
+
+    assert_eq!(request_messages[0].encode_simple(), "102|9000|265598|", "wsh event data request");
+    assert_eq!(request_messages[1].encode_simple(), "103|9000|", "cancel wsh event data");
+}
+
+#[test]
+fn wsh_event_data_api_error() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec!["4|2|9000|10276|News feed is not allowed.|".to_owned()],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::WSHE_CALENDAR);
+
+    let results = client.wsh_event_data(Some(265598), "", false, false, false, None, None, None);
+    assert!(
+        matches!(results, Err(Error::Api { code: 10276, .. })),
+        "expected api error, got {results:?}"
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 2, "request_messages.len()");
+    assert_eq!(request_messages[1].encode_simple(), "103|9000|", "cancel wsh event data");
+}
+
+#[test]
+fn wsh_event_data_single_event() {
+    let mut message = ResponseMessage::from(r#"105|9000|{"event_type":"wshe_ed","data":{"earnings_date":"20240425"}}|"#.replace('|', "\0").as_str());
+
+    match decoders::decode_wsh_event_data(&mut message) {
+        Ok(events) => {
+            assert_eq!(events.len(), 1, "events.len()");
+            assert_eq!(events[0].event_type, "wshe_ed", "events[0].event_type");
+        }
+        Err(err) => assert!(false, "error decoding wsh event data: {err}"),
+    }
+}
+
+#[test]
+fn wsh_not_supported() {
+    let message_bus = RefCell::new(Box::new(MessageBusStub {
+        request_messages: RefCell::new(vec![]),
+        response_messages: vec![],
+    }));
+
+    let client = Client::stubbed(message_bus, server_versions::WSHE_CALENDAR - 1);
+
+    assert!(
+        matches!(client.wsh_meta_data(), Err(Error::ServerVersion(_, _, _))),
+        "expected server version error for meta data"
+    );
+    assert!(
+        matches!(
+            client.wsh_event_data(Some(265598), "", false, false, false, None, None, None),
+            Err(Error::ServerVersion(_, _, _))
+        ),
+        "expected server version error for event data"
+    );
+
+    let client = Client::stubbed(
+        RefCell::new(Box::new(MessageBusStub {
+            request_messages: RefCell::new(vec![]),
+            response_messages: vec![],
+        })),
+        server_versions::WSHE_CALENDAR,
+    );
+
+    let results = client.wsh_event_data(None, r#"{"watchlist":["8314"]}"#, false, false, false, None, None, None);
+    assert!(
+        matches!(results, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error for filters"
+    );
+
+    let results = client.wsh_event_data(Some(265598), "", false, false, false, None, None, Some(10));
+    assert!(
+        matches!(results, Err(Error::ServerVersion(_, _, _))),
+        "expected server version error for limits"
+    );
+
+    let request_messages = client.message_bus.lock().request_messages();
+    assert_eq!(request_messages.len(), 0, "unsupported requests should not be sent");
+}